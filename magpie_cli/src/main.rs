@@ -0,0 +1,337 @@
+//! Command line tool to check sets without running the Discord bot.
+//!
+//! Set maintainers can use this to see if their spreadsheet is fetched and parse correctly and
+//! that queries return what they expect. Every command print a table by default or JSON with the
+//! `--json` flag.
+
+use std::{collections::HashMap, fs, process::exit, sync::Arc};
+
+use poise::serenity_prelude::GuildId;
+use serde_json::{json, Value};
+
+use magpie_tutor::{
+    export::{card_json, set_json},
+    interaction::InteractionStore,
+    logging::init_cli_logging,
+    query::run_query,
+    search::{find_card, process_search},
+    Set, SETS,
+};
+
+const USAGE: &str = "Usage: magpie-cli [--json] <command>
+
+Commands:
+    fetch [set]                 Fetch every set, or a single set, and print a summary with issues
+    query <sets> <query...>     Run a query on the comma separated sets
+    show <set> <card...>        Show a card, the name is fuzzy search like in the bot
+    export <set> [--out <file>] Export a set as JSON to stdout or a file
+    search [--guild <id>] <msg> Run a message like `[[stoat]]` through the bot search and print
+                                the reply without connecting to Discord
+
+Options:
+    --json                      Print JSON instead of a table";
+
+fn main() {
+    init_cli_logging();
+
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let json = if let Some(i) = args.iter().position(|a| a == "--json") {
+        args.remove(i);
+        true
+    } else {
+        false
+    };
+
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["fetch"] => fetch(None, json),
+        ["fetch", set] => fetch(Some(set), json),
+        ["query", sets, ref terms @ ..] => query(sets, &terms.join(" "), json),
+        ["show", set, ref name @ ..] if !name.is_empty() => show(set, &name.join(" "), json),
+        ["export", set] => export(set, None),
+        ["export", set, "--out", file] => export(set, Some(file)),
+        ["search", "--guild", guild, ref message @ ..] if !message.is_empty() => {
+            search(Some(guild), &message.join(" "), json)
+        }
+        ["search", ref message @ ..] if !message.is_empty() => {
+            search(None, &message.join(" "), json)
+        }
+        ["help" | "--help" | "-h"] => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => Err(USAGE.to_owned()),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{err}");
+        exit(1);
+    }
+}
+
+/// Print a summary of the sets along with the data problems found in them.
+fn fetch(code: Option<&str>, json: bool) -> Result<(), String> {
+    let sets = SETS.read().unwrap();
+
+    let mut sets = match code {
+        Some(code) => vec![get_set(&sets, code)?],
+        None => sets.values().collect(),
+    };
+    sets.sort_by_key(|s| s.code.code());
+
+    let summary = sets
+        .iter()
+        .map(|set| {
+            json!({
+                "code": set.code.code(),
+                "name": set.name,
+                "cards": set.cards.len(),
+                "sigils": set.sigils_description.len(),
+                "issues": set.validate().iter().map(ToString::to_string).collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if json {
+        return print_json(&Value::Array(summary));
+    }
+
+    print_table(
+        &["Code", "Name", "Cards", "Sigils", "Issues"],
+        &summary
+            .iter()
+            .map(|s| {
+                vec![
+                    text(&s["code"]),
+                    text(&s["name"]),
+                    text(&s["cards"]),
+                    text(&s["sigils"]),
+                    s["issues"].as_array().map_or(0, Vec::len).to_string(),
+                ]
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    for s in &summary {
+        for issue in s["issues"].as_array().into_iter().flatten() {
+            eprintln!("[{}] {}", text(&s["code"]), text(issue));
+        }
+    }
+
+    Ok(())
+}
+
+/// Run a query with the same syntax as the bot.
+fn query(codes: &str, query: &str, json: bool) -> Result<(), String> {
+    let all_sets = SETS.read().unwrap();
+
+    let sets = codes
+        .split(',')
+        .map(|c| get_set(&all_sets, c.trim()).cloned())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let result = run_query(sets, &all_sets, query, None)?;
+    let cards = result
+        .cards()
+        .iter()
+        .map(|c| card_json(c, all_sets.get(c.set.code()).map(Arc::as_ref)))
+        .collect::<Vec<_>>();
+
+    if json {
+        return print_json(&json!({
+            "count": cards.len(),
+            "filters": result.filters.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "cards": cards,
+        }));
+    }
+
+    for filter in &result.filters {
+        println!("- {filter}");
+    }
+    print_table(
+        &[
+            "Set", "Name", "Rarity", "Temple", "Costs", "Stats", "Sigils",
+        ],
+        &cards
+            .iter()
+            .map(|c| {
+                vec![
+                    text(&c["set"]),
+                    text(&c["name"]),
+                    text(&c["rarity"]),
+                    text(&c["temple"]),
+                    text(&c["costs"]),
+                    format!("{}/{}", text(&c["attack"]), text(&c["health"])),
+                    c["sigils"]
+                        .as_array()
+                        .map(|s| s.iter().map(|s| text(&s["name"])).collect::<Vec<_>>())
+                        .unwrap_or_default()
+                        .join(", "),
+                ]
+            })
+            .collect::<Vec<_>>(),
+    );
+    println!("{} cards", cards.len());
+
+    Ok(())
+}
+
+/// Show every field of a card.
+fn show(code: &str, name: &str, json: bool) -> Result<(), String> {
+    let sets = SETS.read().unwrap();
+    let set = get_set(&sets, code)?;
+
+    let res = find_card(name, set).ok_or_else(|| format!("Card `{name}` not found"))?;
+    let card = card_json(res.data, Some(set));
+
+    if json {
+        return print_json(&json!({ "rank": res.rank, "card": card }));
+    }
+
+    let Value::Object(fields) = card else {
+        unreachable!("card json is always an object")
+    };
+
+    let width = fields.keys().map(String::len).max().unwrap_or_default();
+    for (key, value) in &fields {
+        match (key.as_str(), value) {
+            ("sigils", Value::Array(sigils)) => {
+                println!("{key:width$}");
+                for sigil in sigils {
+                    println!(
+                        "    {}: {}",
+                        text(&sigil["name"]),
+                        text(&sigil["description"])
+                    );
+                }
+            }
+            _ => println!("{key:width$}  {}", text(value)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Export a set as JSON, always JSON because a set does not fit in a table.
+fn export(code: &str, out: Option<&str>) -> Result<(), String> {
+    let sets = SETS.read().unwrap();
+    let set = set_json(get_set(&sets, code)?);
+
+    match out {
+        Some(file) => {
+            let content = serde_json::to_string_pretty(&set).map_err(|e| e.to_string())?;
+            fs::write(file, content).map_err(|e| format!("Cannot write to {file}: {e}"))?;
+            eprintln!(
+                "Exported {} cards to {file}",
+                set["cards"].as_array().map_or(0, Vec::len)
+            );
+            Ok(())
+        }
+        None => print_json(&set),
+    }
+}
+
+/// Run a message through the search like the bot would and print the reply without sending it.
+///
+/// Portraits are still generate so the attachments have their real size.
+fn search(guild: Option<&str>, message: &str, json: bool) -> Result<(), String> {
+    let guild_id = match guild {
+        Some(id) => Some(
+            id.parse()
+                .ok()
+                .filter(|id| *id != 0)
+                .map(GuildId::new)
+                .ok_or_else(|| format!("Invalid guild id `{id}`"))?,
+        ),
+        None => None,
+    };
+
+    let reply = process_search(message, guild_id, false, &InteractionStore::new());
+
+    if json {
+        return print_json(&json!({
+            "content": reply.content,
+            "embeds": reply.embeds,
+            "attachments": reply
+                .attachments
+                .iter()
+                .map(|a| json!({ "filename": a.filename, "bytes": a.data.len() }))
+                .collect::<Vec<_>>(),
+            "components": reply.components,
+        }));
+    }
+
+    println!("{}", reply.to_text());
+    Ok(())
+}
+
+fn get_set<'a>(
+    sets: &'a HashMap<&'static str, Arc<Set>>,
+    code: &str,
+) -> Result<&'a Arc<Set>, String> {
+    sets.get(code).ok_or_else(|| {
+        let mut codes = sets.keys().copied().collect::<Vec<_>>();
+        codes.sort_unstable();
+        format!(
+            "Unknown set code `{code}`, available set: {}",
+            codes.join(", ")
+        )
+    })
+}
+
+fn print_json(value: &Value) -> Result<(), String> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).map_err(|e| e.to_string())?
+    );
+    Ok(())
+}
+
+/// Print rows as a table with column aligned to the longest value.
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(i, h)| {
+            rows.iter()
+                .map(|r| r[i].chars().count())
+                .chain([h.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!("{c:w$}"))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_owned()
+    };
+
+    println!("{}", line(headers.to_vec()));
+    println!(
+        "{}",
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("-+-")
+    );
+    for row in rows {
+        println!("{}", line(row.iter().map(String::as_str).collect()));
+    }
+}
+
+/// Display a JSON value without quotes around strings.
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(a) => a.iter().map(text).collect::<Vec<_>>().join(", "),
+        v => v.to_string(),
+    }
+}
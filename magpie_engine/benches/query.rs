@@ -0,0 +1,74 @@
+//! Benchmark the sequential and parallel query on a big set.
+//!
+//! Run with `cargo bench -p magpie_engine --features rayon` to also benchmark
+//! [`QueryBuilder::par_query`].
+
+// criterion_group! generate a undocumented function
+#![allow(missing_docs)]
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use magpie_engine::prelude::*;
+
+/// Around the amount of cards in every set the bot support combine.
+const CARD_COUNT: isize = 5000;
+
+fn big_set() -> Set<(), ()> {
+    let mut set = SetBuilder::new("bch", "Benchmark")
+        .unwrap()
+        .sigil("Airborne", "This card can fly")
+        .sigil("Bifurcated Strike", "This card attack twice");
+
+    for i in 0..CARD_COUNT {
+        let sigils = match i % 3 {
+            0 => vec![],
+            1 => vec!["Airborne".to_owned()],
+            _ => vec!["Airborne".to_owned(), "Bifurcated Strike".to_owned()],
+        };
+
+        set = set
+            .card(
+                CardBuilder::new(&format!("Card {i}"))
+                    .attack(Attack::Num(i % 7))
+                    .health(i % 11)
+                    .sigils(sigils),
+            )
+            .unwrap();
+    }
+
+    set.build()
+}
+
+fn filters() -> Vec<Filters<(), (), ()>> {
+    vec![
+        Filters::Attack(QueryOrder::GreaterEqual, 2),
+        Filters::Health(QueryOrder::Less, 8),
+        Filters::Sigil("Airborne".to_owned(), vec![]),
+        Filters::Not(Box::new(Filters::Name("Card 1".to_owned()))),
+    ]
+}
+
+fn bench_query(c: &mut Criterion) {
+    let set = big_set();
+
+    c.bench_function("query", |b| {
+        b.iter(|| {
+            QueryBuilder::with_filters(vec![black_box(&set)], filters())
+                .query()
+                .cards
+                .len()
+        });
+    });
+
+    #[cfg(feature = "rayon")]
+    c.bench_function("par_query", |b| {
+        b.iter(|| {
+            QueryBuilder::with_filters(vec![black_box(&set)], filters())
+                .par_query()
+                .cards
+                .len()
+        });
+    });
+}
+
+criterion_group!(benches, bench_query);
+criterion_main!(benches);
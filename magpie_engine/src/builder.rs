@@ -0,0 +1,457 @@
+//! Build cards and sets in code instead of fetching them, like for homebrew sets.
+//!
+//! A [`SetBuilder`] check every card added to it so the built [`Set`] is always valid: card names
+//! are unique, stats are not negative and every sigil have a description.
+//!
+//! # Examples
+//!
+//! ```
+//! use magpie_engine::prelude::*;
+//!
+//! let set: Set<(), ()> = SetBuilder::new("hom", "Homebrew")
+//!     .unwrap()
+//!     .sigil("Sharp Quills", "When this card is struck, the striker is dealt 1 damage.")
+//!     .card(
+//!         CardBuilder::new("Porcupine")
+//!             .attack(Attack::Num(1))
+//!             .health(2)
+//!             .sigils(vec!["Sharp Quills".to_string()]),
+//!     )
+//!     .unwrap()
+//!     .build();
+//!
+//! assert_eq!(set.cards.len(), 1);
+//! assert_eq!(set.cards[0].set.code(), "hom");
+//!
+//! // unknown sigils are rejected
+//! let err = SetBuilder::<(), ()>::new("hom", "Homebrew")
+//!     .unwrap()
+//!     .card(CardBuilder::new("Stoat").sigils(vec!["Nope".to_string()]));
+//! assert!(err.is_err());
+//!
+//! // a card can also be build on its own once it have a set code
+//! let card: Card<(), ()> = CardBuilder::new("Bullfrog")
+//!     .set(SetCode::new("hom").unwrap())
+//!     .attack(Attack::Num(1))
+//!     .health(2)
+//!     .costs(CostsBuilder::new().blood(1).build())
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(card.costs.unwrap().blood, 1);
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::sync::Arc;
+
+use crate::{
+    query::text::TextIndex, Attack, Card, Costs, LowerText, Mox, MoxCount, Rarity, Set, SetCode,
+    Temple, Traits,
+};
+
+/// Error when building a card or set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// The set code is not 3 ascii characters.
+    InvalidSetCode(String),
+    /// The set or card name is empty.
+    EmptyName,
+    /// A card with this name, ignoring case, is already in the set.
+    DuplicateName(String),
+    /// A card stat is negative.
+    NegativeStat(String),
+    /// A card is build on its own without a set code.
+    MissingSetCode(String),
+    /// A card use a sigil that is not in the set sigils description.
+    UnknownSigil(String),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::InvalidSetCode(c) => {
+                write!(f, "set code `{c}` must be 3 ascii characters")
+            }
+            BuildError::EmptyName => write!(f, "name cannot be empty"),
+            BuildError::DuplicateName(n) => write!(f, "a card named {n} is already in the set"),
+            BuildError::NegativeStat(n) => write!(f, "{n} cannot have negative stats"),
+            BuildError::MissingSetCode(n) => write!(f, "{n} is not in any set"),
+            BuildError::UnknownSigil(s) => write!(f, "sigil {s} have no description"),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// Builder for a single [`Card`].
+///
+/// The set code is fill in when added to a [`SetBuilder`], use [`CardBuilder::set`] to
+/// [`build`](CardBuilder::build) the card on its own.
+#[derive(Debug, Clone)]
+pub struct CardBuilder<E, C>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    card: Card<E, C>,
+    /// If the set code have been set.
+    has_set: bool,
+    /// If the card is allow to have negative health.
+    negative_health: bool,
+}
+
+impl<E, C> CardBuilder<E, C>
+where
+    E: Clone + Default,
+    C: Clone + PartialEq,
+{
+    /// Start a new common free card with no stats.
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        CardBuilder {
+            card: Card {
+                set: SetCode::new("___").unwrap(),
+                name: name.trim().to_owned(),
+                description: String::new(),
+                portrait: String::new(),
+                rarity: Rarity::COMMON,
+                temple: Temple::empty(),
+                other_temples: vec![],
+                tribes: None,
+                attack: Attack::Num(0),
+                health: 0,
+                sigils: vec![],
+                costs: None,
+                traits: None,
+                related: vec![],
+                lower: LowerText::default(),
+                extra: E::default(),
+            },
+            has_set: false,
+            negative_health: false,
+        }
+    }
+}
+
+impl<E, C> CardBuilder<E, C>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    /// Set the card set code.
+    #[must_use]
+    pub fn set(mut self, code: SetCode) -> Self {
+        self.card.set = code;
+        self.has_set = true;
+        self
+    }
+
+    /// Set the card description.
+    #[must_use]
+    pub fn description(mut self, description: &str) -> Self {
+        description.clone_into(&mut self.card.description);
+        self
+    }
+
+    /// Set the card portrait url.
+    #[must_use]
+    pub fn portrait(mut self, portrait: &str) -> Self {
+        portrait.clone_into(&mut self.card.portrait);
+        self
+    }
+
+    /// Set the card rarity.
+    #[must_use]
+    pub fn rarity(mut self, rarity: Rarity) -> Self {
+        self.card.rarity = rarity;
+        self
+    }
+
+    /// Set the card temple.
+    #[must_use]
+    pub fn temple(mut self, temple: Temple) -> Self {
+        self.card.temple = temple;
+        self
+    }
+
+    /// Set the temples of the card that are not [`Temple`] flags.
+    #[must_use]
+    pub fn other_temples(mut self, other_temples: Vec<String>) -> Self {
+        self.card.other_temples = other_temples;
+        self
+    }
+
+    /// Set the card tribes.
+    #[must_use]
+    pub fn tribes(mut self, tribes: Option<String>) -> Self {
+        self.card.tribes = tribes;
+        self
+    }
+
+    /// Set the card attack.
+    #[must_use]
+    pub fn attack(mut self, attack: Attack) -> Self {
+        self.card.attack = attack;
+        self
+    }
+
+    /// Set the card health.
+    #[must_use]
+    pub fn health(mut self, health: isize) -> Self {
+        self.card.health = health;
+        self
+    }
+
+    /// Allow the card to have negative health, like cards that die when played.
+    #[must_use]
+    pub fn allow_negative_health(mut self) -> Self {
+        self.negative_health = true;
+        self
+    }
+
+    /// Set the card sigils.
+    #[must_use]
+    pub fn sigils<I, S>(mut self, sigils: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Arc<str>>,
+    {
+        self.card.sigils = sigils.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the card costs, [`None`] for a free card.
+    #[must_use]
+    pub fn costs(mut self, costs: Option<Costs<C>>) -> Self {
+        self.card.costs = costs;
+        self
+    }
+
+    /// Set the card traits.
+    #[must_use]
+    pub fn traits(mut self, traits: Option<Traits>) -> Self {
+        self.card.traits = traits;
+        self
+    }
+
+    /// Set the card related cards.
+    #[must_use]
+    pub fn related(mut self, related: Vec<String>) -> Self {
+        self.card.related = related;
+        self
+    }
+
+    /// Set the card extra info.
+    #[must_use]
+    pub fn extra(mut self, extra: E) -> Self {
+        self.card.extra = extra;
+        self
+    }
+
+    /// Check the card on its own, sigils are check when the card is added to a set.
+    pub fn validate(&self) -> Result<(), BuildError> {
+        if self.card.name.is_empty() {
+            return Err(BuildError::EmptyName);
+        }
+
+        if (self.card.health < 0 && !self.negative_health)
+            || matches!(self.card.attack, Attack::Num(a) if a < 0)
+        {
+            return Err(BuildError::NegativeStat(self.card.name.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Check and build the card, the set code must have been [set](CardBuilder::set).
+    ///
+    /// Sigils are not check since there is no set to check them against.
+    pub fn build(self) -> Result<Card<E, C>, BuildError> {
+        self.validate()?;
+
+        if !self.has_set {
+            return Err(BuildError::MissingSetCode(self.card.name));
+        }
+
+        let mut card = self.card;
+        card.reindex();
+
+        Ok(card)
+    }
+}
+
+/// Builder for a card [`Costs`], every cost start at 0.
+#[derive(Debug, Clone, Default)]
+pub struct CostsBuilder<C> {
+    costs: Costs<C>,
+}
+
+impl<C> CostsBuilder<C>
+where
+    C: Default + PartialEq,
+{
+    /// Start a new free cost.
+    #[must_use]
+    pub fn new() -> Self {
+        CostsBuilder {
+            costs: Costs::default(),
+        }
+    }
+
+    /// Set the blood cost.
+    #[must_use]
+    pub fn blood(mut self, blood: isize) -> Self {
+        self.costs.blood = blood;
+        self
+    }
+
+    /// Set the bone cost.
+    #[must_use]
+    pub fn bone(mut self, bone: isize) -> Self {
+        self.costs.bone = bone;
+        self
+    }
+
+    /// Set the energy cost.
+    #[must_use]
+    pub fn energy(mut self, energy: isize) -> Self {
+        self.costs.energy = energy;
+        self
+    }
+
+    /// Set the mox cost.
+    #[must_use]
+    pub fn mox(mut self, mox: Mox) -> Self {
+        self.costs.mox = mox;
+        self
+    }
+
+    /// Set the mox count for cards that cost more than 1 of a mox.
+    #[must_use]
+    pub fn mox_count(mut self, mox_count: MoxCount) -> Self {
+        self.costs.mox_count = Some(mox_count);
+        self
+    }
+
+    /// Set the extra costs.
+    #[must_use]
+    pub fn extra(mut self, extra: C) -> Self {
+        self.costs.extra = extra;
+        self
+    }
+
+    /// Build the costs, [`None`] if the card is free so it can be pass to
+    /// [`CardBuilder::costs`].
+    #[must_use]
+    pub fn build(self) -> Option<Costs<C>> {
+        let c = &self.costs;
+        let free = c.blood == 0
+            && c.bone == 0
+            && c.energy == 0
+            && c.mox.is_empty()
+            && c.mox_count.is_none()
+            && c.extra == C::default();
+
+        (!free).then_some(self.costs)
+    }
+}
+
+/// Builder for a [`Set`] that check every card added to it.
+#[derive(Debug, Clone)]
+pub struct SetBuilder<E, C>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    set: Set<E, C>,
+}
+
+impl<E, C> SetBuilder<E, C>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    /// Start a new empty set.
+    pub fn new(code: &str, name: &str) -> Result<Self, BuildError> {
+        let set_code =
+            SetCode::new(code).ok_or_else(|| BuildError::InvalidSetCode(code.to_owned()))?;
+
+        if name.trim().is_empty() {
+            return Err(BuildError::EmptyName);
+        }
+
+        Ok(SetBuilder {
+            set: Set {
+                code: set_code,
+                name: name.trim().to_owned(),
+                cards: vec![],
+                sigils_description: HashMap::new(),
+                sigils_category: HashMap::new(),
+                pools: HashMap::new(),
+                text_index: TextIndex::default(),
+                issues: vec![],
+            },
+        })
+    }
+
+    /// Continue building from an existing set, the cards in it are not check again.
+    #[must_use]
+    pub fn from_set(set: Set<E, C>) -> Self {
+        SetBuilder { set }
+    }
+
+    /// Add or replace a sigil description.
+    #[must_use]
+    pub fn sigil(mut self, name: &str, description: &str) -> Self {
+        self.set
+            .sigils_description
+            .insert(name.trim().into(), description.trim().to_owned());
+        self
+    }
+
+    /// Add or replace a sigil category, see [`Set::sigils_category`].
+    #[must_use]
+    pub fn sigil_category(mut self, name: &str, category: &str) -> Self {
+        self.set
+            .sigils_category
+            .insert(name.trim().to_owned(), category.trim().to_owned());
+        self
+    }
+
+    /// Add a card to the set after checking it.
+    pub fn card(mut self, card: CardBuilder<E, C>) -> Result<Self, BuildError> {
+        card.validate()?;
+
+        let mut card = card.card;
+
+        // names are match without case everywhere else, like in the card id
+        let lower = card.name.to_lowercase();
+        if self.set.cards.iter().any(|c| c.lower.name == lower) {
+            return Err(BuildError::DuplicateName(card.name));
+        }
+
+        // share the sigil names with the set
+        for sigil in &mut card.sigils {
+            match self.set.intern_sigil(sigil) {
+                Some(name) => *sigil = name,
+                None => return Err(BuildError::UnknownSigil(sigil.to_string())),
+            }
+        }
+
+        card.set = self.set.code;
+        card.reindex();
+        self.set.cards.push(card);
+
+        Ok(self)
+    }
+
+    /// Finish building the set, this build the set [`text_index`](Set::text_index).
+    #[must_use]
+    pub fn build(self) -> Set<E, C> {
+        let mut set = self.set;
+        set.text_index = TextIndex::new(&set);
+        set
+    }
+}
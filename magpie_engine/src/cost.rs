@@ -0,0 +1,297 @@
+//! Parse cost text like `2 blood + 1 shattered ruby` or `3 Bones, 1 Emerald` into [`Costs`].
+//!
+//! Every source write its costs a bit differently so the words are configure with a
+//! [`CostVocab`]. A cost is a optional count then the cost name, the count default to 1 so
+//! `Orange` is the same as `1 Orange`. Anything that is not a number or a word, like `+` or `,`, is
+//! skip.
+//!
+//! # Examples
+//! ```
+//! use magpie_engine::prelude::*;
+//!
+//! let vocab: CostVocab<()> = CostVocab::default();
+//!
+//! let costs = parse_cost_string("2 blood + 1 bone", &vocab).unwrap().unwrap();
+//! assert_eq!((costs.blood, costs.bone), (2, 1));
+//!
+//! let costs = parse_cost_string("3 Bones, 1 Emerald", &vocab).unwrap().unwrap();
+//! assert_eq!(costs.bone, 3);
+//! assert_eq!(costs.mox, Mox::G);
+//! assert_eq!(costs.mox_count, None);
+//!
+//! // mox counts are only keep when a mox is need more than once
+//! let costs = parse_cost_string("2 ruby, sapphire", &vocab).unwrap().unwrap();
+//! assert_eq!(costs.mox, Mox::O | Mox::B);
+//! assert_eq!(costs.mox_count.unwrap().o, 2);
+//!
+//! // free cards have no costs
+//! assert_eq!(parse_cost_string("Free", &vocab), Ok(None));
+//! assert_eq!(parse_cost_string("", &vocab), Ok(None));
+//!
+//! assert_eq!(
+//!     parse_cost_string("2 gold", &vocab),
+//!     Err(CostError::UnknownCost("gold".to_owned()))
+//! );
+//! assert_eq!(
+//!     parse_cost_string("1 blood 2", &vocab),
+//!     Err(CostError::MissingCost("1 blood 2".to_owned()))
+//! );
+//! ```
+//!
+//! Multi word costs and [custom](CostUnit::Custom) costs can be add for extension costs, a
+//! [compact](CostVocab::compact) vocab read every letter as its own cost like `2b1o`.
+//! ```
+//! use magpie_engine::prelude::*;
+//!
+//! let vocab: CostVocab<isize> = CostVocab {
+//!     units: vec![
+//!         ("b".to_owned(), CostUnit::Blood),
+//!         ("o".to_owned(), CostUnit::Bone),
+//!         ("g".to_owned(), CostUnit::Custom(|c, n| c.extra += n)),
+//!     ],
+//!     compact: true,
+//! };
+//!
+//! let costs = parse_cost_string("2b1o3g", &vocab).unwrap().unwrap();
+//! assert_eq!((costs.blood, costs.bone, costs.extra), (2, 1, 3));
+//!
+//! let vocab: CostVocab<()> = CostVocab {
+//!     units: vec![("shattered ruby".to_owned(), CostUnit::Mox(Mox::O))],
+//!     compact: false,
+//! };
+//!
+//! assert!(parse_cost_string("1 shattered ruby", &vocab).unwrap().is_some());
+//! assert!(parse_cost_string("1 ruby", &vocab).is_err());
+//!
+//! // moxes from a custom cost are not count, they are need once like a plain mox
+//! let vocab: CostVocab<()> = CostVocab {
+//!     units: vec![("cracked ruby".to_owned(), CostUnit::Custom(|c, _| c.mox |= Mox::O))],
+//!     compact: false,
+//! };
+//!
+//! let costs = parse_cost_string("1 cracked ruby", &vocab).unwrap().unwrap();
+//! assert_eq!(costs.mox, Mox::O);
+//! assert_eq!(costs.mox_count, None);
+//! ```
+
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::{Costs, Mox, MoxCount};
+
+/// What a cost add to the card [`Costs`].
+#[derive(Debug, Clone)]
+pub enum CostUnit<C> {
+    /// Add to the blood cost.
+    Blood,
+    /// Add to the bone cost.
+    Bone,
+    /// Add to the energy cost.
+    Energy,
+    /// Add these moxes and count them in the [`MoxCount`].
+    Mox(Mox),
+    /// Custom cost, like the extension costs, with the count.
+    ///
+    /// While parsing [`Costs::mox_count`] hold the count of every [`CostUnit::Mox`] so far, it
+    /// is drop at the end unless one of them is need more than once. Moxes a custom cost add to
+    /// the flags without counting them are need once.
+    Custom(fn(&mut Costs<C>, isize)),
+}
+
+/// The cost words [`parse_cost_string`] understand.
+#[derive(Debug, Clone)]
+pub struct CostVocab<C> {
+    /// Cost name and what it cost, case insensitive.
+    ///
+    /// Names can be multiple words like `shattered ruby`, the longest name that match is use.
+    pub units: Vec<(String, CostUnit<C>)>,
+    /// Read every letter as its own cost name, like the `2b1o` short form.
+    pub compact: bool,
+}
+
+impl<C> Default for CostVocab<C> {
+    /// Blood, bone, energy and the gem names of the moxes with their plural.
+    fn default() -> Self {
+        let unit = |name: &str, unit| (name.to_owned(), unit);
+        CostVocab {
+            units: vec![
+                unit("blood", CostUnit::Blood),
+                unit("bone", CostUnit::Bone),
+                unit("bones", CostUnit::Bone),
+                unit("energy", CostUnit::Energy),
+                unit("ruby", CostUnit::Mox(Mox::O)),
+                unit("rubies", CostUnit::Mox(Mox::O)),
+                unit("emerald", CostUnit::Mox(Mox::G)),
+                unit("emeralds", CostUnit::Mox(Mox::G)),
+                unit("sapphire", CostUnit::Mox(Mox::B)),
+                unit("sapphires", CostUnit::Mox(Mox::B)),
+                unit("prism", CostUnit::Mox(Mox::Y)),
+                unit("prisms", CostUnit::Mox(Mox::Y)),
+                unit("garnet", CostUnit::Mox(Mox::R)),
+                unit("garnets", CostUnit::Mox(Mox::R)),
+                unit("topaz", CostUnit::Mox(Mox::E)),
+                unit("topazes", CostUnit::Mox(Mox::E)),
+                unit("amethyst", CostUnit::Mox(Mox::P)),
+                unit("amethysts", CostUnit::Mox(Mox::P)),
+                unit("onyx", CostUnit::Mox(Mox::K)),
+            ],
+            compact: false,
+        }
+    }
+}
+
+/// Error when parsing a cost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CostError {
+    /// The cost name is not in the [`CostVocab`].
+    UnknownCost(String),
+    /// A count is not follow by a cost name.
+    MissingCost(String),
+}
+
+impl Display for CostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CostError::UnknownCost(c) => write!(f, "unknown cost: {c}"),
+            CostError::MissingCost(c) => write!(f, "a count have no cost in: {c}"),
+        }
+    }
+}
+
+impl Error for CostError {}
+
+/// A number or word in a cost text.
+enum Token<'a> {
+    Num(isize),
+    Word(&'a str),
+}
+
+/// Split a cost text into numbers and words, skipping everything else.
+fn tokenize(text: &str, compact: bool) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let negative = c == '-' && chars.peek().is_some_and(|(_, n)| n.is_ascii_digit());
+
+        if c.is_ascii_digit() || negative {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, n)) = chars.peek().filter(|(_, n)| n.is_ascii_digit()) {
+                end = i + n.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Num(text[start..end].parse().unwrap_or(0)));
+        } else if c.is_alphabetic() {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, n)) = chars.peek().filter(|(_, n)| !compact && n.is_alphabetic()) {
+                end = i + n.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Word(&text[start..end]));
+        }
+    }
+
+    tokens
+}
+
+/// Parse a cost text with a [`CostVocab`], [`None`] if the text is empty or `free`.
+///
+/// See the [module](self) documentation for the format.
+pub fn parse_cost_string<C>(text: &str, vocab: &CostVocab<C>) -> Result<Option<Costs<C>>, CostError>
+where
+    C: Default + PartialEq,
+{
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("free") {
+        return Ok(None);
+    }
+
+    let tokens = tokenize(text, vocab.compact);
+    let mut costs = Costs::<C> {
+        mox_count: Some(MoxCount::default()),
+        ..Costs::default()
+    };
+    let mut count = None;
+    let mut i = 0;
+
+    while let Some(token) = tokens.get(i) {
+        let word = match token {
+            Token::Num(_) if count.is_some() => {
+                return Err(CostError::MissingCost(text.to_owned()))
+            }
+            Token::Num(n) => {
+                count = Some(*n);
+                i += 1;
+                continue;
+            }
+            Token::Word(w) => w,
+        };
+
+        // the longest name that match the next words
+        let (unit, len) = vocab
+            .units
+            .iter()
+            .filter_map(|(name, unit)| {
+                let len = name.split_whitespace().count();
+                let matched = name.split_whitespace().enumerate().all(|(j, n)| {
+                    matches!(tokens.get(i + j), Some(Token::Word(w)) if w.eq_ignore_ascii_case(n))
+                });
+                matched.then_some((unit, len))
+            })
+            .max_by_key(|(_, len)| *len)
+            .ok_or_else(|| CostError::UnknownCost((*word).to_owned()))?;
+
+        let n = count.take().unwrap_or(1);
+        match unit {
+            CostUnit::Blood => costs.blood += n,
+            CostUnit::Bone => costs.bone += n,
+            CostUnit::Energy => costs.energy += n,
+            CostUnit::Mox(mox) => {
+                costs.mox |= *mox;
+                add_mox_count(
+                    costs.mox_count.get_or_insert_with(MoxCount::default),
+                    *mox,
+                    n,
+                );
+            }
+            CostUnit::Custom(add) => add(&mut costs, n),
+        }
+
+        i += len;
+    }
+
+    if count.is_some() {
+        return Err(CostError::MissingCost(text.to_owned()));
+    }
+
+    // only keep the mox count when it say more than the mox flags, like a mox need twice
+    let twice = costs.mox_count.as_ref().is_some_and(|c| {
+        [c.o, c.g, c.b, c.y, c.r, c.e, c.p, c.k]
+            .iter()
+            .any(|&n| n > 1)
+    });
+    if !twice {
+        costs.mox_count = None;
+    }
+
+    Ok((costs != Costs::default()).then_some(costs))
+}
+
+/// Add a count to every mox in the flags.
+pub(crate) fn add_mox_count(mox_count: &mut MoxCount, mox: Mox, count: isize) {
+    let count = usize::try_from(count).unwrap_or(0);
+    for (flag, c) in [
+        (Mox::O, &mut mox_count.o),
+        (Mox::G, &mut mox_count.g),
+        (Mox::B, &mut mox_count.b),
+        (Mox::Y, &mut mox_count.y),
+        (Mox::R, &mut mox_count.r),
+        (Mox::E, &mut mox_count.e),
+        (Mox::P, &mut mox_count.p),
+        (Mox::K, &mut mox_count.k),
+    ] {
+        if mox.contains(flag) {
+            *c += count;
+        }
+    }
+}
@@ -2,8 +2,10 @@ use std::fmt::Debug;
 use std::fmt::Display;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::sync::Arc;
 
 use bitflags::bitflags;
+use serde::{Serialize, Serializer};
 
 use crate::SetCode;
 
@@ -12,7 +14,7 @@ macro_rules! card {
         /// Represent a card containing all the infomation on the cards.
         ///
         /// You can add extra infomation using the [`Card::extra`] field and the generic `E`
-        #[derive(Debug, Clone)]
+        #[derive(Debug, Clone, Serialize)]
         pub struct Card<E, C>
         where
             E: Clone,
@@ -80,6 +82,10 @@ card! {
     /// [`Bitflags`](https://docs.rs/bitflags/) so we can support more temple and make it easier to
     /// extend, if you need more than 16 temples, may god help you.
     temple: Temple,
+    /// Temples the [`Temple`] flags don't cover, like the new scrybes of a mod, by name.
+    ///
+    /// They are keep as text so every set can have its own without running out of bits.
+    other_temples: Vec<String>,
     /// The card tribes.
     tribes: Option<String>,
 
@@ -89,7 +95,10 @@ card! {
     health: isize,
 
     /// The sigils or abilities on the card.
-    sigils: Vec<String>,
+    ///
+    /// Sigil names are share with the set [`sigils_description`](crate::Set::sigils_description)
+    /// keys so cards with the same sigil don't each keep a copy of the name.
+    sigils: Vec<Arc<str>>,
 
     /// The card traits
     ///
@@ -105,6 +114,25 @@ card! {
     /// Usuall for tokens, evolution, etc.
     related: Vec<String>,
 
+    /// Lowercase copy of the card text use by the query filters.
+    ///
+    /// Call [`Card::reindex`] after changing the card name, description or tribes.
+    #[serde(skip)]
+    lower: LowerText,
+}
+
+/// Lowercase copy of a card text fields.
+///
+/// Text filters are case insensitive, lowercasing the text once when the set is load mean the
+/// filters don't have to do it for every card on every query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LowerText {
+    /// The lowercase name.
+    pub name: String,
+    /// The lowercase description.
+    pub description: String,
+    /// The lowercase tribes.
+    pub tribes: Option<String>,
 }
 
 impl<T, U> Hash for Card<T, U>
@@ -118,6 +146,62 @@ where
     }
 }
 
+impl<T, U> Card<T, U>
+where
+    T: Clone,
+    U: Clone + PartialEq,
+{
+    /// Get the stable id of this card.
+    ///
+    /// The id is a hash of the set code and the card name ignoring case, so it stay the same when
+    /// anything else about the card change like its portrait or stats. Unlike the [`Hash`] impl
+    /// this use FNV-1a so the id is the same between runs and builds and can be save.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    ///
+    /// let build = |code, name, portrait| -> Set<(), ()> {
+    ///     SetBuilder::new(code, "Test")
+    ///         .unwrap()
+    ///         .card(CardBuilder::new(name).portrait(portrait))
+    ///         .unwrap()
+    ///         .build()
+    /// };
+    ///
+    /// let old = build("std", "Stoat", "old.png");
+    /// let new = build("std", "STOAT", "new.png");
+    /// let ete = build("ete", "Stoat", "old.png");
+    ///
+    /// assert_eq!(old.cards[0].id(), new.cards[0].id());
+    /// assert_ne!(old.cards[0].id(), ete.cards[0].id());
+    /// ```
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+
+        self.set
+            .bytes()
+            .into_iter()
+            .chain([0])
+            .chain(self.name.to_lowercase().bytes())
+            .fold(OFFSET, |hash, b| (hash ^ u64::from(b)).wrapping_mul(PRIME))
+    }
+
+    /// Recompute the [`lower`](Card::lower) text of this card.
+    ///
+    /// Set fetched or build by this crate are already index, you only need this when changing a card
+    /// text after.
+    pub fn reindex(&mut self) {
+        self.lower = LowerText {
+            name: self.name.to_lowercase(),
+            description: self.description.to_lowercase(),
+            tribes: self.tribes.as_ref().map(|t| t.to_lowercase()),
+        };
+    }
+}
+
 /// Trait for a card to be upgradeable to another card with different generic.
 pub trait UpgradeCard<E, U>
 where
@@ -144,7 +228,7 @@ where
 }
 
 /// Rarities or tiers cards belong to
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub enum Rarity {
     /// Side deck rarity for card.
     ///
@@ -168,6 +252,10 @@ pub enum Rarity {
     ///
     /// This usually map to card that you can have only have 1 of this rarity per deck.
     UNIQUE,
+    /// Rarity that the other variants don't cover, like the ones some mods add.
+    ///
+    /// The value is the rarity name as the set write it.
+    Other(String),
 }
 
 impl Display for Rarity {
@@ -181,6 +269,7 @@ impl Display for Rarity {
                 Rarity::UNCOMMON => "uncommon",
                 Rarity::RARE => "rare",
                 Rarity::UNIQUE => "unique",
+                Rarity::Other(r) => r,
             }
         )
     }
@@ -205,30 +294,50 @@ bitflags! {
     }
 }
 
+/// The built in temples and their name.
+const BUILTIN_TEMPLES: [(Temple, &str); 6] = [
+    (Temple::BEAST, "beast"),
+    (Temple::UNDEAD, "undead"),
+    (Temple::TECH, "tech"),
+    (Temple::MAGICK, "magick"),
+    (Temple::FOOL, "fool"),
+    (Temple::ARTISTRY, "artistry"),
+];
+
+impl Temple {
+    /// The name of every temple in these flags.
+    fn names(self) -> Vec<&'static str> {
+        BUILTIN_TEMPLES
+            .iter()
+            .filter(|(t, _)| self.contains(*t))
+            .map(|(_, n)| *n)
+            .collect()
+    }
+}
+
 impl Display for Temple {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut out = vec![];
-
-        let flags = [
-            (Temple::BEAST, "beast"),
-            (Temple::UNDEAD, "undead"),
-            (Temple::TECH, "tech"),
-            (Temple::FOOL, "fool"),
-            (Temple::ARTISTRY, "artistry"),
-        ];
+        write!(f, "{}", self.names().join(" or "))
+    }
+}
 
-        for (f, v) in flags {
-            if self.contains(f) {
-                out.push(v);
-            }
-        }
+/// Serialize bit flags as the list of their flag names.
+fn serialize_flags<F, S>(flags: &F, serializer: S) -> Result<S::Ok, S::Error>
+where
+    F: bitflags::Flags,
+    S: Serializer,
+{
+    serializer.collect_seq(flags.iter_names().map(|(name, _)| name))
+}
 
-        write!(f, "{}", out.join(" or "))
+impl Serialize for Temple {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_flags(self, serializer)
     }
 }
 
 /// Enum for the diffrent attack type.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Attack {
     /// Numeric attack value.
     Num(isize),
@@ -238,8 +347,59 @@ pub enum Attack {
     Str(String),
 }
 
+impl Attack {
+    /// Format the attack for display with the icons of an [`EmojiTable`].
+    ///
+    /// Special attacks that the set store as a [`String`] are turn into their icon too if the name
+    /// is a known special attack, so every set render them the same way.
+    ///
+    /// ```
+    /// use magpie_engine::{Attack, EmojiTable, SpAtk};
+    ///
+    /// struct Table;
+    /// impl EmojiTable for Table {
+    ///     fn sp_atk(&self, sp: &SpAtk) -> String {
+    ///         format!(":{sp}:")
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(Attack::Num(2).to_display(&Table), "2");
+    /// assert_eq!(Attack::SpAtk(SpAtk::ANT).to_display(&Table), ":ant:");
+    /// assert_eq!(Attack::Str("Mirror".to_owned()).to_display(&Table), ":mirror:");
+    /// assert_eq!(Attack::Str("?".to_owned()).to_display(&Table), "?");
+    /// ```
+    #[must_use]
+    pub fn to_display(&self, table: &impl EmojiTable) -> String {
+        match self {
+            Attack::Num(a) => a.to_string(),
+            Attack::SpAtk(sp) => table.sp_atk(sp),
+            Attack::Str(s) => {
+                let sp = match s.to_lowercase().as_str() {
+                    "mox" => SpAtk::MOX,
+                    "green mox" | "green_mox" => SpAtk::GREEN_MOX,
+                    "mirror" => SpAtk::MIRROR,
+                    "ant" | "ants" => SpAtk::ANT,
+                    "bone" | "bones" => SpAtk::BONE,
+                    "bell" => SpAtk::BELL,
+                    "card" | "cards" | "hand" => SpAtk::CARD,
+                    _ => return s.to_owned(),
+                };
+                table.sp_atk(&sp)
+            }
+        }
+    }
+}
+
+/// Icons use to display values like [`Attack`].
+///
+/// The engine doesn't know how the icons look so the caller provide them.
+pub trait EmojiTable {
+    /// The icon of a special attack.
+    fn sp_atk(&self, sp: &SpAtk) -> String;
+}
+
 /// Special attack for cards.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 #[allow(non_camel_case_types)]
 pub enum SpAtk {
     /// Card that gain power from Mox.
@@ -304,8 +464,14 @@ bitflags! {
     }
 }
 
+impl Serialize for Mox {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_flags(self, serializer)
+    }
+}
+
 /// Component for when card cost multiple of 1 Mox color.
-#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize)]
 pub struct MoxCount {
     /// The Orange component.
     pub o: usize,
@@ -328,7 +494,7 @@ pub struct MoxCount {
 }
 
 /// Contain all the cost info.
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Clone, Debug, PartialEq, Default, Serialize)]
 pub struct Costs<E> {
     /// Other case where the card are not free.
     /// Blood cost for the card.
@@ -461,8 +627,14 @@ impl Display for TraitsFlag {
     }
 }
 
+impl Serialize for TraitsFlag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_flags(self, serializer)
+    }
+}
+
 /// Store both flag based traits and string based traits.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct Traits {
     /// Traits that are not flags so they are [`String`].
     ///
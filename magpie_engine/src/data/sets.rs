@@ -1,8 +1,21 @@
+use crate::builder::BuildError;
+use crate::cost::CostError;
+use crate::helper::similarity;
+use crate::query::text::TextIndex;
+use crate::Attack;
 use crate::Card;
+use crate::Rarity;
+use crate::Temple;
 use crate::UpgradeCard;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fmt::Display;
+use std::hash::BuildHasher;
+use std::sync::Arc;
 
 /// A 3 ascii characters set code for card and set.
 ///
@@ -90,6 +103,12 @@ impl Display for SetCode {
     }
 }
 
+impl Serialize for SetCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
 impl Debug for SetCode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.bytes())
@@ -100,7 +119,11 @@ impl Debug for SetCode {
 ///
 /// Sets are container for cards, they also carry a few other infomation like the sigils look up
 /// table and pools. Pools are pre-sorted cards into categories.
+///
+/// New fields can be add to sets so they can't be make with a struct literal outside this crate,
+/// use [`SetBuilder`](crate::builder::SetBuilder) instead.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct Set<E, C>
 where
     E: Clone,
@@ -118,7 +141,45 @@ where
     ///
     /// Set are require to include **every** sigil in this look up table. So you can safely get
     /// value from this table without worrying about [`None`].
-    pub sigils_description: HashMap<String, String>,
+    ///
+    /// The keys are also use to intern the card sigils, see [`Set::intern_sigil`].
+    pub sigils_description: HashMap<Arc<str>, String>,
+    /// The sigils category look up table for the set.
+    ///
+    /// Only some sources have sigil categories so unlike [`Set::sigils_description`] this can be
+    /// missing sigils, use [`Set::sigil`] to get everything about a sigil.
+    pub sigils_category: HashMap<String, String>,
+    /// The card pools of the set.
+    ///
+    /// Pools are the categories from the set source, like the tiers in Augmented, that don't fit
+    /// in the card fields. Sources without categories have no pools.
+    pub pools: HashMap<String, Vec<CardRef>>,
+    /// The full text index of the cards, build once when the set is load.
+    ///
+    /// Call [`Set::reindex`] after changing the cards so the index stay up to date.
+    pub text_index: TextIndex,
+    /// The problems the fetcher found while parsing the set, like a stat that is not a number or
+    /// a sigil that is not in the sigils description.
+    ///
+    /// These are report by [`Set::validate`] along with its own checks.
+    pub issues: Vec<SetIssue>,
+}
+
+/// Reference to a card in a set by its index in [`Set::cards`].
+pub type CardRef = usize;
+
+/// A sigil with all the info the set have on it.
+///
+/// Sigils are store in the [`Set`] look up tables, this is just a view into them so it is cheap to
+/// get one with [`Set::sigil`] or [`Set::sigils`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sigil<'a> {
+    /// The sigil name.
+    pub name: &'a str,
+    /// The sigil description.
+    pub text: &'a str,
+    /// The sigil category if the source have one.
+    pub category: Option<&'a str>,
 }
 
 impl<T, U> Set<T, U>
@@ -138,6 +199,677 @@ where
             name: self.name,
             cards: self.cards.into_iter().map(UpgradeCard::upgrade).collect(),
             sigils_description: self.sigils_description,
+            sigils_category: self.sigils_category,
+            pools: self.pools,
+            text_index: self.text_index,
+            issues: self.issues,
+        }
+    }
+
+    /// Get the cards in a pool, the pool name is case insensitive.
+    ///
+    /// Return a empty list if the set have no such pool.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    ///
+    /// let mut set: Set<(), ()> = SetBuilder::new("hom", "Homebrew")
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Stoat"))
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Bullfrog"))
+    ///     .unwrap()
+    ///     .build();
+    /// set.pools.insert("Starter".to_owned(), vec![1]);
+    ///
+    /// assert_eq!(set.pool("starter")[0].name, "Bullfrog");
+    /// assert!(set.pool("rare").is_empty());
+    /// ```
+    #[must_use]
+    pub fn pool(&self, name: &str) -> Vec<&Card<T, U>> {
+        self.pools
+            .iter()
+            .filter(|(pool, _)| pool.eq_ignore_ascii_case(name))
+            .flat_map(|(_, cards)| cards.iter().filter_map(|&i| self.cards.get(i)))
+            .collect()
+    }
+
+    /// Recompute the lowercase text of every card and the [`text_index`](Set::text_index), see
+    /// [`Card::reindex`].
+    pub fn reindex(&mut self) {
+        self.cards.iter_mut().for_each(Card::reindex);
+        self.text_index = TextIndex::new(self);
+    }
+
+    /// Pick a random card from this set that pass the filter.
+    ///
+    /// Return [`None`] if no card pass the filter.
+    pub fn random_card<R, F>(&self, rng: &mut R, filter: F) -> Option<&Card<T, U>>
+    where
+        R: Rng + ?Sized,
+        F: FnMut(&&Card<T, U>) -> bool,
+    {
+        self.cards.iter().filter(filter).choose(rng)
+    }
+    /// Get the statistics of this set.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    ///
+    /// let set: Set<(), ()> = SetBuilder::new("std", "Standard").unwrap().build();
+    ///
+    /// assert_eq!(set.stats().size, 0);
+    /// ```
+    #[must_use]
+    pub fn stats(&self) -> SetStats {
+        SetStats::from_cards(&self.cards)
+    }
+
+    /// Check the cards in this set for data problems.
+    ///
+    /// Return every issue found, an empty [`Vec`] mean the set look fine. This start with the
+    /// [`issues`](Set::issues) the fetcher found, fetchers replace sigils that are not in the
+    /// sigils description with a placeholder so the original sigil name is only in those. The
+    /// placeholder is still reported for the cards the fetcher didn't report.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    ///
+    /// let set: Set<(), ()> = SetBuilder::new("std", "Standard").unwrap().build();
+    ///
+    /// assert!(set.validate().is_empty());
+    /// ```
+    #[must_use]
+    pub fn validate(&self) -> Vec<SetIssue> {
+        let mut issues = self.issues.clone();
+        let mut names: HashMap<&str, usize> = HashMap::new();
+
+        for card in &self.cards {
+            *names.entry(card.name.as_str()).or_default() += 1;
+
+            let reported = self.issues.iter().any(
+                |i| matches!(i, SetIssue::MissingSigil { card: name, .. } if *name == card.name),
+            );
+            for sigil in &card.sigils {
+                let placeholder = **sigil == *UNDEFINED_SIGIL;
+                if (placeholder && !reported)
+                    || (!placeholder && !self.sigils_description.contains_key(sigil))
+                {
+                    issues.push(SetIssue::MissingSigil {
+                        card: card.name.clone(),
+                        sigil: sigil.to_string(),
+                    });
+                }
+            }
+
+            if card.portrait.trim().is_empty() {
+                issues.push(SetIssue::EmptyPortrait {
+                    card: card.name.clone(),
+                });
+            }
+
+            match &card.attack {
+                Attack::Str(value) if value.trim().is_empty() => {
+                    issues.push(SetIssue::UnparsableStat {
+                        card: card.name.clone(),
+                        stat: "attack",
+                        value: value.clone(),
+                    });
+                }
+                Attack::Num(value) if *value < 0 => issues.push(SetIssue::UnparsableStat {
+                    card: card.name.clone(),
+                    stat: "attack",
+                    value: value.to_string(),
+                }),
+                _ => (),
+            }
+            if card.health < 0 {
+                issues.push(SetIssue::UnparsableStat {
+                    card: card.name.clone(),
+                    stat: "health",
+                    value: card.health.to_string(),
+                });
+            }
+        }
+
+        let mut duplicates = names
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .collect::<Vec<_>>();
+        duplicates.sort_unstable();
+        issues.extend(
+            duplicates
+                .into_iter()
+                .map(|(name, count)| SetIssue::DuplicateName {
+                    name: name.to_owned(),
+                    count,
+                }),
+        );
+
+        issues
+    }
+
+    /// Get a card in this set by name, ignoring case.
+    #[must_use]
+    pub fn get_card(&self, name: &str) -> Option<&Card<T, U>> {
+        let lower = name.to_lowercase();
+        self.cards.iter().find(|c| c.lower.name == lower)
+    }
+
+    /// Get a sigil in this set by name.
+    ///
+    /// Return [`None`] if the sigil have no description.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    ///
+    /// let set: Set<(), ()> = SetBuilder::new("std", "Standard")
+    ///     .unwrap()
+    ///     .sigil("Airborne", "This card attacks the opponent directly.")
+    ///     .sigil_category("Airborne", "Movement")
+    ///     .build();
+    ///
+    /// let sigil = set.sigil("Airborne").unwrap();
+    /// assert_eq!(sigil.category, Some("Movement"));
+    /// assert!(set.sigil("Nope").is_none());
+    /// ```
+    #[must_use]
+    pub fn sigil(&self, name: &str) -> Option<Sigil<'_>> {
+        self.sigils_description
+            .get_key_value(name)
+            .map(|(name, text)| Sigil {
+                name,
+                text,
+                category: self.sigils_category.get(&**name).map(String::as_str),
+            })
+    }
+
+    /// Find a sigil in this set, allowing small differences in the name.
+    ///
+    /// Return the sigil name and description, see [`match_sigil`] for how the name is match.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    ///
+    /// let set: Set<(), ()> = SetBuilder::new("std", "Standard")
+    ///     .unwrap()
+    ///     .sigil("Airborne", "This card attacks the opponent directly.")
+    ///     .build();
+    ///
+    /// assert_eq!(set.find_sigil("air borne").map(|(name, _)| name), Some("Airborne"));
+    /// assert!(set.find_sigil("Bifurcated Strike").is_none());
+    /// ```
+    #[must_use]
+    pub fn find_sigil(&self, name: &str) -> Option<(&str, &str)> {
+        match_sigil(&self.sigils_description, name).map(|(name, text)| (&**name, text.as_str()))
+    }
+
+    /// Get the shared name of a sigil in this set.
+    ///
+    /// Card sigils should use the name from here so every card with the same sigil point to the
+    /// same string. Return [`None`] if the sigil have no description.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    /// use std::sync::Arc;
+    ///
+    /// let set: Set<(), ()> = SetBuilder::new("hom", "Homebrew")
+    ///     .unwrap()
+    ///     .sigil("Airborne", "This card attacks the opponent directly.")
+    ///     .card(CardBuilder::new("Bat").sigils(vec!["Airborne".to_string()]))
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Raven").sigils(vec!["Airborne".to_string()]))
+    ///     .unwrap()
+    ///     .build();
+    ///
+    /// let airborne = set.intern_sigil("Airborne").unwrap();
+    /// assert!(Arc::ptr_eq(&airborne, &set.cards[0].sigils[0]));
+    /// assert!(Arc::ptr_eq(&set.cards[0].sigils[0], &set.cards[1].sigils[0]));
+    /// ```
+    #[must_use]
+    pub fn intern_sigil(&self, name: &str) -> Option<Arc<str>> {
+        self.sigils_description
+            .get_key_value(name)
+            .map(|(name, _)| Arc::clone(name))
+    }
+
+    /// Iterate over every sigil in this set.
+    pub fn sigils(&self) -> impl Iterator<Item = Sigil<'_>> {
+        self.sigils_description.iter().map(|(name, text)| Sigil {
+            name,
+            text,
+            category: self.sigils_category.get(&**name).map(String::as_str),
+        })
+    }
+
+    /// Get the name of every sigil in a category, ignoring case.
+    #[must_use]
+    pub fn sigils_in_category(&self, category: &str) -> Vec<&str> {
+        let lower = category.to_lowercase();
+        self.sigils()
+            .filter(|s| s.category.is_some_and(|c| c.to_lowercase() == lower))
+            .map(|s| s.name)
+            .collect()
+    }
+
+    /// Get the cards a card is related to, like its tokens or evolutions.
+    ///
+    /// [`Card::related`] only have names, this look them up in the set ignoring case. Names that
+    /// are not in the set are skip.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    ///
+    /// let set: Set<(), ()> = SetBuilder::new("std", "Standard")
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Elk Fawn").related(vec![String::from("elk")]))
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Elk").related(vec![String::from("Moose Buck")]))
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Moose Buck"))
+    ///     .unwrap()
+    ///     .build();
+    ///
+    /// let fawn = set.get_card("Elk Fawn").unwrap();
+    /// assert_eq!(set.related_cards(fawn)[0].name, "Elk");
+    ///
+    /// assert_eq!(set.related_by("Elk")[0].name, "Elk Fawn");
+    ///
+    /// let chain = set.related_chain(fawn);
+    /// assert_eq!(
+    ///     chain.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+    ///     ["Elk", "Moose Buck"]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn related_cards(&self, card: &Card<T, U>) -> Vec<&Card<T, U>> {
+        card.related
+            .iter()
+            .filter_map(|name| self.get_card(name))
+            .collect()
+    }
+
+    /// Get the cards that are related to a card, like the cards that create a token.
+    ///
+    /// This is the reverse of [`Set::related_cards`], the name is match ignoring case.
+    #[must_use]
+    pub fn related_by(&self, name: &str) -> Vec<&Card<T, U>> {
+        let lower = name.to_lowercase();
+        self.cards
+            .iter()
+            .filter(|c| {
+                c.related
+                    .iter()
+                    .any(|r| r.chars().flat_map(char::to_lowercase).eq(lower.chars()))
+            })
+            .collect()
+    }
+
+    /// Get every card that can be reach by following the related cards, like a whole evolution
+    /// chain.
+    ///
+    /// The cards are in the order they are reach and each card only show up once, so loops are
+    /// fine. The starting card is not include unless a loop lead back to it.
+    #[must_use]
+    pub fn related_chain(&self, card: &Card<T, U>) -> Vec<&Card<T, U>> {
+        let mut chain: Vec<&Card<T, U>> = vec![];
+        let mut next = self.related_cards(card);
+        next.reverse();
+
+        while let Some(c) = next.pop() {
+            if chain.iter().any(|o| std::ptr::eq(*o, c)) {
+                continue;
+            }
+            chain.push(c);
+
+            let mut related = self.related_cards(c);
+            related.reverse();
+            next.extend(related);
         }
+
+        chain
+    }
+}
+
+/// Find every printing of a card in multiple sets.
+///
+/// Cards are match by name ignoring case, so the same card in std and ete are both return. The
+/// printings are in the same order as the sets.
+///
+/// # Examples
+/// ```
+/// use magpie_engine::prelude::*;
+///
+/// let std: Set<(), ()> = SetBuilder::new("std", "Standard")
+///     .unwrap()
+///     .card(CardBuilder::new("Stoat"))
+///     .unwrap()
+///     .build();
+/// let ete: Set<(), ()> = SetBuilder::new("ete", "Eternal")
+///     .unwrap()
+///     .card(CardBuilder::new("Stoat"))
+///     .unwrap()
+///     .build();
+///
+/// let found = printings([&std, &ete], "stoat");
+/// assert_eq!(found.len(), 2);
+/// assert_eq!(found[1].set.code(), "ete");
+/// ```
+pub fn printings<'a, E, C, I>(sets: I, name: &str) -> Vec<&'a Card<E, C>>
+where
+    E: Clone + 'a,
+    C: Clone + PartialEq + 'a,
+    I: IntoIterator<Item = &'a Set<E, C>>,
+{
+    sets.into_iter().filter_map(|s| s.get_card(name)).collect()
+}
+
+/// How similar a sigil name need to be to a name in the sigils description for [`match_sigil`].
+pub const SIGIL_MATCH_THRESHOLD: f32 = 0.9;
+
+/// Find a sigil in a sigils description, allowing small differences in the name.
+///
+/// The exact name is try first, then the name ignoring case, spaces and punctuation, then the
+/// similar name if it is at least [`SIGIL_MATCH_THRESHOLD`] similar. The similar name must be the
+/// only one above the threshold and must end with the same number, so `Sniper 2` never match
+/// `Sniper 3`. The fetchers use this so a sigil spell a bit differently in the cards and in the
+/// sigils still match.
+///
+/// # Examples
+/// ```
+/// use magpie_engine::match_sigil;
+/// use std::{collections::HashMap, sync::Arc};
+///
+/// let mut sigils: HashMap<Arc<str>, String> = HashMap::new();
+/// sigils.insert(Arc::from("Touch of Death"), String::from("Kill the opposing card."));
+///
+/// assert_eq!(&**match_sigil(&sigils, "Touch of Death").unwrap().0, "Touch of Death");
+/// assert_eq!(&**match_sigil(&sigils, "touch-of-death").unwrap().0, "Touch of Death");
+/// assert_eq!(&**match_sigil(&sigils, "Touch of Deth").unwrap().0, "Touch of Death");
+/// assert!(match_sigil(&sigils, "Touch of Dread").is_none());
+///
+/// sigils.insert(Arc::from("Sniper 2"), String::from("Choose where to attack twice."));
+/// assert!(match_sigil(&sigils, "Sniper 3").is_none());
+/// ```
+#[must_use]
+pub fn match_sigil<'a, S>(
+    sigils: &'a HashMap<Arc<str>, String, S>,
+    name: &str,
+) -> Option<(&'a Arc<str>, &'a String)>
+where
+    S: BuildHasher,
+{
+    if let Some(found) = sigils.get_key_value(name) {
+        return Some(found);
+    }
+
+    let name = normalize_sigil(name);
+    let candidates = || {
+        sigils
+            .iter()
+            .filter(|(s, _)| ***s != *UNDEFINED_SIGIL)
+            .map(|entry| (normalize_sigil(entry.0), entry))
+    };
+
+    if let Some((_, entry)) = candidates().find(|(s, _)| *s == name) {
+        return Some(entry);
+    }
+
+    let trailing_number = |s: &str| s.chars().rev().take_while(char::is_ascii_digit).count();
+    let number = &name[name.len() - trailing_number(&name)..];
+    let mut found = candidates()
+        .filter(|(s, _)| s.ends_with(number) && trailing_number(s) == number.len())
+        .filter(|(s, _)| similarity(s, &name) >= SIGIL_MATCH_THRESHOLD)
+        .map(|(_, entry)| entry);
+
+    match (found.next(), found.next()) {
+        (Some(entry), None) => Some(entry),
+        _ => None,
+    }
+}
+
+/// Lowercase a sigil name and remove everything that is not a letter or a digit.
+pub(crate) fn normalize_sigil(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Placeholder sigil name the fetchers use when a sigil is not in the sigils description.
+pub(crate) const UNDEFINED_SIGIL: &str = "UNDEFINEDED SIGILS";
+
+/// Description of the [`UNDEFINED_SIGIL`] placeholder.
+pub(crate) const UNDEFINED_SIGIL_DESCRIPTION: &str = "THIS SIGIL IS NOT DEFINED BY THE SET";
+
+/// A data problem in a set, see [`Set::validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SetIssue {
+    /// A card use a sigil that is not in the sigils description.
+    MissingSigil {
+        /// The card name.
+        card: String,
+        /// The sigil name.
+        sigil: String,
+    },
+    /// A card have no portrait.
+    EmptyPortrait {
+        /// The card name.
+        card: String,
+    },
+    /// A card stat is empty or not a valid value.
+    UnparsableStat {
+        /// The card name.
+        card: String,
+        /// Which stat, `attack` or `health`.
+        stat: &'static str,
+        /// The invalid value.
+        value: String,
+    },
+    /// Multiple cards have the same name, searches will only ever find one of them.
+    DuplicateName {
+        /// The card name.
+        name: String,
+        /// How many cards have this name.
+        count: usize,
+    },
+    /// A card sigil is not in the sigils description but was match to a similar name.
+    FuzzySigil {
+        /// The card name.
+        card: String,
+        /// The sigil name in the card.
+        sigil: String,
+        /// The sigil name it was match to.
+        matched: String,
+    },
+    /// A card cost could not be parse, the card is keep as free.
+    InvalidCost {
+        /// The card name.
+        card: String,
+        /// Why the cost is invalid.
+        error: CostError,
+    },
+    /// A card could not be build and was left out of the set.
+    InvalidCard {
+        /// The card name.
+        card: String,
+        /// Why the card is invalid.
+        error: BuildError,
+    },
+}
+
+impl SetIssue {
+    /// Short name of the kind of issue, useful to group issues together.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SetIssue::MissingSigil { .. } => "Missing sigil",
+            SetIssue::EmptyPortrait { .. } => "Empty portrait",
+            SetIssue::UnparsableStat { .. } => "Unparsable stat",
+            SetIssue::DuplicateName { .. } => "Duplicate name",
+            SetIssue::FuzzySigil { .. } => "Fuzzy sigil",
+            SetIssue::InvalidCost { .. } => "Invalid cost",
+            SetIssue::InvalidCard { .. } => "Invalid card",
+        }
+    }
+}
+
+impl Display for SetIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetIssue::MissingSigil { card, sigil } => {
+                write!(f, "{card} use sigil `{sigil}` that have no description")
+            }
+            SetIssue::EmptyPortrait { card } => write!(f, "{card} have no portrait"),
+            SetIssue::UnparsableStat { card, stat, value } => {
+                write!(f, "{card} have an invalid {stat} `{value}`")
+            }
+            SetIssue::DuplicateName { name, count } => {
+                write!(f, "{count} cards are named {name}")
+            }
+            SetIssue::FuzzySigil {
+                card,
+                sigil,
+                matched,
+            } => write!(f, "{card} use sigil `{sigil}` that is match to `{matched}`"),
+            SetIssue::InvalidCost { card, error } => write!(f, "{card} is free: {error}"),
+            SetIssue::InvalidCard { card, error } => write!(f, "{card} is left out: {error}"),
+        }
+    }
+}
+
+/// Statistics of a set, see [`Set::stats`].
+#[derive(Clone, Debug, Default)]
+pub struct SetStats {
+    /// The total amount of cards.
+    pub size: usize,
+    /// How many cards are in each rarity, from side to unique.
+    pub rarities: Vec<(Rarity, usize)>,
+    /// How many cards belong to each temple.
+    ///
+    /// Card with multiple temples are count in every one of them, including their
+    /// [other temples](SetStats::other_temples).
+    pub temples: Vec<(Temple, usize)>,
+    /// How many cards belong to each temple that is not a [`Temple`] flag, see
+    /// [`Card::other_temples`].
+    ///
+    /// Temples are in the order they are first found and their names are match ignoring case.
+    pub other_temples: Vec<(String, usize)>,
+    /// How many cards cost each amount of blood.
+    pub blood_curve: BTreeMap<isize, usize>,
+    /// How many cards cost each amount of bone.
+    pub bone_curve: BTreeMap<isize, usize>,
+    /// How many cards cost each amount of energy.
+    pub energy_curve: BTreeMap<isize, usize>,
+    /// How many cards cost mox.
+    pub mox: usize,
+    /// How many cards are free.
+    pub free: usize,
+    /// The average attack of cards with a numeric attack.
+    pub average_attack: f32,
+    /// The average health of every card.
+    pub average_health: f32,
+}
+
+impl SetStats {
+    /// Get the statistics of any cards, like the result of a query.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    ///
+    /// let cards: Vec<Card<(), ()>> = vec![];
+    /// let stats = SetStats::from_cards(&cards);
+    ///
+    /// assert_eq!(stats.size, 0);
+    /// assert_eq!(stats.free, 0);
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_cards<'a, T, U>(cards: impl IntoIterator<Item = &'a Card<T, U>>) -> Self
+    where
+        T: Clone + 'a,
+        U: Clone + PartialEq + 'a,
+    {
+        let mut stats = SetStats {
+            rarities: [
+                Rarity::SIDE,
+                Rarity::COMMON,
+                Rarity::UNCOMMON,
+                Rarity::RARE,
+                Rarity::UNIQUE,
+            ]
+            .map(|r| (r, 0))
+            .to_vec(),
+            temples: Temple::all().iter().map(|t| (t, 0)).collect(),
+            ..Default::default()
+        };
+
+        let mut attack = 0;
+        let mut numeric = 0;
+        let mut health = 0;
+
+        for card in cards {
+            stats.size += 1;
+            match stats.rarities.iter_mut().find(|(r, _)| *r == card.rarity) {
+                Some((_, count)) => *count += 1,
+                None => stats.rarities.push((card.rarity.clone(), 1)),
+            }
+            for (temple, count) in &mut stats.temples {
+                if card.temple.contains(*temple) {
+                    *count += 1;
+                }
+            }
+            for temple in &card.other_temples {
+                match stats
+                    .other_temples
+                    .iter_mut()
+                    .find(|(t, _)| t.eq_ignore_ascii_case(temple))
+                {
+                    Some((_, count)) => *count += 1,
+                    None => stats.other_temples.push((temple.clone(), 1)),
+                }
+            }
+
+            match &card.costs {
+                None => stats.free += 1,
+                Some(costs) => {
+                    if costs.blood != 0 {
+                        *stats.blood_curve.entry(costs.blood).or_default() += 1;
+                    }
+                    if costs.bone != 0 {
+                        *stats.bone_curve.entry(costs.bone).or_default() += 1;
+                    }
+                    if costs.energy != 0 {
+                        *stats.energy_curve.entry(costs.energy).or_default() += 1;
+                    }
+                    if !costs.mox.is_empty() {
+                        stats.mox += 1;
+                    }
+                }
+            }
+
+            if let Attack::Num(a) = card.attack {
+                attack += a;
+                numeric += 1;
+            }
+            health += card.health;
+        }
+
+        if numeric > 0 {
+            stats.average_attack = attack as f32 / numeric as f32;
+        }
+        if stats.size > 0 {
+            stats.average_health = health as f32 / stats.size as f32;
+        }
+
+        stats
     }
 }
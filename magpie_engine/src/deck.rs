@@ -0,0 +1,533 @@
+//! Implementation for parsing decks and resolving them against a set.
+//!
+//! A deck start out as a [`DeckList`], a list of card names and how many copies of each, parsed
+//! from either a IMF exported deck json or a plain text deck list like the one Augmented use. The
+//! list is then resolved against a [`Set`] to get a [`Deck`] that you can compute stats on or
+//! validate against a [`Ruleset`].
+//!
+//! # Examples
+//!
+//! ```
+//! use magpie_engine::prelude::*;
+//!
+//! let list = DeckList::parse(r#"{"cards": ["Stoat", "Stoat", "Wolf"], "side_deck": "Squirrel"}"#)
+//!     .unwrap();
+//!
+//! assert_eq!(list.cards, vec![("Stoat".to_string(), 2), ("Wolf".to_string(), 1)]);
+//! assert_eq!(list.side_deck, Some("Squirrel".to_string()));
+//!
+//! let list = DeckList::parse("2x Stoat\n1 Wolf\nBullfrog").unwrap();
+//!
+//! assert_eq!(list.size(), 4);
+//! ```
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Attack, Card, Rarity, Set, TraitsFlag};
+
+/// Error when parsing a deck.
+#[derive(Debug)]
+pub enum DeckError {
+    /// The deck json is invalid.
+    InvalidJson(serde_json::Error),
+    /// A line in the deck list is invalid.
+    InvalidLine(String),
+    /// The deck have no cards.
+    Empty,
+}
+
+impl Display for DeckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeckError::InvalidJson(e) => write!(f, "invalid deck json: {e}"),
+            DeckError::InvalidLine(l) => write!(f, "invalid deck line: {l}"),
+            DeckError::Empty => write!(f, "deck have no cards"),
+        }
+    }
+}
+
+impl Error for DeckError {}
+
+/// A list of card names and copies count that is not yet resolved against any set.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DeckList {
+    /// The card names and how many copies of them, in the order they first appear.
+    pub cards: Vec<(String, usize)>,
+    /// The side deck of the deck if the format have one.
+    pub side_deck: Option<String>,
+}
+
+impl DeckList {
+    /// Parse a deck code, detecting if it is a IMF deck json or a plain text deck list.
+    pub fn parse(code: &str) -> Result<Self, DeckError> {
+        if code.trim_start().starts_with('{') {
+            Self::from_imf_json(code)
+        } else {
+            Self::from_text(code)
+        }
+    }
+
+    /// Parse a IMF exported deck json.
+    pub fn from_imf_json(json: &str) -> Result<Self, DeckError> {
+        let deck: ImfDeck = serde_json::from_str(json).map_err(DeckError::InvalidJson)?;
+
+        let mut list = DeckList {
+            cards: vec![],
+            side_deck: deck.side_deck.filter(|s| !s.is_empty()),
+        };
+
+        for c in deck.cards {
+            list.add(c, 1);
+        }
+
+        list.check_empty()
+    }
+
+    /// Parse a plain text deck list.
+    ///
+    /// Each line or comma separated entry is a card with an optional count in front of it like
+    /// `2x Stoat`, `2 Stoat` or just `Stoat`. A line starting with `side:` set the side deck.
+    pub fn from_text(text: &str) -> Result<Self, DeckError> {
+        let mut list = DeckList::default();
+
+        for entry in text.lines().flat_map(|l| l.split(',')).map(str::trim) {
+            if entry.is_empty() || entry.starts_with("//") {
+                continue;
+            }
+
+            if let Some(side) = entry
+                .strip_prefix("side:")
+                .or_else(|| entry.strip_prefix("Side:"))
+            {
+                list.side_deck = Some(side.trim().to_owned());
+                continue;
+            }
+
+            let (count, name) = match entry.split_once(' ') {
+                Some((count, name)) => match count.trim_end_matches(['x', 'X']).parse::<usize>() {
+                    Ok(c) => (c, name.trim()),
+                    Err(_) => (1, entry),
+                },
+                None => (1, entry),
+            };
+
+            if name.is_empty() || count == 0 {
+                return Err(DeckError::InvalidLine(entry.to_owned()));
+            }
+
+            list.add(name.to_owned(), count);
+        }
+
+        list.check_empty()
+    }
+
+    /// The total amount of cards in this list, not counting the side deck.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.cards.iter().map(|(_, c)| c).sum()
+    }
+
+    /// Resolve the card names against a set.
+    ///
+    /// Names are match case insensitively, names that cannot be found in the set are put in
+    /// [`Deck::missing`].
+    #[must_use]
+    pub fn resolve<'a, E, C>(&self, set: &'a Set<E, C>) -> Deck<'a, E, C>
+    where
+        E: Clone,
+        C: Clone + PartialEq,
+    {
+        let mut deck = Deck {
+            cards: vec![],
+            side_deck: self.side_deck.clone(),
+            missing: vec![],
+        };
+
+        for (name, count) in &self.cards {
+            let lower = name.to_lowercase();
+            match set.cards.iter().find(|c| c.lower.name == lower) {
+                Some(card) => deck.cards.push((card, *count)),
+                None => deck.missing.push((name.clone(), *count)),
+            }
+        }
+
+        deck
+    }
+
+    fn add(&mut self, name: String, count: usize) {
+        if let Some((_, c)) = self.cards.iter_mut().find(|(n, _)| *n == name) {
+            *c += count;
+        } else {
+            self.cards.push((name, count));
+        }
+    }
+
+    fn check_empty(self) -> Result<Self, DeckError> {
+        if self.cards.is_empty() {
+            Err(DeckError::Empty)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// A deck that have been resolved against a set.
+#[derive(Debug, Clone)]
+pub struct Deck<'a, E, C>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    /// The cards in the deck and how many copies of them.
+    pub cards: Vec<(&'a Card<E, C>, usize)>,
+    /// The side deck of the deck if the format have one.
+    pub side_deck: Option<String>,
+    /// Card names that cannot be found in the set and how many copies of them.
+    pub missing: Vec<(String, usize)>,
+}
+
+impl<E, C> Deck<'_, E, C>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    /// The total amount of resolved cards in this deck.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.cards.iter().map(|(_, c)| c).sum()
+    }
+
+    /// Compute the stats of this deck.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+    pub fn stats(&self) -> DeckStats {
+        let mut stats = DeckStats {
+            size: self.size(),
+            ..Default::default()
+        };
+
+        let mut attack = 0;
+        let mut numeric = 0;
+        let mut health = 0;
+
+        for (card, count) in &self.cards {
+            let count = *count;
+
+            match &card.costs {
+                None => stats.free += count,
+                Some(costs) => {
+                    if costs.blood != 0 {
+                        *stats.blood_curve.entry(costs.blood).or_default() += count;
+                    }
+                    if costs.bone != 0 {
+                        *stats.bone_curve.entry(costs.bone).or_default() += count;
+                    }
+                    if costs.energy != 0 {
+                        *stats.energy_curve.entry(costs.energy).or_default() += count;
+                    }
+                    if !costs.mox.is_empty() {
+                        stats.mox += count;
+                    }
+                }
+            }
+
+            if let Attack::Num(a) = card.attack {
+                attack += a * count as isize;
+                numeric += count;
+            }
+            health += card.health * count as isize;
+        }
+
+        if numeric > 0 {
+            stats.average_attack = attack as f32 / numeric as f32;
+        }
+        if stats.size > 0 {
+            stats.average_health = health as f32 / stats.size as f32;
+        }
+
+        stats
+    }
+
+    /// Check this deck against a ruleset.
+    ///
+    /// The side deck is look up in `set` since it is not part of [`Deck::cards`]. Return every
+    /// rule this deck break, an empty [`Vec`] mean the deck is legal.
+    #[must_use]
+    pub fn validate(&self, set: &Set<E, C>, ruleset: &Ruleset) -> Vec<Violation> {
+        let mut out = vec![];
+
+        let size = self.size();
+        if size < ruleset.min_size || ruleset.max_size.is_some_and(|m| size > m) {
+            out.push(Violation::Size {
+                size,
+                min: ruleset.min_size,
+                max: ruleset.max_size,
+            });
+        }
+
+        let mut unique = 0;
+        for (card, count) in &self.cards {
+            let count = *count;
+
+            if card
+                .traits
+                .as_ref()
+                .is_some_and(|t| t.flags.contains(TraitsFlag::BAN))
+                || ruleset.is_banned(&card.name)
+            {
+                out.push(Violation::Banned(card.name.clone()));
+            }
+
+            if card.rarity == Rarity::SIDE {
+                out.push(Violation::SideInMain(card.name.clone()));
+                continue;
+            }
+
+            if card.rarity == Rarity::UNIQUE {
+                unique += count;
+            }
+
+            if let Some(max) = ruleset.copies(&card.rarity) {
+                if count > max {
+                    out.push(Violation::Copies {
+                        name: card.name.clone(),
+                        rarity: card.rarity.clone(),
+                        count,
+                        max,
+                    });
+                }
+            }
+        }
+
+        if let Some(max) = ruleset.unique {
+            if unique > max {
+                out.push(Violation::Unique { count: unique, max });
+            }
+        }
+
+        match &self.side_deck {
+            None if ruleset.side_deck => out.push(Violation::NoSideDeck),
+            None => (),
+            Some(name) => {
+                let lower = name.to_lowercase();
+                match set.cards.iter().find(|c| c.lower.name == lower) {
+                    None => out.push(Violation::InvalidSideDeck(name.clone())),
+                    Some(card) if card.rarity != Rarity::SIDE => {
+                        out.push(Violation::InvalidSideDeck(card.name.clone()));
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Deck building rules to validate a [`Deck`] against.
+///
+/// Copy limits are per card, [`None`] mean there are no limit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ruleset {
+    /// The minimum amount of cards in a deck.
+    pub min_size: usize,
+    /// The maximum amount of cards in a deck.
+    pub max_size: Option<usize>,
+    /// The maximum copies of a common card.
+    pub common: Option<usize>,
+    /// The maximum copies of a uncommon card.
+    pub uncommon: Option<usize>,
+    /// The maximum copies of a rare card.
+    pub rare: Option<usize>,
+    /// The maximum amount of unique cards in total.
+    pub unique: Option<usize>,
+    /// If the deck must have a side deck.
+    pub side_deck: bool,
+    /// Name of cards that are banned on top of cards with [`TraitsFlag::BAN`].
+    pub banned: Vec<String>,
+}
+
+impl Default for Ruleset {
+    /// The default ruleset following what each [`Rarity`] usually mean.
+    fn default() -> Self {
+        Ruleset {
+            min_size: 1,
+            max_size: None,
+            common: None,
+            uncommon: None,
+            rare: Some(1),
+            unique: Some(1),
+            side_deck: false,
+            banned: vec![],
+        }
+    }
+}
+
+impl Ruleset {
+    /// Check if a card name is in [`Ruleset::banned`], ignoring case.
+    #[must_use]
+    pub fn is_banned(&self, name: &str) -> bool {
+        self.banned.iter().any(|b| b.eq_ignore_ascii_case(name))
+    }
+
+    /// The maximum copies of a card with this rarity.
+    #[must_use]
+    pub fn copies(&self, rarity: &Rarity) -> Option<usize> {
+        match rarity {
+            Rarity::SIDE => Some(0),
+            Rarity::COMMON => self.common,
+            Rarity::UNCOMMON => self.uncommon,
+            Rarity::RARE => self.rare,
+            // unique are limited by total instead of per card but you can't have more unique of
+            // one card than the total
+            Rarity::UNIQUE => self.unique,
+            // rulesets don't know about custom rarities
+            Rarity::Other(_) => None,
+        }
+    }
+}
+
+/// The rule categories that a [`Violation`] belong to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// Deck size rule.
+    Size,
+    /// Copies limit rule, including the unique limit.
+    Copies,
+    /// Banned cards rule.
+    Ban,
+    /// Side deck rule.
+    SideDeck,
+}
+
+impl Rule {
+    /// All the rule categories in display order.
+    pub const ALL: [Rule; 4] = [Rule::Size, Rule::Copies, Rule::Ban, Rule::SideDeck];
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Rule::Size => "Deck Size",
+                Rule::Copies => "Copies",
+                Rule::Ban => "Banned Cards",
+                Rule::SideDeck => "Side Deck",
+            }
+        )
+    }
+}
+
+/// A broken rule found by [`Deck::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// The deck have too few or too many cards.
+    Size {
+        /// The deck size.
+        size: usize,
+        /// The minimum size.
+        min: usize,
+        /// The maximum size.
+        max: Option<usize>,
+    },
+    /// The deck have too many copies of a card.
+    Copies {
+        /// The card name.
+        name: String,
+        /// The card rarity.
+        rarity: Rarity,
+        /// How many copies are in the deck.
+        count: usize,
+        /// The maximum copies allowed.
+        max: usize,
+    },
+    /// The deck have too many unique cards.
+    Unique {
+        /// How many unique cards are in the deck.
+        count: usize,
+        /// The maximum unique cards allowed.
+        max: usize,
+    },
+    /// The card is banned.
+    Banned(String),
+    /// A side deck card is in the main deck.
+    SideInMain(String),
+    /// The deck need a side deck but have none.
+    NoSideDeck,
+    /// The side deck card is not a side deck card or cannot be found.
+    InvalidSideDeck(String),
+}
+
+impl Violation {
+    /// The rule category this violation belong to.
+    #[must_use]
+    pub fn rule(&self) -> Rule {
+        match self {
+            Violation::Size { .. } => Rule::Size,
+            Violation::Copies { .. } | Violation::Unique { .. } => Rule::Copies,
+            Violation::Banned(_) => Rule::Ban,
+            Violation::SideInMain(_) | Violation::NoSideDeck | Violation::InvalidSideDeck(_) => {
+                Rule::SideDeck
+            }
+        }
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::Size { size, min, max } => match max {
+                Some(max) => write!(f, "{size} cards, need {min} to {max}"),
+                None => write!(f, "{size} cards, need at least {min}"),
+            },
+            Violation::Copies {
+                name,
+                rarity,
+                count,
+                max,
+            } => write!(f, "{count}x {name}, {rarity} cards are limited to {max}"),
+            Violation::Unique { count, max } => {
+                write!(f, "{count} unique cards, limited to {max}")
+            }
+            Violation::Banned(name) => write!(f, "{name} is banned"),
+            Violation::SideInMain(name) => write!(f, "{name} is a side deck card"),
+            Violation::NoSideDeck => write!(f, "missing a side deck"),
+            Violation::InvalidSideDeck(name) => write!(f, "{name} is not a side deck card"),
+        }
+    }
+}
+
+/// Stats of a deck obtain by calling [`Deck::stats`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DeckStats {
+    /// The total amount of cards.
+    pub size: usize,
+    /// How many cards cost each amount of blood.
+    pub blood_curve: BTreeMap<isize, usize>,
+    /// How many cards cost each amount of bone.
+    pub bone_curve: BTreeMap<isize, usize>,
+    /// How many cards cost each amount of energy.
+    pub energy_curve: BTreeMap<isize, usize>,
+    /// How many cards cost mox.
+    pub mox: usize,
+    /// How many cards are free.
+    pub free: usize,
+    /// The average attack of cards with a numeric attack.
+    pub average_attack: f32,
+    /// The average health of every card.
+    pub average_health: f32,
+}
+
+/// Json scheme for IMF deck.
+#[derive(Deserialize)]
+struct ImfDeck {
+    cards: Vec<String>,
+    #[serde(default)]
+    side_deck: Option<String>,
+}
@@ -0,0 +1,170 @@
+//! Compare 2 version of a set to see what changed.
+//!
+//! This is useful to tell players when a set get updated. Cards are match by name so a renamed
+//! card show up as a removed card and an added card. The cost extension need to implement
+//! [`Display`] so cost changes can be show.
+//!
+//! # Example
+//! ```
+//! use magpie_engine::prelude::*;
+//!
+//! let old: Set<(), String> = SetBuilder::new("std", "Standard").unwrap().build();
+//! let new = old.clone();
+//!
+//! assert!(diff(&old, &new).is_empty());
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::{Attack, Card, Set};
+
+/// Every difference between 2 version of a set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SetDiff {
+    /// Name of the cards only in the new set.
+    pub added: Vec<String>,
+    /// Name of the cards only in the old set.
+    pub removed: Vec<String>,
+    /// Cards in both set that have different fields.
+    pub changed: Vec<CardChange>,
+}
+
+impl SetDiff {
+    /// Return `true` if the 2 sets have the same cards.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The changes on a single card.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CardChange {
+    /// The card name.
+    pub name: String,
+    /// Every field that changed.
+    pub fields: Vec<FieldChange>,
+}
+
+/// A single field change on a card.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    /// The field name, like `health` or `sigils`.
+    pub field: &'static str,
+    /// The old value of the field.
+    pub old: String,
+    /// The new value of the field.
+    pub new: String,
+}
+
+/// Compare the old and new version of a set.
+///
+/// The card extra field is not compare since it can be anything. Cards are listed in the order
+/// they are in the set.
+#[must_use]
+pub fn diff<E, C>(old: &Set<E, C>, new: &Set<E, C>) -> SetDiff
+where
+    E: Clone,
+    C: Clone + PartialEq + Display,
+{
+    let old_cards = old
+        .cards
+        .iter()
+        .map(|c| (c.name.as_str(), c))
+        .collect::<HashMap<_, _>>();
+    let new_cards = new
+        .cards
+        .iter()
+        .map(|c| (c.name.as_str(), c))
+        .collect::<HashMap<_, _>>();
+
+    let mut out = SetDiff::default();
+
+    for card in &new.cards {
+        match old_cards.get(card.name.as_str()) {
+            None => out.added.push(card.name.clone()),
+            Some(old_card) => {
+                let fields = card_diff(old_card, card);
+                if !fields.is_empty() {
+                    out.changed.push(CardChange {
+                        name: card.name.clone(),
+                        fields,
+                    });
+                }
+            }
+        }
+    }
+
+    out.removed = old
+        .cards
+        .iter()
+        .filter(|c| !new_cards.contains_key(c.name.as_str()))
+        .map(|c| c.name.clone())
+        .collect();
+
+    out
+}
+
+fn card_diff<E, C>(old: &Card<E, C>, new: &Card<E, C>) -> Vec<FieldChange>
+where
+    E: Clone,
+    C: Clone + PartialEq + Display,
+{
+    let mut fields = vec![];
+    let mut push = |field, old: String, new: String| {
+        if old != new {
+            fields.push(FieldChange { field, old, new });
+        }
+    };
+
+    push(
+        "description",
+        old.description.clone(),
+        new.description.clone(),
+    );
+    push("portrait", old.portrait.clone(), new.portrait.clone());
+    push("rarity", old.rarity.to_string(), new.rarity.to_string());
+    push("temple", old.temple.to_string(), new.temple.to_string());
+    push(
+        "tribes",
+        old.tribes.clone().unwrap_or_default(),
+        new.tribes.clone().unwrap_or_default(),
+    );
+    push("attack", attack_str(&old.attack), attack_str(&new.attack));
+    push("health", old.health.to_string(), new.health.to_string());
+    push("sigils", old.sigils.join(", "), new.sigils.join(", "));
+    push(
+        "costs",
+        old.costs
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        new.costs
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+    );
+    push(
+        "traits",
+        old.traits
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        new.traits
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+    );
+    push("related", old.related.join(", "), new.related.join(", "));
+
+    fields
+}
+
+fn attack_str(attack: &Attack) -> String {
+    match attack {
+        Attack::Num(a) => a.to_string(),
+        Attack::SpAtk(s) => s.to_string(),
+        Attack::Str(s) => s.clone(),
+    }
+}
@@ -1,59 +1,75 @@
 //! Provide function to fetch json and supported sets.
+//!
+//! Every request go through the shared [`HttpClient`] so they all get the same timeout, retry and
+//! user agent.
 
-use isahc::ReadResponseExt;
+use isahc::http::StatusCode;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Display;
-use reqwest::blocking::Client;
+use std::sync::Arc;
 
 mod aug;
 mod cti;
 mod desc;
+mod http;
 mod imf;
+mod sheet;
 
 pub use aug::*;
 pub use cti::*;
 pub use desc::*;
+pub use http::*;
 pub use imf::*;
+pub use sheet::*;
 
-use crate::Set;
+use crate::{
+    builder::BuildError,
+    cost::{parse_cost_string, CostError, CostVocab},
+    match_sigil, normalize_sigil, Card, Costs, Set, SetIssue, UNDEFINED_SIGIL,
+    UNDEFINED_SIGIL_DESCRIPTION,
+};
 
 /// Type alias for set fetch output.
 pub type SetResult<E, C> = Result<Set<E, C>, SetError>;
 
-/// Error that happen when calling [`fetch_json`].
-/// Error that happen when calling [`fetch_json`].
+/// Error that happen when calling [`fetch_json`] or any [`HttpClient`] method.
 #[derive(Debug)]
 pub enum FetchError {
-/// Error variant for handling Isahc errors.
-IsahcError(isahc::Error),
-
-/// Error variant for handling Serde JSON errors.
-SerdeError(serde_json::Error),
-
-/// Error variant for handling Request errors.
-RequestError(reqwest::Error),
-
-/// Error variant for handling errors during deserialization.
-DeserializeError(serde_json::Error),
-
-HttpError(reqwest::StatusCode),
-
+    /// The request cannot be send or the response cannot be read.
+    IsahcError(isahc::Error),
+    /// The response is not the expected json.
+    SerdeError(serde_json::Error),
+    /// The server response with a non success status.
+    HttpError(StatusCode),
+    /// The proxy url is invalid.
+    InvalidProxy(String),
+    /// An [offline](HttpClientBuilder::offline) client have no fixture for the url.
+    MissingFixture(String),
+    /// The response is bigger than the [limit](DownloadLimits::max_bytes) in bytes.
+    TooLarge(u64),
+    /// The response content type is not the [expected one](DownloadLimits::content_type).
+    UnexpectedContentType(String),
 }
 
 impl Display for FetchError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            FetchError::RequestError(e) => write!(f, "Request failed: {e}"),
-            FetchError::DeserializeError(e) => write!(f, "JSON deserialization failed: {e}"),
-            _ => write!(f, "An unknown error occurred"),
+            FetchError::IsahcError(e) => write!(f, "request failed: {e}"),
+            FetchError::SerdeError(e) => write!(f, "json deserialization failed: {e}"),
+            FetchError::HttpError(s) => write!(f, "server response with {s}"),
+            FetchError::InvalidProxy(p) => write!(f, "invalid proxy url: {p}"),
+            FetchError::MissingFixture(u) => write!(f, "no fixture for url: {u}"),
+            FetchError::TooLarge(max) => write!(f, "response is bigger than {max} bytes"),
+            FetchError::UnexpectedContentType(t) => write!(f, "unexpected content type: {t}"),
         }
     }
 }
 
 impl Error for FetchError {}
 
-/// Just a wrapper around [`isahc`](https://docs.rs/isahc) to fetch and parse json.
+/// Fetch and parse json with the shared [`HttpClient`].
 /// # Example
 /// ```rust
 /// use magpie_engine::fetch::fetch_json;
@@ -71,10 +87,7 @@ pub fn fetch_json<S>(url: &str) -> Result<S, FetchError>
 where
     S: for<'de> Deserialize<'de>,
 {
-    isahc::get(url)
-        .map_err(FetchError::IsahcError)?
-        .json()
-        .map_err(FetchError::SerdeError)
+    client().get_json(url)
 }
 
 /// Fetches data from the Notion API.
@@ -93,36 +106,131 @@ pub fn fetch_from_notion<S>(
 where
     S: for<'de> Deserialize<'de>,
 {
-    let client = Client::new();
-    let mut request = client.post(url);
+    let mut headers = vec![];
 
     if let Some(key) = api_key {
-        request = request.header("Authorization", format!("Bearer {}", key));
-        request = request.header("Notion-Version", "2022-06-28");
+        headers.push(("Authorization", format!("Bearer {key}")));
+        headers.push(("Notion-Version", String::from("2022-06-28")));
     }
 
-    if let Some(body) = payload {
-        request = request.json(&body);
+    let res = client().post_json(
+        url,
+        &headers,
+        &payload.unwrap_or_else(|| serde_json::json!({})),
+    );
+
+    if let Err(FetchError::HttpError(StatusCode::UNAUTHORIZED)) = res {
+        eprintln!(
+            "Error: Unauthorized access. Check your API key and database permissions for {url}"
+        );
     }
 
-    let response = request.send().map_err(FetchError::RequestError)?;
+    res
+}
 
-    if !response.status().is_success() {
-        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-            eprintln!(
-                "Error: Unauthorized access. Check your API key and database permissions for {}",
-                url
-            );
-        }
-        return Err(FetchError::HttpError(response.status()));
-    }    
+/// Parse a number cell of a card, an empty cell is `0`.
+///
+/// A cell that is not a number is also `0` but it is record in `issues` so [`Set::validate`]
+/// report it.
+pub(crate) fn parse_stat(
+    card: &str,
+    stat: &'static str,
+    value: &str,
+    issues: &mut Vec<SetIssue>,
+) -> isize {
+    let value = value.trim();
+    if value.is_empty() {
+        return 0;
+    }
+
+    value.parse().unwrap_or_else(|_| {
+        issues.push(SetIssue::UnparsableStat {
+            card: card.to_owned(),
+            stat,
+            value: value.to_owned(),
+        });
+        0
+    })
+}
+
+/// Parse a cost cell with [`parse_cost_string`].
+///
+/// A cost that can't be parse make the card free, it is record in `issues` so one bad cell don't
+/// fail the whole set.
+pub(crate) fn parse_costs<C>(
+    card: &str,
+    value: &str,
+    vocab: &CostVocab<C>,
+    issues: &mut Vec<SetIssue>,
+) -> Option<Costs<C>>
+where
+    C: Default + PartialEq,
+{
+    parse_cost_string(value, vocab).unwrap_or_else(|error| {
+        issues.push(SetIssue::InvalidCost {
+            card: card.to_owned(),
+            error,
+        });
+        None
+    })
+}
 
-    
-    let data = response
-        .json::<S>()
-        .map_err(|err| FetchError::RequestError(err))?;
+/// Unwrap a built card, a invalid card is record in `issues` so one bad row don't fail the whole
+/// set.
+pub(crate) fn keep_valid<E, C>(
+    name: &str,
+    card: Result<Card<E, C>, BuildError>,
+    issues: &mut Vec<SetIssue>,
+) -> Option<Card<E, C>>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    card.map_err(|error| {
+        issues.push(SetIssue::InvalidCard {
+            card: name.to_owned(),
+            error,
+        });
+    })
+    .ok()
+}
 
-    Ok(data)
+/// Get the shared name of a card sigil with [`match_sigil`].
+///
+/// Sigils that are not in the sigils description are replace by a placeholder and record in
+/// `issues` with their original name. The placeholder is add to the sigils description the first
+/// time a card need it so the embeds can always describe it. Sigils that only match a similar
+/// name are record too so a wrong match can be spot.
+pub(crate) fn resolve_sigil(
+    sigils: &mut HashMap<Arc<str>, String>,
+    card: &str,
+    sigil: &str,
+    issues: &mut Vec<SetIssue>,
+) -> Arc<str> {
+    if let Some((name, _)) = match_sigil(sigils, sigil) {
+        if normalize_sigil(name) != normalize_sigil(sigil) {
+            issues.push(SetIssue::FuzzySigil {
+                card: card.to_owned(),
+                sigil: sigil.to_owned(),
+                matched: name.to_string(),
+            });
+        }
+        return Arc::clone(name);
+    }
+
+    issues.push(SetIssue::MissingSigil {
+        card: card.to_owned(),
+        sigil: sigil.to_owned(),
+    });
+    if let Some((name, _)) = sigils.get_key_value(UNDEFINED_SIGIL) {
+        return Arc::clone(name);
+    }
+    let undefined: Arc<str> = Arc::from(UNDEFINED_SIGIL);
+    sigils.insert(
+        Arc::clone(&undefined),
+        UNDEFINED_SIGIL_DESCRIPTION.to_owned(),
+    );
+    undefined
 }
 
 /// Fetch google sheet json using [`opensheet`](https://github.com/benborgers/opensheet).
@@ -143,6 +251,9 @@ pub enum SetError {
     /// Unknown Temple or Scrybe.
     UnknownTemple(String),
     /// Unknown rarity.
+    ///
+    /// The built in fetchers keep unknown rarities as [`Rarity::Other`](crate::Rarity::Other)
+    /// instead.
     UnknownRarity(String),
     /// Unknown Mox color.
     UnknownMoxColor(String),
@@ -152,6 +263,8 @@ pub enum SetError {
     UnknownSpAtk(String),
     /// Invalid cost format
     InvalidCostFormat(String),
+    /// A card fail the [`CardBuilder`](crate::builder::CardBuilder) checks.
+    InvalidCard(BuildError),
     DeserializeError(String),
 }
 
@@ -168,10 +281,19 @@ impl Display for SetError {
             SetError::UnknownCost(e) => write!(f, "unknown cost: {e}"),
             SetError::UnknownSpAtk(e) => write!(f, "unknown special attack: {e}"),
             SetError::InvalidCostFormat(e) => write!(f, "unknown cost format: {e}"),
+            SetError::InvalidCard(e) => write!(f, "invalid card: {e}"),
             SetError::DeserializeError(e) => write!(f, "Missing results field: {e}"),
-
         }
     }
 }
 
 impl Error for SetError {}
+
+impl From<CostError> for SetError {
+    fn from(err: CostError) -> Self {
+        match err {
+            CostError::UnknownCost(c) => SetError::UnknownCost(c),
+            CostError::MissingCost(c) => SetError::InvalidCostFormat(c),
+        }
+    }
+}
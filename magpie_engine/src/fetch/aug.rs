@@ -2,16 +2,13 @@
 //!
 //! [Augmented]: https://steamcommunity.com/sharedfiles/filedetails/?id=2966485639&searchtext=augmented
 
-use std::collections::HashMap;
-
-use serde::Deserialize;
-
 use crate::{
-    fetch::fetch_json, self_upgrade, Attack, Card, Costs, Mox, MoxCount, Rarity, Set, SetCode,
-    Temple, Traits, TraitsFlag,
+    cost::{add_mox_count, CostUnit, CostVocab},
+    self_upgrade, Attack, Card, Costs, Mox, MoxCount, Rarity, SetCode, SpAtk, Temple, Traits,
+    TraitsFlag,
 };
 
-use super::{SetError, SetResult};
+use super::{build_sheet_set_with, fetch_sheet_rows, SetResult, SheetColumns, SheetConfig};
 
 /// Augmented's [`Card`] extensions.
 #[derive(Debug, Default, Clone)]
@@ -32,283 +29,192 @@ pub struct AugCosts {
 self_upgrade!(AugExt, AugCosts);
 
 /// The branches of Augmented
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AugBranch {
     /// The default branch on TTS for now.
     Main,
     /// Experimental and newest branch.
     Snapshot,
+    /// A fork of the balance sheet, with the google sheet id.
+    ///
+    /// The sheet must have the same layout as the main sheet, cards in the second tab and sigils
+    /// in the third tab.
+    Custom(String),
 }
 
 /// Fetch Augmented from the
 /// [sheet](https://docs.google.com/spreadsheets/d/1tvTXSsFDK5xAVALQPdDPJOitBufJE6UB_MN4q5nbLXk).
-#[allow(clippy::too_many_lines)]
+///
+/// Use [`AugBranch::Custom`] to fetch a forked balance sheet instead.
 #[allow(clippy::needless_pass_by_value)]
 pub fn fetch_aug_set(branch: AugBranch, code: SetCode) -> SetResult<AugExt, AugCosts> {
-    let sheet_id = match branch {
+    let sheet_id = match &branch {
         AugBranch::Main => "1tvTXSsFDK5xAVALQPdDPJOitBufJE6UB_MN4q5nbLXk",
         AugBranch::Snapshot => "1en8UMcHTfCyTK_yyqLiSyHk3cfvoJkENfJVWE_IzAn8",
+        AugBranch::Custom(id) => id.as_str(),
     };
 
-    let card_url = format!("https://opensheet.elk.sh/{sheet_id}/2");
-    let raw_card: Vec<AugCard> =
-        fetch_json(&card_url).map_err(|e| SetError::FetchError(e, card_url.to_string()))?;
-
-    let sigil_url = format!("https://opensheet.elk.sh/{sheet_id}/3");
-    let sigil: Vec<AugSigil> =
-        fetch_json(&sigil_url).map_err(|e| SetError::FetchError(e, sigil_url.to_string()))?;
-
-    let mut cards = Vec::with_capacity(raw_card.len());
-
-    let mut sigils_description = HashMap::with_capacity(sigil.len());
-
-    for s in sigil {
-        sigils_description.insert(s.name, s.text.replace('\n', ""));
+    let config = aug_sheet_config(sheet_id);
+    let (cards, sigils) = fetch_sheet_rows(&config)?;
+    let mut set = build_sheet_set_with(
+        &config,
+        &cards,
+        &sigils,
+        code,
+        |row, card: &mut Card<AugExt, AugCosts>| {
+            config
+                .cell(row, "Credit")
+                .clone_into(&mut card.extra.artist);
+        },
+    )?;
+
+    for text in set.sigils_description.values_mut() {
+        *text = text.replace('\n', "");
     }
 
-    sigils_description.insert(
-        String::from("UNDEFINDED SIGILS"),
-        "THIS SIGIL IS NOT DEFINED BY THE SET".to_owned(),
-    );
-
-    for card in raw_card {
-        let costs;
+    Ok(set)
+}
 
-        let mut mox_count = MoxCount::default();
-        let mut shattered_count = MoxCount::default();
+/// The layout of the Augmented sheet.
+fn aug_sheet_config(sheet_id: &str) -> SheetConfig<AugCosts> {
+    SheetConfig {
+        sheet_id: sheet_id.to_owned(),
+        name: String::from("Augmented"),
+        columns: SheetColumns {
+            name: "Card Name".to_owned(),
+            // the portraits are on the card printer repo
+            portrait: String::new(),
+            rarity: "Tier".to_owned(),
+            attack: "🗡".to_owned(),
+            health: "♥".to_owned(),
+            pool: "Tier".to_owned(),
+            ..SheetColumns::default()
+        },
+        empty: vec![],
+        rarities: vec![
+            ("Common".to_owned(), Rarity::COMMON),
+            ("Uncommon".to_owned(), Rarity::UNCOMMON),
+            ("Rare".to_owned(), Rarity::RARE),
+            ("Talking".to_owned(), Rarity::UNIQUE),
+            ("Side Deck".to_owned(), Rarity::SIDE),
+        ],
+        temples: vec![
+            ("Beast".to_owned(), Temple::BEAST),
+            ("Undead".to_owned(), Temple::UNDEAD),
+            ("Tech".to_owned(), Temple::TECH),
+            ("Magick".to_owned(), Temple::MAGICK),
+            ("Fool".to_owned(), Temple::FOOL),
+        ],
+        costs: aug_cost_vocab(),
+        portrait_url: "https://raw.githubusercontent.com/answearingmachine/card-printer/main/dist/printer/assets/art/{name}.png".to_owned(),
+        ..SheetConfig::default()
+    }
+}
 
-        if card.cost != "free" && !card.cost.is_empty() {
-            let mut t: Costs<AugCosts> = Costs::default();
+/// The Augmented cost words, the moxes can be shattered and `asterisk` cost nothing.
+fn aug_cost_vocab() -> CostVocab<AugCosts> {
+    /// Add a shattered mox.
+    fn shatter(costs: &mut Costs<AugCosts>, mox: Mox, count: isize) {
+        costs.mox |= mox;
+        add_mox_count(
+            costs
+                .extra
+                .shattered_count
+                .get_or_insert_with(MoxCount::default),
+            mox,
+            count,
+        );
+    }
 
-            for c in card
-                .cost
-                .replace("bones", "bone")
-                .replace("rubies", "ruby")
-                .replace("emeralds", "emerald")
-                .replace("sapphires", "sapphire")
-                .replace("prisms", "prism")
-                .split('+')
-            {
-                let (count, mut cost): (isize, Vec<String>) = {
-                    let s = c.to_lowercase().trim().to_string();
-                    let mut t = s.split_whitespace().map(ToOwned::to_owned);
+    let custom = CostUnit::<AugCosts>::Custom;
+    let mut vocab = CostVocab::default();
+    vocab.units.extend([
+        ("max".to_owned(), custom(|c, n| c.extra.max += n)),
+        ("asterisk".to_owned(), custom(|_, _| ())),
+        (
+            "shattered ruby".to_owned(),
+            custom(|c, n| shatter(c, Mox::O, n)),
+        ),
+        (
+            "shattered emerald".to_owned(),
+            custom(|c, n| shatter(c, Mox::G, n)),
+        ),
+        (
+            "shattered sapphire".to_owned(),
+            custom(|c, n| shatter(c, Mox::B, n)),
+        ),
+        (
+            "shattered prism".to_owned(),
+            custom(|c, n| shatter(c, Mox::Y, n)),
+        ),
+        (
+            "shattered garnet".to_owned(),
+            custom(|c, n| shatter(c, Mox::R, n)),
+        ),
+        (
+            "shattered topaz".to_owned(),
+            custom(|c, n| shatter(c, Mox::E, n)),
+        ),
+        (
+            "shattered amethyst".to_owned(),
+            custom(|c, n| shatter(c, Mox::P, n)),
+        ),
+    ]);
+    vocab
+}
 
-                    let first = t
-                        .next()
-                        .ok_or_else(|| SetError::InvalidCostFormat(card.cost.clone()))?
-                        .parse::<isize>()
-                        .map_err(|_| SetError::InvalidCostFormat(card.cost.clone()))?;
-                    let mut rest = t.collect::<Vec<String>>();
+/// Parse the attack column, special attacks like `Ant` or `Mirror` are map to [`SpAtk`] and any
+/// other text is kept as [`Attack::Str`].
+pub(super) fn parse_attack(attack: &str) -> Attack {
+    let attack = attack.trim();
 
-                    rest.reverse();
-                    (first, rest)
-                };
+    if let Ok(n) = attack.parse() {
+        return Attack::Num(n);
+    }
 
-                match cost
-                    .pop()
-                    .ok_or_else(|| SetError::InvalidCostFormat(card.cost.clone()))?
-                    .as_str()
-                {
-                    "blood" => t.blood += count,
-                    "bone" => t.bone += count,
-                    "energy" => t.energy += count,
-                    "max" => t.extra.max += count,
-                    "shattered" => match cost.pop().unwrap().as_str() {
-                        "ruby" => {
-                            t.mox |= Mox::O;
-                            shattered_count.o += count as usize;
-                        }
-                        "emerald" => {
-                            t.mox |= Mox::G;
-                            shattered_count.g += count as usize;
-                        }
-                        "sapphire" => {
-                            t.mox |= Mox::B;
-                            shattered_count.b += count as usize;
-                        }
-                        "prism" => {
-                            t.mox |= Mox::Y;
-                            shattered_count.y += count as usize;
-                        }
-                        "garnet" => {
-                            t.mox |= Mox::R;
-                            shattered_count.r += count as usize;
-                        }
-                        "topaz" => {
-                            t.mox |= Mox::E;
-                            shattered_count.e += count as usize;
-                        }
-                        "amethyst" => {
-                            t.mox |= Mox::P;
-                            shattered_count.p += count as usize;
-                        }
-                        m => return Err(SetError::UnknownMoxColor(m.to_owned())),
-                    },
-                    m @ ("ruby" | "sapphire" | "emerald" | "prism" | "garnet" | "topaz"
-                    | "amethyst") => match m {
-                        "ruby" => {
-                            t.mox |= Mox::O;
-                            mox_count.o += count as usize;
-                        }
-                        "emerald" => {
-                            t.mox |= Mox::G;
-                            mox_count.g += count as usize;
-                        }
-                        "sapphire" => {
-                            t.mox |= Mox::B;
-                            mox_count.b += count as usize;
-                        }
-                        "prism" => {
-                            t.mox |= Mox::Y;
-                            mox_count.y += count as usize;
-                        }
-                        "garnet" => {
-                            t.mox |= Mox::R;
-                            mox_count.r += count as usize;
-                        }
-                        "topaz" => {
-                            t.mox |= Mox::E;
-                            mox_count.e += count as usize;
-                        }
-                        "amethyst" => {
-                            t.mox |= Mox::P;
-                            mox_count.p += count as usize;
-                        }
-                        _ => unreachable!(),
-                    },
-                    "asterisk" => (),
-                    c => return Err(SetError::UnknownMoxColor(c.to_string())),
-                }
-            }
+    match attack.to_lowercase().as_str() {
+        "" => Attack::Num(0),
+        "mox" => Attack::SpAtk(SpAtk::MOX),
+        "green mox" | "green_mox" => Attack::SpAtk(SpAtk::GREEN_MOX),
+        "mirror" => Attack::SpAtk(SpAtk::MIRROR),
+        "ant" | "ants" => Attack::SpAtk(SpAtk::ANT),
+        "bone" | "bones" => Attack::SpAtk(SpAtk::BONE),
+        "bell" => Attack::SpAtk(SpAtk::BELL),
+        "card" | "cards" | "hand" => Attack::SpAtk(SpAtk::CARD),
+        _ => Attack::Str(attack.to_owned()),
+    }
+}
 
-            // only include the moxes if they are not the default all 1
-            if mox_count != MoxCount::default() {
-                t.mox_count = Some(mox_count);
-            }
+/// Parse the traits column, known traits are also map to [`TraitsFlag`].
+///
+/// Every trait is kept as a string because the trait text is in the sigils description.
+pub(super) fn parse_traits(traits: &str) -> Option<Traits> {
+    let strings = traits
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(ToOwned::to_owned)
+        .collect::<Vec<_>>();
+
+    if strings.is_empty() {
+        return None;
+    }
 
-            if shattered_count != MoxCount::default() {
-                t.extra.shattered_count = Some(shattered_count);
+    let mut flags = TraitsFlag::empty();
+    for t in &strings {
+        match t.to_lowercase().as_str() {
+            "conductive" => flags |= TraitsFlag::CONDUCTIVE,
+            "banned" => flags |= TraitsFlag::BAN,
+            "terrain" | "unsacrificeable" | "unsacrificable" | "unsaccable" => {
+                flags |= TraitsFlag::TERRAIN;
             }
-            costs = Some(t);
-        } else {
-            costs = None;
+            "hard" | "unhammerable" => flags |= TraitsFlag::HARD,
+            _ => (),
         }
-
-        let card = Card {
-            portrait: format!("https://raw.githubusercontent.com/answearingmachine/card-printer/main/dist/printer/assets/art/{}.png", card.name.replace(' ', "%20")),
-
-            set: code,
-
-            name: card.name,
-            description: card.description,
-
-            rarity: match card.rarity.as_str() {
-                "Common" | "" => Rarity::COMMON,
-                "Uncommon" => Rarity::UNCOMMON,
-                "Rare" => Rarity::RARE,
-                "Talking" => Rarity::UNIQUE,
-                "Side Deck" => Rarity::SIDE,
-                _ => return Err(SetError::UnknownRarity(card.rarity)),
-            },
-            temple:match card.temple.as_str() {
-                "Beast" => Temple::BEAST,
-                "Undead" => Temple::UNDEAD,
-                "Tech" => Temple::TECH,
-                "Magick" => Temple::MAGICK,
-                "Fool" => Temple::FOOL,
-                _ => return Err(SetError::UnknownTemple(card.temple))
-            },
-            tribes: (!card.tribes.is_empty()).then_some(card.tribes),
-
-            attack: Attack::Num(card.attack.parse().unwrap_or(0)),
-            health: card.health.parse().unwrap_or(0),
-            sigils: if card.sigils.is_empty() {
-                vec![]
-            } else {
-                card.sigils.split(", ").map(|s| {
-                    let s = s.to_owned();
-                    if sigils_description.contains_key(&s) {
-                        s
-                    } else {
-                        String::from("UNDEFINEDED SIGILS")
-                    }
-                }).collect()
-            },
-
-            costs,
-
-            traits: (!card.traits.is_empty()).then_some(Traits {
-                strings: Some(
-                     card
-                    .traits
-                    .split(", ")
-                    .map(ToOwned::to_owned)
-                    .collect::<Vec<String>>()
-                ),
-
-                flags: TraitsFlag::empty()
-            }),
-            related: if card.token.is_empty() {
-                vec![]
-            } else {
-                card.token.split(", ").map(ToOwned::to_owned).collect()
-            },
-
-            extra: AugExt {
-                artist: card.artist,
-            }
-        };
-
-        cards.push(card);
     }
 
-    Ok(Set {
-        code,
-        name: String::from("Augmented"),
-        cards,
-        sigils_description,
+    Some(Traits {
+        strings: Some(strings),
+        flags,
     })
 }
-
-/// Json scheme for aug card.
-#[derive(Deserialize)]
-struct AugCard {
-    #[serde(rename = "Card Name")]
-    name: String,
-    #[serde(rename = "Flavor Text")]
-    description: String,
-
-    #[serde(rename = "Temple")]
-    temple: String,
-    #[serde(rename = "Tier")]
-    rarity: String,
-
-    #[serde(rename = "Cost")]
-    cost: String,
-
-    #[serde(rename = "🗡")]
-    attack: String,
-    #[serde(rename = "♥")]
-    health: String,
-
-    #[serde(rename = "Sigils")]
-    sigils: String,
-
-    #[serde(rename = "Token")]
-    token: String,
-    #[serde(rename = "Traits")]
-    traits: String,
-    #[serde(rename = "Tribes")]
-    tribes: String,
-
-    #[serde(rename = "Credit")]
-    artist: String,
-}
-
-/// Json scheme for aug sigil.
-#[derive(Deserialize)]
-struct AugSigil {
-    #[serde(rename = "Name")]
-    name: String,
-    #[serde(rename = "Text")]
-    text: String,
-}
@@ -1,22 +1,46 @@
-use std::collections::HashMap;
+use crate::builder::CardBuilder;
+use crate::cost::CostVocab;
+use crate::fetch::fetch_from_notion;
+use crate::query::text::TextIndex;
+use crate::{Attack, CardRef, Rarity, Set, SetCode, Temple};
 use serde::{Deserialize, Serialize};
-use crate::{fetch::{fetch_from_notion, FetchError}, Attack, Card, Costs, Mox, MoxCount, Rarity, Set, SetCode, Temple};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
 
-use super::{SetError, SetResult};
+use super::{keep_valid, parse_costs, parse_stat, resolve_sigil, SetError, SetResult};
+
+/// Environment variable to change where the Notion snapshots are save.
+pub const NOTION_CACHE_VAR: &str = "NOTION_CACHE_DIR";
+
+/// Default directory the Notion snapshots are save to.
+pub const NOTION_CACHE_DIR: &str = "./notion_cache";
+
+/// Notion return at most 100 pages per query.
+const PAGE_SIZE: usize = 100;
 
 #[derive(Deserialize, Debug)]
-struct NotionResponse {
-    results: Option<Vec<NotionResult>>, // Wrap the results in an Option<Vec> to handle missing results
+struct NotionQuery {
+    results: Option<Vec<Value>>, // Wrap the results in an Option<Vec> to handle missing results
+    #[serde(default)]
+    has_more: bool,
+    next_cursor: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
-struct NotionResult {
-    properties: CtiCard, // The properties field contains a CtiCard
+struct NotionPage {
+    last_edited_time: String,
+    #[serde(default)]
+    archived: bool,
+    #[serde(default)]
+    in_trash: bool,
 }
 
 #[derive(Deserialize, Debug)]
-struct NotionResponseSigils {
-    results: Option<Vec<NotionResultSigils>>, // Wrap the results in an Option<Vec> to handle missing results
+struct NotionResult {
+    properties: CtiCard, // The properties field contains a CtiCard
 }
 
 #[derive(Deserialize, Debug)]
@@ -24,182 +48,281 @@ struct NotionResultSigils {
     properties: CtiSigil, // The properties field contains a CtiCard
 }
 
+/// A local copy of a Notion database along with the last time it was edited.
+#[derive(Serialize, Deserialize, Debug)]
+struct NotionSnapshot {
+    last_edited_time: String,
+    results: Vec<Value>,
+}
+
+/// Query every page of a Notion database that match a filter.
+fn query_pages(url: &str, api_key: &str, filter: Option<&Value>) -> Result<Vec<Value>, SetError> {
+    let mut results = vec![];
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let mut payload = json!({ "page_size": PAGE_SIZE });
+        if let Some(f) = filter {
+            payload["filter"] = f.clone();
+        }
+        if let Some(c) = &cursor {
+            payload["start_cursor"] = json!(c);
+        }
+
+        let page: NotionQuery = fetch_from_notion(url, Some(api_key), Some(payload))
+            .map_err(|e| SetError::FetchError(e, url.to_string()))?;
+        results.extend(
+            page.results
+                .ok_or_else(|| SetError::DeserializeError(url.to_string()))?,
+        );
+
+        match page.next_cursor {
+            Some(c) if page.has_more => cursor = Some(c),
+            _ => break,
+        }
+    }
+
+    Ok(results)
+}
+
+/// Query every page of a Notion database.
+///
+/// When there is a local snapshot only the pages edited since it was save are query and merge
+/// into it. Notion edit times are round to the minute so pages edited on the same minute as the
+/// snapshot are query again. Removed pages don't show up as edited so the ids of every page are
+/// also query, with only the title property to keep it light, to drop the removed ones. The
+/// snapshot is save to [`NOTION_CACHE_DIR`] or the directory in [`NOTION_CACHE_VAR`].
+fn query_database(url: &str, api_key: &str) -> Result<Vec<Value>, SetError> {
+    // the database id is the path segment before `/query`
+    let id = url
+        .trim_end_matches("/query")
+        .rsplit('/')
+        .next()
+        .unwrap_or(url);
+    let dir = std::env::var(NOTION_CACHE_VAR).unwrap_or_else(|_| NOTION_CACHE_DIR.to_owned());
+    let path = PathBuf::from(dir).join(format!("{id}.json"));
+
+    let snapshot = fs::read(&path)
+        .ok()
+        .and_then(|b| serde_json::from_slice::<NotionSnapshot>(&b).ok());
+
+    let results = match snapshot {
+        None => query_pages(url, api_key, None)?,
+        Some(snapshot) => {
+            let edited = query_pages(
+                url,
+                api_key,
+                Some(&json!({
+                    "timestamp": "last_edited_time",
+                    "last_edited_time": { "on_or_after": snapshot.last_edited_time },
+                })),
+            )?;
+            let live = query_pages(&format!("{url}?filter_properties=title"), api_key, None)?
+                .into_iter()
+                .filter_map(|p| p["id"].as_str().map(str::to_owned))
+                .collect::<HashSet<_>>();
+
+            let edited_ids = edited
+                .iter()
+                .filter_map(|p| p["id"].as_str())
+                .collect::<HashSet<_>>();
+            let mut results = snapshot
+                .results
+                .iter()
+                .filter(|p| {
+                    p["id"]
+                        .as_str()
+                        .is_some_and(|id| live.contains(id) && !edited_ids.contains(id))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            results.extend(edited);
+            results
+        }
+    };
+
+    // archived and trashed pages are still return when they were edited
+    let mut latest: Option<String> = None;
+    let results = results
+        .into_iter()
+        .filter(|p| {
+            let Ok(page) = NotionPage::deserialize(p) else {
+                return true;
+            };
+            latest = latest.take().max(Some(page.last_edited_time));
+            !page.archived && !page.in_trash
+        })
+        .collect::<Vec<_>>();
+
+    // a failed save only mean the next boot is slower
+    if let Some(last_edited_time) = latest {
+        let snapshot = NotionSnapshot {
+            last_edited_time,
+            results,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).ok();
+        }
+        if let Err(e) = fs::write(&path, serde_json::to_vec(&snapshot).unwrap_or_default()) {
+            eprintln!("Cannot save Notion snapshot to {}: {e}", path.display());
+        }
+        return Ok(snapshot.results);
+    }
+
+    Ok(results)
+}
+
 /// Fetch Custom TCG Inscryption from the
 /// [Notion Database](https://www.notion.so/inscryption-pvp-wiki/Custom-TCG-Inscryption-3f22fc55858d4cfab2061783b5120f87).
+///
+/// Unchanged databases are load from a local snapshot, see [`NOTION_CACHE_DIR`].
 #[allow(clippy::too_many_lines)]
 pub fn fetch_cti_set(code: SetCode) -> SetResult<(), ()> {
     let notion_api_key = std::env::var("NOTION_API_KEY")
         .map_err(|_| SetError::MissingApiKey("Notion API key not found".to_string()))?;
 
-    match std::env::var("NOTION_API_KEY") {
-        Ok(key) => println!("Retrieved API Key: {}", key),
-        Err(err) => println!("Failed to retrieve API Key: {:?}", err),
-    }
-
     let card_url = "https://api.notion.com/v1/databases/e19c88aa75b44bfe89321bcde8dc7d9f/query";
     let sigil_url = "https://api.notion.com/v1/databases/933d6166cb3f4ee89db51e4cf464f5bd/query";
 
-    // Example payload (empty query for fetching all items)
-    let payload = serde_json::json!({});
-    let payload2 = serde_json::json!({});
-
-    let raw_response: NotionResponse =
-        fetch_from_notion(card_url, Some(&notion_api_key), Some(payload))
-            .map_err(|e| SetError::FetchError(e, card_url.to_string()))?;
-
-    println!("{:?}", raw_response);
-
-    let raw_card = raw_response.results.ok_or_else(|| SetError::DeserializeError(card_url.to_string()))?;
+    let raw_card = query_database(card_url, &notion_api_key)?
+        .into_iter()
+        .map(serde_json::from_value::<NotionResult>)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| SetError::DeserializeError(card_url.to_string()))?;
 
     // Fetch sigils
-    let sigil: NotionResponseSigils =
-        fetch_from_notion(sigil_url, Some(&notion_api_key), Some(payload2))
-            .map_err(|e| SetError::FetchError(e, sigil_url.to_string()))?;
-    
-    println!("{:?}", sigil);
-
-    let raw_sigil = sigil.results.ok_or_else(|| SetError::DeserializeError(sigil_url.to_string()))?;
+    let raw_sigil = query_database(sigil_url, &notion_api_key)?
+        .into_iter()
+        .map(serde_json::from_value::<NotionResultSigils>)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| SetError::DeserializeError(sigil_url.to_string()))?;
 
     // Initialize containers for the cards and sigils descriptions
     let mut cards = Vec::with_capacity(raw_card.len());
-    let mut sigils_description = HashMap::with_capacity(raw_sigil.len());
+    let mut sigils_description: HashMap<Arc<str>, String> = HashMap::with_capacity(raw_sigil.len());
+    let mut sigils_category = HashMap::with_capacity(raw_sigil.len());
+    let mut pools: HashMap<String, Vec<CardRef>> = HashMap::new();
+    let mut issues = vec![];
 
-    // Populate the sigils description map
+    // Populate the sigils description and category map
     for s in raw_sigil {
+        let name = s.properties.name.rich_text[0].plain_text.clone();
+        sigils_category.insert(name.clone(), s.properties.category.select.name);
         sigils_description.insert(
-            s.properties.name.rich_text[0].plain_text.clone(), 
-            s.properties.description.rich_text[0].plain_text.clone().replace('\n', "")
+            name.into(),
+            s.properties.description.rich_text[0]
+                .plain_text
+                .clone()
+                .replace('\n', ""),
         );
     }
 
     // Process the raw card data
+    let vocab = CostVocab::default();
     for card in raw_card {
-        let costs;
-        if card.properties.cost.rich_text[0].plain_text != "Free" && !card.properties.cost.rich_text[0].plain_text.is_empty() {
-            let mut t: Costs<()> = Costs::default();
-            let mut mox_count = MoxCount::default();
-
-            for c in card
-                .properties.cost.rich_text[0].plain_text
-                .to_lowercase()
-                .replace("bones", "bone")
-                .split(", ")
-            {
-                let (count, cost) = {
-                    let s = c.to_lowercase().trim().to_string();
-                    let mut t = s.split_whitespace().map(ToOwned::to_owned);
-
-                    let first = t
-                        .next()
-                        .ok_or_else(|| SetError::InvalidCostFormat(card.properties.cost.rich_text[0].plain_text.clone()))?
-                        .parse::<isize>()
-                        .map_err(|_| SetError::InvalidCostFormat(card.properties.cost.rich_text[0].plain_text.clone()))?;
-
-                    (
-                        first,
-                        t.next()
-                            .ok_or_else(|| SetError::InvalidCostFormat(card.properties.cost.rich_text[0].plain_text.clone()))?,
-                    )
-                };
-
-                match cost.as_str() {
-                    "blood" => t.blood += count,
-                    "bone" => t.bone += count,
-                    "energy" => t.energy += count,
-                    m @ ("ruby" | "sapphire" | "emerald" | "prism") => match m {
-                        "ruby" => {
-                            t.mox |= Mox::O;
-                            mox_count.o += count as usize;
-                        }
-                        "emerald" => {
-                            t.mox |= Mox::G;
-                            mox_count.g += count as usize;
-                        }
-                        "sapphire" => {
-                            t.mox |= Mox::B;
-                            mox_count.b += count as usize;
-                        }
-                        "prism" => {
-                            t.mox |= Mox::Y;
-                            mox_count.y += count as usize;
-                        }
-                        _ => unreachable!(),
-                    },
-                    c => return Err(SetError::UnknownCost(c.to_string())),
-                }
-            }
-
-            // Only include the moxes if they are not the default all 1
-            if mox_count != MoxCount::default() {
-                t.mox_count = Some(mox_count);
-            }
-
-            costs = Some(t);
-        } else {
-            costs = None;
-        }
-
-        cards.push(Card {
-            portrait: card.properties.image.url.clone(), // Using the image URL directly
-            set: code,
-            name: card.properties.name.rich_text[0].plain_text.clone(),
-            description: card.properties.flavor.rich_text[0].plain_text.clone(),
-            rarity: match card.properties.rarity.select.name.as_str() {
-                "Common" | "Common (Joke Card)" | "" => Rarity::COMMON,
-                "Uncommon" => Rarity::UNCOMMON,
-                "Rare" => Rarity::RARE,
-                "Talking" | "Deathcard" => Rarity::UNIQUE,
-                "Side-Deck" => Rarity::SIDE,
-                _ => return Err(SetError::UnknownRarity(card.properties.rarity.select.name)),
-            },
-            temple: match card.properties.temple.select.name.as_str() {
-                "Beast" => Temple::BEAST,
-                "Undead" => Temple::UNDEAD,
-                "Tech" => Temple::TECH,
-                "Magicks" => Temple::MAGICK,
-                "Terrain/Extras" => Temple::empty(),
-                _ => return Err(SetError::UnknownTemple(card.properties.temple.select.name))
-            },
-            tribes: None,
-            attack: Attack::Num(card.properties.power.rich_text[0].plain_text.parse().unwrap_or(0)),
-            health: card.properties.health.rich_text[0].plain_text.parse().unwrap_or(0),
-            sigils: card.properties.sigil_1
+        let name = card.properties.name.rich_text[0].plain_text.clone();
+        let costs = parse_costs(
+            &name,
+            &card.properties.cost.rich_text[0].plain_text,
+            &vocab,
+            &mut issues,
+        );
+        let rarity = match card.properties.rarity.select.name.as_str() {
+            "Common" | "Common (Joke Card)" | "" => Rarity::COMMON,
+            "Uncommon" => Rarity::UNCOMMON,
+            "Rare" => Rarity::RARE,
+            "Talking" | "Deathcard" => Rarity::UNIQUE,
+            "Side-Deck" => Rarity::SIDE,
+            r => Rarity::Other(r.to_owned()),
+        };
+        let (temple, other_temples) = match card.properties.temple.select.name.as_str() {
+            "Beast" => (Temple::BEAST, vec![]),
+            "Undead" => (Temple::UNDEAD, vec![]),
+            "Tech" => (Temple::TECH, vec![]),
+            "Magicks" => (Temple::MAGICK, vec![]),
+            "Terrain/Extras" | "" => (Temple::empty(), vec![]),
+            t => (Temple::empty(), vec![t.to_owned()]),
+        };
+        let attack = parse_stat(
+            &name,
+            "attack",
+            &card.properties.power.rich_text[0].plain_text,
+            &mut issues,
+        );
+        let health = parse_stat(
+            &name,
+            "health",
+            &card.properties.health.rich_text[0].plain_text,
+            &mut issues,
+        );
+        let sigils = card
+            .properties
+            .sigil_1
             .iter()
             .chain(card.properties.sigil_2.iter())
             .chain(card.properties.sigil_3.iter())
             .chain(card.properties.sigil_4.iter())
             .filter_map(|sigil| {
-                let sigil_name = sigil.rich_text.get(0)?.plain_text.clone();
+                let sigil_name = sigil.rich_text.first()?.plain_text.clone();
                 if sigil_name.is_empty() {
                     None
                 } else {
-                    Some(
-                        sigils_description
-                            .get(&sigil_name)
-                            .cloned()
-                            .unwrap_or_else(|| "UNDEFINED SIGIL".to_string()),
-                    )
+                    Some(resolve_sigil(
+                        &mut sigils_description,
+                        &name,
+                        &sigil_name,
+                        &mut issues,
+                    ))
                 }
             })
-            .collect(),
-            costs,
-            traits: None,
-            related: card.properties.token
+            .collect::<Vec<_>>();
+        let related = card
+            .properties
+            .token
             .as_ref()
-            .and_then(|token| token.rich_text.get(0))
+            .and_then(|token| token.rich_text.first())
             .map(|token_text| vec![token_text.plain_text.clone()])
-            .unwrap_or_else(Vec::new),      
-            extra: (),
-        });
+            .unwrap_or_default();
+
+        let built = CardBuilder::new(&name)
+            .set(code)
+            .portrait(&card.properties.image.url) // Using the image URL directly
+            .description(&card.properties.flavor.rich_text[0].plain_text)
+            .rarity(rarity)
+            .temple(temple)
+            .other_temples(other_temples)
+            .attack(Attack::Num(attack))
+            .health(health)
+            .sigils(sigils)
+            .costs(costs)
+            .related(related)
+            .build();
+        let Some(built) = keep_valid(&name, built, &mut issues) else {
+            continue;
+        };
+
+        let category = &card.properties.rarity.select.name;
+        if !category.is_empty() {
+            pools.entry(category.clone()).or_default().push(cards.len());
+        }
+
+        cards.push(built);
     }
 
     // Return the assembled set
-    Ok(Set {
+    let mut set = Set {
         code,
         name: String::from("Custom TCG Inscryption"),
         cards,
         sigils_description,
-    })
+        sigils_category,
+        pools,
+        text_index: TextIndex::default(),
+        issues,
+    };
+    set.reindex();
+
+    Ok(set)
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -257,7 +380,7 @@ pub struct CtiCard {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RichTextContent {
     #[serde(rename = "rich_text")]
-    pub rich_text: Vec<PlainText>
+    pub rich_text: Vec<PlainText>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -275,7 +398,7 @@ pub struct Link {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SelectPrerequisite {
     #[serde(rename = "select")]
-    pub select: SelectOption
+    pub select: SelectOption,
 }
 
 // Select option for Rarity and Temple (with the name)
@@ -308,4 +431,4 @@ struct CtiSigil {
     name: RichTextContent,
     #[serde(rename = "Internal Name")]
     internam_name: InternalName,
-}
\ No newline at end of file
+}
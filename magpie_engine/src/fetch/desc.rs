@@ -3,10 +3,12 @@ use std::collections::HashMap;
 use serde::Deserialize;
 
 use crate::{
-    fetch::fetch_json, Attack, Card, Costs, Mox, Rarity, Set, SetCode, Temple, Traits, TraitsFlag,
+    cost::{add_mox_count, CostUnit, CostVocab},
+    fetch::fetch_json,
+    Attack, Card, Mox, MoxCount, Rarity, SetCode, Temple, Traits, TraitsFlag,
 };
 
-use super::{SetError, SetResult};
+use super::{build_sheet_set_with, fetch_sheet_rows, SetResult, SheetColumns, SheetConfig};
 
 /// Descryption's [`Costs`] extension.
 #[derive(Default, Clone, PartialEq)]
@@ -17,218 +19,165 @@ pub struct DescCosts {
     pub gold: isize,
 }
 
+/// Descryption's [`Card`] extension.
+#[derive(Debug, Default, Clone)]
+pub struct DescExt {
+    /// Rulings and clarifications from the notes column and the rulings tab.
+    pub rulings: Vec<String>,
+}
+
 /// Fetch Descryption from the
 /// [sheet](https://docs.google.com/spreadsheets/d/1EjOtqUrjsMRl7wiVMN7tMuvAHvkw7snv1dNyFJIFbaE).
-pub fn fetch_desc_set(code: SetCode) -> SetResult<(), DescCosts> {
-    let card_url = "https://opensheet.elk.sh/1EjOtqUrjsMRl7wiVMN7tMuvAHvkw7snv1dNyFJIFbaE/2";
-    let card_raw: Vec<DescCard> =
-        fetch_json(card_url).map_err(|e| SetError::FetchError(e, card_url.to_string()))?;
-
-    let sigil_url = "https://opensheet.elk.sh/1EjOtqUrjsMRl7wiVMN7tMuvAHvkw7snv1dNyFJIFbaE/4";
-    let sigils: Vec<DescSigil> =
-        fetch_json(sigil_url).map_err(|e| SetError::FetchError(e, sigil_url.to_string()))?;
-
-    let mut cards = Vec::with_capacity(card_raw.len());
-    let sigils_description = {
-        let mut h = HashMap::with_capacity(sigils.len());
-        for s in sigils {
-            h.insert(s.name, s.text);
+///
+/// The rulings tab is optional, if it cannot be fetch the cards only have the rulings from their
+/// notes column.
+pub fn fetch_desc_set(code: SetCode) -> SetResult<DescExt, DescCosts> {
+    let config = desc_sheet_config();
+    let (cards, sigils) = fetch_sheet_rows(&config)?;
+
+    let rulings_url = format!("https://opensheet.elk.sh/{}/Rulings", config.sheet_id);
+    let rulings = {
+        let raw: Vec<DescRuling> = fetch_json(&rulings_url).unwrap_or_default();
+        let mut h: HashMap<String, Vec<String>> = HashMap::new();
+        for r in raw {
+            if !is_empty(&r.name) && !is_empty(&r.ruling) {
+                h.entry(r.name).or_default().push(r.ruling);
+            }
         }
         h
     };
 
-    for card in card_raw {
-        if card.name.is_empty() {
-            continue;
-        }
-
-        let mut temple = Temple::empty();
-
-        if !is_empty(&card.temple) {
-            for t in card.temple.split(", ") {
-                temple |= match t {
-                    "Leshy" => Temple::BEAST,
-
-                    "Grimora" => Temple::UNDEAD,
-                    "P03" => Temple::TECH,
-                    "Magnificus" => Temple::MAGICK,
-                    "Galliard" => Temple::ARTISTRY,
-
-                    _ => return Err(SetError::UnknownTemple(t.to_owned())),
-                }
-            }
-        }
-
-        let mut costs = Costs::<DescCosts>::default();
-
-        if !is_empty(&card.cost) {
-            if card.cost.contains(',') | !card.cost.contains(' ') {
-                for m in card.cost.split(", ") {
-                    costs.mox |= match m {
-                        "Orange" => Mox::O,
-                        "Green" => Mox::G,
-                        "Blue" => Mox::B,
-                        "Black" => {
-                            if costs.mox.is_empty() {
-                                Mox::K
-                            } else {
-                                Mox::P1
-                            }
-                        }
-                        _ => return Err(SetError::UnknownMoxColor(m.to_owned())),
-                    }
-                }
-            } else {
-                let (count, cost) = {
-                    let mut t = card.cost.split_whitespace();
-                    (
-                        t.next().unwrap().parse::<isize>().unwrap(),
-                        t.next().unwrap(),
-                    )
-                };
-
-                match cost.to_lowercase().as_str() {
-                    "blood" => costs.blood += count,
-                    "bone" | "bones" => costs.bone += count,
-                    "energy" => costs.energy += count,
-                    "links" | "link" => costs.extra.link += count,
-                    "gold" | "golds" => costs.extra.gold += count,
-                    _ => return Err(SetError::UnknownCost(cost.to_owned())),
-                }
-            }
-        }
+    build_sheet_set_with(
+        &config,
+        &cards,
+        &sigils,
+        code,
+        |row, card: &mut Card<DescExt, DescCosts>| {
+            let named = config.cell(row, "Traits (Named)");
 
-        let card = Card {
-            set: code,
-            portrait: format!(
-                "https://raw.githubusercontent.com/EternalHours/Descryption/main/images/portraits/{}_{}.png", 
-                if card.traits_unique.contains("Full Art") { 
+            card.portrait = format!(
+                "https://raw.githubusercontent.com/EternalHours/Descryption/main/images/portraits/{}_{}.png",
+                if named.contains("Full Art") {
                     "fullpixel"
                 } else {
-                   "pixelportrait"
+                    "pixelportrait"
                 },
                 card.name
                     .to_lowercase()
                     .replace([' ', '\'', '(', ')', '-', '.'], "")
-            ),
-            name: card.name,
-            description: String::new(),
-            rarity: if is_empty(&card.rarity) {
-                Rarity::COMMON
-            } else {
-                match card.rarity.as_str() {
-                    "Common" => Rarity::COMMON,
-                    "Rare" => Rarity::RARE,
-                    "Unique" => Rarity::UNIQUE,
-                    _ => return Err(SetError::UnknownRarity(card.rarity)),
-                }
-            },
-            temple,
-            tribes: (!is_empty(&card.tribes)).then_some(card.tribes),
-            attack: if let Ok(a) = card.attack.parse() {
+            );
+
+            let attack = config.cell(row, &config.columns.attack);
+            card.attack = if let Ok(a) = attack.parse() {
                 Attack::Num(a)
             } else {
-                Attack::Str(card.attack)
-            },
-            health: card.health.parse().unwrap_or(0),
-            sigils: if is_empty(&card.sigils) {
-                vec![]
-            } else {
-                card.sigils
-                    .split(", ")
-                    .map(|s| {
-                        let s = s.to_owned();
-                        if sigils_description.contains_key(&s) {
-                            s
-                        } else {
-                            String::from("UNDEFINEDED SIGILS")
-                        }
-                    })
-                    .collect()
-            },
-            costs: if is_empty(&card.cost) {
-                None
-            } else {
-                Some(costs)
-            },
-            traits: Some(Traits {
-                strings: {
-                    (!(is_empty(&card.traits_unique) && is_empty(&card.traits))).then(|| {
-                        card.traits_unique
-                            .split("; ")
-                            .chain(card.traits.split("; "))
-                            .map(ToOwned::to_owned)
-                            .filter(|t| !is_empty(t))
-                            .collect()
-                    })
-                },
+                Attack::Str(attack.to_owned())
+            };
+
+            let traits = config.cell(row, "Traits");
+            card.traits = Some(Traits {
+                strings: (!(named.is_empty() && traits.is_empty())).then(|| {
+                    named
+                        .split("; ")
+                        .chain(traits.split("; "))
+                        .map(ToOwned::to_owned)
+                        .filter(|t| !is_empty(t))
+                        .collect()
+                }),
                 flags: TraitsFlag::empty(),
-            }),
-            related: vec![],
-            extra: (),
-        };
+            });
+
+            if card.description.is_empty() {
+                config
+                    .cell(row, "Description")
+                    .clone_into(&mut card.description);
+            }
 
-        cards.push(card);
+            card.extra.rulings = config
+                .cell(row, "Notes")
+                .split('\n')
+                .map(str::trim)
+                .filter(|n| !is_empty(n))
+                .map(ToOwned::to_owned)
+                .collect();
+            if let Some(r) = rulings.get(&card.name) {
+                card.extra.rulings.extend(r.iter().cloned());
+            }
+        },
+    )
+}
+
+/// The Descryption sheet, the traits, attack, portrait and rulings are fill in by
+/// [`fetch_desc_set`].
+fn desc_sheet_config() -> SheetConfig<DescCosts> {
+    SheetConfig {
+        sheet_id: "1EjOtqUrjsMRl7wiVMN7tMuvAHvkw7snv1dNyFJIFbaE".to_owned(),
+        name: "Descryption".to_owned(),
+        card_tab: "2".to_owned(),
+        sigil_tab: "4".to_owned(),
+        columns: SheetColumns {
+            temple: "Scrybes".to_owned(),
+            portrait: String::new(),
+            traits: String::new(),
+            related: String::new(),
+            ..SheetColumns::default()
+        },
+        rarities: vec![
+            ("Common".to_owned(), Rarity::COMMON),
+            ("Rare".to_owned(), Rarity::RARE),
+            ("Unique".to_owned(), Rarity::UNIQUE),
+        ],
+        temples: vec![
+            ("Leshy".to_owned(), Temple::BEAST),
+            ("Grimora".to_owned(), Temple::UNDEAD),
+            ("P03".to_owned(), Temple::TECH),
+            ("Magnificus".to_owned(), Temple::MAGICK),
+            ("Galliard".to_owned(), Temple::ARTISTRY),
+        ],
+        costs: desc_cost_vocab(),
+        ..SheetConfig::default()
     }
+}
 
-    Ok(Set {
-        code,
-        name: String::from("Descryption"),
-        cards,
-        sigils_description,
-    })
+/// The Descryption cost words, moxes are write by their color and the first black mox is a onyx
+/// while the other are a plus 1.
+fn desc_cost_vocab() -> CostVocab<DescCosts> {
+    let custom = CostUnit::<DescCosts>::Custom;
+    let mut vocab = CostVocab::default();
+    vocab.units.extend([
+        ("orange".to_owned(), CostUnit::Mox(Mox::O)),
+        ("green".to_owned(), CostUnit::Mox(Mox::G)),
+        ("blue".to_owned(), CostUnit::Mox(Mox::B)),
+        (
+            "black".to_owned(),
+            custom(|c, _| {
+                if c.mox.is_empty() {
+                    c.mox |= Mox::K;
+                    add_mox_count(c.mox_count.get_or_insert_with(MoxCount::default), Mox::K, 1);
+                } else {
+                    c.mox |= Mox::P1;
+                }
+            }),
+        ),
+        ("link".to_owned(), custom(|c, n| c.extra.link += n)),
+        ("links".to_owned(), custom(|c, n| c.extra.link += n)),
+        ("gold".to_owned(), custom(|c, n| c.extra.gold += n)),
+        ("golds".to_owned(), custom(|c, n| c.extra.gold += n)),
+    ]);
+    vocab
 }
 
 fn is_empty(str: &str) -> bool {
     str.is_empty() || str == "-" || str == "N/A"
 }
 
-/// Json scheme for desc card.
+/// Json scheme for desc ruling.
 #[derive(Deserialize)]
-struct DescCard {
+struct DescRuling {
     #[serde(rename = "Name")]
     #[serde(default)]
     name: String,
-
-    #[serde(rename = "Scrybes")]
-    #[serde(default)]
-    temple: String,
-    #[serde(rename = "Rarity")]
-    #[serde(default)]
-    rarity: String,
-
-    #[serde(rename = "Cost")]
-    #[serde(default)]
-    cost: String,
-
-    #[serde(rename = "Power")]
+    #[serde(rename = "Ruling")]
     #[serde(default)]
-    attack: String,
-    #[serde(rename = "Health")]
-    #[serde(default)]
-    health: String,
-
-    #[serde(rename = "Sigils")]
-    #[serde(default)]
-    sigils: String,
-
-    #[serde(rename = "Traits")]
-    #[serde(default)]
-    traits: String,
-    #[serde(rename = "Traits (Named)")]
-    #[serde(default)]
-    traits_unique: String,
-
-    #[serde(rename = "Tribes")]
-    #[serde(default)]
-    tribes: String,
-}
-
-/// Json scheme for desc sigil.
-#[derive(Deserialize)]
-struct DescSigil {
-    #[serde(rename = "Name")]
-    name: String,
-    #[serde(rename = "Text")]
-    text: String,
+    ruling: String,
 }
@@ -0,0 +1,418 @@
+use std::{collections::HashMap, io::Read, sync::OnceLock, thread, time::Duration};
+
+use isahc::{
+    config::Configurable,
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE, USER_AGENT},
+        StatusCode, Uri,
+    },
+    Body, ReadResponseExt, Request, Response,
+};
+use serde::Deserialize;
+
+use super::FetchError;
+
+/// The client use by every fetch function, see [`client`].
+static CLIENT: OnceLock<HttpClient> = OnceLock::new();
+
+/// Get the shared [`HttpClient`], it is build with the default config on first use unless
+/// [`set_client`] was call before.
+pub fn client() -> &'static HttpClient {
+    CLIENT.get_or_init(HttpClient::default)
+}
+
+/// Replace the default shared client, this must be call before any fetch.
+///
+/// Return the client back if the shared client was already set or use. Tests use this to make
+/// every fetcher read [fixtures](HttpClientBuilder::fixture) instead of the network.
+pub fn set_client(client: HttpClient) -> Result<(), HttpClient> {
+    CLIENT.set(client)
+}
+
+/// Http client with timeout, retry and a custom user agent.
+///
+/// Every request that fail to connect or get a server error (`5xx` or `429`) is retry with
+/// exponential backoff, so the wait double after every attempt. Other errors are return right
+/// away.
+///
+/// # Example
+/// ```
+/// use magpie_engine::fetch::HttpClient;
+/// use std::time::Duration;
+///
+/// let client = HttpClient::builder()
+///     .timeout(Duration::from_secs(10))
+///     .retries(5)
+///     .user_agent("my-bot/1.0")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct HttpClient {
+    client: isahc::HttpClient,
+    retries: u32,
+    backoff: Duration,
+    fixtures: HashMap<String, Vec<u8>>,
+    offline: bool,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        HttpClient::builder()
+            .build()
+            .expect("Cannot build default http client")
+    }
+}
+
+impl HttpClient {
+    /// Start building a client with the default config.
+    #[must_use]
+    pub fn builder() -> HttpClientBuilder {
+        HttpClientBuilder::default()
+    }
+
+    /// Get the body of a url.
+    pub fn get(&self, url: &str) -> Result<Vec<u8>, FetchError> {
+        if let Some(body) = self.fixture(url)? {
+            return Ok(body.to_vec());
+        }
+
+        self.send(true, || self.client.get(url))?
+            .bytes()
+            .map_err(|e| FetchError::IsahcError(e.into()))
+    }
+
+    /// Get the body of a url within some limits, use for urls from user data like portraits.
+    ///
+    /// Connection errors and server errors are retry like the other requests but timeouts are
+    /// not, so a slow host only hold the caller for the [timeout](DownloadLimits::timeout) once.
+    ///
+    /// The content type is not check for [fixtures](HttpClientBuilder::fixture) since they
+    /// don't have one.
+    ///
+    /// # Example
+    /// ```
+    /// use magpie_engine::fetch::{DownloadLimits, FetchError, HttpClient};
+    ///
+    /// let client = HttpClient::builder()
+    ///     .fixture("https://example.com/big.png", vec![0; 64])
+    ///     .offline()
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let limits = DownloadLimits::new().max_bytes(16);
+    /// assert!(matches!(
+    ///     client.get_limited("https://example.com/big.png", &limits),
+    ///     Err(FetchError::TooLarge(16))
+    /// ));
+    ///
+    /// let limits = DownloadLimits::new().max_bytes(64);
+    /// assert_eq!(
+    ///     client.get_limited("https://example.com/big.png", &limits).unwrap().len(),
+    ///     64
+    /// );
+    /// ```
+    pub fn get_limited(&self, url: &str, limits: &DownloadLimits) -> Result<Vec<u8>, FetchError> {
+        if let Some(body) = self.fixture(url)? {
+            return match limits.max_bytes {
+                Some(max) if body.len() as u64 > max => Err(FetchError::TooLarge(max)),
+                _ => Ok(body.to_vec()),
+            };
+        }
+
+        // a slow host would stall the caller for every retry so timeouts are not retry here
+        let mut res = self.send(false, || {
+            let mut request = Request::get(url);
+            if let Some(timeout) = limits.timeout {
+                request = request.timeout(timeout);
+            }
+            self.client
+                .send(request.body(()).map_err(isahc::Error::from)?)
+        })?;
+
+        if let Some(want) = &limits.content_type {
+            let got = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|t| t.to_str().ok())
+                .unwrap_or_default();
+            if !got.starts_with(want.as_str()) {
+                return Err(FetchError::UnexpectedContentType(got.to_owned()));
+            }
+        }
+
+        let Some(max) = limits.max_bytes else {
+            return res.bytes().map_err(|e| FetchError::IsahcError(e.into()));
+        };
+
+        // a server that tell the size up front can be reject without reading anything
+        let length = res
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|l| l.to_str().ok())
+            .and_then(|l| l.parse::<u64>().ok());
+        if length.is_some_and(|l| l > max) {
+            return Err(FetchError::TooLarge(max));
+        }
+
+        // read 1 more byte than the limit to know if the body is over it
+        let mut body = vec![];
+        res.body_mut()
+            .take(max + 1)
+            .read_to_end(&mut body)
+            .map_err(|e| FetchError::IsahcError(e.into()))?;
+
+        if body.len() as u64 > max {
+            return Err(FetchError::TooLarge(max));
+        }
+
+        Ok(body)
+    }
+
+    /// Get and parse json from a url.
+    pub fn get_json<S>(&self, url: &str) -> Result<S, FetchError>
+    where
+        S: for<'de> Deserialize<'de>,
+    {
+        if let Some(body) = self.fixture(url)? {
+            return serde_json::from_slice(body).map_err(FetchError::SerdeError);
+        }
+
+        self.send(true, || self.client.get(url))?
+            .json()
+            .map_err(FetchError::SerdeError)
+    }
+
+    /// Post a json body to a url with extra headers and parse the json response.
+    pub fn post_json<S>(
+        &self,
+        url: &str,
+        headers: &[(&str, String)],
+        body: &serde_json::Value,
+    ) -> Result<S, FetchError>
+    where
+        S: for<'de> Deserialize<'de>,
+    {
+        if let Some(body) = self.fixture(url)? {
+            return serde_json::from_slice(body).map_err(FetchError::SerdeError);
+        }
+
+        let body = serde_json::to_vec(body).map_err(FetchError::SerdeError)?;
+
+        self.send(true, || {
+            let mut request = Request::post(url).header("Content-Type", "application/json");
+            for (k, v) in headers {
+                request = request.header(*k, v);
+            }
+
+            self.client
+                .send(request.body(body.clone()).map_err(isahc::Error::from)?)
+        })?
+        .json()
+        .map_err(FetchError::SerdeError)
+    }
+
+    /// Get the fixture of a url, offline clients error on urls without one.
+    fn fixture(&self, url: &str) -> Result<Option<&[u8]>, FetchError> {
+        match self.fixtures.get(url) {
+            Some(body) => Ok(Some(body)),
+            None if self.offline => Err(FetchError::MissingFixture(url.to_owned())),
+            None => Ok(None),
+        }
+    }
+
+    /// Send a request, retrying if it fail with a transient error.
+    ///
+    /// Timeouts only count as transient if `retry_timeout` is set.
+    fn send<F>(&self, retry_timeout: bool, mut send: F) -> Result<Response<Body>, FetchError>
+    where
+        F: FnMut() -> Result<Response<Body>, isahc::Error>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            let res = send();
+
+            let transient = match &res {
+                Ok(r) => {
+                    r.status().is_server_error() || r.status() == StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => e.is_network() || (retry_timeout && e.is_timeout()),
+            };
+
+            if transient && attempt < self.retries {
+                thread::sleep(self.backoff * 2u32.pow(attempt));
+                attempt += 1;
+                continue;
+            }
+
+            let res = res.map_err(FetchError::IsahcError)?;
+            return if res.status().is_success() {
+                Ok(res)
+            } else {
+                Err(FetchError::HttpError(res.status()))
+            };
+        }
+    }
+}
+
+/// Limits for [`HttpClient::get_limited`], every limit is off by default.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadLimits {
+    timeout: Option<Duration>,
+    max_bytes: Option<u64>,
+    content_type: Option<String>,
+}
+
+impl DownloadLimits {
+    /// Create limits with every limit off.
+    #[must_use]
+    pub fn new() -> Self {
+        DownloadLimits::default()
+    }
+
+    /// Set how long the download can take, this replace the client timeout.
+    ///
+    /// Unlike the other requests a download that time out is not retry.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the most bytes the body can have.
+    #[must_use]
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Set what the content type must start with, like `image/` for any image.
+    #[must_use]
+    pub fn content_type(mut self, content_type: &str) -> Self {
+        self.content_type = Some(content_type.to_owned());
+        self
+    }
+}
+
+/// Builder for [`HttpClient`].
+#[derive(Debug, Clone)]
+pub struct HttpClientBuilder {
+    timeout: Duration,
+    retries: u32,
+    backoff: Duration,
+    user_agent: String,
+    proxy: Option<String>,
+    fixtures: HashMap<String, Vec<u8>>,
+    offline: bool,
+}
+
+impl Default for HttpClientBuilder {
+    fn default() -> Self {
+        HttpClientBuilder {
+            timeout: Duration::from_secs(30),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            user_agent: format!("magpie_engine/{}", env!("CARGO_PKG_VERSION")),
+            proxy: None,
+            fixtures: HashMap::new(),
+            offline: false,
+        }
+    }
+}
+
+impl HttpClientBuilder {
+    /// Set how long a single attempt can take, default to 30 seconds.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set how many time a failed request is retry, default to 3.
+    #[must_use]
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Set the wait before the first retry, default to 500ms.
+    #[must_use]
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set the user agent send with every request.
+    #[must_use]
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        user_agent.clone_into(&mut self.user_agent);
+        self
+    }
+
+    /// Send every request through a proxy.
+    ///
+    /// Without this the proxy environment variables like `https_proxy` are use.
+    #[must_use]
+    pub fn proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(proxy.to_owned());
+        self
+    }
+
+    /// Answer every request to `url` with `body` instead of sending it, use to test fetchers with
+    /// recorded responses.
+    ///
+    /// # Example
+    /// ```
+    /// use magpie_engine::fetch::{set_client, HttpClient};
+    /// use magpie_engine::prelude::*;
+    ///
+    /// let json = r#"{ "ruleset": "Test", "cards": [{ "name": "Stoat", "attack": 1, "health": 3 }], "sigils": {} }"#;
+    /// let client = HttpClient::builder()
+    ///     .fixture("https://example.com/set.json", json)
+    ///     .offline()
+    ///     .build()
+    ///     .unwrap();
+    /// set_client(client).unwrap();
+    ///
+    /// let set = fetch_imf_set("https://example.com/set.json", SetCode::new("tst").unwrap()).unwrap();
+    /// assert_eq!(set.cards[0].name, "Stoat");
+    ///
+    /// assert!(fetch_imf_set("https://example.com/other.json", SetCode::new("tst").unwrap()).is_err());
+    /// ```
+    #[must_use]
+    pub fn fixture(mut self, url: &str, body: impl Into<Vec<u8>>) -> Self {
+        self.fixtures.insert(url.to_owned(), body.into());
+        self
+    }
+
+    /// Error with [`FetchError::MissingFixture`] on urls without a [fixture](Self::fixture)
+    /// instead of sending the request.
+    #[must_use]
+    pub fn offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+
+    /// Build the client.
+    pub fn build(self) -> Result<HttpClient, FetchError> {
+        let mut builder = isahc::HttpClient::builder()
+            .timeout(self.timeout)
+            .default_header(USER_AGENT, self.user_agent);
+
+        if let Some(proxy) = self.proxy {
+            let uri = proxy
+                .parse::<Uri>()
+                .map_err(|_| FetchError::InvalidProxy(proxy.clone()))?;
+            builder = builder.proxy(Some(uri));
+        }
+
+        Ok(HttpClient {
+            client: builder.build().map_err(FetchError::IsahcError)?,
+            retries: self.retries,
+            backoff: self.backoff,
+            fixtures: self.fixtures,
+            offline: self.offline,
+        })
+    }
+}
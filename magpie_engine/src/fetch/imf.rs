@@ -1,200 +1,364 @@
 //! Implementation for [IMF] set.
 //!
+//! Cards can also be export back to the IMF ruleset scheme with [`to_imf_set`] so custom card
+//! creators can reuse cards from other sets in their own ruleset. Some infomation are lost since
+//! IMF have less feature, like the extra moxes or string special attacks.
+//!
 //! [IMF]: https://107zxz.itch.io/inscryption-multiplayer-godot
 
-use std::collections::HashMap;
+use std::{collections::HashMap, hash::BuildHasher, sync::Arc};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    fetch::fetch_json, helper::FlagsExt, Attack, Card, Costs, Mox, Rarity, Set, SetCode, SpAtk,
-    Temple, Traits, TraitsFlag,
+    builder::{CardBuilder, CostsBuilder},
+    fetch::fetch_json,
+    helper::FlagsExt,
+    query::text::TextIndex,
+    Attack, Card, Mox, Rarity, Set, SetCode, SpAtk, Temple, Traits, TraitsFlag,
 };
 
-use super::{SetError, SetResult};
+use super::{keep_valid, resolve_sigil, SetError, SetResult};
 
 /// Fetch a IMF Set from a url.
 pub fn fetch_imf_set(url: &str, code: SetCode) -> SetResult<(), ()> {
     let set: ImfSet = fetch_json(url).map_err(|e| SetError::FetchError(e, url.to_string()))?;
+    convert_imf_set(set, code)
+}
+
+/// Parse a IMF Set from json, like a user uploaded file.
+///
+/// The json can be a whole ruleset or a single card. A single card have no sigil descriptions so
+/// its sigils are keep with an empty description for the caller to fill in.
+///
+/// # Examples
+/// ```
+/// use magpie_engine::prelude::*;
+///
+/// let json = r#"{ "name": "Stoat", "attack": 1, "health": 3, "blood_cost": 1 }"#;
+/// let set = parse_imf_set(json, SetCode::new("prv").unwrap()).unwrap();
+///
+/// assert_eq!(set.cards[0].name, "Stoat");
+/// assert_eq!(set.cards[0].costs.as_ref().unwrap().blood, 1);
+///
+/// assert!(parse_imf_set("{}", SetCode::new("prv").unwrap()).is_err());
+/// ```
+pub fn parse_imf_set(json: &str, code: SetCode) -> SetResult<(), ()> {
+    let set = match serde_json::from_str::<ImfSet>(json) {
+        Ok(set) => set,
+        Err(set_err) => {
+            let card: ImfCard = serde_json::from_str(json).map_err(|card_err| {
+                SetError::DeserializeError(format!(
+                    "not a IMF ruleset ({set_err}) or card ({card_err})"
+                ))
+            })?;
 
+            ImfSet {
+                ruleset: card.name.clone(),
+                sigils: card
+                    .sigils
+                    .iter()
+                    .map(|s| (s.clone(), String::new()))
+                    .collect(),
+                cards: vec![card],
+            }
+        }
+    };
+
+    convert_imf_set(set, code)
+}
+
+/// Convert the IMF json scheme to a set.
+fn convert_imf_set(set: ImfSet, code: SetCode) -> SetResult<(), ()> {
     let mut cards = Vec::with_capacity(set.cards.len() + 1);
+    let mut issues = vec![];
 
-    let mut sigils_description = HashMap::with_capacity(set.sigils.len());
+    let mut sigils_description: HashMap<Arc<str>, String> =
+        HashMap::with_capacity(set.sigils.len());
 
     for s in set.sigils {
-        sigils_description.insert(s.0, s.1);
+        sigils_description.insert(s.0.into(), s.1);
     }
 
-    sigils_description.insert(
-        String::from("UNDEFINEDED SIGILS"),
-        "THIS SIGIL IS NOT DEFINED BY THE SET".to_owned(),
-    );
-
     for c in set.cards {
-        let card = Card {
-            set: code,
-
-            portrait: c
-                .pixport_url
-                .is_empty()
-                .then_some(format!(
-                    "https://raw.githubusercontent.com/107zxz/inscr-onln/main/gfx/pixport/{}.png",
-                    c.name.replace(' ', "%20")
-                ))
-                .unwrap_or(c.pixport_url),
-
-            name: c.name,
-            description: c.description,
-
-            rarity: if c.rare { Rarity::RARE } else { Rarity::COMMON },
-            temple: Temple::empty()
-                .set_if(Temple::BEAST, c.blood_cost != 0)
-                .set_if(Temple::UNDEAD, c.bone_cost != 0)
-                .set_if(Temple::TECH, c.energy_cost != 0)
-                .set_if(Temple::MAGICK, !c.mox_cost.is_empty()),
-            tribes: None,
-
-            attack: {
-                if c.atkspecial.is_empty() {
-                    Attack::Num(c.attack)
-                } else {
-                    let atk = c.atkspecial.as_str();
-                    Attack::SpAtk(match atk {
-                        "mox" => SpAtk::MOX,
-                        "green_mox" => SpAtk::GREEN_MOX,
-                        "mirror" => SpAtk::MIRROR,
-                        "ant" => SpAtk::ANT,
-                        _ => return Err(SetError::UnknownSpAtk(c.atkspecial)),
-                    })
-                }
-            },
-            health: c.health,
-            sigils: c
-                .sigils
-                .into_iter()
-                .map(|s| {
-                    if sigils_description.contains_key(&s) {
-                        s
-                    } else {
-                        String::from("UNDEFINEDED SIGILS")
-                    }
-                })
-                .collect(),
-
-            costs: ((c.blood_cost > 0)
-                | (c.bone_cost > 0)
-                | (c.energy_cost > 0)
-                | (!c.mox_cost.is_empty()))
-            .then(|| Costs {
-                blood: c.blood_cost,
-                bone: c.bone_cost,
-                energy: c.energy_cost,
-                mox: c
-                    .mox_cost
-                    .iter()
-                    .fold(Mox::empty(), |flags, mox| match mox.as_str() {
-                        "Orange" => flags | Mox::O,
-                        "Green" => flags | Mox::G,
-                        "Blue" => flags | Mox::B,
-                        _ => unreachable!(),
-                    }),
-                mox_count: None,
-                extra: (),
-            }),
-
-            traits: (c.conduit | c.banned | c.nosac | c.nohammer).then(|| Traits {
-                strings: None,
-                flags: TraitsFlag::empty()
-                    .set_if(TraitsFlag::CONDUCTIVE, c.conduit)
-                    .set_if(TraitsFlag::BAN, c.banned)
-                    .set_if(TraitsFlag::TERRAIN, c.nosac)
-                    .set_if(TraitsFlag::HARD, c.nohammer),
-            }),
-
-            related: {
-                let mut v = Vec::new();
-
-                if !c.evolution.is_empty() {
-                    v.push(c.evolution);
-                }
+        let mox = c.mox_cost.iter().try_fold(Mox::empty(), |flags, mox| {
+            Ok(match mox.as_str() {
+                "Orange" => flags | Mox::O,
+                "Green" => flags | Mox::G,
+                "Blue" => flags | Mox::B,
+                _ => return Err(SetError::UnknownMoxColor(mox.clone())),
+            })
+        })?;
+
+        let portrait = if c.pixport_url.is_empty() {
+            format!(
+                "https://raw.githubusercontent.com/107zxz/inscr-onln/main/gfx/pixport/{}.png",
+                c.name.replace(' ', "%20")
+            )
+        } else {
+            c.pixport_url
+        };
 
-                if !c.left_half.is_empty() {
-                    v.push(c.left_half);
-                }
+        let attack = if c.atkspecial.is_empty() {
+            Attack::Num(c.attack)
+        } else {
+            Attack::SpAtk(match c.atkspecial.as_str() {
+                "mox" => SpAtk::MOX,
+                "green_mox" => SpAtk::GREEN_MOX,
+                "mirror" => SpAtk::MIRROR,
+                "ant" => SpAtk::ANT,
+                _ => return Err(SetError::UnknownSpAtk(c.atkspecial)),
+            })
+        };
 
-                if !c.right_half.is_empty() {
-                    v.push(c.right_half);
+        let sigils = c
+            .sigils
+            .iter()
+            .map(|s| resolve_sigil(&mut sigils_description, &c.name, s, &mut issues))
+            .collect::<Vec<_>>();
+
+        let related = [c.evolution, c.left_half, c.right_half]
+            .into_iter()
+            .filter(|r| !r.is_empty())
+            .collect();
+
+        let card = CardBuilder::new(&c.name)
+            .set(code)
+            .portrait(&portrait)
+            .description(&c.description)
+            .rarity(if c.rare { Rarity::RARE } else { Rarity::COMMON })
+            .temple(
+                Temple::empty()
+                    .set_if(Temple::BEAST, c.blood_cost != 0)
+                    .set_if(Temple::UNDEAD, c.bone_cost != 0)
+                    .set_if(Temple::TECH, c.energy_cost != 0)
+                    .set_if(Temple::MAGICK, !c.mox_cost.is_empty()),
+            )
+            .attack(attack)
+            .health(c.health)
+            .sigils(sigils)
+            .costs(
+                CostsBuilder::new()
+                    .blood(c.blood_cost)
+                    .bone(c.bone_cost)
+                    .energy(c.energy_cost)
+                    .mox(mox)
+                    .build(),
+            )
+            .traits((c.conduit | c.banned | c.nosac | c.nohammer).then(|| {
+                Traits {
+                    strings: None,
+                    flags: TraitsFlag::empty()
+                        .set_if(TraitsFlag::CONDUCTIVE, c.conduit)
+                        .set_if(TraitsFlag::BAN, c.banned)
+                        .set_if(TraitsFlag::TERRAIN, c.nosac)
+                        .set_if(TraitsFlag::HARD, c.nohammer),
                 }
+            }))
+            .related(related)
+            .build();
 
-                v
-            },
-
-            extra: (),
-        };
-
-        cards.push(card);
+        if let Some(card) = keep_valid(&c.name, card, &mut issues) {
+            cards.push(card);
+        }
     }
-    Ok(Set {
+
+    let mut set = Set {
         code,
         name: set.ruleset,
         cards,
         sigils_description,
-    })
+        sigils_category: HashMap::new(),
+        pools: HashMap::new(),
+        text_index: TextIndex::default(),
+        issues,
+    };
+    set.reindex();
+
+    Ok(set)
+}
+
+/// Convert cards to a IMF ruleset.
+///
+/// Only the description of the sigils the cards use are include.
+///
+/// # Examples
+/// ```
+/// use magpie_engine::prelude::*;
+/// let set: Set<(), ()> = SetBuilder::new("hom", "Homebrew")
+///     .unwrap()
+///     .sigil("Sharp Quills", "When this card is struck, the striker is dealt 1 damage.")
+///     .card(
+///         CardBuilder::new("Porcupine")
+///             .attack(Attack::Num(1))
+///             .health(2)
+///             .sigils(vec!["Sharp Quills".to_string()]),
+///     )
+///     .unwrap()
+///     .build();
+///
+/// let imf = to_imf_set("My Ruleset", &set.cards, &set.sigils_description);
+///
+/// assert_eq!(imf.cards[0].name, "Porcupine");
+/// assert_eq!(imf.sigils.len(), 1);
+/// assert!(serde_json::to_string(&imf).is_ok());
+/// ```
+pub fn to_imf_set<'a, E, C, S>(
+    name: &str,
+    cards: impl IntoIterator<Item = &'a Card<E, C>>,
+    sigils_description: &HashMap<Arc<str>, String, S>,
+) -> ImfSet
+where
+    S: BuildHasher,
+    E: Clone + 'a,
+    C: Clone + PartialEq + 'a,
+{
+    let cards = cards.into_iter().map(to_imf_card).collect::<Vec<_>>();
+
+    let sigils = cards
+        .iter()
+        .flat_map(|c| &c.sigils)
+        .filter_map(|s| {
+            sigils_description
+                .get(s.as_str())
+                .map(|desc| (s.clone(), desc.clone()))
+        })
+        .collect();
+
+    ImfSet {
+        ruleset: name.to_owned(),
+        cards,
+        sigils,
+    }
+}
+
+/// Convert a card to a IMF card.
+///
+/// The first 3 related cards are use as the evolution, left half and right half.
+#[must_use]
+pub fn to_imf_card<E, C>(card: &Card<E, C>) -> ImfCard
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    let (attack, atkspecial) = match &card.attack {
+        Attack::Num(a) => (*a, ""),
+        Attack::SpAtk(SpAtk::MOX) => (0, "mox"),
+        Attack::SpAtk(SpAtk::GREEN_MOX) => (0, "green_mox"),
+        Attack::SpAtk(SpAtk::MIRROR) => (0, "mirror"),
+        Attack::SpAtk(SpAtk::ANT) => (0, "ant"),
+        Attack::SpAtk(_) | Attack::Str(_) => (0, ""),
+    };
+
+    let flags = card
+        .traits
+        .as_ref()
+        .map_or(TraitsFlag::empty(), |t| t.flags);
+    let mut related = card.related.iter().cloned();
+
+    ImfCard {
+        name: card.name.clone(),
+        description: card.description.clone(),
+
+        attack,
+        health: card.health,
+
+        sigils: card.sigils.iter().map(ToString::to_string).collect(),
+        atkspecial: atkspecial.to_owned(),
+
+        blood_cost: card.costs.as_ref().map_or(0, |c| c.blood),
+        bone_cost: card.costs.as_ref().map_or(0, |c| c.bone),
+        energy_cost: card.costs.as_ref().map_or(0, |c| c.energy),
+        mox_cost: card.costs.as_ref().map_or(vec![], |c| {
+            [(Mox::O, "Orange"), (Mox::G, "Green"), (Mox::B, "Blue")]
+                .into_iter()
+                .filter(|(m, _)| c.mox.contains(*m))
+                .map(|(_, name)| name.to_owned())
+                .collect()
+        }),
+
+        pixport_url: card.portrait.clone(),
+
+        conduit: flags.contains(TraitsFlag::CONDUCTIVE),
+        banned: flags.contains(TraitsFlag::BAN),
+        rare: matches!(card.rarity, Rarity::RARE | Rarity::UNIQUE),
+        nosac: flags.contains(TraitsFlag::TERRAIN),
+        nohammer: flags.contains(TraitsFlag::HARD),
+
+        evolution: related.next().unwrap_or_default(),
+        left_half: related.next().unwrap_or_default(),
+        right_half: related.next().unwrap_or_default(),
+    }
 }
 
 /// Json scheme for IMF set.
-#[derive(Deserialize, Debug)]
-struct ImfSet {
-    ruleset: String,
-    cards: Vec<ImfCard>,
-    sigils: HashMap<String, String>,
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ImfSet {
+    /// The ruleset name.
+    pub ruleset: String,
+    /// The cards in the ruleset.
+    pub cards: Vec<ImfCard>,
+    /// The sigils name and description.
+    pub sigils: HashMap<String, String>,
 }
 
 /// Json scheme for IMF card.
-#[derive(Debug, Deserialize)]
-#[allow(clippy::struct_excessive_bools)]
-struct ImfCard {
+///
+/// Empty and default fields are not serialize to keep the export close to a hand written ruleset.
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools, missing_docs)]
+pub struct ImfCard {
     pub name: String,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub description: String,
 
     pub attack: isize,
     pub health: isize,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub sigils: Vec<String>,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub atkspecial: String,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub blood_cost: isize,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub bone_cost: isize,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub energy_cost: isize,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub mox_cost: Vec<String>,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub pixport_url: String,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub conduit: bool,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub banned: bool,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub rare: bool,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub nosac: bool,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub nohammer: bool,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub evolution: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub left_half: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub right_half: String,
 }
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // serde pass the field by ref
+fn is_zero(value: &isize) -> bool {
+    *value == 0
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(value: &bool) -> bool {
+    !value
+}
@@ -0,0 +1,386 @@
+//! Implementation for sets on a google sheet.
+//!
+//! Most sets keep their cards on a google sheet that [opensheet] turn into json. Instead of a new
+//! fetcher for every sheet, a [`SheetConfig`] describe which column hold which card field and how
+//! to read the cost column with a [`CostVocab`] then [`fetch_sheet_set`] do the rest. Sets with
+//! columns the config don't cover, like Augmented and Descryption, fill them in with
+//! [`build_sheet_set_with`].
+//!
+//! [opensheet]: https://github.com/benborgers/opensheet
+//!
+//! # Examples
+//! ```
+//! use magpie_engine::prelude::*;
+//!
+//! let config: SheetConfig<()> = SheetConfig {
+//!     name: "Homebrew".to_owned(),
+//!     ..SheetConfig::default()
+//! };
+//!
+//! // rows as opensheet return them
+//! let row = |pairs: &[(&str, &str)]| {
+//!     pairs
+//!         .iter()
+//!         .map(|(k, v)| (k.to_string(), v.to_string()))
+//!         .collect::<SheetRow>()
+//! };
+//! let cards = vec![row(&[
+//!     ("Name", "Bullfrog"),
+//!     ("Cost", "1 blood"),
+//!     ("Power", "1"),
+//!     ("Health", "2"),
+//!     ("Sigils", "Mighty Leap"),
+//! ])];
+//! let sigils = vec![row(&[("Name", "Mighty Leap"), ("Text", "Block airborne creatures.")])];
+//!
+//! let set: Set<(), ()> =
+//!     build_sheet_set(&config, &cards, &sigils, SetCode::new("hom").unwrap()).unwrap();
+//!
+//! assert_eq!(set.cards[0].costs.as_ref().unwrap().blood, 1);
+//! assert_eq!(&*set.cards[0].sigils[0], "Mighty Leap");
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    builder::CardBuilder, cost::CostVocab, fetch::fetch_json, query::text::TextIndex, Card,
+    CardRef, Rarity, Set, SetCode, Temple,
+};
+
+use super::{
+    aug::{parse_attack, parse_traits},
+    keep_valid, parse_costs, parse_stat, resolve_sigil, SetError, SetResult,
+};
+
+/// A row of a sheet, the column name to the cell text.
+pub type SheetRow = HashMap<String, String>;
+
+/// Config for a set on a google sheet.
+#[derive(Debug, Clone)]
+pub struct SheetConfig<C> {
+    /// The google sheet id.
+    pub sheet_id: String,
+    /// The set name.
+    pub name: String,
+    /// The tab with the cards, either the tab name or its number.
+    pub card_tab: String,
+    /// The tab with the sigils, either the tab name or its number.
+    pub sigil_tab: String,
+    /// Which column hold which card field.
+    pub columns: SheetColumns,
+    /// Separator for columns with multiple values like sigils and temples.
+    pub separator: String,
+    /// Cell text that count as empty, beside a actual empty cell.
+    pub empty: Vec<String>,
+    /// Rarity column text and their rarity, case insensitive.
+    ///
+    /// Unknown rarities are keep as [`Rarity::Other`].
+    pub rarities: Vec<(String, Rarity)>,
+    /// Temple column text and their temple, case insensitive.
+    ///
+    /// Unknown temples are keep in [`Card::other_temples`].
+    pub temples: Vec<(String, Temple)>,
+    /// The cost words of the cost column.
+    pub costs: CostVocab<C>,
+    /// Url for the card portrait when the portrait column is empty, `{name}` is replace by the
+    /// card name.
+    pub portrait_url: String,
+}
+
+/// The column name of every card field in a [`SheetConfig`].
+///
+/// Columns that are not in the sheet are treat as empty.
+#[derive(Debug, Clone)]
+pub struct SheetColumns {
+    /// Card name column.
+    pub name: String,
+    /// Card description column.
+    pub description: String,
+    /// Card portrait url column.
+    pub portrait: String,
+    /// Card rarity column.
+    pub rarity: String,
+    /// Card temple column.
+    pub temple: String,
+    /// Card tribes column.
+    pub tribes: String,
+    /// Card cost column.
+    pub cost: String,
+    /// Card attack column.
+    pub attack: String,
+    /// Card health column.
+    pub health: String,
+    /// Card sigils column.
+    pub sigils: String,
+    /// Card traits column.
+    pub traits: String,
+    /// Card related cards column.
+    pub related: String,
+    /// Column to [pool](Set::pools) the cards by.
+    pub pool: String,
+    /// Sigil name column in the sigil tab.
+    pub sigil_name: String,
+    /// Sigil description column in the sigil tab.
+    pub sigil_text: String,
+}
+
+impl Default for SheetColumns {
+    /// The column names most sheets use.
+    fn default() -> Self {
+        SheetColumns {
+            name: "Name".to_owned(),
+            description: "Flavor Text".to_owned(),
+            portrait: "Portrait".to_owned(),
+            rarity: "Rarity".to_owned(),
+            temple: "Temple".to_owned(),
+            tribes: "Tribes".to_owned(),
+            cost: "Cost".to_owned(),
+            attack: "Power".to_owned(),
+            health: "Health".to_owned(),
+            sigils: "Sigils".to_owned(),
+            traits: "Traits".to_owned(),
+            related: "Token".to_owned(),
+            pool: String::new(),
+            sigil_name: "Name".to_owned(),
+            sigil_text: "Text".to_owned(),
+        }
+    }
+}
+
+impl<C> Default for SheetConfig<C> {
+    /// Cards in the second tab and sigils in the third with the built in rarities, temples and
+    /// costs.
+    fn default() -> Self {
+        SheetConfig {
+            sheet_id: String::new(),
+            name: String::new(),
+            card_tab: "2".to_owned(),
+            sigil_tab: "3".to_owned(),
+            columns: SheetColumns::default(),
+            separator: ",".to_owned(),
+            empty: vec!["-".to_owned(), "N/A".to_owned()],
+            rarities: vec![
+                ("Side Deck".to_owned(), Rarity::SIDE),
+                ("Common".to_owned(), Rarity::COMMON),
+                ("Uncommon".to_owned(), Rarity::UNCOMMON),
+                ("Rare".to_owned(), Rarity::RARE),
+                ("Unique".to_owned(), Rarity::UNIQUE),
+            ],
+            temples: vec![
+                ("Beast".to_owned(), Temple::BEAST),
+                ("Undead".to_owned(), Temple::UNDEAD),
+                ("Tech".to_owned(), Temple::TECH),
+                ("Magick".to_owned(), Temple::MAGICK),
+                ("Fool".to_owned(), Temple::FOOL),
+                ("Artistry".to_owned(), Temple::ARTISTRY),
+            ],
+            costs: CostVocab::default(),
+            portrait_url: String::new(),
+        }
+    }
+}
+
+/// Fetch a set from a google sheet with a [`SheetConfig`].
+pub fn fetch_sheet_set<E, C>(config: &SheetConfig<C>, code: SetCode) -> SetResult<E, C>
+where
+    E: Clone + Default,
+    C: Clone + PartialEq + Default,
+{
+    let (cards, sigils) = fetch_sheet_rows(config)?;
+    build_sheet_set(config, &cards, &sigils, code)
+}
+
+/// Fetch the card rows and the sigil rows of a sheet.
+pub fn fetch_sheet_rows<C>(
+    config: &SheetConfig<C>,
+) -> Result<(Vec<SheetRow>, Vec<SheetRow>), SetError> {
+    let card_url = format!(
+        "https://opensheet.elk.sh/{}/{}",
+        config.sheet_id, config.card_tab
+    );
+    let cards: Vec<SheetRow> =
+        fetch_json(&card_url).map_err(|e| SetError::FetchError(e, card_url.clone()))?;
+
+    let sigil_url = format!(
+        "https://opensheet.elk.sh/{}/{}",
+        config.sheet_id, config.sigil_tab
+    );
+    let sigils: Vec<SheetRow> =
+        fetch_json(&sigil_url).map_err(|e| SetError::FetchError(e, sigil_url.clone()))?;
+
+    Ok((cards, sigils))
+}
+
+/// Build a set from already fetched sheet rows with a [`SheetConfig`].
+///
+/// Rows without a name are skip.
+pub fn build_sheet_set<E, C>(
+    config: &SheetConfig<C>,
+    cards: &[SheetRow],
+    sigils: &[SheetRow],
+    code: SetCode,
+) -> SetResult<E, C>
+where
+    E: Clone + Default,
+    C: Clone + PartialEq + Default,
+{
+    build_sheet_set_with(config, cards, sigils, code, |_, _| ())
+}
+
+/// Same as [`build_sheet_set`] but `finish` is call with the row and the card of every card.
+///
+/// Use it for the fields the config don't cover, like the card [`extra`](Card::extra) or a
+/// portrait url that need more than the card name.
+pub fn build_sheet_set_with<E, C, F>(
+    config: &SheetConfig<C>,
+    cards: &[SheetRow],
+    sigils: &[SheetRow],
+    code: SetCode,
+    mut finish: F,
+) -> SetResult<E, C>
+where
+    E: Clone + Default,
+    C: Clone + PartialEq + Default,
+    F: FnMut(&SheetRow, &mut Card<E, C>),
+{
+    let col = &config.columns;
+
+    let mut sigils_description: HashMap<Arc<str>, String> =
+        HashMap::with_capacity(sigils.len() + 1);
+    for s in sigils {
+        let name = config.cell(s, &col.sigil_name);
+        if !name.is_empty() {
+            sigils_description.insert(name.into(), config.cell(s, &col.sigil_text).to_owned());
+        }
+    }
+
+    let mut set_cards: Vec<Card<E, C>> = Vec::with_capacity(cards.len());
+    let mut pools: HashMap<String, Vec<CardRef>> = HashMap::new();
+    let mut issues = vec![];
+
+    for row in cards {
+        let name = config.cell(row, &col.name);
+        if name.is_empty() {
+            continue;
+        }
+
+        let mut temple = Temple::empty();
+        let mut other_temples = vec![];
+        for t in config.list(row, &col.temple) {
+            match config
+                .temples
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(t))
+            {
+                Some((_, found)) => temple |= *found,
+                None => other_temples.push(t.to_owned()),
+            }
+        }
+
+        let rarity = match config.cell(row, &col.rarity) {
+            "" => Rarity::COMMON,
+            r => config
+                .rarities
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(r))
+                .map_or_else(|| Rarity::Other(r.to_owned()), |(_, r)| r.clone()),
+        };
+
+        let portrait = match config.cell(row, &col.portrait) {
+            "" => config
+                .portrait_url
+                .replace("{name}", &name.replace(' ', "%20")),
+            p => p.to_owned(),
+        };
+
+        let tribes = config.cell(row, &col.tribes);
+        let sigils = config
+            .list(row, &col.sigils)
+            .map(|s| resolve_sigil(&mut sigils_description, name, s, &mut issues))
+            .collect::<Vec<_>>();
+
+        let card = CardBuilder::new(name)
+            .set(code)
+            .description(config.cell(row, &col.description))
+            .portrait(&portrait)
+            .rarity(rarity)
+            .temple(temple)
+            .other_temples(other_temples)
+            .tribes((!tribes.is_empty()).then(|| tribes.to_owned()))
+            .attack(parse_attack(config.cell(row, &col.attack)))
+            .health(parse_stat(
+                name,
+                "health",
+                config.cell(row, &col.health),
+                &mut issues,
+            ))
+            .sigils(sigils)
+            .costs(parse_costs(
+                name,
+                config.cell(row, &col.cost),
+                &config.costs,
+                &mut issues,
+            ))
+            .traits(parse_traits(config.cell(row, &col.traits)))
+            .related(
+                config
+                    .list(row, &col.related)
+                    .map(ToOwned::to_owned)
+                    .collect(),
+            )
+            .build();
+        let Some(mut card) = keep_valid(name, card, &mut issues) else {
+            continue;
+        };
+
+        let pool = config.cell(row, &col.pool);
+        if !pool.is_empty() {
+            pools
+                .entry(pool.to_owned())
+                .or_default()
+                .push(set_cards.len());
+        }
+
+        finish(row, &mut card);
+        set_cards.push(card);
+    }
+
+    let mut set = Set {
+        code,
+        name: config.name.clone(),
+        cards: set_cards,
+        sigils_description,
+        sigils_category: HashMap::new(),
+        pools,
+        text_index: TextIndex::default(),
+        issues,
+    };
+    set.reindex();
+
+    Ok(set)
+}
+
+impl<C> SheetConfig<C>
+where
+    C: Default + PartialEq,
+{
+    /// Get a trimmed cell, missing columns and [empty](SheetConfig::empty) cells are `""`.
+    #[must_use]
+    pub fn cell<'a>(&self, row: &'a SheetRow, column: &str) -> &'a str {
+        let cell = row.get(column).map_or("", |c| c.trim());
+        if self.empty.iter().any(|e| e == cell) {
+            ""
+        } else {
+            cell
+        }
+    }
+
+    /// Split a cell with multiple values.
+    pub fn list<'a>(&'a self, row: &'a SheetRow, column: &str) -> impl Iterator<Item = &'a str> {
+        self.cell(row, column)
+            .split(self.separator.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    }
+}
@@ -23,3 +23,28 @@ pub trait FlagsExt: Flags {
 }
 
 impl<T> FlagsExt for T where T: Flags {}
+
+/// Similarity of 2 strings from 0 to 1 base on the levenshtein distance, 1 is the same string.
+pub(crate) fn similarity(a: &str, b: &str) -> f32 {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let max = a.len().max(b.len());
+    if max == 0 {
+        return 1.0;
+    }
+
+    let mut prev: Vec<usize> = (0..=a.len()).collect();
+    for (row, cb) in b.iter().enumerate() {
+        let mut diag = prev[0];
+        prev[0] = row + 1;
+        for (col, ca) in a.iter().enumerate() {
+            let cost = if ca == cb { diag } else { diag + 1 };
+            diag = prev[col + 1];
+            prev[col + 1] = cost.min(prev[col] + 1).min(prev[col + 1] + 1);
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let sim = (max - prev[a.len()]) as f32 / max as f32;
+    sim
+}
@@ -13,9 +13,16 @@ pub mod prelude;
 
 mod helper;
 
+pub mod builder;
+pub mod cost;
+pub mod deck;
+pub mod diff;
 pub mod fetch;
+pub mod pack;
 pub mod query;
 
+pub use diff::diff;
+
 pub use data::cards::*;
 pub use data::sets::*;
 
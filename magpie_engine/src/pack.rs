@@ -0,0 +1,119 @@
+//! Implementation for generating random booster packs from a set.
+//!
+//! Each slot of a pack first roll a rarity using the [`PackRules::weights`] then pick a random card
+//! of that rarity. A card won't show up twice in the same pack and rarities that run out of cards
+//! are skip. Banned cards are never put into a pack.
+//!
+//! # Examples
+//!
+//! ```
+//! use magpie_engine::prelude::*;
+//!
+//! // Fetch the set to open packs from
+//! let imf = fetch_imf_set(
+//!     "https://raw.githubusercontent.com/107zxz/inscr-onln-ruleset/main/standard.json",
+//!     SetCode::new("std").unwrap(),
+//! ).unwrap();
+//!
+//! // Open a pack with the default rarity distribution
+//! let pack = PackRules::default().open(&imf, &mut rand::thread_rng());
+//!
+//! assert!(pack.len() <= 8);
+//! ```
+
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+
+use crate::{Card, Rarity, Set, TraitsFlag};
+
+/// Rules on how to generate a pack.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackRules {
+    /// How many cards are in a pack.
+    pub size: usize,
+    /// The rarities that can show up in a pack and their weight.
+    ///
+    /// The chance for a slot to be a rarity is the rarity weight over the total weight.
+    pub weights: Vec<(Rarity, u32)>,
+}
+
+impl Default for PackRules {
+    /// A 8 cards pack with mostly common and a small chance for rare and unique.
+    fn default() -> Self {
+        PackRules {
+            size: 8,
+            weights: vec![
+                (Rarity::COMMON, 60),
+                (Rarity::UNCOMMON, 25),
+                (Rarity::RARE, 12),
+                (Rarity::UNIQUE, 3),
+            ],
+        }
+    }
+}
+
+impl PackRules {
+    /// Open a pack from a set.
+    ///
+    /// The pack may be smaller than [`PackRules::size`] if the set run out of cards with the
+    /// rarities in [`PackRules::weights`].
+    pub fn open<'a, E, C, R>(&self, set: &'a Set<E, C>, rng: &mut R) -> Vec<&'a Card<E, C>>
+    where
+        E: Clone,
+        C: Clone + PartialEq,
+        R: Rng + ?Sized,
+    {
+        let mut pools = self
+            .weights
+            .iter()
+            .map(|(rarity, weight)| {
+                let pool = set
+                    .cards
+                    .iter()
+                    .filter(|c| {
+                        c.rarity == *rarity
+                            && !c
+                                .traits
+                                .as_ref()
+                                .is_some_and(|t| t.flags.contains(TraitsFlag::BAN))
+                    })
+                    .collect::<Vec<_>>();
+
+                (pool, *weight)
+            })
+            .collect::<Vec<_>>();
+
+        let mut pack = Vec::with_capacity(self.size);
+
+        while pack.len() < self.size {
+            pools.retain(|(pool, weight)| !pool.is_empty() && *weight > 0);
+
+            // no more cards to pick from
+            let Ok(dist) = WeightedIndex::new(pools.iter().map(|(_, w)| *w)) else {
+                break;
+            };
+
+            let pool = &mut pools[dist.sample(rng)].0;
+            pack.push(pool.swap_remove(rng.gen_range(0..pool.len())));
+        }
+
+        pack
+    }
+
+    /// Open multiple packs from a set, like for a sealed pool.
+    pub fn open_many<'a, E, C, R>(
+        &self,
+        set: &'a Set<E, C>,
+        count: usize,
+        rng: &mut R,
+    ) -> Vec<Vec<&'a Card<E, C>>>
+    where
+        E: Clone,
+        C: Clone + PartialEq,
+        R: Rng + ?Sized,
+    {
+        (0..count).map(|_| self.open(set, rng)).collect()
+    }
+}
@@ -9,7 +9,20 @@
 //! ```
 
 pub use crate::{
-    fetch::{fetch_aug_set, fetch_cti_set, fetch_desc_set, fetch_imf_set, AugCosts, AugExt, DescCosts, SetError},
-    query::{FilterFn, Filters, QueryBuilder, QueryOrder, ToFilter},
+    builder::{BuildError, CardBuilder, CostsBuilder, SetBuilder},
+    cost::{parse_cost_string, CostError, CostUnit, CostVocab},
+    deck::{Deck, DeckError, DeckList, DeckStats, Rule, Ruleset, Violation},
+    diff::{diff, CardChange, FieldChange, SetDiff},
+    fetch::{
+        build_sheet_set, fetch_aug_set, fetch_cti_set, fetch_desc_set, fetch_imf_set,
+        fetch_sheet_set, parse_imf_set, to_imf_card, to_imf_set, AugCosts, AugExt, DescCosts,
+        DescExt, SetError, SheetColumns, SheetConfig, SheetRow,
+    },
+    pack::PackRules,
+    query::{
+        parse::{parse_query, KeywordInfo, QueryError, QueryExt},
+        text::{TextIndex, TextMatches},
+        ExplainStep, FilterFn, Filters, MaybeSync, OwnedQuery, QueryBuilder, QueryOrder, ToFilter,
+    },
     *,
 };
@@ -20,7 +20,7 @@
 //!     vec![
 //!         Filters::Attack(QueryOrder::GreaterEqual, 3),
 //!         Filters::Health(QueryOrder::Less, 3 ),
-//!         Filters::Sigil("Airborne".to_string()),
+//!         Filters::Sigil("Airborne".to_string(), vec![]),
 //!     ]
 //! );
 //!
@@ -28,11 +28,19 @@
 //! let result = query.query();
 //! ```
 
+pub mod parse;
+pub mod text;
+
 use crate::{Attack, Card, Costs, Rarity, Set, SpAtk, Temple, Traits};
+use parse::QueryError;
+use std::borrow::Borrow;
 use std::convert::Infallible;
 use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
+use std::slice;
+use std::sync::Arc;
 use std::vec;
+use text::TextMatches;
 
 /// The result of a filters obtain by calling [`QueryBuilder::query`].
 #[derive(Debug)]
@@ -42,10 +50,203 @@ where
     C: Clone + PartialEq,
     F: ToFilter<E, C>,
 {
-    /// The results of this query.
+    /// The results of this query, the most relevant first.
+    ///
+    /// See [`Query::score`] for how relevant a card is.
     pub cards: Vec<&'a Card<E, C>>,
     /// The filters that produce this query.
     pub filters: Vec<Filters<E, C, F>>,
+    /// Every card the filters were run on.
+    pub pool: Vec<&'a Card<E, C>>,
+}
+
+/// The result of a query on shared sets, obtain by calling [`QueryBuilder::query`] on a
+/// [`QueryBuilder::from_arcs`].
+///
+/// Unlike [`Query`] it own its sets so it can be keep around or send to other threads, like to
+/// remember what page of the result a user is on.
+#[derive(Debug, Clone)]
+pub struct OwnedQuery<E, C, F>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: ToFilter<E, C>,
+{
+    /// The sets this query was run on.
+    pub sets: Vec<Arc<Set<E, C>>>,
+    /// The filters that produce this query.
+    pub filters: Vec<Filters<E, C, F>>,
+    /// The results of this query as index in the [pool](OwnedQuery::pool), the most relevant
+    /// first.
+    pub indices: Vec<usize>,
+}
+
+impl<E, C, F> OwnedQuery<E, C, F>
+where
+    E: Clone + 'static,
+    C: Clone + PartialEq + MaybeSync + 'static,
+    F: ToFilter<E, C> + 'static,
+{
+    /// Every card the filters were run on.
+    pub fn pool(&self) -> impl Iterator<Item = &Card<E, C>> {
+        self.sets.iter().flat_map(|s| &s.cards)
+    }
+
+    /// The results of this query, the most relevant first.
+    #[must_use]
+    pub fn cards(&self) -> Vec<&Card<E, C>> {
+        let pool = self.pool().collect::<Vec<_>>();
+        self.indices.iter().map(|&i| pool[i]).collect()
+    }
+
+    /// Get a result of this query, `i` is the position in the results not in the pool.
+    ///
+    /// Unlike indexing [`cards`](OwnedQuery::cards) this don't collect the whole pool.
+    #[must_use]
+    pub fn get(&self, i: usize) -> Option<&Card<E, C>> {
+        pool_card(&self.sets, *self.indices.get(i)?)
+    }
+
+    /// Only keep the results that match a predicate.
+    pub fn retain(&mut self, mut f: impl FnMut(&Card<E, C>) -> bool) {
+        let sets = &self.sets;
+        self.indices
+            .retain(|&i| pool_card(sets, i).is_some_and(&mut f));
+    }
+
+    /// Borrow this query as a [`Query`] to use [`score`](Query::score) and
+    /// [`explain`](Query::explain).
+    #[must_use]
+    pub fn as_query(&self) -> Query<'_, E, C, F> {
+        let pool = self.pool().collect::<Vec<_>>();
+        Query {
+            cards: self.indices.iter().map(|&i| pool[i]).collect(),
+            filters: self.filters.clone(),
+            pool,
+        }
+    }
+}
+
+/// Get a card of the sets by its index in the pool, walking the sets instead of collecting them.
+fn pool_card<E, C>(sets: &[Arc<Set<E, C>>], mut index: usize) -> Option<&Card<E, C>>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    for set in sets {
+        match set.cards.get(index) {
+            Some(card) => return Some(card),
+            None => index -= set.cards.len(),
+        }
+    }
+    None
+}
+
+/// How many cards a filter eliminated, produce by [`Query::explain`].
+#[derive(Debug)]
+pub struct ExplainStep<'q, E, C, F>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: ToFilter<E, C>,
+{
+    /// The filter.
+    pub filter: &'q Filters<E, C, F>,
+    /// How many of the remaining cards this filter eliminated.
+    pub eliminated: usize,
+    /// How many cards are left after this filter.
+    pub remaining: usize,
+}
+
+impl<E, C, F> Query<'_, E, C, F>
+where
+    E: Clone + 'static,
+    C: Clone + PartialEq + MaybeSync + 'static,
+    F: ToFilter<E, C> + 'static,
+{
+    /// Get how relevant a card is to this query, higher is better.
+    ///
+    /// Only text filters score: a exact name match score more than a name that start with the
+    /// search, which score more than a name that just contain it, then description matches and
+    /// [full text](Filters::FullText) scores are add on top.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    /// use magpie_engine::query::Query;
+    ///
+    /// let set: Set<(), ()> = SetBuilder::new("hom", "Homebrew")
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Great Stoat"))
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Stoat King"))
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Stoat"))
+    ///     .unwrap()
+    ///     .build();
+    ///
+    /// let query: Query<(), (), ()> =
+    ///     QueryBuilder::with_filters(vec![&set], vec![Filters::Name("stoat".to_string())]).query();
+    ///
+    /// let names = query.cards.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(names, ["Stoat", "Stoat King", "Great Stoat"]);
+    /// assert!(query.score(query.cards[0]) > query.score(query.cards[1]));
+    /// ```
+    #[must_use]
+    pub fn score(&self, card: &Card<E, C>) -> f32 {
+        relevance(card, &self.filters)
+    }
+
+    /// Run the filters one by one on the pool and report how many cards each one eliminated.
+    ///
+    /// This is useful to understand why a query return no result, the filters are apply in order
+    /// so a filter only count the cards that the previous filters let through.
+    ///
+    /// # Examples
+    /// ```
+    /// use magpie_engine::prelude::*;
+    /// use magpie_engine::query::Query;
+    ///
+    /// let set: Set<(), ()> = SetBuilder::new("hom", "Homebrew")
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Stoat").attack(Attack::Num(1)).health(3))
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Wolf").attack(Attack::Num(3)).health(2))
+    ///     .unwrap()
+    ///     .build();
+    ///
+    /// let query: Query<(), (), ()> = QueryBuilder::with_filters(
+    ///     vec![&set],
+    ///     vec![
+    ///         Filters::Attack(QueryOrder::GreaterEqual, 3),
+    ///         Filters::Health(QueryOrder::Greater, 2),
+    ///     ],
+    /// )
+    /// .query();
+    ///
+    /// let steps = query.explain();
+    /// assert_eq!((steps[0].eliminated, steps[0].remaining), (1, 1));
+    /// assert_eq!((steps[1].eliminated, steps[1].remaining), (1, 0));
+    /// ```
+    #[must_use]
+    pub fn explain(&self) -> Vec<ExplainStep<'_, E, C, F>> {
+        let mut remaining = self.pool.clone();
+
+        self.filters
+            .iter()
+            .map(|filter| {
+                let func = filter.clone().to_fn();
+                let before = remaining.len();
+                remaining.retain(|c| func(c));
+
+                ExplainStep {
+                    filter,
+                    eliminated: before - remaining.len(),
+                    remaining: remaining.len(),
+                }
+            })
+            .collect()
+    }
 }
 
 impl<E, C, F> Display for Query<'_, E, C, F>
@@ -68,8 +269,31 @@ where
 }
 
 /// Type alias for a filter function.
+///
+/// With the `rayon` feature filter functions are `Send + Sync` so they can be share between
+/// threads by `QueryBuilder::par_query`.
+#[cfg(feature = "rayon")]
+pub type FilterFn<E, C> = Box<dyn Fn(&Card<E, C>) -> bool + Send + Sync>;
+
+/// Type alias for a filter function.
+#[cfg(not(feature = "rayon"))]
 pub type FilterFn<E, C> = Box<dyn Fn(&Card<E, C>) -> bool>;
 
+/// `Send + Sync` with the `rayon` feature and nothing without it.
+///
+/// The costs extension end up in the [filter functions](FilterFn) so it only need to be thread
+/// safe when they are.
+#[cfg(feature = "rayon")]
+pub trait MaybeSync: Send + Sync {}
+#[cfg(feature = "rayon")]
+impl<T: Send + Sync + ?Sized> MaybeSync for T {}
+
+/// `Send + Sync` with the `rayon` feature and nothing without it.
+#[cfg(not(feature = "rayon"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "rayon"))]
+impl<T: ?Sized> MaybeSync for T {}
+
 /// Query builder, it contain the set and is the main way to query cards.
 ///
 /// You build up your query using [`add_filter`](QueryBuilder::add_filter) then call [`query`](QueryBuilder::query) to compile all the filters and
@@ -79,6 +303,9 @@ pub type FilterFn<E, C> = Box<dyn Fn(&Card<E, C>) -> bool>;
 /// The final result will be a [`Query`] that contain what filters was used and the cards that
 /// satisfied those filters.
 ///
+/// The sets are borrow by default, use [`from_arcs`](QueryBuilder::from_arcs) to query shared
+/// sets and get a [`OwnedQuery`] that is not tie to the sets lifetime.
+///
 /// # Examples
 ///
 /// ```
@@ -99,22 +326,23 @@ pub type FilterFn<E, C> = Box<dyn Fn(&Card<E, C>) -> bool>;
 /// // Finally compile and get the results
 /// let result = query.query();
 /// ```
-pub struct QueryBuilder<'a, E, C, F>
+pub struct QueryBuilder<'a, E, C, F, S = &'a Set<E, C>>
 where
     E: Clone,
     C: Clone + PartialEq,
     F: ToFilter<E, C>,
 {
     /// All the set that is use for this query.
-    sets: Vec<&'a Set<E, C>>,
+    sets: Vec<S>,
 
     filters: Vec<Filters<E, C, F>>,
     funcs: Vec<FilterFn<E, C>>,
+    lifetime: PhantomData<&'a ()>,
 }
 
 impl<'a, E, C, F> QueryBuilder<'a, E, C, F>
 where
-    C: Clone + PartialEq + 'static,
+    C: Clone + PartialEq + MaybeSync + 'static,
     E: Clone + 'static,
     F: ToFilter<E, C> + 'static,
 {
@@ -157,6 +385,7 @@ where
             sets,
             filters: vec![],
             funcs: vec![],
+            lifetime: PhantomData,
         }
     }
 
@@ -183,52 +412,187 @@ where
     /// let result = query.query();
     /// ```
     #[must_use]
-    pub fn with_filters(sets: Vec<&'a Set<E, C>>, filters: Vec<Filters<E, C, F>>) -> Self {
+    pub fn with_filters(sets: Vec<&'a Set<E, C>>, mut filters: Vec<Filters<E, C, F>>) -> Self {
+        for filter in &mut filters {
+            fill_from_sets(filter, &sets);
+        }
+
         QueryBuilder {
             funcs: filters.clone().into_iter().map(|f| f.to_fn()).collect(),
             sets,
             filters,
+            lifetime: PhantomData,
         }
     }
 
+    /// Compile all the query and give you the result.
+    #[must_use]
+    pub fn query(self) -> Query<'a, E, C, F> {
+        let filter = move |c: &Card<E, C>| self.funcs.iter().all(move |f| f(c));
+        let pool = self.sets.iter().flat_map(|s| &s.cards).collect::<Vec<_>>();
+
+        let mut cards = pool.iter().copied().filter(|&c| filter(c)).collect();
+        rank(&mut cards, &self.filters);
+
+        Query {
+            filters: self.filters,
+            cards,
+            pool,
+        }
+    }
+
+    /// Compile all the query and give you the result, the cards are filter on multiple threads.
+    ///
+    /// This give the same result as [`query`](QueryBuilder::query) but is faster when querying a
+    /// lot of cards like when every set is selected. Only available with the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    #[must_use]
+    pub fn par_query(self) -> Query<'a, E, C, F>
+    where
+        E: Send + Sync,
+        C: Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        let pool = self.sets.iter().flat_map(|s| &s.cards).collect::<Vec<_>>();
+
+        let mut cards = pool
+            .par_iter()
+            .copied()
+            .filter(|&c| self.funcs.iter().all(|f| f(c)))
+            .collect();
+        rank(&mut cards, &self.filters);
+
+        Query {
+            filters: self.filters,
+            cards,
+            pool,
+        }
+    }
+}
+
+impl<E, C, F, S> QueryBuilder<'_, E, C, F, S>
+where
+    C: Clone + PartialEq + MaybeSync + 'static,
+    E: Clone + 'static,
+    F: ToFilter<E, C> + 'static,
+    S: Borrow<Set<E, C>>,
+{
     /// Add a new filter to this query.
     ///
     /// If you want to in place version use [`add_filter_mut`](QueryBuilder::add_filter_mut)
     /// instead
     #[must_use]
     pub fn add_filter(mut self, filter: Filters<E, C, F>) -> Self {
-        self.filters.push(filter.clone());
-        self.funcs.push(filter.to_fn());
+        self.add_filter_mut(filter);
         self
     }
 
     /// Add a new filter in place.
     ///
     /// If you want to use the builder pattern use [`add_filter`](QueryBuilder::add_filter) instead
-    pub fn add_filter_mut(&mut self, filter: Filters<E, C, F>) {
+    pub fn add_filter_mut(&mut self, mut filter: Filters<E, C, F>) {
+        let sets = self.sets.iter().map(Borrow::borrow).collect::<Vec<_>>();
+        fill_from_sets(&mut filter, &sets);
         self.filters.push(filter.clone());
         self.funcs.push(filter.to_fn());
     }
+}
+
+impl<E, C, F> QueryBuilder<'static, E, C, F, Arc<Set<E, C>>>
+where
+    C: Clone + PartialEq + MaybeSync + 'static,
+    E: Clone + 'static,
+    F: ToFilter<E, C> + 'static,
+{
+    /// Create a new empty [`QueryBuilder`] from shared sets.
+    ///
+    /// Querying give a [`OwnedQuery`] that keep the sets alive instead of borrowing them.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use magpie_engine::prelude::*;
+    ///
+    /// let set: Set<(), ()> = SetBuilder::new("hom", "Homebrew")
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Stoat").attack(Attack::Num(1)).health(3))
+    ///     .unwrap()
+    ///     .card(CardBuilder::new("Wolf").attack(Attack::Num(3)).health(2))
+    ///     .unwrap()
+    ///     .build();
+    ///
+    /// let query: OwnedQuery<(), (), ()> = QueryBuilder::from_arcs(vec![Arc::new(set)])
+    ///     .add_filter(Filters::Attack(QueryOrder::GreaterEqual, 3))
+    ///     .query();
+    ///
+    /// // the query own its sets so it can outlive this thread
+    /// let names = std::thread::spawn(move || {
+    ///     query.cards().iter().map(|c| c.name.clone()).collect::<Vec<_>>()
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// assert_eq!(names, ["Wolf"]);
+    /// ```
+    #[must_use]
+    pub fn from_arcs(sets: Vec<Arc<Set<E, C>>>) -> Self {
+        QueryBuilder {
+            sets,
+            filters: vec![],
+            funcs: vec![],
+            lifetime: PhantomData,
+        }
+    }
 
     /// Compile all the query and give you the result.
     #[must_use]
-    pub fn query(self) -> Query<'a, E, C, F> {
-        let filter = move |c: &Card<E, C>| self.funcs.iter().all(move |f| f(c));
+    pub fn query(self) -> OwnedQuery<E, C, F> {
+        let pool = self.sets.iter().flat_map(|s| &s.cards).collect::<Vec<_>>();
 
-        Query {
+        let mut indices = (0..pool.len())
+            .filter(|&i| self.funcs.iter().all(|f| f(pool[i])))
+            .collect();
+        rank_indices(&mut indices, &pool, &self.filters);
+
+        OwnedQuery {
+            indices,
             filters: self.filters,
-            cards: self
-                .sets
-                .iter()
-                .flat_map(|s| &s.cards)
-                .filter(|&c| filter(c))
-                .collect(),
+            sets: self.sets,
+        }
+    }
+
+    /// Compile all the query and give you the result, the cards are filter on multiple threads.
+    ///
+    /// This give the same result as [`query`](QueryBuilder::query) but is faster when querying a
+    /// lot of cards like when every set is selected. Only available with the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    #[must_use]
+    pub fn par_query(self) -> OwnedQuery<E, C, F>
+    where
+        E: Send + Sync,
+        C: Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        let pool = self.sets.iter().flat_map(|s| &s.cards).collect::<Vec<_>>();
+
+        let mut indices = (0..pool.len())
+            .into_par_iter()
+            .filter(|&i| self.funcs.iter().all(|f| f(pool[i])))
+            .collect();
+        rank_indices(&mut indices, &pool, &self.filters);
+
+        OwnedQuery {
+            indices,
+            filters: self.filters,
+            sets: self.sets,
         }
     }
 }
 
 /// [`Ordering`](std::cmp::Ordering) extension for more ordering.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum QueryOrder {
     /// Greater than another.
     Greater,
@@ -258,6 +622,174 @@ impl Display for QueryOrder {
     }
 }
 
+/// Fill in the matching cards of every full text and pool filter and the sigil names of every
+/// sigil filter from the sets.
+fn fill_from_sets<E, C, F>(filter: &mut Filters<E, C, F>, sets: &[&Set<E, C>])
+where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: ToFilter<E, C>,
+{
+    match filter {
+        Filters::FullText(search, matches) => *matches = TextMatches::new(search, sets),
+        Filters::Pool(name, ids) => {
+            *ids = sets
+                .iter()
+                .flat_map(|s| s.pool(name))
+                .map(Card::id)
+                .collect();
+            ids.sort_unstable();
+        }
+        Filters::Sigil(name, names) => {
+            let lower = name.to_lowercase();
+            *names = sets
+                .iter()
+                .filter_map(|s| {
+                    s.sigils_description
+                        .keys()
+                        .find(|s| s.to_lowercase() == lower)
+                        .map(Arc::clone)
+                })
+                .collect();
+        }
+        Filters::Or(a, b) => {
+            fill_from_sets(a, sets);
+            fill_from_sets(b, sets);
+        }
+        Filters::Not(f) => fill_from_sets(f, sets),
+        Filters::AnyOf(filters) | Filters::AllOf(filters) => {
+            for f in filters {
+                fill_from_sets(f, sets);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Check that every [`Rarity::Other`] in the filters is the rarity of a card in the sets.
+///
+/// The parser turn any unknown rarity into [`Rarity::Other`] since it don't know the sets, so a
+/// typo like `rarity:rrae` would silently match nothing without this.
+///
+/// # Errors
+/// Return [`QueryError::InvalidValue`] for the first rarity no card have.
+pub fn check_rarities<E, C, F>(
+    filters: &[Filters<E, C, F>],
+    sets: &[&Set<E, C>],
+) -> Result<(), QueryError>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: ToFilter<E, C>,
+{
+    filters.iter().try_for_each(|filter| match filter {
+        Filters::Rarity(Rarity::Other(rarity)) => {
+            let used = sets
+                .iter()
+                .flat_map(|s| &s.cards)
+                .any(|c| matches!(&c.rarity, Rarity::Other(r) if r.eq_ignore_ascii_case(rarity)));
+            if used {
+                Ok(())
+            } else {
+                Err(QueryError::InvalidValue(
+                    String::from("rarity"),
+                    rarity.clone(),
+                ))
+            }
+        }
+        Filters::Or(a, b) => check_rarities(slice::from_ref(&**a), sets)
+            .and_then(|()| check_rarities(slice::from_ref(&**b), sets)),
+        Filters::Not(f) => check_rarities(slice::from_ref(&**f), sets),
+        Filters::AnyOf(filters) | Filters::AllOf(filters) => check_rarities(filters, sets),
+        _ => Ok(()),
+    })
+}
+
+/// Score of a card which name is exactly the name filter.
+const EXACT_NAME_SCORE: f32 = 4.0;
+/// Score of a card which name start with the name filter.
+const PREFIX_NAME_SCORE: f32 = 3.0;
+/// Score of a card which name contain the name filter.
+const SUBSTRING_NAME_SCORE: f32 = 2.0;
+/// Score of a card which description contain the description filter.
+const DESCRIPTION_SCORE: f32 = 1.0;
+
+/// How relevant a card is to some filters, higher is better.
+///
+/// Name filters score by how close the name is, exact match > prefix > substring, then description
+/// hits and full text scores are add on top. Filters that are not about text don't score.
+fn relevance<E, C, F>(card: &Card<E, C>, filters: &[Filters<E, C, F>]) -> f32
+where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: ToFilter<E, C>,
+{
+    filters
+        .iter()
+        .map(|filter| match filter {
+            Filters::Name(name) => {
+                let name = name.to_lowercase();
+                if card.lower.name == name {
+                    EXACT_NAME_SCORE
+                } else if card.lower.name.starts_with(&name) {
+                    PREFIX_NAME_SCORE
+                } else if card.lower.name.contains(&name) {
+                    SUBSTRING_NAME_SCORE
+                } else {
+                    0.0
+                }
+            }
+            Filters::Description(desc) if card.lower.description.contains(&desc.to_lowercase()) => {
+                DESCRIPTION_SCORE
+            }
+            Filters::FullText(_, matches) => matches.score(card).unwrap_or(0.0),
+            Filters::Or(a, b) => {
+                relevance(card, slice::from_ref(a.as_ref()))
+                    + relevance(card, slice::from_ref(b.as_ref()))
+            }
+            Filters::AnyOf(filters) | Filters::AllOf(filters) => relevance(card, filters),
+            _ => 0.0,
+        })
+        .sum()
+}
+
+/// Sort the cards by [relevance](relevance), the most relevant first.
+///
+/// The sort is stable so cards with the same score keep their set order.
+fn rank<E, C, F>(cards: &mut Vec<&Card<E, C>>, filters: &[Filters<E, C, F>])
+where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: ToFilter<E, C>,
+{
+    let mut scored = cards
+        .iter()
+        .map(|&c| (relevance(c, filters), c))
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    *cards = scored.into_iter().map(|(_, c)| c).collect();
+}
+
+/// Same as [`rank`] but for index in a pool of cards.
+fn rank_indices<E, C, F>(
+    indices: &mut Vec<usize>,
+    pool: &[&Card<E, C>],
+    filters: &[Filters<E, C, F>],
+) where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: ToFilter<E, C>,
+{
+    let mut scored = indices
+        .iter()
+        .map(|&i| (relevance(pool[i], filters), i))
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    *indices = scored.into_iter().map(|(_, i)| i).collect();
+}
+
 /// Filters to be apply to when querying card.
 ///
 /// You can add custom filter by providing the `F` generic and implementing [`ToFilter`] trait for
@@ -277,6 +809,17 @@ where
     ///
     /// The value in this variant is the description to filter for.
     Description(String),
+    /// Full text search over the card name, description, sigils and sigil descriptions.
+    ///
+    /// The first value is the search, cards matching any of its words are keep and the result are
+    /// rank by how well they match. The second value is the matching cards, [`QueryBuilder`] fill
+    /// it in from its sets so you can leave it as [`TextMatches::default`].
+    FullText(String, TextMatches),
+    /// Filter for cards in a set [pool](Set::pools).
+    ///
+    /// The first value is the pool name. The second value is the [id](Card::id) of the cards in
+    /// the pool, [`QueryBuilder`] fill it in from its sets so you can leave it empty.
+    Pool(String, Vec<u64>),
 
     /// Filter for card rarity.
     ///
@@ -286,6 +829,10 @@ where
     ///
     /// The value in this variant is bit flags to match against.
     Temple(Temple),
+    /// Filter for a temple that is not a [`Temple`] flag, see [`Card::other_temples`].
+    ///
+    /// The value is the temple name, ignoring case.
+    OtherTemple(String),
     /// Filter for card tribe
     ///
     /// The value is the tribe or tribes to match against.
@@ -304,8 +851,11 @@ where
 
     /// Filter for card sigil
     ///
-    /// The value in this variant is the sigil name to filter for in the card sigils.
-    Sigil(String),
+    /// The first value is the sigil name to filter for in the card sigils, ignoring case. The
+    /// second value is the [interned](Set::intern_sigil) name of that sigil in every set,
+    /// [`QueryBuilder`] fill it in from its sets so you can leave it empty. When it is still empty
+    /// the sigil names are compare instead, which is slower.
+    Sigil(String, Vec<Arc<str>>),
 
     /// filter for card special attack.
     ///
@@ -330,6 +880,13 @@ where
     Or(Box<Filters<E, C, F>>, Box<Filters<E, C, F>>),
     /// Logical `not` for a filter.
     Not(Box<Filters<E, C, F>>),
+    /// Match if any of the filters match, a flat version of chaining [`Filters::Or`].
+    AnyOf(Vec<Filters<E, C, F>>),
+    /// Match if all of the filters match.
+    ///
+    /// Filters pass to a [`QueryBuilder`] are already all match, this is for grouping them inside
+    /// other filters.
+    AllOf(Vec<Filters<E, C, F>>),
 
     /// Extra filter you can add.
     Extra(F),
@@ -369,26 +926,39 @@ macro_rules! match_query_order {
 impl<E, C, F> ToFilter<E, C> for Filters<E, C, F>
 where
     E: Clone + 'static,
-    C: Clone + PartialEq + 'static,
+    C: Clone + PartialEq + MaybeSync + 'static,
     F: ToFilter<E, C> + 'static,
 {
     fn to_fn(self) -> FilterFn<E, C> {
         match self {
             Filters::Name(name) => {
-                Box::new(move |c| c.name.to_lowercase().contains(&name.to_lowercase()))
+                let lower = name.to_lowercase();
+                Box::new(move |c| c.lower.name.contains(&lower))
             }
             Filters::Description(desc) => {
-                Box::new(move |c| c.description.to_lowercase().contains(&desc.to_lowercase()))
+                let lower = desc.to_lowercase();
+                Box::new(move |c| c.lower.description.contains(&lower))
             }
 
-            Filters::Rarity(rarity) => Box::new(move |c| c.rarity == rarity),
-            Filters::Temple(temple) => Box::new(move |c| c.temple == temple),
-            Filters::Tribe(tribes) => Box::new(move |c| match &c.tribes {
-                Some(tr) if tribes.is_some() => tr
-                    .to_lowercase()
-                    .contains(&tribes.as_ref().unwrap().to_lowercase()),
-                _ => c.tribes == tribes,
+            Filters::FullText(_, matches) => Box::new(move |c| matches.score(c).is_some()),
+            Filters::Pool(_, ids) => Box::new(move |c| ids.binary_search(&c.id()).is_ok()),
+
+            Filters::Rarity(rarity) => Box::new(move |c| match (&c.rarity, &rarity) {
+                (Rarity::Other(a), Rarity::Other(b)) => a.eq_ignore_ascii_case(b),
+                (a, b) => a == b,
             }),
+            Filters::Temple(temple) => Box::new(move |c| c.temple == temple),
+            Filters::OtherTemple(name) => {
+                let name = name.to_lowercase();
+                Box::new(move |c| c.other_temples.iter().any(|t| t.to_lowercase() == name))
+            }
+            Filters::Tribe(tribes) => {
+                let lower = tribes.as_ref().map(|t| t.to_lowercase());
+                Box::new(move |c| match (&c.lower.tribes, &lower) {
+                    (Some(tr), Some(lower)) => tr.contains(lower.as_str()),
+                    _ => c.tribes == tribes,
+                })
+            }
             Filters::Attack(ord, attack) => Box::new(move |c| {
                 if let Attack::Num(a) = c.attack {
                     match_query_order!(ord, a, attack)
@@ -399,15 +969,16 @@ where
             Filters::Health(ord, health) => {
                 Box::new(move |c| match_query_order!(ord, c.health, health))
             }
-            Filters::Sigil(s) => {
-                let lower = s.to_lowercase();
-                Box::new(move |c| {
-                    c.sigils
-                        .iter()
-                        .map(|s| s.to_lowercase())
-                        .any(|s| s.eq(&lower))
-                })
+            // without the interned names fall back to comparing the names
+            Filters::Sigil(name, names) if names.is_empty() => {
+                let lower = name.to_lowercase();
+                Box::new(move |c| c.sigils.iter().any(|s| s.to_lowercase() == lower))
             }
+            Filters::Sigil(_, names) => Box::new(move |c| {
+                c.sigils
+                    .iter()
+                    .any(|s| names.iter().any(|n| Arc::ptr_eq(n, s)))
+            }),
             Filters::SpAtk(a) => Box::new(move |c| {
                 if let Attack::SpAtk(sp) = &c.attack {
                     *sp == a
@@ -436,6 +1007,15 @@ where
                 Box::new(move |c| !f(c))
             }
 
+            Filters::AnyOf(filters) => {
+                let fns = filters.into_iter().map(ToFilter::to_fn).collect::<Vec<_>>();
+                Box::new(move |c| fns.iter().any(|f| f(c)))
+            }
+            Filters::AllOf(filters) => {
+                let fns = filters.into_iter().map(ToFilter::to_fn).collect::<Vec<_>>();
+                Box::new(move |c| fns.iter().all(|f| f(c)))
+            }
+
             Filters::Extra(filter) => filter.to_fn(),
 
             Filters::McGuffin(..) | Filters::Cake(..) => unreachable!(),
@@ -463,15 +1043,18 @@ where
         match self {
             Filters::Name(n) => write!(f, "name includes {n}"),
             Filters::Description(d) => write!(f, "description includes {d}"),
+            Filters::FullText(t, _) => write!(f, "text match {t}"),
+            Filters::Pool(p, _) => write!(f, "in the {p} pool"),
             Filters::Rarity(r) => write!(f, "is {r}"),
             Filters::Temple(t) => write!(f, "from the {t} temple"),
+            Filters::OtherTemple(t) => write!(f, "from the {t} temple"),
             Filters::Tribe(t) => match t {
                 None => write!(f, "is tribeless"),
                 Some(t) => write!(f, "is a {t}"),
             },
             Filters::Attack(o, a) => write!(f, "attack {o} {a}"),
             Filters::Health(o, a) => write!(f, "health {o} {a}"),
-            Filters::Sigil(s) => write!(f, "have {s}"),
+            Filters::Sigil(s, _) => write!(f, "have {s}"),
             Filters::SpAtk(a) => write!(f, "attack value is {a}"),
             Filters::StrAtk(s) => write!(f, "attack value is {s}"),
             Filters::Costs(c) => match c {
@@ -484,8 +1067,24 @@ where
             },
             Filters::Or(a, b) => write!(f, "{a} or {b}"),
             Filters::Not(a) => write!(f, "not {a}"),
+            Filters::AnyOf(filters) => write!(f, "({})", join_filters(filters, " or ")),
+            Filters::AllOf(filters) => write!(f, "({})", join_filters(filters, " and ")),
             Filters::Extra(e) => write!(f, "{e}"),
             Filters::McGuffin(..) | Filters::Cake(..) => unreachable!(),
         }
     }
 }
+
+/// Join the display of filters with a separator.
+fn join_filters<E, C, F>(filters: &[Filters<E, C, F>], sep: &str) -> String
+where
+    E: Clone + 'static,
+    C: Clone + PartialEq + Display + 'static,
+    F: ToFilter<E, C> + Display + 'static,
+{
+    filters
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(sep)
+}
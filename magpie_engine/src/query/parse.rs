@@ -0,0 +1,584 @@
+//! Parse text query into [`Filters`].
+//!
+//! The syntax is a list of keyword, every keyword must match for a card to be in the result.
+//! Keywords can be join with `or`, negated with `!` and group with parentheses, every keyword in a
+//! group must match.
+//!
+//! ```ebnf
+//! program = { expr }
+//!
+//! expr = not { "or" not }
+//! not = [ "!" ] atom
+//! atom = "(" expr { expr } ")" | keyword | "~" value | value
+//!
+//! keyword = WORD ( ":" | "=" | ">" | "<" | ">=" | "<=" ) value
+//! value = WORD | STR
+//! ```
+//!
+//! A value without a keyword is a card name, `~` in front of it is the same as the `fuzzy` keyword.
+//! The extension can change both with [`QueryExt::parse_term`] and [`QueryExt::parse_keyword`].
+//!
+//! The built in keywords are list in [`KEYWORDS`] along with their short form. Extra keywords can be
+//! add by implementing [`QueryExt`] for the extension filter.
+//!
+//! # Examples
+//! ```
+//! use magpie_engine::prelude::*;
+//! use magpie_engine::query::parse::parse_query;
+//!
+//! let filters: Vec<Filters<(), (), ()>> = parse_query("a>=3 sigil:airborne").unwrap();
+//!
+//! assert_eq!(filters.len(), 2);
+//! assert!(matches!(filters[0], Filters::Attack(QueryOrder::GreaterEqual, 3)));
+//!
+//! let filters: Vec<Filters<(), (), ()>> = parse_query("(a:1 h:2) or tb:squirrel").unwrap();
+//!
+//! assert_eq!(filters.len(), 1);
+//! assert!(matches!(&filters[0], Filters::AnyOf(f) if matches!(&f[0], Filters::AllOf(_))));
+//!
+//! assert!(parse_query::<(), (), ()>("a:three").is_err());
+//!
+//! let filters: Vec<Filters<(), (), ()>> = parse_query("stoat ~\"wolf cub\"").unwrap();
+//!
+//! assert!(matches!(&filters[0], Filters::Name(n) if n == "stoat"));
+//! assert!(matches!(&filters[1], Filters::Name(n) if n == "wolf cub"));
+//! ```
+
+use std::error::Error;
+use std::fmt::Display;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::{Rarity, SpAtk, Temple, Traits, TraitsFlag};
+
+use super::{text::TextMatches, Filters, QueryOrder, ToFilter};
+
+/// Error when parsing a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// A character that is not part of the syntax.
+    UnknownToken(char),
+    /// A string that is never closed.
+    UnclosedString,
+    /// A token that is not expected here.
+    UnexpectedToken(&'static str, String),
+    /// A keyword that no filter use.
+    UnknownKeyword(String),
+    /// A value that is not valid for a keyword.
+    InvalidValue(String, String),
+    /// A comparison on a keyword that only match.
+    InvalidOrder(String),
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::UnknownToken(c) => write!(f, "Unrecognized token: {c}"),
+            QueryError::UnclosedString => write!(f, "A string is missing its closing quote"),
+            QueryError::UnexpectedToken(expect, found) => {
+                write!(f, "Expected {expect} but found {found}")
+            }
+            QueryError::UnknownKeyword(k) => write!(f, "Unknown keyword {k}"),
+            QueryError::InvalidValue(k, v) => write!(f, "Invalid value {v} for {k}"),
+            QueryError::InvalidOrder(k) => write!(f, "{k} can only be use with : or ="),
+        }
+    }
+}
+
+impl Error for QueryError {}
+
+/// Token produce by [`tokenize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// `(`
+    OpenParen,
+    /// `)`
+    CloseParen,
+    /// `!`
+    Not,
+    /// The `or` word.
+    Or,
+    /// `~`
+    Tilde,
+    /// `:`, `=`, `>`, `<`, `>=` or `<=`, colon and equal are both [`QueryOrder::Equal`].
+    Order(QueryOrder),
+    /// A word, keyword name or value.
+    Word(String),
+    /// A quoted string.
+    Str(String),
+    /// The end of the query.
+    Eof,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::OpenParen => write!(f, "("),
+            Token::CloseParen => write!(f, ")"),
+            Token::Not => write!(f, "!"),
+            Token::Or => write!(f, "or"),
+            Token::Tilde => write!(f, "~"),
+            Token::Order(o) => write!(f, "{o}"),
+            Token::Word(w) => write!(f, "{w}"),
+            Token::Str(s) => write!(f, "\"{s}\""),
+            Token::Eof => write!(f, "the end of the query"),
+        }
+    }
+}
+
+/// A keyword in the query, like `attack>=3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyword {
+    /// The keyword name in lowercase.
+    pub name: String,
+    /// The comparison.
+    pub order: QueryOrder,
+    /// The value to compare to.
+    pub value: String,
+}
+
+impl Keyword {
+    /// Get the value as a number.
+    pub fn num(&self) -> Result<isize, QueryError> {
+        self.value
+            .parse()
+            .map_err(|_| QueryError::InvalidValue(self.name.clone(), self.value.clone()))
+    }
+
+    /// Get the value, erroring if the keyword use a comparison other than equal.
+    pub fn str(&self) -> Result<&str, QueryError> {
+        if self.order == QueryOrder::Equal {
+            Ok(&self.value)
+        } else {
+            Err(QueryError::InvalidOrder(self.name.clone()))
+        }
+    }
+
+    /// Error for when the value is not valid.
+    #[must_use]
+    pub fn invalid(&self) -> QueryError {
+        QueryError::InvalidValue(self.name.clone(), self.value.clone())
+    }
+}
+
+/// Information on a keyword, use to parse the keyword name and to generate the help.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeywordInfo {
+    /// The full name of the keyword followed by its short forms.
+    pub names: &'static [&'static str],
+    /// The category the keyword is list under in the help.
+    pub category: &'static str,
+    /// What the keyword match.
+    pub description: &'static str,
+}
+
+impl KeywordInfo {
+    /// Get the full name of the keyword.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        self.names[0]
+    }
+}
+
+/// The built in keywords.
+pub const KEYWORDS: &[KeywordInfo] = &[
+    KeywordInfo {
+        names: &["name", "n"],
+        category: "Text",
+        description: "The card name include the value",
+    },
+    KeywordInfo {
+        names: &["fuzzy", "fz"],
+        category: "Text",
+        description: "The card name include the exact value, extensions may match close names",
+    },
+    KeywordInfo {
+        names: &["description", "d"],
+        category: "Text",
+        description: "The card description include the value",
+    },
+    KeywordInfo {
+        names: &["text", "tx"],
+        category: "Text",
+        description: "Any text on the card contain a word of the value, rank by how well it match",
+    },
+    KeywordInfo {
+        names: &["sigil", "s"],
+        category: "Text",
+        description: "The card have the sigil",
+    },
+    KeywordInfo {
+        names: &["attack", "a"],
+        category: "Stats",
+        description: "The card attack compare to the value",
+    },
+    KeywordInfo {
+        names: &["health", "h"],
+        category: "Stats",
+        description: "The card health compare to the value",
+    },
+    KeywordInfo {
+        names: &["spatk", "sp"],
+        category: "Stats",
+        description: "The card have the special attack, `mox`, `green`, `mirror`, `ant`, `bone`, \
+            `bell` or `card`",
+    },
+    KeywordInfo {
+        names: &["rarity", "r"],
+        category: "Traits",
+        description: "The card rarity, `side`, `common`, `uncommon`, `rare` or `unique`",
+    },
+    KeywordInfo {
+        names: &["temple", "tp"],
+        category: "Traits",
+        description: "The card temple, `beast`, `undead`, `tech`, `magick`, `fool` or `artistry`",
+    },
+    KeywordInfo {
+        names: &["tribe", "tb"],
+        category: "Traits",
+        description: "The card have the tribe",
+    },
+    KeywordInfo {
+        names: &["trait", "tr"],
+        category: "Traits",
+        description: "The card have the trait, `conductive`, `ban`, `terrain`, `hard` or a list \
+            of trait separate by `,`",
+    },
+    KeywordInfo {
+        names: &["pool", "pl"],
+        category: "Traits",
+        description: "The card is in the set pool",
+    },
+];
+
+/// Find the full name of a keyword from its full name or one of its short forms.
+///
+/// # Examples
+/// ```
+/// use magpie_engine::query::parse::{find_keyword, KEYWORDS};
+///
+/// assert_eq!(find_keyword(KEYWORDS, "tp"), Some("temple"));
+/// assert_eq!(find_keyword(KEYWORDS, "temple"), Some("temple"));
+/// assert_eq!(find_keyword(KEYWORDS, "color"), None);
+/// ```
+#[must_use]
+pub fn find_keyword(keywords: &[KeywordInfo], name: &str) -> Option<&'static str> {
+    keywords
+        .iter()
+        .find(|k| k.names.contains(&name))
+        .map(KeywordInfo::name)
+}
+
+/// Get every keyword a extension can parse, its own keywords replace the built in keywords with
+/// the same full name.
+#[must_use]
+pub fn all_keywords<E, C, F>() -> Vec<KeywordInfo>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: QueryExt<E, C>,
+{
+    let mut keywords: Vec<KeywordInfo> = KEYWORDS
+        .iter()
+        .filter(|k| F::KEYWORDS.iter().all(|e| e.name() != k.name()))
+        .copied()
+        .collect();
+    keywords.extend_from_slice(F::KEYWORDS);
+    keywords
+}
+
+/// The syntax tree of a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// A single keyword.
+    Keyword(Keyword),
+    /// A value without a keyword.
+    Term(String),
+    /// Any of the expressions match.
+    Or(Vec<Expr>),
+    /// All of the expressions match.
+    And(Vec<Expr>),
+    /// The expression doesn't match.
+    Not(Box<Expr>),
+}
+
+/// Extension for the keywords of the extension filter.
+///
+/// [`QueryExt::parse_keyword`] is call before the built in keywords so it can also replace them.
+pub trait QueryExt<E, C>: ToFilter<E, C>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    /// The keywords of the extension, they are list in the help along with the built in
+    /// [`KEYWORDS`].
+    const KEYWORDS: &'static [KeywordInfo] = &[];
+
+    /// Convert a keyword to a filter, return [`None`] if this is not a keyword of the extension.
+    #[must_use]
+    fn parse_keyword(_keyword: &Keyword) -> Option<Result<Filters<E, C, Self>, QueryError>> {
+        None
+    }
+
+    /// Convert a value without a keyword to a filter, return [`None`] to use the default
+    /// [`Filters::Name`].
+    #[must_use]
+    fn parse_term(_term: &str) -> Option<Result<Filters<E, C, Self>, QueryError>> {
+        None
+    }
+}
+
+impl<E, C> QueryExt<E, C> for ()
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+}
+
+/// Parse a query into filters.
+pub fn parse_query<E, C, F>(query: &str) -> Result<Vec<Filters<E, C, F>>, QueryError>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: QueryExt<E, C>,
+{
+    parse_ast(tokenize(query)?)?
+        .into_iter()
+        .map(to_filter)
+        .collect()
+}
+
+/// Split a query into tokens.
+pub fn tokenize(query: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            '!' => Token::Not,
+            '~' => Token::Tilde,
+            ':' | '=' => Token::Order(QueryOrder::Equal),
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Order(QueryOrder::GreaterEqual),
+            '>' => Token::Order(QueryOrder::Greater),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Order(QueryOrder::LessEqual),
+            '<' => Token::Order(QueryOrder::Less),
+            '"' => {
+                let mut str = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => str.push(c),
+                        None => return Err(QueryError::UnclosedString),
+                    }
+                }
+                Token::Str(str)
+            }
+            c if is_word(c) => {
+                let word = take_word(c, &mut chars);
+                if word == "or" {
+                    Token::Or
+                } else {
+                    Token::Word(word)
+                }
+            }
+            c => return Err(QueryError::UnknownToken(c)),
+        });
+    }
+
+    tokens.push(Token::Eof);
+    Ok(tokens)
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '\''
+}
+
+fn take_word(first: char, chars: &mut Peekable<Chars>) -> String {
+    let mut word = String::from(first);
+    while let Some(c) = chars.next_if(|c| is_word(*c)) {
+        word.push(c);
+    }
+    word
+}
+
+/// Parse tokens into a list of expression.
+pub fn parse_ast(mut tokens: Vec<Token>) -> Result<Vec<Expr>, QueryError> {
+    tokens.reverse();
+    let mut parser = Parser { tokens };
+
+    let mut ast = vec![];
+    while !matches!(parser.curr(), Token::Eof) {
+        ast.push(parser.parse_or()?);
+    }
+
+    Ok(ast)
+}
+
+/// Recursive descent parser, the tokens are reverse so the next token can be pop off the end.
+struct Parser {
+    tokens: Vec<Token>,
+}
+
+impl Parser {
+    fn curr(&self) -> &Token {
+        self.tokens.last().unwrap_or(&Token::Eof)
+    }
+
+    fn next(&mut self) -> Token {
+        self.tokens.pop().unwrap_or(Token::Eof)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut exprs = vec![self.parse_not()?];
+
+        while self.curr() == &Token::Or {
+            self.next();
+            exprs.push(self.parse_not()?);
+        }
+
+        Ok(if exprs.len() == 1 {
+            exprs.pop().unwrap()
+        } else {
+            Expr::Or(exprs)
+        })
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, QueryError> {
+        if self.curr() == &Token::Not {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.parse_atom()?)));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, QueryError> {
+        match self.next() {
+            Token::OpenParen => {
+                let mut exprs = vec![self.parse_or()?];
+                loop {
+                    match self.curr() {
+                        Token::CloseParen => break,
+                        Token::Eof => {
+                            return Err(QueryError::UnexpectedToken(")", Token::Eof.to_string()))
+                        }
+                        _ => exprs.push(self.parse_or()?),
+                    }
+                }
+                self.next();
+
+                Ok(if exprs.len() == 1 {
+                    exprs.pop().unwrap()
+                } else {
+                    Expr::And(exprs)
+                })
+            }
+            Token::Tilde => match self.next() {
+                Token::Word(value) | Token::Str(value) => Ok(Expr::Keyword(Keyword {
+                    name: "fuzzy".to_owned(),
+                    order: QueryOrder::Equal,
+                    value,
+                })),
+                tk => Err(QueryError::UnexpectedToken("a value", tk.to_string())),
+            },
+            Token::Str(term) => Ok(Expr::Term(term)),
+            Token::Word(name) => {
+                let Token::Order(order) = self.curr().clone() else {
+                    return Ok(Expr::Term(name));
+                };
+                self.next();
+
+                let value = match self.next() {
+                    Token::Word(v) | Token::Str(v) => v,
+                    tk => return Err(QueryError::UnexpectedToken("a value", tk.to_string())),
+                };
+
+                Ok(Expr::Keyword(Keyword {
+                    name: name.to_lowercase(),
+                    order,
+                    value,
+                }))
+            }
+            tk => Err(QueryError::UnexpectedToken("a keyword", tk.to_string())),
+        }
+    }
+}
+
+/// Convert a expression into a filter.
+pub fn to_filter<E, C, F>(expr: Expr) -> Result<Filters<E, C, F>, QueryError>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: QueryExt<E, C>,
+{
+    match expr {
+        Expr::Keyword(kw) => F::parse_keyword(&kw).unwrap_or_else(|| keyword_filter(&kw)),
+        Expr::Term(term) => F::parse_term(&term).unwrap_or(Ok(Filters::Name(term))),
+        Expr::Or(exprs) => Ok(Filters::AnyOf(
+            exprs.into_iter().map(to_filter).collect::<Result<_, _>>()?,
+        )),
+        Expr::And(exprs) => Ok(Filters::AllOf(
+            exprs.into_iter().map(to_filter).collect::<Result<_, _>>()?,
+        )),
+        Expr::Not(a) => Ok(Filters::Not(Box::new(to_filter(*a)?))),
+    }
+}
+
+/// Convert a built in keyword into a filter.
+fn keyword_filter<E, C, F>(kw: &Keyword) -> Result<Filters<E, C, F>, QueryError>
+where
+    E: Clone,
+    C: Clone + PartialEq,
+    F: ToFilter<E, C>,
+{
+    // only the keywords in the table are parse so the help can't miss one
+    Ok(match find_keyword(KEYWORDS, &kw.name).unwrap_or_default() {
+        // there is no fuzzy matching in the engine, the extension can replace it
+        "name" | "fuzzy" => Filters::Name(kw.str()?.to_owned()),
+        "description" => Filters::Description(kw.str()?.to_owned()),
+        "text" => Filters::FullText(kw.str()?.to_owned(), TextMatches::default()),
+        "pool" => Filters::Pool(kw.str()?.to_owned(), vec![]),
+        "rarity" => Filters::Rarity(match kw.str()?.to_lowercase().as_str() {
+            "side" | "s" => Rarity::SIDE,
+            "common" | "c" => Rarity::COMMON,
+            "uncommon" | "u" => Rarity::UNCOMMON,
+            "rare" | "r" => Rarity::RARE,
+            "unique" | "n" => Rarity::UNIQUE,
+            // the sets are not known here, see `check_rarities`
+            r => Rarity::Other(r.to_owned()),
+        }),
+        "temple" => match kw.str()?.to_lowercase().as_str() {
+            "beast" | "b" => Filters::Temple(Temple::BEAST),
+            "undead" | "u" => Filters::Temple(Temple::UNDEAD),
+            "technology" | "tech" | "t" => Filters::Temple(Temple::TECH),
+            "magick" | "m" => Filters::Temple(Temple::MAGICK),
+            "fool" | "f" => Filters::Temple(Temple::FOOL),
+            "artistry" | "a" => Filters::Temple(Temple::ARTISTRY),
+            t => Filters::OtherTemple(t.to_owned()),
+        },
+        "tribe" => Filters::Tribe(Some(kw.str()?.to_owned())),
+        "attack" => Filters::Attack(kw.order.clone(), kw.num()?),
+        "health" => Filters::Health(kw.order.clone(), kw.num()?),
+        "sigil" => Filters::Sigil(kw.str()?.to_owned(), vec![]),
+        "spatk" => Filters::SpAtk(match kw.str()?.to_lowercase().as_str() {
+            "mox" => SpAtk::MOX,
+            "green" => SpAtk::GREEN_MOX,
+            "mirror" => SpAtk::MIRROR,
+            "ant" => SpAtk::ANT,
+            "bone" => SpAtk::BONE,
+            "bell" => SpAtk::BELL,
+            "card" => SpAtk::CARD,
+            _ => return Err(kw.invalid()),
+        }),
+        "trait" => Filters::Traits(Some(match kw.str()?.to_lowercase().as_str() {
+            "conductive" => Traits::with_flags(TraitsFlag::CONDUCTIVE),
+            "ban" => Traits::with_flags(TraitsFlag::BAN),
+            "terrain" => Traits::with_flags(TraitsFlag::TERRAIN),
+            "hard" => Traits::with_flags(TraitsFlag::HARD),
+            _ => Traits::with_string(kw.value.split(',').map(ToOwned::to_owned).collect()),
+        })),
+        _ => return Err(QueryError::UnknownKeyword(kw.name.clone())),
+    })
+}
@@ -0,0 +1,198 @@
+//! Full text search over the card names, descriptions and sigils.
+//!
+//! A [`TextIndex`] is a inverted index from words to the cards that use them, every set keep one
+//! in [`Set::text_index`]. The words of a card
+//! come from its name, description, sigils and the description of those sigils, name and sigil
+//! words are weigh more than the rest. Searching score the cards with tf-idf so rare words like
+//! `submerge` count more than common words like `damage`.
+//!
+//! [`Filters::FullText`](super::Filters::FullText) use this to rank natural language searches.
+//!
+//! # Examples
+//! ```
+//! use magpie_engine::prelude::*;
+//! use magpie_engine::query::Query;
+//!
+//! let set: Set<(), ()> = SetBuilder::new("hom", "Homebrew")
+//!     .unwrap()
+//!     .sigil("Waterborne", "This card submerges during the opponent's turn.")
+//!     .sigil("Airborne", "This card attacks the opponent directly.")
+//!     .card(
+//!         CardBuilder::new("Kingfisher")
+//!             .sigils(vec!["Airborne".to_string(), "Waterborne".to_string()]),
+//!     )
+//!     .unwrap()
+//!     .card(CardBuilder::new("Bullfrog").sigils(vec!["Waterborne".to_string()]))
+//!     .unwrap()
+//!     .card(CardBuilder::new("Stoat"))
+//!     .unwrap()
+//!     .build();
+//!
+//! // the index is build with the set
+//! let result = set.text_index.search("attack the opponent directly");
+//!
+//! assert_eq!(result.len(), 2);
+//! assert_eq!(set.cards[result[0].0].name, "Kingfisher");
+//!
+//! // the `text` keyword do the same in a query
+//! let filters = parse_query("text:\"attack the opponent directly\"").unwrap();
+//! let query: Query<(), (), ()> = QueryBuilder::with_filters(vec![&set], filters).query();
+//!
+//! assert_eq!(query.cards.len(), 2);
+//! assert_eq!(query.cards[0].name, "Kingfisher");
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{Card, Set};
+
+/// Common words that are skip when indexing and searching.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "if", "in", "is", "it", "its",
+    "of", "on", "or", "that", "the", "this", "to", "with",
+];
+
+/// Weight of the words in a card name.
+const NAME_WEIGHT: f32 = 3.0;
+
+/// Weight of the words in a card sigil names.
+const SIGIL_WEIGHT: f32 = 2.0;
+
+/// Split a text into lowercase words, skipping [stop words](STOP_WORDS).
+#[must_use]
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Inverted index of the words in a set cards.
+#[derive(Debug, Clone, Default)]
+pub struct TextIndex {
+    /// Every word and the cards using it, by index in the set, with the weighted word count.
+    postings: HashMap<String, Vec<(usize, f32)>>,
+    /// How many cards are index.
+    card_count: usize,
+}
+
+impl TextIndex {
+    /// Index the cards of a set.
+    #[must_use]
+    pub fn new<E, C>(set: &Set<E, C>) -> Self
+    where
+        E: Clone,
+        C: Clone + PartialEq,
+    {
+        let mut postings: HashMap<String, Vec<(usize, f32)>> = HashMap::new();
+
+        for (i, card) in set.cards.iter().enumerate() {
+            let mut weights: HashMap<String, f32> = HashMap::new();
+            let mut add = |text: &str, weight: f32| {
+                for word in tokenize(text) {
+                    *weights.entry(word).or_default() += weight;
+                }
+            };
+
+            add(&card.name, NAME_WEIGHT);
+            add(&card.description, 1.0);
+            for sigil in &card.sigils {
+                add(sigil, SIGIL_WEIGHT);
+                if let Some(text) = set.sigils_description.get(sigil) {
+                    add(text, 1.0);
+                }
+            }
+
+            for (word, weight) in weights {
+                postings.entry(word).or_default().push((i, weight));
+            }
+        }
+
+        TextIndex {
+            postings,
+            card_count: set.cards.len(),
+        }
+    }
+
+    /// Search the index and return the matching cards index in the set with their score, the best
+    /// match first.
+    ///
+    /// Cards that don't have any of the words in the search are not include.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn search(&self, search: &str) -> Vec<(usize, f32)> {
+        let mut words = tokenize(search);
+        words.sort();
+        words.dedup();
+
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        for word in words {
+            let Some(posting) = self.postings.get(&word) else {
+                continue;
+            };
+
+            let idf = (1.0 + self.card_count as f32 / posting.len() as f32).ln();
+            for &(card, weight) in posting {
+                *scores.entry(card).or_default() += weight * idf;
+            }
+        }
+
+        let mut scores = scores.into_iter().collect::<Vec<_>>();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scores
+    }
+}
+
+/// The cards matching a full text search with their score.
+///
+/// Cards are identify by their set code and name so the matches from multiple sets can be keep
+/// together, looking up a card score don't allocate.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextMatches {
+    /// Set code to the name and score of the matching cards in that set.
+    scores: HashMap<[u8; 3], HashMap<String, f32>>,
+}
+
+impl TextMatches {
+    /// Search the cards of multiple sets with their [`text_index`](Set::text_index).
+    #[must_use]
+    pub fn new<E, C>(search: &str, sets: &[&Set<E, C>]) -> Self
+    where
+        E: Clone,
+        C: Clone + PartialEq,
+    {
+        let mut scores: HashMap<[u8; 3], HashMap<String, f32>> = HashMap::new();
+        for set in sets {
+            let matches = set.text_index.search(search);
+            if !matches.is_empty() {
+                scores.entry(set.code.bytes()).or_default().extend(
+                    matches
+                        .into_iter()
+                        .map(|(i, score)| (set.cards[i].name.clone(), score)),
+                );
+            }
+        }
+
+        TextMatches { scores }
+    }
+
+    /// Get the score of a card, [`None`] if the card don't match.
+    #[must_use]
+    pub fn score<E, C>(&self, card: &Card<E, C>) -> Option<f32>
+    where
+        E: Clone,
+        C: Clone + PartialEq,
+    {
+        self.scores
+            .get(&card.set.bytes())
+            .and_then(|cards| cards.get(card.name.as_str()))
+            .copied()
+    }
+
+    /// Return `true` if no card match.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+}
@@ -0,0 +1,121 @@
+//! Shared setup for the fixture tests.
+//!
+//! Every set url is answer with a recorded response from `tests/fixtures` so the fetchers run
+//! their whole parsing without the network. Set outputs are compare to the golden files in
+//! `tests/golden`, run the tests with `UPDATE_GOLDEN=1` to write them again after a intended
+//! change.
+#![allow(dead_code)] // not every test file use every helper
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Once,
+};
+
+use magpie_engine::fetch::{set_client, HttpClient, NOTION_CACHE_VAR};
+
+/// Url the IMF fixture is serve at.
+pub const IMF_URL: &str = "https://example.com/standard.json";
+
+/// Url the portrait fixture is serve at.
+pub const PORTRAIT_URL: &str = "https://example.com/portrait.png";
+
+/// Directory of the recorded responses.
+pub const FIXTURE_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../magpie_engine/tests/fixtures"
+);
+
+/// The urls every fetcher use and the fixture that answer them.
+const FIXTURES: &[(&str, &str)] = &[
+    (IMF_URL, "imf.json"),
+    (PORTRAIT_URL, "portrait.png"),
+    (
+        "https://raw.githubusercontent.com/answearingmachine/card-printer/main/dist/printer/assets/bg/bg_common_beast.png",
+        "portrait.png",
+    ),
+    (
+        "https://opensheet.elk.sh/1tvTXSsFDK5xAVALQPdDPJOitBufJE6UB_MN4q5nbLXk/2",
+        "aug_cards.json",
+    ),
+    (
+        "https://opensheet.elk.sh/1tvTXSsFDK5xAVALQPdDPJOitBufJE6UB_MN4q5nbLXk/3",
+        "aug_sigils.json",
+    ),
+    (
+        "https://opensheet.elk.sh/1EjOtqUrjsMRl7wiVMN7tMuvAHvkw7snv1dNyFJIFbaE/2",
+        "desc_cards.json",
+    ),
+    (
+        "https://opensheet.elk.sh/1EjOtqUrjsMRl7wiVMN7tMuvAHvkw7snv1dNyFJIFbaE/4",
+        "desc_sigils.json",
+    ),
+    (
+        "https://opensheet.elk.sh/1EjOtqUrjsMRl7wiVMN7tMuvAHvkw7snv1dNyFJIFbaE/Rulings",
+        "desc_rulings.json",
+    ),
+    (
+        "https://api.notion.com/v1/databases/e19c88aa75b44bfe89321bcde8dc7d9f/query",
+        "cti_cards.json",
+    ),
+    (
+        "https://api.notion.com/v1/databases/933d6166cb3f4ee89db51e4cf464f5bd/query",
+        "cti_sigils.json",
+    ),
+    // the page ids list of a database that already have a snapshot
+    (
+        "https://api.notion.com/v1/databases/e19c88aa75b44bfe89321bcde8dc7d9f/query?filter_properties=title",
+        "cti_cards.json",
+    ),
+    (
+        "https://api.notion.com/v1/databases/933d6166cb3f4ee89db51e4cf464f5bd/query?filter_properties=title",
+        "cti_sigils.json",
+    ),
+];
+
+/// Make the shared client answer from the fixtures, safe to call from every test.
+pub fn install_fixtures() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let mut builder = HttpClient::builder().offline();
+        for (url, file) in FIXTURES {
+            let path = Path::new(FIXTURE_DIR).join(file);
+            let body = fs::read(&path)
+                .unwrap_or_else(|e| panic!("Cannot read fixture {}: {e}", path.display()));
+            builder = builder.fixture(url, body);
+        }
+
+        set_client(builder.build().unwrap()).expect("Shared client was already use");
+
+        // the notion snapshots must not leak into the working directory
+        env::set_var("NOTION_API_KEY", "fixture");
+        env::set_var(
+            NOTION_CACHE_VAR,
+            env::temp_dir().join("magpie_notion_fixtures"),
+        );
+    });
+}
+
+/// Compare `actual` to a golden file in the `tests/golden` directory of the crate.
+///
+/// The golden file is write instead when `UPDATE_GOLDEN` is set.
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Cannot read golden file {}: {e}", path.display()));
+    assert!(
+        expected == actual,
+        "{} does not match the golden file, run with UPDATE_GOLDEN=1 if the change is intended\n\n--- expected\n{expected}\n--- actual\n{actual}",
+        path.display()
+    );
+}
@@ -0,0 +1,138 @@
+//! Golden tests for the set fetchers, see [`common`] for how the fixtures are serve.
+
+mod common;
+
+use std::fmt::Write;
+
+use common::{assert_golden, install_fixtures, IMF_URL};
+use magpie_engine::{
+    fetch::{AugBranch, FetchError},
+    prelude::*,
+};
+
+/// Render every part of a set that a fetcher parse, `extra` render the set specific fields of a
+/// card.
+fn summary<E, C>(set: &Set<E, C>, extra: impl Fn(&Card<E, C>) -> String) -> String
+where
+    E: Clone,
+    C: Clone + PartialEq,
+{
+    let mut out = format!("{} ({})\n", set.name, set.code.code());
+
+    for card in &set.cards {
+        writeln!(out, "\n[{}]", card.name).unwrap();
+        writeln!(out, "description: {}", card.description).unwrap();
+        writeln!(out, "portrait: {}", card.portrait).unwrap();
+        writeln!(out, "rarity: {:?}", card.rarity).unwrap();
+        writeln!(out, "temple: {:?}", card.temple).unwrap();
+        writeln!(out, "tribes: {:?}", card.tribes).unwrap();
+        writeln!(out, "stats: {:?} / {}", card.attack, card.health).unwrap();
+        writeln!(out, "sigils: {:?}", card.sigils).unwrap();
+        match &card.costs {
+            Some(c) => writeln!(
+                out,
+                "costs: {} blood, {} bone, {} energy, {:?} mox, {:?}",
+                c.blood, c.bone, c.energy, c.mox, c.mox_count
+            ),
+            None => writeln!(out, "costs: free"),
+        }
+        .unwrap();
+        writeln!(out, "traits: {:?}", card.traits).unwrap();
+        writeln!(out, "related: {:?}", card.related).unwrap();
+        out.push_str(&extra(card));
+    }
+
+    let mut sigils = set.sigils_description.iter().collect::<Vec<_>>();
+    sigils.sort();
+    out.push_str("\n[sigils]\n");
+    for (name, text) in sigils {
+        let category = set.sigils_category.get(&**name);
+        writeln!(out, "{name} {category:?}: {text}").unwrap();
+    }
+
+    let mut pools = set.pools.iter().collect::<Vec<_>>();
+    pools.sort();
+    out.push_str("\n[pools]\n");
+    for (name, cards) in pools {
+        writeln!(out, "{name}: {cards:?}").unwrap();
+    }
+
+    out
+}
+
+#[test]
+fn imf_set() {
+    install_fixtures();
+    let set = fetch_imf_set(IMF_URL, SetCode::new("std").unwrap()).unwrap();
+
+    assert_golden("imf.txt", &summary(&set, |_| String::new()));
+}
+
+#[test]
+fn aug_set() {
+    install_fixtures();
+    let set = fetch_aug_set(AugBranch::Main, SetCode::new("aug").unwrap()).unwrap();
+
+    assert_golden(
+        "aug.txt",
+        &summary(&set, |c| {
+            let shattered = c
+                .costs
+                .as_ref()
+                .and_then(|c| c.extra.shattered_count.clone());
+            let max = c.costs.as_ref().map_or(0, |c| c.extra.max);
+            format!(
+                "shattered: {shattered:?}\nmax: {max}\nartist: {}\n",
+                c.extra.artist
+            )
+        }),
+    );
+}
+
+#[test]
+fn desc_set() {
+    install_fixtures();
+    let set = fetch_desc_set(SetCode::new("des").unwrap()).unwrap();
+
+    assert_golden(
+        "desc.txt",
+        &summary(&set, |c| {
+            let (link, gold) = c
+                .costs
+                .as_ref()
+                .map_or((0, 0), |c| (c.extra.link, c.extra.gold));
+            format!(
+                "link: {link}\ngold: {gold}\nrulings: {:?}\n",
+                c.extra.rulings
+            )
+        }),
+    );
+}
+
+#[test]
+fn cti_set() {
+    install_fixtures();
+    let set = fetch_cti_set(SetCode::new("cti").unwrap()).unwrap();
+
+    // a bad cost make the card free instead of failing the set
+    assert!(set.issues.contains(&SetIssue::InvalidCost {
+        card: "Skeleton".to_owned(),
+        error: CostError::UnknownCost("gold".to_owned()),
+    }));
+    assert_golden("cti.txt", &summary(&set, |_| String::new()));
+}
+
+#[test]
+fn missing_fixture() {
+    install_fixtures();
+    let err = fetch_imf_set(
+        "https://example.com/missing.json",
+        SetCode::new("std").unwrap(),
+    )
+    .unwrap_err();
+
+    assert!(matches!(
+        err,
+        SetError::FetchError(FetchError::MissingFixture(_), _)
+    ));
+}
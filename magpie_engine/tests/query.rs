@@ -0,0 +1,35 @@
+//! Tests for the filters that need a fetched set, see [`common`] for how the fixtures are serve.
+
+mod common;
+
+use common::{install_fixtures, IMF_URL};
+use magpie_engine::prelude::*;
+
+#[test]
+fn sigil_without_interned_names() {
+    install_fixtures();
+    let set = fetch_imf_set(IMF_URL, SetCode::new("std").unwrap()).unwrap();
+
+    // a filter made outside `QueryBuilder` never get the interned names
+    let filter: FilterFn<(), ()> =
+        Filters::<(), (), ()>::Sigil("mighty LEAP".to_owned(), vec![]).to_fn();
+    let names = set
+        .cards
+        .iter()
+        .filter(|c| filter(c))
+        .map(|c| c.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Bullfrog"]);
+
+    let query = QueryBuilder::<(), (), ()>::with_filters(
+        vec![&set],
+        vec![Filters::Sigil("mighty LEAP".to_owned(), vec![])],
+    )
+    .query();
+    let names = query
+        .cards
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Bullfrog"]);
+}
@@ -0,0 +1,37 @@
+//! Bundle the emoji images in `assets/emojis/<table>/<NAME>.png` into the binary.
+//!
+//! The images are list into `bundled_emojis.rs` in the build output as `(table, name, png)` so
+//! `src/emojis.rs` can `include!` them. A missing directory bundle no emojis.
+
+use std::{env, fs, path::Path};
+
+fn main() {
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("assets/emojis");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut entries = vec![];
+    for table in fs::read_dir(&dir).into_iter().flatten().flatten() {
+        // a directory only rerun the build when its direct entries change
+        println!("cargo:rerun-if-changed={}", table.path().display());
+
+        let Some(table_name) = table.file_name().to_str().map(str::to_owned) else {
+            continue;
+        };
+        for image in fs::read_dir(table.path()).into_iter().flatten().flatten() {
+            let path = image.path();
+            let Some(name) = path.file_stem().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if path.extension().is_some_and(|e| e == "png") {
+                entries.push(format!(
+                    "({table_name:?}, {name:?}, include_bytes!({:?})),",
+                    path.display().to_string()
+                ));
+            }
+        }
+    }
+    entries.sort();
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("bundled_emojis.rs");
+    fs::write(out, format!("&[\n{}\n]\n", entries.join("\n"))).unwrap();
+}
@@ -0,0 +1,104 @@
+//! Implementation for the card API.
+//!
+//! The API expose the same data and query syntax as the bot so websites and other bots can use
+//! them. It is only compile with the `server` feature and is run by the `magpie_server` binary.
+//!
+//! - `GET /api/cards?set=<codes>&q=<query>&limit=<n>` run a query on the comma separated sets.
+//! - `GET /api/card/<set>/<name>` fuzzy search a card by name in a set.
+
+use std::sync::Arc;
+
+use serde_json::json;
+
+use crate::{
+    export::card_json,
+    http::{self, decode, Response},
+    query::run_query,
+    search::find_card,
+    SETS,
+};
+
+/// The default maximum number of cards a query return.
+const DEFAULT_LIMIT: usize = 100;
+
+/// Start the API server on an address.
+pub async fn start_server(addr: &str) {
+    http::serve("API", addr, route).await;
+}
+
+fn route(target: &str) -> Response {
+    let (path, params) = http::split_target(target);
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    };
+
+    let parts = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    match parts[..] {
+        ["api", "cards"] => cards(
+            param("set").unwrap_or("std"),
+            param("q").unwrap_or_default(),
+            param("limit")
+                .and_then(|l| l.parse().ok())
+                .unwrap_or(DEFAULT_LIMIT),
+        ),
+        ["api", "card", set, name] => card(&decode(set), &decode(name)),
+        _ => error("404 Not Found", "Unknown endpoint"),
+    }
+}
+
+fn cards(set_codes: &str, query: &str, limit: usize) -> Response {
+    let all_sets = SETS.read().unwrap();
+
+    let mut sets = vec![];
+    for code in set_codes.split(',').map(str::trim) {
+        match all_sets.get(code) {
+            Some(set) => sets.push(Arc::clone(set)),
+            None => return error("404 Not Found", &format!("Unknown set code `{code}`")),
+        }
+    }
+
+    let result = match run_query(sets, &all_sets, query, None) {
+        Ok(r) => r,
+        Err(err) => return error("400 Bad Request", &err),
+    };
+
+    Response::json(
+        "200 OK",
+        &json!({
+            "count": result.indices.len(),
+            "filters": result.filters.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "cards": result
+                .cards()
+                .iter()
+                .take(limit)
+                .map(|c| card_json(c, all_sets.get(c.set.code()).map(Arc::as_ref)))
+                .collect::<Vec<_>>(),
+        }),
+    )
+}
+
+fn card(set_code: &str, name: &str) -> Response {
+    let sets = SETS.read().unwrap();
+
+    let Some(set) = sets.get(set_code).map(Arc::as_ref) else {
+        return error("404 Not Found", &format!("Unknown set code `{set_code}`"));
+    };
+
+    match find_card(name, set) {
+        Some(res) => Response::json(
+            "200 OK",
+            &json!({
+                "rank": res.rank,
+                "card": card_json(res.data, Some(set)),
+            }),
+        ),
+        None => error("404 Not Found", &format!("Card `{name}` not found")),
+    }
+}
+
+fn error(status: &'static str, msg: &str) -> Response {
+    Response::json(status, &json!({ "error": msg }))
+}
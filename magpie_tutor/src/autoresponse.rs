@@ -0,0 +1,275 @@
+//! Implementation for the guild auto responder.
+//!
+//! Each guild can add rules with `/autoresponse`. A rule have a keyword or regex trigger, can be
+//! limited to some channels or ignore some channels, and have a cooldown so it doesn't spam the
+//! channel. Rules are save to [`AUTORESPONSE_FILE_PATH`] while cooldowns are only kept in memory.
+//!
+//! The response is a template, `{user}` is replace with a mention of the user and `{channel}` with
+//! a mention of the channel the message was send in.
+
+use std::collections::HashMap;
+
+use poise::serenity_prelude::{ChannelId, Context, GuildId, Message};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    current_epoch, done,
+    shard::{read_data, write_data},
+    Color, Res, AUTORESPONSES, AUTORESPONSE_COOLDOWNS,
+};
+
+/// Location of the auto responder file.
+pub const AUTORESPONSE_FILE_PATH: &str = "./autoresponse.bin";
+
+/// Most rules a guild can have.
+pub const MAX_RULES: usize = 25;
+
+/// Biggest size a compiled trigger regex can be, so a rule can't slow the bot down.
+const REGEX_SIZE_LIMIT: usize = 1 << 16;
+
+/// Type alias for the auto responder table, guild id to rules.
+pub type AutoResponses = HashMap<u64, Vec<AutoResponse>>;
+
+/// Type alias for the cooldown table, guild id and rule name to when the rule last trigger.
+pub type Cooldowns = HashMap<(u64, String), u128>;
+
+/// How a rule trigger is match.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum TriggerKind {
+    /// The message contain the trigger, ignoring case.
+    Keyword,
+    /// The message match the trigger regex, ignoring case.
+    Regex,
+}
+
+/// A auto responder rule.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AutoResponse {
+    /// The rule name, it is unique in a guild.
+    pub name: String,
+    /// The keyword or regex.
+    pub trigger: String,
+    /// How the trigger is match.
+    pub kind: TriggerKind,
+    /// The response template.
+    pub response: String,
+    /// Channels the rule work in, empty for every channel.
+    pub channels: Vec<u64>,
+    /// Channels the rule never work in.
+    pub ignore_channels: Vec<u64>,
+    /// How long to wait in seconds before the rule can trigger again.
+    pub cooldown: u64,
+    /// The compiled trigger of regex rules, it is compile when the rule is add or load.
+    #[serde(skip)]
+    pub regex: Option<Regex>,
+}
+
+impl AutoResponse {
+    /// Check if the rule trigger on a message content in a channel.
+    pub fn matches(&self, channel_id: ChannelId, content: &str) -> bool {
+        let channel = channel_id.get();
+        if self.ignore_channels.contains(&channel)
+            || (!self.channels.is_empty() && !self.channels.contains(&channel))
+        {
+            return false;
+        }
+
+        match self.kind {
+            TriggerKind::Keyword => content
+                .to_lowercase()
+                .contains(&self.trigger.to_lowercase()),
+            TriggerKind::Regex => self.regex.as_ref().is_some_and(|r| r.is_match(content)),
+        }
+    }
+
+    /// Fill the response template for a message.
+    pub fn render(&self, msg: &Message) -> String {
+        self.response
+            .replace("{user}", &format!("<@{}>", msg.author.id))
+            .replace("{channel}", &format!("<#{}>", msg.channel_id))
+    }
+}
+
+/// Compile a trigger regex with the size limit.
+fn compile_trigger(trigger: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(trigger)
+        .case_insensitive(true)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+}
+
+/// The rules the bot use before they were configurable, they are use when there is no auto
+/// responder file.
+fn default_rules() -> AutoResponses {
+    HashMap::from([(
+        994573431880286289,
+        vec![AutoResponse {
+            name: String::from("lfg-redirect"),
+            trigger: String::from(r"wants? to (?:play|fight)"),
+            kind: TriggerKind::Regex,
+            response: String::from("
+You seem to be asking for a game in the the wrong channel!
+You can look at [this faq](https://discord.com/channels/994573431880286289/1168644586319659100/1181115229610983424), or:
+- Host a room in the game
+- Go to the <#1065751579485032629> channel
+- Choose a inactive lobby (choose one that no one is talking in). Competive lobby usually entail harder and more meta gameplay.
+- Send a message with the room code and ping the `Gamer (PING IF LFG)` role"),
+            channels: vec![],
+            ignore_channels: vec![1067593222987198465, 1067561938969710754, 1066543653339791440],
+            cooldown: 0,
+            regex: None,
+        }],
+    )])
+}
+
+/// Load the auto responder rules from the auto responder file.
+pub(crate) fn load_autoresponses() -> AutoResponses {
+    let mut rules: AutoResponses = read_data(AUTORESPONSE_FILE_PATH)
+        .map_or_else(default_rules, |bytes| {
+            bincode::deserialize(&bytes).unwrap_or_default()
+        });
+
+    for rule in rules.values_mut().flatten() {
+        if rule.kind == TriggerKind::Regex {
+            rule.regex = compile_trigger(&rule.trigger).ok();
+        }
+    }
+
+    rules
+}
+
+/// Save the auto responder rules to the auto responder file.
+///
+/// This lock [`AUTORESPONSES`] so make sure you drop any guard before calling this.
+pub fn save_autoresponses() {
+    write_data(AUTORESPONSE_FILE_PATH, &*AUTORESPONSES);
+    done!(
+        "Auto responder rules save successfully to {}",
+        AUTORESPONSE_FILE_PATH.green()
+    );
+}
+
+/// Reply to a message with the first rule that trigger on it and is not on cooldown.
+pub async fn auto_respond(msg: &Message, ctx: &Context) -> Res {
+    let Some(guild_id) = msg.guild_id else {
+        return Ok(());
+    };
+
+    let response = {
+        let rules = AUTORESPONSES.lock().unwrap();
+        let mut cooldowns = AUTORESPONSE_COOLDOWNS.lock().unwrap();
+        let now = current_epoch();
+
+        rules
+            .get(&guild_id.get())
+            .into_iter()
+            .flatten()
+            .filter(|r| r.matches(msg.channel_id, &msg.content))
+            .find(|r| {
+                cooldowns
+                    .get(&(guild_id.get(), r.name.clone()))
+                    .is_none_or(|last| now - last >= u128::from(r.cooldown) * 1000)
+            })
+            .map(|r| {
+                cooldowns.insert((guild_id.get(), r.name.clone()), now);
+                r.render(msg)
+            })
+    };
+
+    if let Some(response) = response {
+        msg.reply(ctx, response).await?;
+    }
+    Ok(())
+}
+
+/// Add or replace a rule in a guild.
+pub fn add_rule(guild_id: GuildId, mut rule: AutoResponse) -> Result<(), String> {
+    if rule.name.trim().is_empty() {
+        return Err(String::from("The rule name cannot be empty"));
+    }
+    if rule.trigger.is_empty() {
+        return Err(String::from("The trigger cannot be empty"));
+    }
+    if rule.kind == TriggerKind::Regex {
+        rule.regex =
+            Some(compile_trigger(&rule.trigger).map_err(|e| format!("Invalid regex: {e}"))?);
+    }
+
+    {
+        let mut rules = AUTORESPONSES.lock().unwrap();
+        let rules = rules.entry(guild_id.get()).or_default();
+
+        if let Some(old) = rules.iter_mut().find(|r| r.name == rule.name) {
+            *old = rule;
+        } else if rules.len() >= MAX_RULES {
+            return Err(format!("A server can only have {MAX_RULES} rules"));
+        } else {
+            rules.push(rule);
+        }
+    }
+
+    save_autoresponses();
+    Ok(())
+}
+
+/// Remove a rule from a guild.
+///
+/// Return if the rule was removed.
+pub fn remove_rule(guild_id: GuildId, name: &str) -> bool {
+    let removed = {
+        let mut rules = AUTORESPONSES.lock().unwrap();
+        let Some(rules) = rules.get_mut(&guild_id.get()) else {
+            return false;
+        };
+
+        let before = rules.len();
+        rules.retain(|r| r.name != name);
+        before != rules.len()
+    };
+
+    if removed {
+        save_autoresponses();
+    }
+    removed
+}
+
+/// Toggle if a rule ignore a channel.
+///
+/// Return if the channel is now ignore.
+pub fn toggle_ignore(guild_id: GuildId, name: &str, channel_id: ChannelId) -> Result<bool, String> {
+    let ignored = {
+        let mut rules = AUTORESPONSES.lock().unwrap();
+        let Some(rule) = rules
+            .get_mut(&guild_id.get())
+            .and_then(|r| r.iter_mut().find(|r| r.name == name))
+        else {
+            return Err(format!("There is no rule named `{name}` in this server"));
+        };
+
+        if let Some(i) = rule
+            .ignore_channels
+            .iter()
+            .position(|c| *c == channel_id.get())
+        {
+            rule.ignore_channels.remove(i);
+            false
+        } else {
+            rule.ignore_channels.push(channel_id.get());
+            true
+        }
+    };
+
+    save_autoresponses();
+    Ok(ignored)
+}
+
+/// Get the rules of a guild.
+pub fn guild_rules(guild_id: GuildId) -> Vec<AutoResponse> {
+    AUTORESPONSES
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .cloned()
+        .unwrap_or_default()
+}
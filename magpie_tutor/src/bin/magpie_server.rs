@@ -0,0 +1,31 @@
+//! Standalone card API server, see [`magpie_tutor::api`] for the endpoints.
+
+use magpie_tutor::{api::start_server, done, info, logging::init_logging, Color, SETS};
+
+/// Environment variable with the address for the API server to listen on.
+const API_ADDR_VAR: &str = "MAGPIE_API_ADDR";
+
+/// The address to listen on when the environment variable is not set.
+const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+#[tokio::main]
+async fn main() {
+    // keep the guard alive so the log file get flush on exit
+    let _guard = init_logging();
+
+    info!("Fetching set...");
+    tokio::task::block_in_place(|| {
+        done!(
+            "Finish fetching {} sets",
+            SETS.read().unwrap().len().green()
+        );
+    });
+
+    let addr = std::env::var(API_ADDR_VAR).unwrap_or_else(|_| DEFAULT_ADDR.to_owned());
+    start_server(&addr).await;
+
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Cannot listen for shutdown signal");
+    info!("Shutting down API server");
+}
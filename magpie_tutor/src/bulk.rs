@@ -0,0 +1,185 @@
+//! Implementation for the bulk lookup of card names from a text file.
+//!
+//! Each line of the file is a card name, optionally with a copies count in front like `3x Stoat`
+//! or `3 Stoat` so deck lists from other tools work as is. The matches are attached as a CSV or
+//! JSON file and the misses are list in the embed.
+
+use std::{fmt::Write, sync::Arc};
+
+use poise::serenity_prelude::{colours::roles, CreateAttachment, CreateEmbed, GuildId};
+use serde_json::json;
+
+use crate::{
+    export::card_json, homebrew, locale::guild_lang, search::find_card, spoiler::is_spoiler, tr,
+    Card, MessageAdapter, SETS,
+};
+
+/// Largest file the bulk lookup accept in bytes.
+pub const MAX_BULK_SIZE: u32 = 256 * 1024;
+
+/// Most lines look up in a single file.
+const MAX_BULK_LINES: usize = 1000;
+
+/// Most misses list in the embed, the rest are only count.
+const MAX_SHOWN_MISSES: usize = 20;
+
+/// The file format of the bulk lookup result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, poise::ChoiceParameter)]
+pub enum BulkFormat {
+    /// One row per match.
+    #[default]
+    Csv,
+    /// The full card data of each match along with the misses.
+    Json,
+}
+
+/// A line that match a card.
+struct BulkMatch<'a> {
+    line: &'a str,
+    count: usize,
+    card: &'a Card,
+    rank: f32,
+}
+
+/// Split the copies count from a line, the count is 1 if there is none.
+fn split_count(line: &str) -> (usize, &str) {
+    let Some((count, name)) = line.split_once(' ') else {
+        return (1, line);
+    };
+
+    match count.trim_end_matches(['x', 'X']).parse() {
+        Ok(count) => (count, name.trim()),
+        Err(_) => (1, line),
+    }
+}
+
+/// Quote a CSV field if it need to be.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Look up every line of a file in a set and generate the result message.
+///
+/// The guild homebrew set can be look up too but not the guild spoiler sets, since the file would
+/// reveal every card at once.
+pub fn bulk_lookup_message(
+    content: &str,
+    set_code: &str,
+    format: BulkFormat,
+    guild_id: Option<GuildId>,
+) -> MessageAdapter {
+    let lang = guild_lang(guild_id);
+    let error = |desc: String| {
+        MessageAdapter::new().embeds(vec![CreateEmbed::new()
+            .color(roles::RED)
+            .title(tr!(lang, "bulk.error_title"))
+            .description(desc)])
+    };
+
+    if is_spoiler(guild_id, set_code) {
+        return error(tr!(lang, "bulk.spoiler_set", code = set_code));
+    }
+
+    let homebrew = homebrew::guild_set(guild_id).filter(|h| h.code.code() == set_code);
+    let sets = SETS.read().unwrap();
+    let Some(set) = sets.get(set_code).map(Arc::as_ref).or(homebrew.as_ref()) else {
+        return error(tr!(lang, "bulk.unknown_set", code = set_code));
+    };
+
+    let lines = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>();
+
+    if lines.len() > MAX_BULK_LINES {
+        return error(tr!(
+            lang,
+            "bulk.too_many_lines",
+            lines = lines.len(),
+            max = MAX_BULK_LINES
+        ));
+    }
+
+    let mut matches = vec![];
+    let mut misses = vec![];
+    for line in lines {
+        let (count, name) = split_count(line);
+        match find_card(name, set) {
+            Some(res) => matches.push(BulkMatch {
+                line,
+                count,
+                card: res.data,
+                rank: res.rank,
+            }),
+            None => misses.push(line),
+        }
+    }
+
+    let (data, filename) = match format {
+        BulkFormat::Csv => {
+            let mut csv = String::from("line,count,name,set,id,match\n");
+            for m in &matches {
+                writeln!(
+                    csv,
+                    "{},{},{},{},{},{:.1}",
+                    csv_field(m.line),
+                    m.count,
+                    csv_field(&m.card.name),
+                    m.card.set.code(),
+                    m.card.id(),
+                    m.rank * 100.0
+                )
+                .unwrap();
+            }
+            (csv.into_bytes(), "bulk_lookup.csv")
+        }
+        BulkFormat::Json => {
+            let json = json!({
+                "set": set.code.code(),
+                "matches": matches
+                    .iter()
+                    .map(|m| json!({
+                        "line": m.line,
+                        "count": m.count,
+                        "match": m.rank * 100.0,
+                        "card": card_json(m.card, Some(set)),
+                    }))
+                    .collect::<Vec<_>>(),
+                "misses": misses,
+            });
+            (
+                serde_json::to_vec_pretty(&json).unwrap_or_default(),
+                "bulk_lookup.json",
+            )
+        }
+    };
+
+    let mut desc = format!(
+        "{}\n{}\n",
+        tr!(lang, "bulk.found", count = matches.len()),
+        tr!(lang, "bulk.missed", count = misses.len())
+    );
+    for miss in misses.iter().take(MAX_SHOWN_MISSES) {
+        writeln!(desc, "- {miss}").unwrap();
+    }
+    if misses.len() > MAX_SHOWN_MISSES {
+        let count = misses.len() - MAX_SHOWN_MISSES;
+        writeln!(desc, "{}", tr!(lang, "bulk.more", count = count)).unwrap();
+    }
+
+    MessageAdapter::new()
+        .embeds(vec![CreateEmbed::new()
+            .color(if misses.is_empty() {
+                roles::GREEN
+            } else {
+                roles::ORANGE
+            })
+            .title(tr!(lang, "bulk.title", set = set.name))
+            .description(desc)])
+        .attachments(vec![CreateAttachment::bytes(data, filename)])
+}
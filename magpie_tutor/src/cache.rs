@@ -0,0 +1,461 @@
+//! Implementation for the portrait cache maintenance.
+//!
+//! The portraits of searched cards are cache as the Discord attachment they were upload as, the
+//! bot owners can remove entries with `/cache purge` and a background job drop the entries of cards
+//! that are not in any loaded set anymore, like cards that were renamed or removed.
+//!
+//! By default the portraits are attach to the search reply and cache from the reply embeds. When
+//! `TUTOR_CACHE_CHANNEL` is set to a private channel id, the portraits are upload there first and
+//! cache straight from the uploaded attachments. The portraits of the most searched cards are also
+//! upload there after boot so the first searches after a restart are not slow,
+//! `TUTOR_WARM_COUNT` is how many cards to warm, [`DEFAULT_WARM_COUNT`] if not set.
+
+use std::{
+    collections::HashSet,
+    env,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    time::Duration,
+};
+
+use magpie_engine::printings;
+use poise::serenity_prelude::{
+    self as serenity, CacheHttp, ChannelId, Context, CreateAttachment, CreateMessage, Http,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{task, time};
+
+use crate::{
+    current_epoch, done, error, hash_card_url, health, info, portrait_filename, save_cache,
+    search::gen_portraits, stats::top_cards, CacheData, Card, Color, Death, Error, CACHE,
+    DEBUG_CARD, HOMEBREW, PORTRAITS, SETS,
+};
+
+/// How often the orphan cache entries are drop.
+pub const CLEANUP_INTERVAL: Duration = Duration::from_hours(6);
+
+/// Environment variable with the channel id to upload the portraits to.
+pub const CACHE_CHANNEL_VAR: &str = "TUTOR_CACHE_CHANNEL";
+
+/// Environment variable with how many of the most searched cards to warm.
+pub const WARM_COUNT_VAR: &str = "TUTOR_WARM_COUNT";
+
+/// How many cards to warm when [`WARM_COUNT_VAR`] is not set.
+pub const DEFAULT_WARM_COUNT: usize = 25;
+
+/// Discord endpoint to refresh expired attachment urls.
+const REFRESH_URL: &str = "https://discord.com/api/v10/attachments/refresh-urls";
+
+/// How many portraits are upload per message, Discord allow 10 attachments per message.
+const UPLOAD_BATCH: usize = 10;
+
+/// Which cache entries to purge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PurgeTarget {
+    /// The entries of a set cards, by set code.
+    Set(String),
+    /// Every entries.
+    All,
+    /// The entries which attachment expired.
+    Expired,
+}
+
+impl PurgeTarget {
+    /// Parse `all`, `expired` or a set code.
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "all" => PurgeTarget::All,
+            "expired" => PurgeTarget::Expired,
+            code => PurgeTarget::Set(code.to_owned()),
+        }
+    }
+}
+
+/// The card id of every card in a set, the homebrew sets included.
+fn set_card_ids(code: &str) -> Option<HashSet<u64>> {
+    if let Some(set) = SETS.read().unwrap().get(code) {
+        return Some(set.cards.iter().map(Card::id).collect());
+    }
+
+    let homebrew = HOMEBREW.lock().unwrap();
+    let sets = homebrew
+        .values()
+        .filter(|h| h.code == code)
+        .filter_map(|h| h.to_set().ok())
+        .collect::<Vec<_>>();
+
+    (!sets.is_empty()).then(|| sets.iter().flat_map(|s| &s.cards).map(Card::id).collect())
+}
+
+/// The card id of every card that can be search.
+fn loaded_card_ids() -> HashSet<u64> {
+    let mut ids = SETS
+        .read()
+        .unwrap()
+        .values()
+        .flat_map(|s| &s.cards)
+        .map(Card::id)
+        .collect::<HashSet<_>>();
+
+    for set in HOMEBREW
+        .lock()
+        .unwrap()
+        .values()
+        .filter_map(|h| h.to_set().ok())
+    {
+        ids.extend(set.cards.iter().map(Card::id));
+    }
+    ids.insert(DEBUG_CARD.id());
+
+    ids
+}
+
+/// Remove the matching cache entries and return how many were remove.
+///
+/// Return an error message if the set code is unknown.
+pub fn purge_cache(target: &PurgeTarget) -> Result<usize, String> {
+    let set_ids = match target {
+        PurgeTarget::Set(code) => {
+            set_card_ids(code).ok_or_else(|| format!("Unknown set code: `{code}`"))?
+        }
+        _ => HashSet::new(),
+    };
+
+    let removed = {
+        let mut cache = CACHE.lock().unwrap_or_die("Cannot lock cache");
+        let before = cache.len();
+
+        match target {
+            PurgeTarget::All => {
+                // the portraits are most likely purge to regenerate them so drop the recent ones
+                PORTRAITS.lock().unwrap().clear();
+                cache.clear();
+            }
+            PurgeTarget::Expired => cache.retain(|_, c| !is_expired(c)),
+            PurgeTarget::Set(_) => {
+                PORTRAITS.lock().unwrap().remove_cards(&set_ids);
+                cache.retain(|id, _| !set_ids.contains(id));
+            }
+        }
+
+        before - cache.len()
+    };
+
+    if removed > 0 {
+        done!("{} {} cache entries", "Purged".red(), removed.red());
+        save_cache();
+    }
+
+    Ok(removed)
+}
+
+/// Remove the cache entries of cards that are not in any loaded set and return how many were
+/// remove.
+pub fn remove_orphans() -> usize {
+    let ids = loaded_card_ids();
+
+    let removed = {
+        let mut cache = CACHE.lock().unwrap_or_die("Cannot lock cache");
+        let before = cache.len();
+        cache.retain(|id, _| ids.contains(id));
+        before - cache.len()
+    };
+
+    if removed > 0 {
+        done!("{} {} orphan cache entries", "Removed".red(), removed.red());
+        save_cache();
+    }
+
+    removed
+}
+
+/// Start the orphan cleanup job, only the first call start it.
+///
+/// The cleanup is skip while some set fail to load so their cache is not lost.
+pub fn start_cleanup() {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        info!("Cache cleanup started");
+        let mut interval = time::interval(CLEANUP_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if health::has_failed_sets() {
+                info!("Some sets fail to load, skipping cache cleanup...");
+                continue;
+            }
+
+            remove_orphans();
+        }
+    });
+}
+
+/// A generated portrait waiting to be upload to the cache channel.
+pub struct PendingPortrait {
+    /// The card id.
+    pub card_id: u64,
+    /// The hash of the card portrait url.
+    pub portrait: u64,
+    /// The generated portrait.
+    pub attachment: CreateAttachment,
+}
+
+impl PendingPortrait {
+    /// Check if a card have a portrait that is not cache yet.
+    pub fn needed(card: &Card) -> bool {
+        let portrait = hash_card_url(card);
+        let cached = CACHE
+            .lock()
+            .unwrap_or_die("Cannot lock cache")
+            .get(&card.id())
+            .is_some_and(|c| c.portrait == portrait && !is_expired(c));
+
+        !card.portrait.is_empty() && !cached
+    }
+
+    /// Create the pending portrait of a card from an already generated portrait.
+    pub fn with_data(card: &Card, data: Vec<u8>) -> Self {
+        PendingPortrait {
+            card_id: card.id(),
+            portrait: hash_card_url(card),
+            attachment: CreateAttachment::bytes(data, portrait_filename(card)),
+        }
+    }
+}
+
+/// Get the cache channel from [`CACHE_CHANNEL_VAR`].
+pub fn cache_channel() -> Option<ChannelId> {
+    env::var(CACHE_CHANNEL_VAR)
+        .ok()
+        .and_then(|c| c.parse().ok())
+}
+
+/// Get the expire date of a Discord attachment url, the `ex` query parameter.
+fn expire_date(url: &str) -> u64 {
+    url.split_once('?')
+        .and_then(|(_, query)| query.split('&').find_map(|p| p.strip_prefix("ex=")))
+        .and_then(|ex| u64::from_str_radix(ex, 16).ok())
+        .unwrap_or_default()
+}
+
+/// Check if the attachment of a cache entry expired.
+pub fn is_expired(data: &CacheData) -> bool {
+    // the attachment expire date is in seconds
+    u128::from(data.expire_date) <= current_epoch() / 1000
+}
+
+/// The url of a cached portrait without the expire parameters.
+fn attachment_url(card_id: u64, data: &CacheData) -> String {
+    format!(
+        "https://cdn.discordapp.com/attachments/{}/{}/{card_id}-{}.png",
+        data.channel_id, data.attachment_id, data.portrait
+    )
+}
+
+/// Get the url of a card cached portrait if it is cache, not expired and still the same portrait.
+pub fn cached_url(card: &Card) -> Option<String> {
+    CACHE
+        .lock()
+        .unwrap_or_die("Cannot lock cache")
+        .get(&card.id())
+        .filter(|c| c.portrait == hash_card_url(card) && !is_expired(c))
+        .map(|c| attachment_url(card.id(), c))
+}
+
+#[derive(Deserialize)]
+struct RefreshedUrl {
+    refreshed: String,
+}
+
+#[derive(Deserialize)]
+struct RefreshedUrls {
+    refreshed_urls: Vec<RefreshedUrl>,
+}
+
+/// Refresh the expired attachments of some cards and return how many were refresh.
+///
+/// The attachments are refresh with the Discord refresh endpoint instead of uploading the
+/// portraits again, entries that can't be refresh stay expired so their portrait is generate
+/// again. Serenity has no route for the endpoint so the request go through a client share by
+/// every refresh, a rate limited refresh is an error and the portraits are generate.
+pub async fn refresh_expired(http: &Http, card_ids: &[u64]) -> Result<usize, Error> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+    let urls = {
+        let cache = CACHE.lock().unwrap_or_die("Cannot lock cache");
+        card_ids
+            .iter()
+            .filter_map(|id| {
+                cache
+                    .get(id)
+                    .filter(|c| is_expired(c))
+                    .map(|c| (*id, attachment_url(*id, c)))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    if urls.is_empty() {
+        return Ok(0);
+    }
+
+    let res = CLIENT
+        .get_or_init(reqwest::Client::new)
+        .post(REFRESH_URL)
+        .header("Authorization", http.token())
+        .json(&json!({ "attachment_urls": urls.iter().map(|(_, u)| u).collect::<Vec<_>>() }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<RefreshedUrls>()
+        .await?;
+
+    let mut refreshed = 0;
+    {
+        let mut cache = CACHE.lock().unwrap_or_die("Cannot lock cache");
+        for RefreshedUrl { refreshed: url } in &res.refreshed_urls {
+            let path = url.split_once('?').map_or(url.as_str(), |(p, _)| p);
+            let Some(data) = urls
+                .iter()
+                .find(|(_, u)| u == path)
+                .and_then(|(id, _)| cache.get_mut(id))
+            else {
+                continue;
+            };
+
+            data.expire_date = expire_date(url);
+            refreshed += 1;
+        }
+    }
+
+    if refreshed > 0 {
+        done!(
+            "{} {} expired portraits",
+            "Refreshed".green(),
+            refreshed.green()
+        );
+        save_cache();
+    }
+
+    Ok(refreshed)
+}
+
+/// Upload portraits to the cache channel and cache them from the uploaded attachments.
+///
+/// Return how many portraits were cache.
+pub async fn upload_portraits(
+    http: impl CacheHttp,
+    channel_id: ChannelId,
+    portraits: Vec<PendingPortrait>,
+) -> Result<usize, serenity::Error> {
+    let mut cached = 0;
+
+    for batch in portraits.chunks(UPLOAD_BATCH) {
+        let msg = channel_id
+            .send_message(
+                &http,
+                CreateMessage::new().add_files(batch.iter().map(|p| p.attachment.clone())),
+            )
+            .await?;
+
+        let mut cache = CACHE.lock().unwrap_or_die("Cannot lock cache");
+        for portrait in batch {
+            let Some(attachment) = msg
+                .attachments
+                .iter()
+                .find(|a| a.filename == portrait.attachment.filename)
+            else {
+                continue;
+            };
+
+            cache.insert(
+                portrait.card_id,
+                CacheData {
+                    channel_id: channel_id.get(),
+                    attachment_id: attachment.id.get(),
+                    expire_date: expire_date(&attachment.url),
+                    portrait: portrait.portrait,
+                },
+            );
+            cached += 1;
+        }
+    }
+
+    if cached > 0 {
+        done!(
+            "{} {} portraits to the cache channel",
+            "Uploaded".green(),
+            cached.green()
+        );
+        save_cache();
+    }
+
+    Ok(cached)
+}
+
+/// Generate the portraits of the most searched cards that are not cache yet.
+///
+/// Every printing of a card is warm since they can have different portraits. The cards are
+/// collect first so searches are not block by the sets lock while the portraits download.
+fn popular_portraits(count: usize) -> Vec<PendingPortrait> {
+    let mut cards: Vec<Card> = vec![];
+    {
+        let sets = SETS.read().unwrap();
+        for name in top_cards(count) {
+            for card in printings(sets.values().map(Arc::as_ref), &name) {
+                if PendingPortrait::needed(card) && cards.iter().all(|c| c.id() != card.id()) {
+                    cards.push(card.clone());
+                }
+            }
+        }
+    }
+
+    // portraits that cannot be generated are left out so they are try again next time
+    gen_portraits(&cards.iter().collect::<Vec<_>>())
+        .into_iter()
+        .zip(&cards)
+        .filter_map(|(data, card)| Some(PendingPortrait::with_data(card, data?)))
+        .collect()
+}
+
+/// Start warming the cache with the most searched cards, only the first call start it.
+///
+/// Nothing is done if [`CACHE_CHANNEL_VAR`] is not set.
+pub fn start_warmup(ctx: &Context) {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+
+    let Some(channel_id) = cache_channel() else {
+        return;
+    };
+
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let count = env::var(WARM_COUNT_VAR)
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .unwrap_or(DEFAULT_WARM_COUNT);
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let portraits = task::block_in_place(|| popular_portraits(count));
+        info!(
+            "Warming the cache with {} portraits...",
+            portraits.len().blue()
+        );
+
+        match upload_portraits(&ctx, channel_id, portraits).await {
+            Ok(_) => done!("Cache warm up finished"),
+            Err(err) => error!("Cannot upload the warm up portraits due to: {err}"),
+        }
+    });
+}
@@ -0,0 +1,239 @@
+//! Implementation for the set refresh and changelog.
+//!
+//! Sets are fetch again every [`REFRESH_INTERVAL`] in the background, or every [`RETRY_INTERVAL`]
+//! while some sets fail to load. When a set changed the new version replace the old one and a
+//! changelog with the added, removed and changed cards is posted to the changelog channel of every
+//! guild that set one. The channels are save to [`CHANGELOG_FILE_PATH`]. The changes are also
+//! record per card in the [`timeline`].
+
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use magpie_engine::prelude::*;
+use poise::serenity_prelude::{
+    colours::roles, ChannelId, Context, CreateEmbed, CreateMessage, GuildId,
+};
+use tokio::{task, time};
+
+use crate::{
+    done, error, fetch_sets, health, info,
+    shard::{read_data, write_data},
+    timeline, Color, CHANGELOG, SETS,
+};
+
+/// Location of the changelog channels file.
+pub const CHANGELOG_FILE_PATH: &str = "./changelog.bin";
+
+/// How often the sets are fetch again.
+#[allow(clippy::duration_suboptimal_units)]
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often the sets are fetch again while some of them fail to load.
+#[allow(clippy::duration_suboptimal_units)]
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Embed description are limited to 4096 characters, leave some room for the overflow line.
+const MAX_DESC_LEN: usize = 3900;
+
+/// Longest field value to show in a change before it get cut off.
+const MAX_VALUE_LEN: usize = 80;
+
+/// Type alias for the changelog channels, guild id to channel id.
+pub type Changelog = HashMap<u64, u64>;
+
+/// Load the changelog channels from the changelog file.
+pub(crate) fn load_changelog() -> Changelog {
+    read_data(CHANGELOG_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the changelog channels to the changelog file.
+///
+/// This lock [`CHANGELOG`] so make sure you drop any guard before calling this.
+pub fn save_changelog() {
+    write_data(CHANGELOG_FILE_PATH, &*CHANGELOG);
+    done!(
+        "Changelog channels save successfully to {}",
+        CHANGELOG_FILE_PATH.green()
+    );
+}
+
+/// Set the channel to post the changelog in for a guild.
+pub fn enable_changelog(guild_id: GuildId, channel_id: ChannelId) {
+    CHANGELOG
+        .lock()
+        .unwrap()
+        .insert(guild_id.get(), channel_id.get());
+
+    save_changelog();
+}
+
+/// Stop posting the changelog in a guild.
+///
+/// Return `false` if it was not enable.
+pub fn disable_changelog(guild_id: GuildId) -> bool {
+    let removed = CHANGELOG.lock().unwrap().remove(&guild_id.get()).is_some();
+
+    if removed {
+        save_changelog();
+    }
+
+    removed
+}
+
+/// Fetch every set again and replace the ones that changed.
+///
+/// Sets that fail to fetch keep their old version. Return the name of every set that changed
+/// along with what changed. This is blocking so call it in [`task::block_in_place`].
+pub fn refresh_sets() -> Vec<(String, SetDiff)> {
+    // fetch before locking so searches are not block while fetching
+    let fetched = fetch_sets();
+
+    let mut diffs = vec![];
+    let mut recorded = vec![];
+    {
+        let mut sets = SETS.write().unwrap();
+
+        for (code, new) in fetched {
+            let new = match new {
+                Ok(set) => set,
+                Err(err) => {
+                    error!("Cannot refresh set {} due to: {err}", code.yellow());
+                    continue;
+                }
+            };
+
+            // a set that fail to load before is not new to players so there is nothing to post
+            let Some(old) = sets.get(code) else {
+                done!("Set {} recovered", code.green());
+                sets.insert(code, Arc::new(new));
+                continue;
+            };
+
+            let changes = diff(old, &new);
+
+            if changes.is_empty() {
+                continue;
+            }
+
+            recorded.push((code, changes.clone()));
+            diffs.push((new.name.clone(), changes));
+            sets.insert(code, Arc::new(new));
+        }
+    }
+
+    timeline::record_changes(&recorded);
+    health::mark_sets_loaded();
+    diffs
+}
+
+/// Generate the changelog embed for a set.
+pub fn changelog_embed(set_name: &str, changes: &SetDiff) -> CreateEmbed {
+    let mut lines = vec![];
+
+    if !changes.added.is_empty() {
+        lines.push(format!("**Added:** {}", changes.added.join(", ")));
+    }
+    if !changes.removed.is_empty() {
+        lines.push(format!("**Removed:** {}", changes.removed.join(", ")));
+    }
+    for card in &changes.changed {
+        lines.push(format!(
+            "**{}:** {}",
+            card.name,
+            card.fields
+                .iter()
+                .map(|f| format!("{} `{}` → `{}`", f.field, shorten(&f.old), shorten(&f.new)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let mut desc = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if desc.len() + line.len() > MAX_DESC_LEN {
+            write!(desc, "...and {} more changes", lines.len() - i).unwrap();
+            break;
+        }
+        writeln!(desc, "{line}").unwrap();
+    }
+
+    CreateEmbed::new()
+        .color(roles::BLUE)
+        .title(format!("{set_name} Updated"))
+        .description(desc)
+}
+
+/// Cut a value off so a long description does not take the whole changelog.
+pub(crate) fn shorten(value: &str) -> String {
+    let value = value.replace('`', "'").replace('\n', " ");
+
+    match value {
+        v if v.is_empty() => String::from("none"),
+        v if v.chars().count() > MAX_VALUE_LEN => {
+            v.chars().take(MAX_VALUE_LEN).collect::<String>() + "..."
+        }
+        v => v,
+    }
+}
+
+/// Start the background set refresh, only the first call start it.
+pub fn start_refresh(ctx: &Context) {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        info!("Set refresh started");
+        let mut interval = time::interval(RETRY_INTERVAL);
+        // the first tick is immediate and the sets were just loaded
+        interval.tick().await;
+        let mut last_refresh = Instant::now();
+
+        loop {
+            interval.tick().await;
+
+            if !health::has_failed_sets() && last_refresh.elapsed() < REFRESH_INTERVAL {
+                continue;
+            }
+            last_refresh = Instant::now();
+
+            info!("Refreshing sets...");
+            let diffs = task::block_in_place(refresh_sets);
+            done!("Finish refreshing sets, {} changed", diffs.len().green());
+
+            if diffs.is_empty() {
+                continue;
+            }
+
+            // only post in guilds this process handle
+            let channels = CHANGELOG
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(g, _)| ctx.cache.guild(GuildId::new(**g)).is_some())
+                .map(|(_, c)| ChannelId::new(*c))
+                .collect::<Vec<_>>();
+
+            for channel_id in channels {
+                for (name, changes) in &diffs {
+                    let msg = CreateMessage::new().embed(changelog_embed(name, changes));
+                    if let Err(err) = channel_id.send_message(&ctx, msg).await {
+                        error!("Cannot post changelog in {channel_id} due to: {err}");
+                    }
+                }
+            }
+        }
+    });
+}
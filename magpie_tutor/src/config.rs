@@ -0,0 +1,58 @@
+//! Implementation for the environment variable config.
+//!
+//! Some config like the extra set sources or the status services are a comma separated list of
+//! `<key>=<value>` in an environment variable. They are parse once at startup and invalid config
+//! is log and ignored so a typo does not take the bot down.
+
+use std::env;
+
+use magpie_engine::prelude::*;
+
+use crate::{error, Color};
+
+/// Parse a list of `<key>=<value>` separated by comma, `parse` convert each pair.
+///
+/// `format` is the expected format show in the error, like `<set code>=<filter>`. Return an error
+/// message for the first invalid entry.
+pub fn parse_env_pairs<T>(
+    value: &str,
+    format: &str,
+    mut parse: impl FnMut(&str, &str) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let (key, value) = entry
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .filter(|(k, v)| !k.is_empty() && !v.is_empty())
+                .ok_or_else(|| format!("`{entry}` is not in the {format} format"))?;
+
+            parse(key, value)
+        })
+        .collect()
+}
+
+/// Load a config from an environment variable with `parse`.
+///
+/// Return [`None`] if the variable is not set or the config is invalid, in which case the error
+/// is log.
+pub fn load_env_pairs<T>(var: &str, parse: impl FnOnce(&str) -> Result<T, String>) -> Option<T> {
+    let value = env::var(var).ok()?;
+
+    parse(&value)
+        .map_err(|err| error!("Ignoring {} due to: {err}", var.red()))
+        .ok()
+}
+
+/// Check that a config key is a set code and give it a static lifetime.
+pub fn static_set_code(code: &str) -> Result<&'static str, String> {
+    if SetCode::new(code).is_none() {
+        return Err(format!("set code `{code}` must be 3 ascii characters"));
+    }
+
+    // config are only parse once at startup so this is fine
+    Ok(Box::leak(code.to_owned().into_boxed_str()))
+}
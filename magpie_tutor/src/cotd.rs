@@ -0,0 +1,226 @@
+//! Implementation for the card of the day.
+//!
+//! Guilds that enable the card of the day get a random card posted daily in a channel at a set
+//! time (in UTC). Each guild keep a small history of the cards posted so the same card don't get
+//! posted again too soon. The configs are save to [`COTD_FILE_PATH`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use chrono::{Datelike, Timelike, Utc};
+use poise::serenity_prelude::{ChannelId, Context, CreateAttachment, CreateMessage, GuildId};
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+use tokio::{task, time};
+
+use crate::{
+    done,
+    emojis::{cached_permissions, plain_emojis, with_plain_emojis_sync},
+    error, info,
+    locale::guild_lang,
+    search::{gen_plain_embed, gen_portraits, missing_portrait},
+    shard::{read_data, write_data},
+    Color, COTD, SETS,
+};
+
+/// Location of the card of the day file.
+pub const COTD_FILE_PATH: &str = "./cotd.bin";
+
+/// How many past cards to remember so they are not posted again.
+const HISTORY_SIZE: usize = 30;
+
+/// How often the scheduler check if a card need to be posted.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Card of the day config for a guild.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CotdConfig {
+    /// The channel to post the card in.
+    pub channel_id: u64,
+    /// The set to pick the card from.
+    pub set: String,
+    /// The hour to post the card at in UTC.
+    pub hour: u32,
+    /// The minute to post the card at in UTC.
+    pub minute: u32,
+    /// The day the last card was posted on, counted from the common era.
+    pub last_day: i32,
+    /// The name of the recently posted cards.
+    pub history: VecDeque<String>,
+}
+
+/// Type alias for the card of the day table, guild id to config.
+pub type Cotd = HashMap<u64, CotdConfig>;
+
+/// Load the card of the day configs from the card of the day file.
+pub(crate) fn load_cotd() -> Cotd {
+    read_data(COTD_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the card of the day configs to the card of the day file.
+///
+/// This lock [`COTD`] so make sure you drop any guard before calling this.
+pub fn save_cotd() {
+    write_data(COTD_FILE_PATH, &*COTD);
+    done!(
+        "Card of the day save successfully to {}",
+        COTD_FILE_PATH.green()
+    );
+}
+
+/// Parse a `HH:MM` time into hour and minute.
+pub fn parse_time(time: &str) -> Option<(u32, u32)> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let (hour, minute) = (hour.parse().ok()?, minute.parse().ok()?);
+
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+/// Enable the card of the day for a guild, replacing the old config but keeping the history.
+pub fn enable_cotd(
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    set: String,
+    (hour, minute): (u32, u32),
+) {
+    {
+        let mut cotd = COTD.lock().unwrap();
+        let history = cotd
+            .remove(&guild_id.get())
+            .map(|c| c.history)
+            .unwrap_or_default();
+
+        cotd.insert(
+            guild_id.get(),
+            CotdConfig {
+                channel_id: channel_id.get(),
+                set,
+                hour,
+                minute,
+                last_day: 0,
+                history,
+            },
+        );
+    }
+
+    save_cotd();
+}
+
+/// Disable the card of the day for a guild.
+///
+/// Return `false` if it was not enable.
+pub fn disable_cotd(guild_id: GuildId) -> bool {
+    let removed = COTD.lock().unwrap().remove(&guild_id.get()).is_some();
+
+    if removed {
+        save_cotd();
+    }
+
+    removed
+}
+
+/// Start the card of the day scheduler, only the first call start it.
+pub fn start_scheduler(ctx: &Context) {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        info!("Card of the day scheduler started");
+        let mut interval = time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            // portraits are fetch with blocking request, only post in guilds this process handle
+            let posts = task::block_in_place(|| due_posts(&ctx));
+            for (channel_id, msg) in posts {
+                if let Err(err) = channel_id.send_message(&ctx, msg).await {
+                    error!("Cannot post card of the day in {channel_id} due to: {err}");
+                }
+            }
+        }
+    });
+}
+
+/// Get the card of the day messages that need to be posted now and mark them as posted.
+///
+/// Only guilds in the cache are checked, so processes sharing the config don't double post.
+/// The portraits are generated after the locks are release since they may need downloading.
+fn due_posts(ctx: &Context) -> Vec<(ChannelId, CreateMessage)> {
+    let now = Utc::now();
+    let today = now.num_days_from_ce();
+    let minutes = now.hour() * 60 + now.minute();
+
+    let mut picks = vec![];
+    {
+        let sets = SETS.read().unwrap();
+        let mut cotd = COTD.lock().unwrap();
+
+        for (guild_id, config) in cotd.iter_mut() {
+            if ctx.cache.guild(GuildId::new(*guild_id)).is_none() {
+                continue;
+            }
+
+            if config.last_day == today || minutes < config.hour * 60 + config.minute {
+                continue;
+            }
+            config.last_day = today;
+
+            let Some(set) = sets.get(config.set.as_str()) else {
+                continue;
+            };
+
+            // fallback to any card when every card is in the history
+            let Some(card) = set
+                .random_card(&mut thread_rng(), |c| !config.history.contains(&c.name))
+                .or_else(|| set.random_card(&mut thread_rng(), |_| true))
+            else {
+                continue;
+            };
+
+            config.history.push_back(card.name.clone());
+            if config.history.len() > HISTORY_SIZE {
+                config.history.pop_front();
+            }
+
+            let guild = Some(GuildId::new(*guild_id));
+            let channel_id = ChannelId::new(config.channel_id);
+            let lang = guild_lang(guild);
+            let permissions = cached_permissions(ctx, guild, channel_id);
+            let embed = with_plain_emojis_sync(plain_emojis(guild, permissions), || {
+                gen_plain_embed(card, set, false, lang)
+            })
+            .thumbnail("attachment://cotd.png");
+
+            picks.push((channel_id, embed, card.clone()));
+        }
+    }
+
+    if picks.is_empty() {
+        return vec![];
+    }
+    save_cotd();
+
+    let portraits = gen_portraits(&picks.iter().map(|(_, _, card)| card).collect::<Vec<_>>());
+    picks
+        .into_iter()
+        .zip(portraits)
+        .map(|((channel_id, embed, card), portrait)| {
+            let portrait = portrait.unwrap_or_else(|| missing_portrait(&card).to_vec());
+            let msg = CreateMessage::new()
+                .content("# Card of the Day")
+                .add_file(CreateAttachment::bytes(portrait, "cotd.png"))
+                .embed(embed);
+            (channel_id, msg)
+        })
+        .collect()
+}
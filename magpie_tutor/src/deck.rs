@@ -0,0 +1,319 @@
+//! Implementation for showing deck summary.
+
+use std::{collections::BTreeMap, fmt::Write, io::Cursor};
+
+use image::{imageops, GenericImageView, ImageFormat, Rgba, RgbaImage};
+use magpie_engine::prelude::*;
+use poise::serenity_prelude::{colours::roles, CreateAttachment, CreateEmbed};
+use tokio::task;
+
+use crate::{formats::Format, search::gen_portraits, Card, MessageAdapter, SETS};
+
+/// How many portraits per row in the deck image.
+const DECK_IMAGE_COLUMNS: u32 = 5;
+
+/// The file name of the deck image attachment.
+const DECK_IMAGE_NAME: &str = "deck.png";
+
+/// 3x5 pixel glyphs for the digits then `x` to draw the copies count with.
+const GLYPHS: [[u8; 5]; 11] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b000, 0b101, 0b010, 0b101, 0b000],
+];
+
+/// Parse a deck code, resolve it against a set and generate the deck summary message.
+///
+/// The deck is validate against the format ruleset if there is one, otherwise the set default
+/// ruleset.
+pub fn deck_message(code: &str, set_code: &str, format: Option<&Format>) -> MessageAdapter {
+    let (embed, image) = deck_embed(code, set_code, format);
+
+    match image {
+        Some(image) => MessageAdapter::new()
+            .embeds(vec![embed.image(format!("attachment://{DECK_IMAGE_NAME}"))])
+            .attachments(vec![CreateAttachment::bytes(image, DECK_IMAGE_NAME)]),
+        None => MessageAdapter::new().embeds(vec![embed]),
+    }
+}
+
+fn deck_embed(
+    code: &str,
+    set_code: &str,
+    format: Option<&Format>,
+) -> (CreateEmbed, Option<Vec<u8>>) {
+    let list = match DeckList::parse(code) {
+        Ok(it) => it,
+        Err(err) => {
+            return (
+                CreateEmbed::new()
+                    .color(roles::RED)
+                    .title("Deck Error")
+                    .description(format!("Cannot read the deck: {err}")),
+                None,
+            )
+        }
+    };
+
+    if format.is_some_and(|f| !f.allow_set(set_code)) {
+        return (
+            CreateEmbed::new()
+                .color(roles::RED)
+                .title("Deck Error")
+                .description(format!("Set `{set_code}` is not allowed in this format")),
+            None,
+        );
+    }
+
+    let sets = SETS.read().unwrap();
+    let Some(set) = sets.get(set_code) else {
+        return (
+            CreateEmbed::new()
+                .color(roles::RED)
+                .title("Deck Error")
+                .description(format!("Unknown set code `{set_code}`")),
+            None,
+        );
+    };
+
+    let deck = list.resolve(set);
+    let stats = deck.stats();
+
+    let mut desc = format!("**Size:** {} cards\n", stats.size);
+    if let Some(side) = &deck.side_deck {
+        writeln!(desc, "**Side Deck:** {side}").unwrap();
+    }
+    desc.push('\n');
+
+    for (card, count) in &deck.cards {
+        writeln!(desc, "{count}x {}", card.name).unwrap();
+    }
+
+    let mut embed = CreateEmbed::new()
+        .color(roles::PURPLE)
+        .title(format!("Deck ({})", set.name))
+        .description(desc);
+
+    let mut stat = String::new();
+    if stats.free != 0 {
+        writeln!(stat, "**Free:** {}", stats.free).unwrap();
+    }
+    append_curve(&mut stat, "Blood", &stats.blood_curve);
+    append_curve(&mut stat, "Bone", &stats.bone_curve);
+    append_curve(&mut stat, "Energy", &stats.energy_curve);
+    if stats.mox != 0 {
+        writeln!(stat, "**Mox:** {}", stats.mox).unwrap();
+    }
+    write!(
+        stat,
+        "**Average Stat:** {:.1} / {:.1}",
+        stats.average_attack, stats.average_health
+    )
+    .unwrap();
+
+    embed = embed.field("== STATS ==", stat, false);
+
+    let violations = deck.validate(
+        set,
+        &format.map_or_else(|| ruleset(set_code), |f| f.ruleset.clone()),
+    );
+    let mut legality = String::new();
+    for rule in Rule::ALL {
+        let broken = violations
+            .iter()
+            .filter(|v| v.rule() == rule)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        if broken.is_empty() {
+            writeln!(legality, "✅ {rule}").unwrap();
+        } else {
+            writeln!(legality, "❌ {rule}: {}", broken.join(", ")).unwrap();
+        }
+    }
+
+    embed = embed.field("== LEGALITY ==", legality, false);
+
+    if !deck.missing.is_empty() {
+        embed = embed.field(
+            "== NOT FOUND ==",
+            deck.missing
+                .iter()
+                .map(|(name, count)| format!("{count}x {name}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            false,
+        );
+    }
+
+    // portraits can take a while to download so don't keep the sets lock while generating them
+    let cards = deck
+        .cards
+        .iter()
+        .map(|(card, count)| ((*card).clone(), *count))
+        .collect::<Vec<_>>();
+    drop(sets);
+
+    let image = gen_deck_image(&cards);
+
+    (embed, (!image.is_empty()).then_some(image))
+}
+
+/// Composite the deck portraits into a single grid image with the copies count in the corner of
+/// each portrait.
+///
+/// The portraits are generated with [`gen_portraits`] so recently use portraits are not download
+/// again. Portraits are scaled to the size of the first one so mixed portrait sizes still line up.
+/// Return an empty [`Vec`] if no portrait can be generated.
+#[allow(clippy::cast_possible_truncation)]
+pub fn gen_deck_image(cards: &[(Card, usize)]) -> Vec<u8> {
+    task::block_in_place(|| {
+        let cards = cards
+            .iter()
+            .filter(|(card, _)| !card.portrait.is_empty())
+            .collect::<Vec<_>>();
+        let portraits = gen_portraits(&cards.iter().map(|(card, _)| card).collect::<Vec<_>>())
+            .into_iter()
+            .zip(&cards)
+            .filter_map(|(data, (_, count))| {
+                image::load_from_memory(&data?)
+                    .ok()
+                    .map(|img| (img, *count))
+            })
+            .collect::<Vec<_>>();
+
+        let Some((first, _)) = portraits.first() else {
+            return Vec::new();
+        };
+
+        let (w, h) = first.dimensions();
+        let len = portraits.len() as u32;
+        let cols = DECK_IMAGE_COLUMNS.min(len);
+        let mut grid = RgbaImage::new(cols * w, len.div_ceil(DECK_IMAGE_COLUMNS) * h);
+
+        for (i, (img, count)) in portraits.iter().enumerate() {
+            let (x, y) = (i as u32 % cols * w, i as u32 / cols * h);
+
+            let img = if img.dimensions() == (w, h) {
+                img.to_rgba8()
+            } else {
+                img.resize_exact(w, h, imageops::Nearest).to_rgba8()
+            };
+
+            imageops::overlay(&mut grid, &img, x.into(), y.into());
+            draw_count(&mut grid, *count, x + w, y + h, (h / 24).max(1));
+        }
+
+        let mut out = vec![];
+        grid.write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .expect("Encode deck image fails");
+        out
+    })
+}
+
+/// Draw the copies count like `x2` with its bottom right corner at `right` and `bottom`.
+fn draw_count(img: &mut RgbaImage, count: usize, right: u32, bottom: u32, scale: u32) {
+    let glyphs = std::iter::once(GLYPHS[10])
+        .chain(number_glyphs(count))
+        .collect::<Vec<_>>();
+
+    // 1 pixel padding around the text
+    let width = glyphs_width(glyphs.len(), scale) + 2 * scale;
+    let height = 7 * scale;
+    let (left, top) = (right.saturating_sub(width), bottom.saturating_sub(height));
+
+    fill(img, (left, top), (right, bottom), Rgba([0, 0, 0, 200]));
+    draw_glyphs(
+        img,
+        &glyphs,
+        left + scale,
+        top + scale,
+        scale,
+        Rgba([255; 4]),
+    );
+}
+
+/// Get the glyphs to draw a number with.
+pub(crate) fn number_glyphs(n: usize) -> Vec<[u8; 5]> {
+    n.to_string()
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|g| GLYPHS[g as usize])
+        .collect()
+}
+
+/// The width of the given amount of glyphs, each glyph is 3 pixels wide with 1 pixel gap.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn glyphs_width(len: usize, scale: u32) -> u32 {
+    (len as u32 * 4).saturating_sub(1) * scale
+}
+
+/// Draw glyphs with their top left corner at `left` and `top`.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn draw_glyphs(
+    img: &mut RgbaImage,
+    glyphs: &[[u8; 5]],
+    left: u32,
+    top: u32,
+    scale: u32,
+    color: Rgba<u8>,
+) {
+    for (i, glyph) in glyphs.iter().enumerate() {
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..3 {
+                if bits >> (2 - col) & 1 == 1 {
+                    let x = left + (i as u32 * 4 + col) * scale;
+                    let y = top + row as u32 * scale;
+                    fill(img, (x, y), (x + scale, y + scale), color);
+                }
+            }
+        }
+    }
+}
+
+/// Fill a rectangle from `start` to `end` exclusive, clipped to the image.
+pub(crate) fn fill(img: &mut RgbaImage, start: (u32, u32), end: (u32, u32), color: Rgba<u8>) {
+    for x in start.0..end.0.min(img.width()) {
+        for y in start.1..end.1.min(img.height()) {
+            img.put_pixel(x, y, color);
+        }
+    }
+}
+
+/// Get the deck building rules for a set.
+fn ruleset(set_code: &str) -> Ruleset {
+    match set_code {
+        // IMF formats always come with a side deck
+        "std" | "ete" | "egg" => Ruleset {
+            side_deck: true,
+            ..Default::default()
+        },
+        _ => Ruleset::default(),
+    }
+}
+
+/// Append a cost curve line in the form of `cost: count`.
+pub(crate) fn append_curve(out: &mut String, label: &str, curve: &BTreeMap<isize, usize>) {
+    if curve.is_empty() {
+        return;
+    }
+
+    writeln!(
+        out,
+        "**{label}:** {}",
+        curve
+            .iter()
+            .map(|(cost, count)| format!("{cost}: {count}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+    .unwrap();
+}
@@ -0,0 +1,143 @@
+//! Implementation for the search delimiters of each guild.
+//!
+//! Searches are wrap in `[[]]` by default but some guilds have other bots that also use it, so
+//! guilds can pick other delimiters with `/delimiters`. The search regex of every [`Delimiter`] is
+//! compile once into [`SEARCH_REGEXES`] and each message is match with the one of its guild.
+
+use std::collections::HashMap;
+
+use poise::serenity_prelude::GuildId;
+use regex::{escape, Regex};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    done,
+    shard::{read_data, write_data},
+    Color, Death, DELIMITERS, SEARCH_REGEXES,
+};
+
+/// Location of the guild delimiters file.
+pub const DELIMITERS_FILE_PATH: &str = "./delimiters.bin";
+
+/// Type alias for the delimiters table, guild id to delimiter.
+pub type Delimiters = HashMap<u64, Delimiter>;
+
+/// What a search is wrap in.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, poise::ChoiceParameter,
+)]
+pub enum Delimiter {
+    /// `[[stoat]]`
+    #[default]
+    #[name = "[[...]]"]
+    Brackets,
+    /// `{{stoat}}`
+    #[name = "{{...}}"]
+    Braces,
+    /// `((stoat))`
+    #[name = "((...))"]
+    Parens,
+}
+
+impl Delimiter {
+    /// Every delimiter.
+    pub const ALL: [Delimiter; 3] = [Delimiter::Brackets, Delimiter::Braces, Delimiter::Parens];
+
+    /// The opening and closing text of this delimiter.
+    #[must_use]
+    pub fn pair(self) -> (&'static str, &'static str) {
+        match self {
+            Delimiter::Brackets => ("[[", "]]"),
+            Delimiter::Braces => ("{{", "}}"),
+            Delimiter::Parens => ("((", "))"),
+        }
+    }
+
+    /// Replace the default `[[` and `]]` in a text with this delimiter, for the help texts.
+    #[must_use]
+    pub fn apply(self, text: &str) -> String {
+        let (open, close) = self.pair();
+        text.replace("[[", open).replace("]]", close)
+    }
+}
+
+/// Modifiers and search term pattern of [`Delimiter::Parens`].
+///
+/// The parentheses inside the term must be balance (up to 2 level deep) so a query like
+/// `((a or (b and c)))` is not cut at the first `))`. The modifiers can't have a parenthesis or
+/// they would eat the opening one of the query.
+const PARENS_PATTERN: (&str, &str) = (r"[^\s(]*", r"(?:[^()]|\((?:[^()]|\([^()]*\))*\))*?");
+
+/// Compile the search regex of every delimiter.
+///
+/// The first group is the modifiers and the second group is the search term.
+pub(crate) fn search_regexes() -> HashMap<Delimiter, Regex> {
+    Delimiter::ALL
+        .into_iter()
+        .map(|d| {
+            let (open, close) = d.pair();
+            let (modifier, term) = match d {
+                Delimiter::Parens => PARENS_PATTERN,
+                Delimiter::Brackets | Delimiter::Braces => (r"\S*", ".*?"),
+            };
+            let regex = Regex::new(&format!(
+                r"({modifier}){}({term}){}",
+                escape(open),
+                escape(close)
+            ))
+            .unwrap_or_die("Cannot compile search regex");
+            (d, regex)
+        })
+        .collect()
+}
+
+/// Load the guild delimiters from the delimiters file.
+pub(crate) fn load_delimiters() -> Delimiters {
+    read_data(DELIMITERS_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the guild delimiters to the delimiters file.
+///
+/// This lock [`DELIMITERS`] so make sure you drop any guard before calling this.
+pub fn save_delimiters() {
+    write_data(DELIMITERS_FILE_PATH, &*DELIMITERS);
+    done!(
+        "Delimiters save successfully to {}",
+        DELIMITERS_FILE_PATH.green()
+    );
+}
+
+/// Get the delimiter of a guild, this is [`Delimiter::Brackets`] outside of guilds.
+pub fn guild_delimiter(guild_id: Option<GuildId>) -> Delimiter {
+    let Some(guild_id) = guild_id else {
+        return Delimiter::default();
+    };
+
+    DELIMITERS
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Set the delimiter of a guild.
+pub fn set_guild_delimiter(guild_id: GuildId, delimiter: Delimiter) {
+    {
+        let mut delimiters = DELIMITERS.lock().unwrap();
+        if delimiter == Delimiter::default() {
+            delimiters.remove(&guild_id.get());
+        } else {
+            delimiters.insert(guild_id.get(), delimiter);
+        }
+    }
+
+    save_delimiters();
+}
+
+/// Get the search regex of a guild.
+pub fn search_regex(guild_id: Option<GuildId>) -> &'static Regex {
+    &SEARCH_REGEXES[&guild_delimiter(guild_id)]
+}
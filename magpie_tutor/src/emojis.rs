@@ -1,81 +1,238 @@
-//! Emoji constant for the bot.
+//! Emoji table for the bot.
+//!
+//! Every emoji have a default value, a text fallback and can be override using the emoji config
+//! file at [`EMOJI_FILE_PATH`]. The config is a json object of table to emoji name to value like
+//! so:
+//!
+//! ```json
+//! {
+//!     "cost": {
+//!         "BLOOD": "<:blood:1234567890>"
+//!     }
+//! }
+//! ```
+//!
+//! The bot can also upload it own copy of the emojis as application emojis at startup so it does
+//! not need to share a guild with the emojis. Put the emojis images at
+//! `assets/emojis/<table>/<NAME>.png` (for example `assets/emojis/cost/BLOOD.png`), they are
+//! bundle into the binary at build time and uploaded and use instead of the default.
+//!
+//! Custom emojis show up as broken `<:blood:...>` text where the bot cannot use them, like in DMs
+//! or channels where it lack the use external emojis permission. Guilds pick an [`EmojiMode`] with
+//! `/emojis` and replies render inside [`with_plain_emojis`] use the text fallbacks instead.
 
-use magpie_engine::{SpAtk, TraitsFlag};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    fs::File,
+    future::Future,
+    ops::Deref,
+    sync::{Mutex, RwLock},
+};
+
+use magpie_engine::{EmojiTable, Rarity, SpAtk, TraitsFlag};
+use poise::serenity_prelude::{ChannelId, Context, CreateAttachment, GuildId, Http, Permissions};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    done, error, info,
+    shard::{read_data, write_data},
+    Color, Res, EMOJI_MODES, EMOJI_REGEX,
+};
+
+/// Location of the emoji config file.
+pub const EMOJI_FILE_PATH: &str = "./emojis.json";
+/// Location of the guild emoji mode file.
+pub const EMOJI_MODE_FILE_PATH: &str = "./emoji_mode.bin";
+
+/// Type alias for the emoji mode table, guild id to mode.
+pub type EmojiModes = HashMap<u64, EmojiMode>;
+
+/// How a guild want the emojis in replies to render.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, poise::ChoiceParameter,
+)]
+pub enum EmojiMode {
+    /// Custom emojis unless the bot cannot use them in the channel.
+    #[default]
+    Auto,
+    /// Always the custom emojis.
+    Custom,
+    /// Always the plain text fallbacks.
+    Plain,
+}
+
+tokio::task_local! {
+    /// If emojis render as their text fallback in the current task.
+    static PLAIN: bool;
+}
+
+/// Id of the application emojis the bot own, these are always usable.
+static APP_EMOJIS: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+/// Every value an emoji was set to, so setting the same value again reuse it instead of leaking
+/// a new copy.
+static INTERNED: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+/// The emoji images bundle from `assets/emojis`, as the table, the emoji name and the PNG.
+const BUNDLED_EMOJIS: &[(&str, &str, &[u8])] =
+    include!(concat!(env!("OUT_DIR"), "/bundled_emojis.rs"));
+
+/// A single emoji in the emoji table.
+///
+/// Emoji deref to the [`str`] value currently in use so they can be use like a normal string.
+#[derive(Debug)]
+pub struct Emoji {
+    /// The name of this emoji in the table.
+    pub name: &'static str,
+    /// The text to use when the emoji cannot be use.
+    pub fallback: &'static str,
+    value: RwLock<&'static str>,
+}
+
+impl Emoji {
+    /// Create a new emoji with a default value and a text fallback.
+    pub const fn new(name: &'static str, value: &'static str, fallback: &'static str) -> Self {
+        Emoji {
+            name,
+            fallback,
+            value: RwLock::new(value),
+        }
+    }
+
+    /// Get the current value of this emoji, the text fallback inside [`with_plain_emojis`].
+    pub fn get(&self) -> &'static str {
+        if PLAIN.try_with(|p| *p).unwrap_or(false) {
+            return self.fallback;
+        }
+
+        *self.value.read().unwrap()
+    }
+
+    /// Replace the value of this emoji.
+    pub fn set(&self, value: &str) {
+        let mut interned = INTERNED.lock().unwrap();
+        let value = if let Some(value) = interned.get(value) {
+            *value
+        } else {
+            let value: &'static str = Box::leak(value.into());
+            interned.insert(value);
+            value
+        };
+        *self.value.write().unwrap() = value;
+    }
+
+    /// Replace the value of this emoji with the text fallback.
+    pub fn use_fallback(&self) {
+        *self.value.write().unwrap() = self.fallback;
+    }
+}
+
+impl Deref for Emoji {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl Display for Emoji {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get())
+    }
+}
 
 macro_rules! emoji_table {
-    (pub mod $mod:ident {$($name:ident = $value:literal;)*}) => {
+    (pub mod $mod:ident {$($name:ident = $value:literal | $fallback:literal;)*}) => {
         pub mod $mod {
             #![allow(missing_docs)]
-            $(pub const $name: &'static str = $value;)*
+            use super::Emoji;
+
+            $(pub static $name: Emoji = Emoji::new(stringify!($name), $value, $fallback);)*
+
+            /// Every emoji in this table.
+            pub static ALL: &[&Emoji] = &[$(&$name,)*];
         }
     };
 }
 
 emoji_table! {
     pub mod number {
-        NEGATE = "<:negative:1254856239108853810>";
-        X = "<:x_:1254844718907654204>";
+        NEGATE = "<:negative:1254856239108853810>" | "-";
+        X = "<:x_:1254844718907654204>" | "x";
 
         // Number icon
-        ZERO = "<:0_:1254844707817787415>";
-        ONE = "<:1_:1254844708375625739>";
-        TWO = "<:2_:1254844709612814578>";
-        THREE = "<:3_:1254844710531629107>";
-        FOUR = "<:4_:1254844711294992498>";
-        FIVE = "<:5_:1254844711806701682>";
-        SIX = "<:6_:1254844713043755038>";
-        SEVEN = "<:7_:1254844781255725127>";
-        EIGHT = "<:8_:1254844715568730224>";
-        NINE = "<:9_:1254844782136786988>";
+        ZERO = "<:0_:1254844707817787415>" | "0";
+        ONE = "<:1_:1254844708375625739>" | "1";
+        TWO = "<:2_:1254844709612814578>" | "2";
+        THREE = "<:3_:1254844710531629107>" | "3";
+        FOUR = "<:4_:1254844711294992498>" | "4";
+        FIVE = "<:5_:1254844711806701682>" | "5";
+        SIX = "<:6_:1254844713043755038>" | "6";
+        SEVEN = "<:7_:1254844781255725127>" | "7";
+        EIGHT = "<:8_:1254844715568730224>" | "8";
+        NINE = "<:9_:1254844782136786988>" | "9";
     }
 }
 
 emoji_table! {
     pub mod cost {
         // Cost icon
-        BLOOD = "<:blood:1254812601452597350>";
-        BONE = "<:bones:1254812629181137036>";
-        ENERGY = "<:energy:1254812689608343674>";
-        MAX = "<:overcharge:1254812739118043198>";
-        LINK = "<:link:1292910794064789564>";
-        GOLD = "<:gold:1292910650342768640>";
+        BLOOD = "<:blood:1254812601452597350>" | "🩸";
+        BONE = "<:bones:1254812629181137036>" | "🦴";
+        ENERGY = "<:energy:1254812689608343674>" | "⚡";
+        MAX = "<:overcharge:1254812739118043198>" | "🔋";
+        LINK = "<:link:1292910794064789564>" | "🔗";
+        GOLD = "<:gold:1292910650342768640>" | "🪙";
 
         // Mox color
-        ORANGE = "<:ruby:1254812785985196134>";
-        GREEN = "<:emerald:1254812654795624531>";
-        BLUE = "<:sapphire:1254812816351952956>";
-        RED = "Garnet,";
-        YELLOW = "Topaz,";
-        PURPLE = "Amethyst,";
-        GRAY = "<:prism:1254812757757268142>";
-        BLACK = "<:onyx:1292911543159230746>";
+        ORANGE = "<:ruby:1254812785985196134>" | "🟧";
+        GREEN = "<:emerald:1254812654795624531>" | "🟩";
+        BLUE = "<:sapphire:1254812816351952956>" | "🟦";
+        RED = "Garnet," | "🟥";
+        YELLOW = "Topaz," | "🟨";
+        PURPLE = "Amethyst," | "🟪";
+        GRAY = "<:prism:1254812757757268142>" | "⬜";
+        BLACK = "<:onyx:1292911543159230746>" | "⬛";
         // Shattered Mox color
-        SHATTERED_ORANGE = "SHATTER ORANGE,";
-        SHATTERED_GREEN = "SHATTER GREEN,";
-        SHATTERED_BLUE = "SHATTER BLUE,";
-        SHATTERED_GRAY = "SHATTER GRAY,";
+        SHATTERED_ORANGE = "SHATTER ORANGE," | "[shattered ruby]";
+        SHATTERED_GREEN = "SHATTER GREEN," | "[shattered emerald]";
+        SHATTERED_BLUE = "SHATTER BLUE," | "[shattered sapphire]";
+        SHATTERED_GRAY = "SHATTER GRAY," | "[shattered prism]";
 
-        SHATTERED_RED = "SHATTER RED,";
-        SHATTERED_YELLOW = "SHATTER YELLOW,";
-        SHATTERED_PURPLE = "SHATTER PURPLE,";
+        SHATTERED_RED = "SHATTER RED," | "[shattered garnet]";
+        SHATTERED_YELLOW = "SHATTER YELLOW," | "[shattered topaz]";
+        SHATTERED_PURPLE = "SHATTER PURPLE," | "[shattered amethyst]";
+        SHATTERED_BLACK = "SHATTER BLACK," | "[shattered onyx]";
 
-        PLUS1 = "<:1_cost:1274031134442913872>";
+        PLUS1 = "<:1_cost:1274031134442913872>" | "+1";
     }
 }
 
 emoji_table! {
     pub mod icon {
-        CONDUCTIVE = "<:conductive:1254849745869078569>";
-        RARE = "<:rare:1254852219090767897>";
-        BAN = "<:banned:1254841974129692764>";
-        HARD = "<:unhammerable:1254848827970555975>";
-        TERRAIN = "<:bloodless:1254848805032038591>";
+        CONDUCTIVE = "<:conductive:1254849745869078569>" | "[conductive]";
+        RARE = "<:rare:1254852219090767897>" | "⭐";
+        BAN = "<:banned:1254841974129692764>" | "🚫";
+        HARD = "<:unhammerable:1254848827970555975>" | "[unhammerable]";
+        TERRAIN = "<:bloodless:1254848805032038591>" | "[terrain]";
+
+        ANT = "<:ant:1254853395097976953>" | "🐜";
+        BELL = "<:bell:1254854216875507722>" | "🔔";
+        MOX = "<:mox:1254853396079312906>" | "💎";
+        CARD = "<:card_atk:1274031231255969885>" | "🃏";
+        MIRROR = "<:mirror:1254853397908164682>" | "🪞";
+    }
+}
 
-        ANT = "<:ant:1254853395097976953>";
-        BELL = "<:bell:1254854216875507722>";
-        MOX = "<:mox:1254853396079312906>";
-        CARD = "<:card_atk:1274031231255969885>";
-        MIRROR = "<:mirror:1254853397908164682>";
+emoji_table! {
+    pub mod rarity {
+        SIDE = "▫️" | "▫️";
+        COMMON = "🔹" | "🔹";
+        UNCOMMON = "🔸" | "🔸";
+        RARE = "⭐" | "⭐";
+        UNIQUE = "🌟" | "🌟";
+        OTHER = "❔" | "❔";
     }
 }
 
@@ -88,12 +245,35 @@ pub trait ToEmoji {
 impl ToEmoji for SpAtk {
     fn to_emoji(&self) -> String {
         match self {
-            SpAtk::MOX | SpAtk::GREEN_MOX => icon::MOX,
-            SpAtk::MIRROR => icon::MIRROR,
-            SpAtk::ANT => icon::ANT,
-            SpAtk::BONE => todo!(),
-            SpAtk::BELL => icon::BELL,
-            SpAtk::CARD => icon::CARD,
+            SpAtk::MOX | SpAtk::GREEN_MOX => &icon::MOX,
+            SpAtk::MIRROR => &icon::MIRROR,
+            SpAtk::ANT => &icon::ANT,
+            SpAtk::BONE => &cost::BONE,
+            SpAtk::BELL => &icon::BELL,
+            SpAtk::CARD => &icon::CARD,
+        }
+        .to_string()
+    }
+}
+
+/// The [`EmojiTable`] of the bot emojis.
+pub struct AppEmojis;
+
+impl EmojiTable for AppEmojis {
+    fn sp_atk(&self, sp: &SpAtk) -> String {
+        sp.to_emoji()
+    }
+}
+
+impl ToEmoji for Rarity {
+    fn to_emoji(&self) -> String {
+        match self {
+            Rarity::SIDE => &rarity::SIDE,
+            Rarity::COMMON => &rarity::COMMON,
+            Rarity::UNCOMMON => &rarity::UNCOMMON,
+            Rarity::RARE => &rarity::RARE,
+            Rarity::UNIQUE => &rarity::UNIQUE,
+            Rarity::Other(_) => &rarity::OTHER,
         }
         .to_string()
     }
@@ -103,10 +283,10 @@ impl ToEmoji for TraitsFlag {
     fn to_emoji(&self) -> String {
         self.iter()
             .map(|v| match v {
-                TraitsFlag::CONDUCTIVE => icon::CONDUCTIVE,
-                TraitsFlag::BAN => icon::BAN,
-                TraitsFlag::TERRAIN => icon::TERRAIN,
-                TraitsFlag::HARD => icon::HARD,
+                TraitsFlag::CONDUCTIVE => &icon::CONDUCTIVE,
+                TraitsFlag::BAN => &icon::BAN,
+                TraitsFlag::TERRAIN => &icon::TERRAIN,
+                TraitsFlag::HARD => &icon::HARD,
                 _ => unreachable!(),
             })
             .fold(String::new(), |a, b| a + b + " ") // this could def be faster but whatever
@@ -123,18 +303,18 @@ macro_rules! impl_emoji {
 
                 for d in self.to_string().chars() {
                     out.push_str(match d {
-                        '-' => self::number::NEGATE,
-
-                        '0' => number::ZERO,
-                        '1' => number::ONE,
-                        '2' => number::TWO,
-                        '3' => number::THREE,
-                        '4' => number::FOUR,
-                        '5' => number::FIVE,
-                        '6' => number::SIX,
-                        '7' => number::SEVEN,
-                        '8' => number::EIGHT,
-                        '9' => number::NINE,
+                        '-' => &self::number::NEGATE,
+
+                        '0' => &number::ZERO,
+                        '1' => &number::ONE,
+                        '2' => &number::TWO,
+                        '3' => &number::THREE,
+                        '4' => &number::FOUR,
+                        '5' => &number::FIVE,
+                        '6' => &number::SIX,
+                        '7' => &number::SEVEN,
+                        '8' => &number::EIGHT,
+                        '9' => &number::NINE,
                         _ => unreachable!(),
                     });
                 }
@@ -151,3 +331,276 @@ impl_emoji!(
     i8 i16 i32 i64 i128 isize
     u8 u16 u32 u64 u128 usize
 );
+
+/// Run `f` with every emoji render as its text fallback if `plain` is true.
+///
+/// The mode follow `f` across awaits but not into spawned tasks.
+pub async fn with_plain_emojis<F: Future>(plain: bool, f: F) -> F::Output {
+    PLAIN.scope(plain, f).await
+}
+
+/// Same as [`with_plain_emojis`] for code that does not await.
+pub fn with_plain_emojis_sync<T>(plain: bool, f: impl FnOnce() -> T) -> T {
+    PLAIN.sync_scope(plain, f)
+}
+
+/// Check if a reply should use the text fallbacks.
+///
+/// In [`EmojiMode::Auto`] the fallbacks are use outside of guilds, like DMs and user installed
+/// apps, and when `app_permissions` does not allow external emojis.
+pub fn plain_emojis(guild_id: Option<GuildId>, app_permissions: Option<Permissions>) -> bool {
+    let Some(guild_id) = guild_id else {
+        return true;
+    };
+
+    match guild_emoji_mode(guild_id) {
+        EmojiMode::Custom => false,
+        EmojiMode::Plain => true,
+        EmojiMode::Auto => {
+            app_permissions.is_some_and(|p| !p.contains(Permissions::USE_EXTERNAL_EMOJIS))
+        }
+    }
+}
+
+/// Get the bot permissions in a guild channel from the cache.
+///
+/// Messages don't come with the bot permissions like interactions do, so they are compute from
+/// the cached guild instead. Threads use the permissions of their parent channel. Return [`None`]
+/// outside of guilds or if the guild is not cached.
+pub fn cached_permissions(
+    ctx: &Context,
+    guild_id: Option<GuildId>,
+    channel_id: ChannelId,
+) -> Option<Permissions> {
+    let bot_id = ctx.cache.current_user().id;
+    let guild = ctx.cache.guild(guild_id?)?;
+    let member = guild.members.get(&bot_id)?;
+    let channel = guild.channels.get(&channel_id).or_else(|| {
+        let parent = guild
+            .threads
+            .iter()
+            .find(|t| t.id == channel_id)?
+            .parent_id?;
+        guild.channels.get(&parent)
+    })?;
+
+    Some(guild.user_permissions_in(channel, member))
+}
+
+/// Get the emoji mode of a guild.
+pub fn guild_emoji_mode(guild_id: GuildId) -> EmojiMode {
+    EMOJI_MODES
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Set the emoji mode of a guild.
+pub fn set_guild_emoji_mode(guild_id: GuildId, mode: EmojiMode) {
+    {
+        let mut modes = EMOJI_MODES.lock().unwrap();
+        if mode == EmojiMode::Auto {
+            modes.remove(&guild_id.get());
+        } else {
+            modes.insert(guild_id.get(), mode);
+        }
+    }
+
+    save_emoji_modes();
+}
+
+/// Load the guild emoji modes from the emoji mode file.
+pub(crate) fn load_emoji_modes() -> EmojiModes {
+    read_data(EMOJI_MODE_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the guild emoji modes to the emoji mode file.
+///
+/// This lock [`EMOJI_MODES`] so make sure you drop any guard before calling this.
+pub fn save_emoji_modes() {
+    write_data(EMOJI_MODE_FILE_PATH, &*EMOJI_MODES);
+    done!(
+        "Emoji modes save successfully to {}",
+        EMOJI_MODE_FILE_PATH.green()
+    );
+}
+
+/// Every emoji table along with their name in the config file.
+fn tables() -> [(&'static str, &'static [&'static Emoji]); 4] {
+    [
+        ("number", number::ALL),
+        ("cost", cost::ALL),
+        ("icon", icon::ALL),
+        ("rarity", rarity::ALL),
+    ]
+}
+
+/// Load the emoji config file and override the emoji table with it.
+///
+/// Missing config file is not an error, the default emoji will be use instead.
+pub fn load_emojis() {
+    let Ok(file) = File::open(EMOJI_FILE_PATH) else {
+        info!(
+            "No emoji config found at {}, using default emojis",
+            EMOJI_FILE_PATH.green()
+        );
+        return;
+    };
+
+    let config: HashMap<String, HashMap<String, String>> = match serde_json::from_reader(file) {
+        Ok(it) => it,
+        Err(err) => {
+            error!("Cannot parse emoji config due to: {}", err.red());
+            return;
+        }
+    };
+
+    let mut count = 0;
+    for (table, values) in config {
+        let Some((_, emojis)) = tables().into_iter().find(|(name, _)| *name == table) else {
+            error!("Unknown emoji table {}", table.red());
+            continue;
+        };
+
+        for (name, value) in values {
+            if let Some(emoji) = emojis.iter().find(|e| e.name == name) {
+                emoji.set(&value);
+                count += 1;
+            } else {
+                error!("Unknown emoji {} in table {}", name.red(), table.blue());
+            }
+        }
+    }
+
+    done!("Loaded {} emoji(s) from config", count.green());
+}
+
+/// Check that every custom emoji can be use by the bot.
+///
+/// The bot can only use emojis from guilds it is in, any emoji that cannot be found will be
+/// replaced with its text fallback.
+pub fn validate_emojis(ctx: &Context) {
+    info!("Validating emojis...");
+
+    let mut available: HashSet<u64> = ctx
+        .cache
+        .guilds()
+        .into_iter()
+        .filter_map(|id| {
+            ctx.cache
+                .guild(id)
+                .map(|g| g.emojis.keys().map(|e| e.get()).collect::<Vec<_>>())
+        })
+        .flatten()
+        .collect();
+    available.extend(APP_EMOJIS.lock().unwrap().iter());
+
+    let mut invalid = 0;
+    for (table, emojis) in tables() {
+        for emoji in emojis {
+            let Some(id) = EMOJI_REGEX
+                .captures(emoji.get())
+                .and_then(|c| c.get(2))
+                .and_then(|id| id.as_str().parse::<u64>().ok())
+            else {
+                // plain text emoji are always usable
+                continue;
+            };
+
+            if !available.contains(&id) {
+                error!(
+                    "Emoji {} in table {} is not usable, falling back to {}",
+                    emoji.name.red(),
+                    table.blue(),
+                    emoji.fallback.yellow()
+                );
+                emoji.use_fallback();
+                invalid += 1;
+            }
+        }
+    }
+
+    if invalid > 0 {
+        done!("Found {} unusable emoji(s)", invalid.red());
+    } else {
+        done!("All emojis are usable");
+    }
+}
+
+/// Upload the bundled emoji images as application emojis and use them in the emoji table.
+///
+/// Emojis that are already uploaded are reused so this is safe to call on every startup. Emojis
+/// without a bundled image are left alone.
+pub async fn upload_emojis(http: &Http) -> Res {
+    if BUNDLED_EMOJIS.is_empty() {
+        info!("No bundled emojis, skipping upload");
+        return Ok(());
+    }
+
+    info!("Fetching application emojis...");
+    let existing = http.get_application_emojis().await?;
+
+    let mut uploaded = 0;
+    let mut reused = 0;
+    for (table, name, image) in BUNDLED_EMOJIS {
+        let Some(emoji) = tables()
+            .into_iter()
+            .find(|(t, _)| t == table)
+            .and_then(|(_, emojis)| emojis.iter().find(|e| e.name == *name))
+        else {
+            error!(
+                "Unknown bundled emoji {} in table {}",
+                name.red(),
+                table.blue()
+            );
+            continue;
+        };
+
+        // application emoji names are unique so prefix them with the table
+        let app_name = format!("{table}_{name}").to_lowercase();
+
+        let app_emoji = if let Some(e) = existing.iter().find(|e| e.name == app_name) {
+            reused += 1;
+            e.clone()
+        } else {
+            let image = CreateAttachment::bytes(*image, format!("{app_name}.png"));
+            let map = serde_json::json!({
+                "name": app_name,
+                "image": image.to_base64(),
+            });
+
+            match http.create_application_emoji(&map).await {
+                Ok(e) => {
+                    uploaded += 1;
+                    e
+                }
+                Err(err) => {
+                    error!(
+                        "Cannot upload emoji {} due to: {}",
+                        app_name.red(),
+                        err.red()
+                    );
+                    continue;
+                }
+            }
+        };
+
+        emoji.set(&app_emoji.to_string());
+        let mut app_emojis = APP_EMOJIS.lock().unwrap();
+        if !app_emojis.contains(&app_emoji.id.get()) {
+            app_emojis.push(app_emoji.id.get());
+        }
+    }
+
+    done!(
+        "Uploaded {} and reused {} application emoji(s)",
+        uploaded.green(),
+        reused.green()
+    );
+
+    Ok(())
+}
@@ -3,9 +3,14 @@
 use std::fmt::Display;
 
 use bitflags::bitflags;
-use magpie_engine::prelude::*;
+use magpie_engine::{
+    prelude::*,
+    query::parse::{find_keyword, Keyword},
+};
+use poise::serenity_prelude::{colours::roles, Colour};
+use serde::Serialize;
 
-use crate::lev;
+use crate::{lev, rulings::Ruling, sources::is_aug_source};
 
 bitflags! {
     /// Cost type value for filter
@@ -44,19 +49,28 @@ impl Display for CostType {
 }
 
 /// Extra Filter for query
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FilterExt {
     /// Fuzzy match the card name
     Fuzzy(String),
-    /// Fuzzy match the card name
+    /// Match cards that have every cost type
     CostType(CostType),
+    /// Match cards with a sigil in a category, the sigils are the sigils in that category
+    SigilCategory(String, Vec<String>),
+    /// Compare the total cost, every blood, bone, energy and mox count as 1
+    TotalCost(QueryOrder, isize),
+    /// Match cards that cost at least these costs, the card can cost more
+    CostIncludes(Costs<MagpieCosts>),
+    /// Match cards with a ruling that contain the text
+    Ruling(String),
 }
 
 impl ToFilter<MagpieExt, MagpieCosts> for FilterExt {
     fn to_fn(self) -> FilterFn<MagpieExt, MagpieCosts> {
         match self {
             FilterExt::Fuzzy(str) => {
-                Box::new(move |c| lev(&c.name, &str, 0.5) != 0. || c.name.contains(&str))
+                let lower = str.to_lowercase();
+                Box::new(move |c| lev(&c.name, &str, 0.5) != 0. || c.lower.name.contains(&lower))
             }
             FilterExt::CostType(t) => Box::new(move |c| {
                 if let Some(c) = &c.costs {
@@ -68,28 +82,252 @@ impl ToFilter<MagpieExt, MagpieCosts> for FilterExt {
                     false
                 }
             }),
+            FilterExt::SigilCategory(_, sigils) => {
+                Box::new(move |c| c.sigils.iter().any(|s| sigils.iter().any(|n| *n == **s)))
+            }
+            FilterExt::TotalCost(ord, total) => {
+                Box::new(move |c| match_query_order!(ord, total_cost(c), total))
+            }
+            FilterExt::CostIncludes(want) => {
+                let want_mox = mox_counts(&want);
+                Box::new(move |c| {
+                    let Some(have) = &c.costs else {
+                        return false;
+                    };
+
+                    have.blood >= want.blood
+                        && have.bone >= want.bone
+                        && have.energy >= want.energy
+                        && have.mox.contains(want.mox)
+                        && mox_counts(have)
+                            .iter()
+                            .zip(&want_mox)
+                            .all(|(have, want)| have >= want)
+                })
+            }
+            FilterExt::Ruling(text) => {
+                let lower = text.to_lowercase();
+                Box::new(move |c| {
+                    c.extra
+                        .rulings
+                        .iter()
+                        .any(|r| r.text.to_lowercase().contains(&lower))
+                })
+            }
         }
     }
 }
 
+/// The tutor only keywords.
+///
+/// Some Scryfall shorthands are also accepted for player coming from MTG, `t` for tribe, `o` for
+/// sigil or description text, `pow` and `tou` for attack and health and `cmc` or `mv` for the
+/// total cost.
+impl QueryExt<MagpieExt, MagpieCosts> for FilterExt {
+    const KEYWORDS: &'static [KeywordInfo] = &[
+        KeywordInfo {
+            names: &["fuzzy", "fz"],
+            category: "Text",
+            description: "The card name is close to the value",
+        },
+        KeywordInfo {
+            names: &["sigilcategory", "sc"],
+            category: "Text",
+            description: "The card have a sigil in the category",
+        },
+        KeywordInfo {
+            names: &["ruling", "rl"],
+            category: "Text",
+            description: "One of the card rulings include the value",
+        },
+        KeywordInfo {
+            names: &["cost", "c"],
+            category: "Costs",
+            description: "The card cost is the value like `2b1o`, `free` for free cards, use `>=` \
+                for cards that cost at least the value. `b` is blood, `o` bone, `e` energy and the \
+                moxes are `r` ruby, `g` emerald, `u` sapphire, `y` prism, `a` garnet, `t` topaz, \
+                `p` amethyst and `k` onyx",
+        },
+        KeywordInfo {
+            names: &["costtype", "ct"],
+            category: "Costs",
+            description: "The card cost have every cost type in the value, `b` for blood, `o` for \
+                bone, `e` for energy and `m` for mox",
+        },
+        KeywordInfo {
+            names: &["cmc", "mv"],
+            category: "Costs",
+            description: "The card total cost compare to the value",
+        },
+        KeywordInfo {
+            names: &["type", "t"],
+            category: "Scryfall",
+            description: "Same as `tribe`",
+        },
+        KeywordInfo {
+            names: &["power", "pow"],
+            category: "Scryfall",
+            description: "Same as `attack`",
+        },
+        KeywordInfo {
+            names: &["toughness", "tou"],
+            category: "Scryfall",
+            description: "Same as `health`",
+        },
+        KeywordInfo {
+            names: &["oracle", "o"],
+            category: "Scryfall",
+            description: "The card have the sigil or the description include the value",
+        },
+    ];
+
+    fn parse_keyword(
+        kw: &Keyword,
+    ) -> Option<Result<Filters<MagpieExt, MagpieCosts, Self>, QueryError>> {
+        let filter = match find_keyword(Self::KEYWORDS, &kw.name)? {
+            // the sigils are fill in by the query once it know the sets
+            "sigilcategory" => kw
+                .str()
+                .map(|c| Filters::Extra(FilterExt::SigilCategory(c.to_owned(), vec![]))),
+            "fuzzy" => kw
+                .str()
+                .map(|n| Filters::Extra(FilterExt::Fuzzy(n.to_owned()))),
+            "ruling" => kw
+                .str()
+                .map(|t| Filters::Extra(FilterExt::Ruling(t.to_owned()))),
+            "cost" => parse_costs(kw),
+            "costtype" => kw.str().and_then(|c| {
+                let mut t = CostType::empty();
+                for c in c.chars() {
+                    t |= match c {
+                        'b' => CostType::BLOOD,
+                        'o' => CostType::BONE,
+                        'e' => CostType::ENERGY,
+                        'm' => CostType::MOX,
+                        _ => return Err(kw.invalid()),
+                    }
+                }
+                Ok(Filters::Extra(FilterExt::CostType(t)))
+            }),
+
+            // Scryfall compatibility
+            "type" => kw.str().map(|t| Filters::Tribe(Some(t.to_owned()))),
+            "power" => kw.num().map(|n| Filters::Attack(kw.order.clone(), n)),
+            "toughness" => kw.num().map(|n| Filters::Health(kw.order.clone(), n)),
+            // sigils are the closest thing to oracle text
+            "oracle" => kw.str().map(|text| {
+                Filters::AnyOf(vec![
+                    Filters::Sigil(text.to_owned(), vec![]),
+                    Filters::Description(text.to_owned()),
+                ])
+            }),
+            "cmc" => kw
+                .num()
+                .map(|n| Filters::Extra(FilterExt::TotalCost(kw.order.clone(), n))),
+            _ => return None,
+        };
+
+        Some(filter)
+    }
+
+    /// Bare words are fuzzy name match so `q[[ouro s:airborne]]` work like a normal search.
+    fn parse_term(term: &str) -> Option<Result<Filters<MagpieExt, MagpieCosts, Self>, QueryError>> {
+        Some(Ok(Filters::Extra(FilterExt::Fuzzy(term.to_owned()))))
+    }
+}
+
+/// Parse a cost value like `2b1o` into a cost filter, `free` for free cards.
+///
+/// `c:2g` match cards that cost exactly 2 green moxes while `c>=2g` match every card that cost at
+/// least 2 green moxes.
+fn parse_costs(kw: &Keyword) -> Result<Filters<MagpieExt, MagpieCosts, FilterExt>, QueryError> {
+    let vocab = CostVocab {
+        units: vec![
+            ("b".to_owned(), CostUnit::Blood),
+            ("o".to_owned(), CostUnit::Bone),
+            ("e".to_owned(), CostUnit::Energy),
+            ("r".to_owned(), CostUnit::Mox(Mox::O)),
+            ("g".to_owned(), CostUnit::Mox(Mox::G)),
+            ("u".to_owned(), CostUnit::Mox(Mox::B)),
+            ("y".to_owned(), CostUnit::Mox(Mox::Y)),
+            ("a".to_owned(), CostUnit::Mox(Mox::R)),
+            ("t".to_owned(), CostUnit::Mox(Mox::E)),
+            ("p".to_owned(), CostUnit::Mox(Mox::P)),
+            ("k".to_owned(), CostUnit::Mox(Mox::K)),
+        ],
+        compact: true,
+    };
+
+    let costs = parse_cost_string(&kw.value, &vocab).map_err(|_| kw.invalid())?;
+
+    match kw.order {
+        QueryOrder::Equal => Ok(Filters::Costs(costs)),
+        QueryOrder::GreaterEqual => Ok(Filters::Extra(FilterExt::CostIncludes(
+            costs.ok_or_else(|| kw.invalid())?,
+        ))),
+        _ => Err(QueryError::InvalidOrder(kw.name.clone())),
+    }
+}
+
+/// Get how many of each mox a cost need, moxes without a count are need once.
+fn mox_counts<C>(costs: &Costs<C>) -> [usize; 8] {
+    let once = |m: Mox| usize::from(costs.mox.contains(m));
+    match &costs.mox_count {
+        Some(m) => [m.o, m.g, m.b, m.y, m.r, m.e, m.p, m.k],
+        None => [
+            once(Mox::O),
+            once(Mox::G),
+            once(Mox::B),
+            once(Mox::Y),
+            once(Mox::R),
+            once(Mox::E),
+            once(Mox::P),
+            once(Mox::K),
+        ],
+    }
+}
+
 impl Display for FilterExt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             FilterExt::Fuzzy(n) => write!(f, "name similar to {n}"),
             FilterExt::CostType(t) => write!(f, "cost includes {t}"),
+            FilterExt::SigilCategory(c, _) => write!(f, "have a {c} sigil"),
+            FilterExt::TotalCost(o, t) => write!(f, "total cost {o} {t}"),
+            FilterExt::CostIncludes(c) => write!(f, "cost includes {c}"),
+            FilterExt::Ruling(t) => write!(f, "have a ruling with {t}"),
         }
     }
 }
 
+/// Get the total cost of a card, every blood, bone, energy and mox count as 1.
+#[allow(clippy::cast_possible_wrap)]
+fn total_cost(card: &Card<MagpieExt, MagpieCosts>) -> isize {
+    let Some(c) = &card.costs else {
+        return 0;
+    };
+
+    let mox = match &c.mox_count {
+        Some(m) => (m.o + m.g + m.b + m.y + m.r + m.e + m.p + m.k) as isize,
+        None => c.mox.iter().filter(|m| *m != Mox::P1).count() as isize,
+    };
+
+    c.blood + c.bone + c.energy + mox
+}
+
 /// Magpie's [`Card`] Extension to unify all the extension
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct MagpieExt {
     /// Artist credit from [`AugExt`]
     pub artist: String,
+    /// The errata note if the card was patch by an override
+    pub errata: Option<String>,
+    /// Rulings from [`DescExt`] and the [`rulings`](crate::rulings) store
+    pub rulings: Vec<Ruling>,
 }
 
 /// Magpie's [`Costs`] extension to unify all cost
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct MagpieCosts {
     /// Shattered mox count from [`AugCosts`]
     pub shattered_count: Option<MoxCount>,
@@ -140,7 +378,11 @@ impl Display for MagpieCosts {
 impl UpgradeCard<MagpieExt, MagpieCosts> for Card<AugExt, AugCosts> {
     fn upgrade(self) -> Card<MagpieExt, MagpieCosts> {
         upgrade_card! {
-            extra: MagpieExt { artist: self.extra.artist },
+            extra: MagpieExt {
+                artist: self.extra.artist,
+                errata: None,
+                rulings: vec![],
+            },
             costs: |c: Costs<AugCosts>| MagpieCosts {
                 shattered_count: c.extra.shattered_count,
                 max: c.extra.max,
@@ -152,10 +394,14 @@ impl UpgradeCard<MagpieExt, MagpieCosts> for Card<AugExt, AugCosts> {
     }
 }
 
-impl UpgradeCard<MagpieExt, MagpieCosts> for Card<(), DescCosts> {
+impl UpgradeCard<MagpieExt, MagpieCosts> for Card<DescExt, DescCosts> {
     fn upgrade(self) -> Card<MagpieExt, MagpieCosts> {
         upgrade_card! {
-            extra: MagpieExt { artist: String::new() },
+            extra: MagpieExt {
+                artist: String::new(),
+                errata: None,
+                rulings: self.extra.rulings.into_iter().map(Ruling::from).collect(),
+            },
             costs: |c: Costs<DescCosts>| MagpieCosts {
                 shattered_count: None,
                 max: 0,
@@ -165,4 +411,47 @@ impl UpgradeCard<MagpieExt, MagpieCosts> for Card<(), DescCosts> {
             ..self
         }
     }
-}
\ No newline at end of file
+}
+
+/// Extra methods for Magpie's cards.
+pub trait CardExt {
+    /// The accent color of the card, use by the embeds and anything else that show the card.
+    ///
+    /// Augmented and Descryption cards are color by their temple, they each have their own
+    /// palette. Every other set only have rarity so rare cards are green and the rest are grey.
+    fn accent_color(&self) -> Colour;
+}
+
+impl CardExt for Card<MagpieExt, MagpieCosts> {
+    fn accent_color(&self) -> Colour {
+        let temple = self.temple.iter().next().unwrap_or(Temple::empty());
+
+        match self.set.code() {
+            "aug" | "Aug" | "cti" => aug_color(temple),
+            code if is_aug_source(code) => aug_color(temple),
+            "des" => match temple {
+                Temple::BEAST => roles::DARK_GOLD,
+                Temple::UNDEAD => roles::GREEN,
+                Temple::TECH => roles::BLUE,
+                Temple::MAGICK => roles::MAGENTA,
+                Temple::ARTISTRY => Colour::new(0x003c_3f4a),
+                _ => roles::LIGHT_GREY,
+            },
+            // std, ete, egg and homebrew sets only have the imf fields
+            _ if self.rarity == Rarity::RARE => roles::GREEN,
+            _ => roles::LIGHT_GREY,
+        }
+    }
+}
+
+/// The accent color of an Augmented temple.
+pub(crate) fn aug_color(temple: Temple) -> Colour {
+    match temple {
+        Temple::BEAST => roles::DARK_GOLD,
+        Temple::UNDEAD => roles::GREEN,
+        Temple::TECH => roles::BLUE,
+        Temple::MAGICK => roles::RED,
+        Temple::FOOL => roles::MAGENTA,
+        _ => roles::LIGHT_GREY,
+    }
+}
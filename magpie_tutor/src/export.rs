@@ -0,0 +1,67 @@
+//! JSON export of cards and sets.
+//!
+//! The JSON use plain strings for the rarity, temple, costs and traits so consumers don't need to
+//! know how magpie store them. The color is the card [accent color](CardExt::accent_color) as
+//! `#rrggbb` so consumers can match the embeds.
+
+use serde_json::{json, Map, Value};
+
+use magpie_engine::Attack;
+
+use crate::{engine::CardExt, Card, Set};
+
+/// Convert a card to JSON, the set is use to look up the sigil descriptions.
+pub fn card_json(card: &Card, set: Option<&Set>) -> Value {
+    let attack = match &card.attack {
+        Attack::Num(a) => a.to_string(),
+        Attack::SpAtk(s) => s.to_string(),
+        Attack::Str(s) => s.clone(),
+    };
+
+    let sigils = card
+        .sigils
+        .iter()
+        .map(|s| {
+            json!({
+                "name": s,
+                "description": set.and_then(|set| set.sigils_description.get(s)),
+                "category": set.and_then(|set| set.sigils_category.get(&**s)),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "set": card.set.code(),
+        "name": card.name,
+        "description": card.description,
+        "portrait": card.portrait,
+        "rarity": card.rarity.to_string(),
+        "temple": card.temple.to_string(),
+        "color": format!("#{}", card.accent_color().hex()),
+        "tribes": card.tribes,
+        "attack": attack,
+        "health": card.health,
+        "sigils": sigils,
+        "costs": card.costs.as_ref().map(ToString::to_string),
+        "traits": card.traits.as_ref().map(ToString::to_string),
+        "id": card.id(),
+        "related": card.related,
+        "artist": card.extra.artist,
+        "errata": card.extra.errata,
+        "rulings": card.extra.rulings,
+    })
+}
+
+/// Convert a whole set to JSON with the sigil descriptions.
+pub fn set_json(set: &Set) -> Value {
+    json!({
+        "code": set.code.code(),
+        "name": set.name,
+        "cards": set.cards.iter().map(|c| card_json(c, Some(set))).collect::<Vec<_>>(),
+        "sigils": set
+            .sigils_description
+            .iter()
+            .map(|(k, v)| (k.to_string(), Value::String(v.clone())))
+            .collect::<Map<_, _>>(),
+    })
+}
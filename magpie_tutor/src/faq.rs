@@ -0,0 +1,203 @@
+//! Implementation for the FAQ answers.
+//!
+//! The global answers are load from [`FAQ_FILE_PATH`] or the default `faq.toml` bundle with the
+//! bot if there is no file. Each guild can add its own answers with `/faq add`, they are save to
+//! [`GUILD_FAQ_FILE_PATH`] and win over the global answers.
+//!
+//! ```toml
+//! [[faq]]
+//! questions = ["what is link", "what are links"]
+//! answer = "Links are an alternate cost type in Descryption."
+//! ```
+//!
+//! Messages that look like a question are fuzzy match against every question, `{user}` in an
+//! answer is replace with a mention of the user that ask.
+
+use std::{collections::HashMap, fs, io::ErrorKind};
+
+use poise::serenity_prelude::{GuildId, UserId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    done, error, fuzzy_best,
+    shard::{read_data, write_data},
+    Color, FuzzyRes, FAQ, GUILD_FAQ,
+};
+
+/// Location of the global FAQ file.
+pub const FAQ_FILE_PATH: &str = "./faq.toml";
+
+/// Location of the guild FAQ file.
+pub const GUILD_FAQ_FILE_PATH: &str = "./guild_faq.bin";
+
+/// The FAQ use when there is no FAQ file.
+const DEFAULT_FAQ: &str = include_str!("../faq.toml");
+
+/// How similar a message need to be with a question to get the answer.
+const FAQ_THRESHOLD: f32 = 0.85;
+
+/// Most answers a guild can add.
+pub const MAX_GUILD_FAQ: usize = 50;
+
+/// Type alias for the guild FAQ table, guild id to answers.
+pub type GuildFaq = HashMap<u64, Vec<FaqEntry>>;
+
+/// A FAQ answer and the questions that lead to it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FaqEntry {
+    /// The questions, they are match ignoring case.
+    pub questions: Vec<String>,
+    /// The answer template.
+    pub answer: String,
+}
+
+/// The FAQ file scheme.
+#[derive(Deserialize, Debug, Default)]
+struct FaqFile {
+    #[serde(default)]
+    faq: Vec<FaqEntry>,
+}
+
+/// Load the global FAQ from the FAQ file, falling back to the default FAQ.
+pub(crate) fn load_faq() -> Vec<FaqEntry> {
+    let content = match fs::read_to_string(FAQ_FILE_PATH) {
+        Ok(c) => c,
+        Err(err) if err.kind() == ErrorKind::NotFound => DEFAULT_FAQ.to_owned(),
+        Err(err) => {
+            error!("Cannot read FAQ file {FAQ_FILE_PATH} due to: {err}");
+            DEFAULT_FAQ.to_owned()
+        }
+    };
+
+    match toml::from_str::<FaqFile>(&content) {
+        Ok(f) => f.faq,
+        Err(err) => {
+            error!("Cannot parse FAQ file {FAQ_FILE_PATH} due to: {err}");
+            toml::from_str::<FaqFile>(DEFAULT_FAQ)
+                .map(|f| f.faq)
+                .unwrap_or_default()
+        }
+    }
+}
+
+/// Load the guild FAQ from the guild FAQ file.
+pub(crate) fn load_guild_faq() -> GuildFaq {
+    read_data(GUILD_FAQ_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the guild FAQ to the guild FAQ file.
+///
+/// This lock [`GUILD_FAQ`] so make sure you drop any guard before calling this.
+pub fn save_guild_faq() {
+    write_data(GUILD_FAQ_FILE_PATH, &*GUILD_FAQ);
+    done!(
+        "Guild FAQ save successfully to {}",
+        GUILD_FAQ_FILE_PATH.green()
+    );
+}
+
+/// Check if a message look like a question.
+pub fn is_question(content: &str) -> bool {
+    let lower = content.trim().to_lowercase();
+
+    lower.ends_with('?')
+        || ["what", "how", "why", "who", "when", "where"]
+            .iter()
+            .any(|w| lower.starts_with(w))
+}
+
+/// Find the answer to a question, the guild answers win over the global ones.
+///
+/// Return [`None`] if no question is similar enough.
+pub fn faq_answer(guild_id: Option<GuildId>, user_id: UserId, content: &str) -> Option<String> {
+    let question = content.trim().trim_end_matches('?').trim();
+
+    let guild = guild_id
+        .and_then(|id| GUILD_FAQ.lock().unwrap().get(&id.get()).cloned())
+        .unwrap_or_default();
+
+    // guild answers are last so they win when the rank is the same
+    let questions = FAQ
+        .iter()
+        .chain(guild.iter())
+        .flat_map(|e| {
+            e.questions
+                .iter()
+                .map(move |q| (q.as_str(), e.answer.as_str()))
+        })
+        .collect::<Vec<_>>();
+
+    fuzzy_best(
+        question,
+        questions.iter().collect(),
+        FAQ_THRESHOLD,
+        |(q, _)| q,
+    )
+    .map(
+        |FuzzyRes {
+             data: (_, answer), ..
+         }| answer.replace("{user}", &format!("<@{user_id}>")),
+    )
+}
+
+/// Add an answer to a guild FAQ.
+pub fn add_faq(guild_id: GuildId, question: &str, answer: &str) -> Result<(), String> {
+    {
+        let mut faq = GUILD_FAQ.lock().unwrap();
+        let entries = faq.entry(guild_id.get()).or_default();
+
+        if entries.len() >= MAX_GUILD_FAQ {
+            return Err(format!(
+                "A server can only have {MAX_GUILD_FAQ} FAQ answers"
+            ));
+        }
+
+        let question = question.trim().trim_end_matches('?').trim().to_lowercase();
+        if question.is_empty() {
+            return Err(String::from("The question cannot be empty"));
+        }
+
+        entries.push(FaqEntry {
+            questions: vec![question],
+            answer: answer.trim().to_owned(),
+        });
+    }
+
+    save_guild_faq();
+    Ok(())
+}
+
+/// Remove the answers to a question from a guild FAQ.
+///
+/// Return if any answer was removed.
+pub fn remove_faq(guild_id: GuildId, question: &str) -> bool {
+    let question = question.trim().trim_end_matches('?').trim().to_lowercase();
+
+    let removed = {
+        let mut faq = GUILD_FAQ.lock().unwrap();
+        let Some(entries) = faq.get_mut(&guild_id.get()) else {
+            return false;
+        };
+
+        let before = entries.len();
+        entries.retain(|e| !e.questions.iter().any(|q| q.to_lowercase() == question));
+        before != entries.len()
+    };
+
+    if removed {
+        save_guild_faq();
+    }
+    removed
+}
+
+/// Get the questions of a guild FAQ.
+pub fn guild_questions(guild_id: GuildId) -> Vec<String> {
+    GUILD_FAQ
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .map(|e| e.iter().flat_map(|e| e.questions.clone()).collect())
+        .unwrap_or_default()
+}
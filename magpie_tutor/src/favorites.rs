@@ -0,0 +1,193 @@
+//! Implementation for the user favorites.
+//!
+//! Users can star any card from a search with the ⭐ button and list them later with
+//! `/favorites`. Favorites are save by [`Card::id`](magpie_engine::Card::id) so they stay valid
+//! when the card portrait or stats change.
+
+use std::{collections::HashMap, sync::Arc};
+
+use poise::serenity_prelude::{
+    colours::roles,
+    ButtonStyle::Secondary,
+    CreateActionRow::{self, Buttons},
+    CreateButton, CreateEmbed, UserId,
+};
+
+use crate::{
+    done,
+    search::{card_embed, card_message},
+    shard::{read_shared, shared_changed, with_shared_lock, write_shared},
+    Card, Color, MessageAdapter, Set, FAVORITES, SETS,
+};
+
+/// Location of the favorites file.
+pub const FAVORITES_FILE_PATH: &str = "./favorites.bin";
+
+/// Custom id prefix of the ⭐ buttons on search messages.
+pub const FAVORITE_PREFIX: &str = "fav_";
+
+/// Custom id prefix of the buttons to show a favorite card again.
+pub const SHOW_FAVORITE_PREFIX: &str = "favshow_";
+
+/// Most favorites a user can have, Discord allow 25 buttons in a message.
+pub const MAX_FAVORITES: usize = 25;
+
+/// Type alias for the favorites table, user id to card ids in the order they were added.
+pub type Favorites = HashMap<u64, Vec<u64>>;
+
+/// Load the favorites from the favorites file.
+///
+/// The favorites file is share by every shard process since users are not tied to a shard.
+pub(crate) fn load_favorites() -> Favorites {
+    read_shared(FAVORITES_FILE_PATH)
+        .ok()
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Reload the favorites if another process change them.
+fn reload_favorites() {
+    if shared_changed(FAVORITES_FILE_PATH) {
+        *FAVORITES.lock().unwrap() = load_favorites();
+    }
+}
+
+/// Save the favorites to the favorites file.
+///
+/// This lock [`FAVORITES`] so make sure you drop any guard before calling this.
+pub fn save_favorites() {
+    write_shared(FAVORITES_FILE_PATH, &*FAVORITES);
+    done!(
+        "Favorites save successfully to {}",
+        FAVORITES_FILE_PATH.green()
+    );
+}
+
+/// Find a card and its set by card id.
+fn find_card<'a>(
+    sets: &'a HashMap<&'static str, Arc<Set>>,
+    id: u64,
+) -> Option<(&'a Set, &'a Card)> {
+    sets.values()
+        .map(Arc::as_ref)
+        .find_map(|set| set.cards.iter().find(|c| c.id() == id).map(|c| (set, c)))
+}
+
+/// Add the card to the user favorites or remove it if it is already there, then save.
+///
+/// Return the message to tell the user what happen.
+pub fn toggle_favorite(user_id: UserId, custom_id: &str) -> String {
+    let Some(id) = custom_id
+        .strip_prefix(FAVORITE_PREFIX)
+        .and_then(|id| id.parse::<u64>().ok())
+    else {
+        return String::from("This card is no longer available.");
+    };
+
+    let name = {
+        let sets = SETS.read().unwrap();
+        match find_card(&sets, id) {
+            Some((_, card)) => card.name.clone(),
+            None => return String::from("This card is no longer available."),
+        }
+    };
+
+    with_shared_lock(FAVORITES_FILE_PATH, || {
+        reload_favorites();
+
+        let msg = {
+            let mut favorites = FAVORITES.lock().unwrap();
+            let list = favorites.entry(user_id.get()).or_default();
+
+            if let Some(i) = list.iter().position(|f| *f == id) {
+                list.remove(i);
+                format!("Removed **{name}** from your favorites.")
+            } else if list.len() >= MAX_FAVORITES {
+                return format!(
+                    "You can only have {MAX_FAVORITES} favorites, remove some with `/favorites` first."
+                );
+            } else {
+                list.push(id);
+                format!("Added **{name}** to your favorites.")
+            }
+        };
+
+        save_favorites();
+        msg
+    })
+}
+
+/// Generate the favorites list of a user with a button to show each card again.
+pub fn favorites_message(user_id: UserId) -> MessageAdapter {
+    reload_favorites();
+    let favorites = FAVORITES
+        .lock()
+        .unwrap()
+        .get(&user_id.get())
+        .cloned()
+        .unwrap_or_default();
+
+    if favorites.is_empty() {
+        return MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::ORANGE)
+                .title("No favorites")
+                .description(
+                    "Star a card with the ⭐ button on a search to add it here.",
+                )])
+            .ephemeral(true);
+    }
+
+    let sets = SETS.read().unwrap();
+    let mut lines = vec![];
+    let mut buttons = vec![];
+
+    for id in favorites {
+        // cards can be remove from their set after they were star
+        let Some((set, card)) = find_card(&sets, id) else {
+            lines.push(String::from("- *Card no longer available*"));
+            continue;
+        };
+
+        lines.push(format!("- **{}** ({})", card.name, set.name));
+        buttons.push(
+            CreateButton::new(format!("{SHOW_FAVORITE_PREFIX}{id}"))
+                .style(Secondary)
+                .label(format!("{} ({})", card.name, set.code)),
+        );
+    }
+
+    MessageAdapter::new()
+        .embeds(vec![CreateEmbed::new()
+            .color(roles::GOLD)
+            .title(format!("Favorites ({})", lines.len()))
+            .description(lines.join("\n"))])
+        .components(
+            buttons
+                .chunks(5)
+                .map(|row| Buttons(row.to_vec()))
+                .collect::<Vec<CreateActionRow>>(),
+        )
+        .ephemeral(true)
+}
+
+/// Generate the message for a show favorite button.
+pub fn show_favorite_message(custom_id: &str, lang: &str) -> MessageAdapter {
+    let sets = SETS.read().unwrap();
+    let found = custom_id
+        .strip_prefix(SHOW_FAVORITE_PREFIX)
+        .and_then(|id| id.parse::<u64>().ok())
+        .and_then(|id| find_card(&sets, id))
+        .map(|(set, card)| (card.clone(), card_embed(card, set, lang)));
+    drop(sets);
+
+    match found {
+        Some((card, embed)) => card_message(&card, embed),
+        None => MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::RED)
+                .title("Favorite not found")
+                .description("This card is no longer in its set.")])
+            .ephemeral(true),
+    }
+}
@@ -0,0 +1,151 @@
+//! Implementation for guild defined formats.
+//!
+//! A format is a named restriction on top of the sets magpie have. It limit which sets are allowed
+//! and carry a [`Ruleset`] with the copies limit and the banlist. Each guild have their own formats
+//! and they are save to [`FORMATS_FILE_PATH`].
+
+use std::{collections::HashMap, sync::Mutex};
+
+use magpie_engine::prelude::*;
+use poise::serenity_prelude::GuildId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    done,
+    shard::{read_data, write_data},
+    Color, FORMATS, FORMAT_REGEX,
+};
+
+/// Location of the formats file.
+pub const FORMATS_FILE_PATH: &str = "./formats.bin";
+
+/// A guild defined format.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Format {
+    /// The set codes that are allowed in this format.
+    pub sets: Vec<String>,
+    /// The deck building rules and banlist of this format.
+    pub ruleset: Ruleset,
+}
+
+impl Format {
+    /// Create a new format allowing these sets with the default ruleset.
+    pub fn new(sets: Vec<String>) -> Self {
+        Format {
+            sets,
+            ruleset: Ruleset::default(),
+        }
+    }
+
+    /// Check if a set is allowed in this format.
+    pub fn allow_set(&self, set_code: &str) -> bool {
+        self.sets.iter().any(|s| s == set_code)
+    }
+}
+
+/// Type alias for the formats table, guild id to format name to format.
+pub type Formats = HashMap<u64, HashMap<String, Format>>;
+
+/// Load the formats from the formats file.
+pub(crate) fn load_formats() -> Mutex<Formats> {
+    Mutex::new(
+        read_data(FORMATS_FILE_PATH)
+            .and_then(|bytes| bincode::deserialize(&bytes).ok())
+            .unwrap_or_default(),
+    )
+}
+
+/// Save the formats to the formats file.
+///
+/// This lock [`FORMATS`](crate::FORMATS) so make sure you drop any guard before calling this.
+pub fn save_formats() {
+    write_data(FORMATS_FILE_PATH, &*FORMATS);
+    done!("Formats save successfully to {}", FORMATS_FILE_PATH.green());
+}
+
+/// Get a format of a guild by name.
+pub fn get_format(guild_id: GuildId, name: &str) -> Option<Format> {
+    FORMATS
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .and_then(|f| f.get(&name.to_lowercase()))
+        .cloned()
+}
+
+/// Take the `format:<name>` keyword out of a query and look up the format.
+///
+/// Return the query without the keyword and the format if there is one, or an error message if
+/// the format cannot be found.
+pub fn take_format(query: &str, guild_id: GuildId) -> Result<(String, Option<Format>), String> {
+    let Some(caps) = FORMAT_REGEX.captures(query) else {
+        return Ok((query.to_owned(), None));
+    };
+
+    let name = &caps[1];
+    let format = get_format(guild_id, name).ok_or_else(|| format!("Unknown format `{name}`"))?;
+
+    Ok((FORMAT_REGEX.replace(query, "").into_owned(), Some(format)))
+}
+
+/// Get all the formats of a guild sorted by name.
+pub fn guild_formats(guild_id: GuildId) -> Vec<(String, Format)> {
+    let mut formats = FORMATS
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .map(|f| f.clone().into_iter().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    formats.sort_by(|a, b| a.0.cmp(&b.0));
+    formats
+}
+
+/// Add or replace a format of a guild then save the formats.
+pub fn insert_format(guild_id: GuildId, name: &str, format: Format) {
+    FORMATS
+        .lock()
+        .unwrap()
+        .entry(guild_id.get())
+        .or_default()
+        .insert(name.to_lowercase(), format);
+
+    save_formats();
+}
+
+/// Remove a format of a guild then save the formats.
+///
+/// Return `false` if the format doesn't exist.
+pub fn remove_format(guild_id: GuildId, name: &str) -> bool {
+    let removed = FORMATS
+        .lock()
+        .unwrap()
+        .get_mut(&guild_id.get())
+        .and_then(|f| f.remove(&name.to_lowercase()))
+        .is_some();
+
+    if removed {
+        save_formats();
+    }
+
+    removed
+}
+
+/// Modify a format of a guild then save the formats.
+///
+/// Return `false` if the format doesn't exist.
+pub fn update_format(guild_id: GuildId, name: &str, func: impl FnOnce(&mut Format)) -> bool {
+    let updated = FORMATS
+        .lock()
+        .unwrap()
+        .get_mut(&guild_id.get())
+        .and_then(|f| f.get_mut(&name.to_lowercase()))
+        .map(func)
+        .is_some();
+
+    if updated {
+        save_formats();
+    }
+
+    updated
+}
@@ -1,28 +1,44 @@
 use poise::{
     serenity_prelude::{
         self as serenity, ComponentInteraction, ComponentInteractionData,
-        ComponentInteractionDataKind::Button, Context as EvtCtx, FullEvent::*,
+        ComponentInteractionDataKind::{Button, StringSelect},
+        Context as EvtCtx,
+        FullEvent::*,
         Interaction::Component,
     },
     FrameworkContext,
 };
 
-use crate::{done, error, search::search_message, Color, Data, Error, Res};
+use crate::{
+    cache, changelog, cotd,
+    delimiter::guild_delimiter,
+    done,
+    emojis::{cached_permissions, plain_emojis, upload_emojis, validate_emojis, with_plain_emojis},
+    error, health,
+    search::search_message,
+    stats, Color, Data, Error, Res,
+};
 
 mod button;
 mod message;
+mod permission;
+mod select;
 
 use button::button_handler;
 use message::message_handler;
+use select::select_handler;
 
 /// The event handler or dispatcher for serenity event.
 pub async fn handler(
     ctx: &EvtCtx,
     event: &serenity::FullEvent,
     _: FrameworkContext<'_, Data, Error>,
-    _: &Data,
+    data: &Data,
 ) -> Res {
-    let res: Res = match event {
+    health::record_event(event, ctx.shard_id);
+
+    // errors are reported by the framework error handler
+    match event {
         Ready {
             data_about_bot: serenity::Ready { user, .. },
         } => {
@@ -33,11 +49,32 @@ pub async fn handler(
             Ok(())
         }
 
-        // only search if message contain [[
+        // guilds are only fully cached after ready so check the emojis here
+        CacheReady { .. } => {
+            if let Err(err) = upload_emojis(&ctx.http).await {
+                error!("Cannot upload application emojis due to: {err}");
+            }
+            validate_emojis(ctx);
+            cotd::start_scheduler(ctx);
+            changelog::start_refresh(ctx);
+            stats::start_scheduler(ctx);
+            cache::start_cleanup();
+            cache::start_warmup(ctx);
+            Ok(())
+        }
+
+        // only search if message contain the guild opening delimiter
         Message { new_message: msg }
-            if msg.author.id != ctx.cache.current_user().id && msg.content.contains("[[") =>
+            if msg.author.id != ctx.cache.current_user().id
+                && msg.content.contains(guild_delimiter(msg.guild_id).pair().0) =>
         {
-            search_message(ctx, msg, msg.guild_id.unwrap()).await
+            let permissions = cached_permissions(ctx, msg.guild_id, msg.channel_id);
+            let plain = plain_emojis(msg.guild_id, permissions);
+            with_plain_emojis(
+                plain,
+                search_message(ctx, msg, msg.guild_id, &data.interactions),
+            )
+            .await
         }
 
         Message { new_message: msg } => message_handler(msg, ctx).await,
@@ -56,19 +93,33 @@ pub async fn handler(
                         ..
                     },
                 ),
-        } => button_handler(interaction, ctx, custom_id).await,
-
-        _ => Ok(()),
-    };
+        } => {
+            let plain = plain_emojis(interaction.guild_id, interaction.app_permissions);
+            with_plain_emojis(plain, button_handler(interaction, ctx, data, custom_id)).await
+        }
 
-    match res {
-        Ok(()) => Ok(()),
-        Err(err) => {
-            error!(
-                "Cannot handle {} event due to: {err}",
-                event.snake_case_name()
-            );
-            Err(err)
+        InteractionCreate {
+            interaction:
+                Component(
+                    interaction @ ComponentInteraction {
+                        data:
+                            ComponentInteractionData {
+                                custom_id,
+                                kind: StringSelect { values },
+                                ..
+                            },
+                        ..
+                    },
+                ),
+        } => {
+            let plain = plain_emojis(interaction.guild_id, interaction.app_permissions);
+            with_plain_emojis(
+                plain,
+                select_handler(interaction, ctx, data, custom_id, values),
+            )
+            .await
         }
+
+        _ => Ok(()),
     }
 }
@@ -1,28 +1,164 @@
+use std::collections::HashSet;
 use std::time::Duration;
 
 use poise::serenity_prelude::CacheHttp;
 use poise::serenity_prelude::{
-    ComponentInteraction, Context, CreateInputText, CreateInteractionResponse::UpdateMessage,
-    CreateInteractionResponseFollowup, CreateQuickModal, InputTextStyle::*,
+    colours::roles,
+    ComponentInteraction, Context, CreateEmbed, CreateInputText,
+    CreateInteractionResponse::{Message, UpdateMessage},
+    CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateQuickModal,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, GuildId,
+    InputTextStyle::*,
 };
 
-use crate::search::process_search;
-use crate::{done, info, save_cache, Color, Death, Res, CACHE};
+use super::permission::{can_manage, deny, is_protected};
+use crate::favorites::{
+    show_favorite_message, toggle_favorite, FAVORITE_PREFIX, SHOW_FAVORITE_PREFIX,
+};
+use crate::higherlower::{next_round, HIGHER_PREFIX, LOWER_PREFIX};
+use crate::history::{repeat_message, HISTORY_PREFIX};
+use crate::interaction::expired_message;
+use crate::lfg::{cancel_lobby, join_lobby};
+use crate::locale::guild_lang;
+use crate::search::{
+    expand_sigils, export_imf, printing_message, process_search, rulings_message, SearchState,
+    PRINTING_PREFIX, RETRY_ALL_PREFIX, RETRY_PREFIX, RULINGS_PREFIX,
+};
+use crate::spoiler::{reveal_message, SPOILER_PREFIX};
+use crate::timeline::{timeline_message, TIMELINE_PREFIX};
+use crate::{
+    done, info, save_cache, Color, Data, Death, Error, Res, CACHE, CACHE_REGEX, PORTRAITS,
+};
 
 pub async fn button_handler(
     interaction: &ComponentInteraction,
     ctx: &Context,
+    data: &Data,
     custom_id: &str,
 ) -> Res {
+    // drop the state of old buttons
+    data.interactions.cleanup();
+
+    if is_protected(custom_id) && !can_manage(interaction) {
+        return deny(interaction, ctx).await;
+    }
+
     match custom_id {
         "remove_cache" => cache_remove(interaction, ctx).await,
-        "retry" => retry(interaction, ctx).await,
+        // plain `retry` are from before the retry state was store
+        "retry" => retry(interaction, ctx, data, custom_id, false).await,
+        id if id.starts_with(RETRY_ALL_PREFIX) => retry(interaction, ctx, data, id, true).await,
+        id if id.starts_with(RETRY_PREFIX) => retry(interaction, ctx, data, id, false).await,
+        "expand_sigils" => expand(interaction, ctx).await,
+        "export_imf" => export(interaction, ctx).await,
+        "lfg_join" => join_lobby(interaction, ctx).await,
+        "lfg_cancel" => cancel_lobby(interaction, ctx).await,
+        id if id.starts_with(HIGHER_PREFIX) || id.starts_with(LOWER_PREFIX) => {
+            higher_lower(interaction, ctx, data, id).await
+        }
+        id if id.starts_with(PRINTING_PREFIX) => printing(interaction, ctx, id).await,
+        id if id.starts_with(RULINGS_PREFIX) => rulings(interaction, ctx, id).await,
+        id if id.starts_with(TIMELINE_PREFIX) => timeline(interaction, ctx, id).await,
+        id if id.starts_with(FAVORITE_PREFIX) => favorite(interaction, ctx, id).await,
+        id if id.starts_with(SHOW_FAVORITE_PREFIX) => show_favorite(interaction, ctx, id).await,
+        id if id.starts_with(HISTORY_PREFIX) => repeat(interaction, ctx, data, id).await,
+        id if id.starts_with(SPOILER_PREFIX) => reveal(interaction, ctx, id).await,
         _ => Ok(()),
     }
 }
 
+/// Custom id of the select menu to pick which cache to remove.
+pub const CACHE_SELECT_ID: &str = "remove_cache_select";
+
+/// Value of the select menu option to type a card id instead.
+pub const CACHE_MANUAL_VALUE: &str = "manual";
+
+/// Get the name and card id of every cached portrait in a search message.
+fn cached_cards(interaction: &ComponentInteraction) -> Vec<(String, u64)> {
+    let mut cards: Vec<(String, u64)> = vec![];
+
+    for embed in &interaction.message.embeds {
+        let Some(id): Option<u64> = embed
+            .thumbnail
+            .as_ref()
+            .and_then(|t| CACHE_REGEX.captures(&t.url))
+            .and_then(|c| c[3].parse().ok())
+        else {
+            continue;
+        };
+
+        if !cards.iter().any(|(_, i)| *i == id) {
+            cards.push((embed.title.clone().unwrap_or_else(|| id.to_string()), id));
+        }
+    }
+
+    cards
+}
+
 async fn cache_remove(interaction: &ComponentInteraction, ctx: &Context) -> Res {
     info!("Cache removal request receive...");
+
+    let cards = cached_cards(interaction);
+    if cards.is_empty() {
+        return cache_remove_manual(interaction, ctx).await;
+    }
+
+    info!("Asking for which cache to remove...");
+
+    let options = cards
+        .into_iter()
+        .map(|(name, id)| {
+            CreateSelectMenuOption::new(name, id.to_string()).description(format!("Card id {id}"))
+        })
+        .chain([
+            CreateSelectMenuOption::new("Other card", CACHE_MANUAL_VALUE)
+                .description("Type the card id yourself"),
+        ])
+        .collect();
+
+    interaction
+        .create_response(
+            &ctx.http,
+            Message(
+                CreateInteractionResponseMessage::new()
+                    .content("Which cache do you want to remove?")
+                    .select_menu(
+                        CreateSelectMenu::new(
+                            CACHE_SELECT_ID,
+                            CreateSelectMenuKind::String { options },
+                        )
+                        .placeholder("Pick a card"),
+                    )
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Remove the portrait cache of a card, return `true` if there was one.
+pub fn remove_card_cache(id: u64) -> bool {
+    info!("Request to remove cache for card id {}", id.red());
+    info!("Checking caches...");
+
+    // the recent portrait would be reuse instead of generating a new one
+    PORTRAITS.lock().unwrap().remove_cards(&HashSet::from([id]));
+    let res = { CACHE.lock().unwrap_or_die("Cannnot lock cache").remove(&id) };
+
+    if res.is_some() {
+        done!("{} cache for card id {}", "Removed".red(), id.red());
+        info!("Saving caches...");
+        save_cache();
+        true
+    } else {
+        info!("Cache for card id {} not found", id.red());
+        false
+    }
+}
+
+/// Ask for the card id of the cache to remove with a modal.
+pub async fn cache_remove_manual(interaction: &ComponentInteraction, ctx: &Context) -> Res {
     info!("Asking for which cache to remove...");
 
     let res = interaction
@@ -31,7 +167,7 @@ async fn cache_remove(interaction: &ComponentInteraction, ctx: &Context) -> Res
             CreateQuickModal::new("Remove Cache")
                 .timeout(Duration::from_secs(5))
                 .field(
-                    CreateInputText::new(Short, "Portrait Hash", "")
+                    CreateInputText::new(Short, "Card Id", "")
                         .placeholder("If you don't know what this do cancel this pop up."),
                 ),
         )
@@ -48,20 +184,26 @@ async fn cache_remove(interaction: &ComponentInteraction, ctx: &Context) -> Res
 
     res.interaction.defer(&ctx.http).await?;
 
-    let hash: u64 = res.inputs.first().unwrap().parse().unwrap();
-
-    info!("Request to remove cache for hash {}", hash.red());
-    info!("Checking caches...");
-
-    let res = {
-        CACHE
-            .lock()
-            .unwrap_or_die("Cannnot lock cache")
-            .remove(&hash)
+    let input = res.inputs.first().map_or("", String::as_str);
+    let Ok(id) = input.trim().parse::<u64>() else {
+        interaction
+            .create_followup(
+                &ctx.http,
+                CreateInteractionResponseFollowup::new()
+                    .embed(
+                        CreateEmbed::new()
+                            .color(roles::RED)
+                            .title("Invalid Card Id")
+                            .description(format!("`{input}` is not a card id")),
+                    )
+                    .ephemeral(true),
+            )
+            .await?;
+        done!("Canceling removal");
+        return Ok(());
     };
 
-    if res.is_some() {
-        done!("{} cache for card hash {}", "Removed".red(), hash.red());
+    if remove_card_cache(id) {
         interaction
             .create_followup(
                 &ctx.http,
@@ -70,11 +212,7 @@ async fn cache_remove(interaction: &ComponentInteraction, ctx: &Context) -> Res
                     .ephemeral(true),
             )
             .await?;
-
-        info!("Saving caches...");
-        save_cache();
     } else {
-        info!("Cache for card hash {} not found", hash.red());
         interaction
             .create_followup(
                 &ctx.http,
@@ -88,27 +226,36 @@ async fn cache_remove(interaction: &ComponentInteraction, ctx: &Context) -> Res
 
     Ok(())
 }
-async fn retry(interaction: &ComponentInteraction, ctx: &Context) -> Res {
+#[tracing::instrument(skip(interaction, ctx, data), fields(user = %interaction.user.id))]
+async fn retry(
+    interaction: &ComponentInteraction,
+    ctx: &Context,
+    data: &Data,
+    custom_id: &str,
+    all_sets: bool,
+) -> Res {
+    // fallback to the message we reply to when the state expired
+    let state = if let Some(state) = data.interactions.get::<SearchState>(custom_id) {
+        state
+    } else {
+        let Some(content) = replied_content(interaction, ctx).await? else {
+            return Ok(());
+        };
+        SearchState {
+            content,
+            guild_id: interaction.guild_id.map(GuildId::get),
+        }
+    };
+
     interaction
         .create_response(
             &ctx.http,
             UpdateMessage(
                 process_search(
-                    ctx.http()
-                        .get_message(
-                            interaction.message.channel_id,
-                            interaction
-                                .message
-                                .message_reference
-                                .as_ref()
-                                .unwrap()
-                                .message_id
-                                .unwrap(),
-                        )
-                        .await?
-                        .content
-                        .as_str(),
-                    interaction.guild_id.unwrap(),
+                    &state.content,
+                    state.guild_id.map(GuildId::new),
+                    all_sets,
+                    &data.interactions,
                 )
                 .into(),
             ),
@@ -117,3 +264,188 @@ async fn retry(interaction: &ComponentInteraction, ctx: &Context) -> Res {
 
     Ok(())
 }
+
+/// Get the content of the search message that the button message reply to.
+///
+/// Respond with an ephemeral error and return [`None`] when the search message can't be get, like
+/// when it was deleted.
+async fn replied_content(
+    interaction: &ComponentInteraction,
+    ctx: &Context,
+) -> Result<Option<String>, Error> {
+    let reply = match interaction
+        .message
+        .message_reference
+        .as_ref()
+        .and_then(|r| r.message_id)
+    {
+        Some(id) => ctx
+            .http()
+            .get_message(interaction.message.channel_id, id)
+            .await
+            .ok(),
+        None => None,
+    };
+
+    if reply.is_none() {
+        interaction
+            .create_response(
+                &ctx.http,
+                Message(
+                    CreateInteractionResponseMessage::new()
+                        .content("The search message of this button no longer exist")
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+    }
+
+    Ok(reply.map(|m| m.content))
+}
+
+async fn expand(interaction: &ComponentInteraction, ctx: &Context) -> Res {
+    let Some(content) = replied_content(interaction, ctx).await? else {
+        return Ok(());
+    };
+
+    interaction
+        .create_response(
+            &ctx.http,
+            Message(expand_sigils(&content, interaction.guild_id).into()),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn export(interaction: &ComponentInteraction, ctx: &Context) -> Res {
+    let Some(content) = replied_content(interaction, ctx).await? else {
+        return Ok(());
+    };
+
+    interaction
+        .create_response(
+            &ctx.http,
+            Message(
+                export_imf(
+                    &content,
+                    interaction.guild_id,
+                    guild_lang(interaction.guild_id),
+                )
+                .into(),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn printing(interaction: &ComponentInteraction, ctx: &Context, custom_id: &str) -> Res {
+    // generating the portrait can take a while
+    interaction.defer_ephemeral(&ctx.http).await?;
+
+    interaction
+        .edit_response(
+            &ctx.http,
+            printing_message(custom_id, guild_lang(interaction.guild_id)).into(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn rulings(interaction: &ComponentInteraction, ctx: &Context, custom_id: &str) -> Res {
+    interaction
+        .create_response(
+            &ctx.http,
+            Message(rulings_message(custom_id, guild_lang(interaction.guild_id)).into()),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn timeline(interaction: &ComponentInteraction, ctx: &Context, custom_id: &str) -> Res {
+    interaction
+        .create_response(&ctx.http, Message(timeline_message(custom_id).into()))
+        .await?;
+
+    Ok(())
+}
+
+async fn favorite(interaction: &ComponentInteraction, ctx: &Context, custom_id: &str) -> Res {
+    interaction
+        .create_response(
+            &ctx.http,
+            Message(
+                CreateInteractionResponseMessage::new()
+                    .content(toggle_favorite(interaction.user.id, custom_id))
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn show_favorite(interaction: &ComponentInteraction, ctx: &Context, custom_id: &str) -> Res {
+    // generating the portrait can take a while
+    interaction.defer_ephemeral(&ctx.http).await?;
+
+    interaction
+        .edit_response(
+            &ctx.http,
+            show_favorite_message(custom_id, guild_lang(interaction.guild_id)).into(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn reveal(interaction: &ComponentInteraction, ctx: &Context, custom_id: &str) -> Res {
+    // generating the portrait can take a while
+    interaction.defer_ephemeral(&ctx.http).await?;
+
+    interaction
+        .edit_response(
+            &ctx.http,
+            reveal_message(custom_id, guild_lang(interaction.guild_id)).into(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn repeat(
+    interaction: &ComponentInteraction,
+    ctx: &Context,
+    data: &Data,
+    custom_id: &str,
+) -> Res {
+    // searching can take a while
+    interaction.defer_ephemeral(&ctx.http).await?;
+
+    interaction
+        .edit_response(
+            &ctx.http,
+            repeat_message(interaction.user.id, custom_id, &data.interactions).into(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn higher_lower(
+    interaction: &ComponentInteraction,
+    ctx: &Context,
+    data: &Data,
+    custom_id: &str,
+) -> Res {
+    let response = match next_round(custom_id, &data.interactions) {
+        Some(msg) => UpdateMessage(msg.into()),
+        None => Message(expired_message()),
+    };
+    interaction.create_response(&ctx.http, response).await?;
+
+    Ok(())
+}
@@ -1,50 +1,31 @@
-use poise::serenity_prelude::{Context, GuildId, Message};
+use poise::serenity_prelude::{Context, Message};
 
-use crate::{Res, FIGHT_REGEX};
+use crate::{
+    autoresponse::auto_respond,
+    faq::{faq_answer, is_question},
+    lfg::{is_lfg_channel, open_lobby},
+    Res, FIGHT_REGEX,
+};
 
 pub async fn message_handler(msg: &Message, ctx: &Context) -> Res {
-    if msg.content.starts_with("what") {
-        let content = desc_faq(msg.content.to_lowercase().as_str());
-        if !content.is_empty() {
-            msg.reply(ctx, content).await?;
-        }
-    } else if (FIGHT_REGEX.is_match(&msg.content))
+    if msg.author.bot {
+        return Ok(());
+    }
+
+    if FIGHT_REGEX.is_match(&msg.content)
         && msg
             .guild_id
-            .is_some_and(|id| id == GuildId::new(994573431880286289))
-        && !matches!(
-            msg.channel_id.get(),
-            1067593222987198465 | 1067561938969710754 | 1066543653339791440
-        )
+            .is_some_and(|id| is_lfg_channel(id, msg.channel_id))
     {
-        msg.reply(ctx, "
-You seem to be asking for a game in the the wrong channel!
-You can look at [this faq](https://discord.com/channels/994573431880286289/1168644586319659100/1181115229610983424), or:
-- Host a room in the game
-- Go to the <#1065751579485032629> channel
-- Choose a inactive lobby (choose one that no one is talking in). Competive lobby usually entail harder and more meta gameplay.
-- Send a message with the room code and ping the `Gamer (PING IF LFG)` role"
-        ).await?;
+        open_lobby(msg, ctx).await?;
+    } else if is_question(&msg.content) {
+        if let Some(answer) = faq_answer(msg.guild_id, msg.author.id, &msg.content) {
+            msg.reply(ctx, answer).await?;
+        } else {
+            auto_respond(msg, ctx).await?;
+        }
+    } else {
+        auto_respond(msg, ctx).await?;
     }
     Ok(())
 }
-
-fn desc_faq(what: &str) -> &'static str {
-    match what {
-        "what is link" | "what is <:cost_link:1240999261831958599>" | "what are links" => "
-Links are an alternate cost type in Descryption. This cost type predominantly appears on Artistry cards. 
-
-Links work as follows:
-- Whenever a card is played in any way, it yields 1 link to its owner.
-- Cards which cost links expend that many links as they are being played. (They then still yield the normal 1.)
-- All links are lost whenever your turn ends. Links yielded to you during your opponent's turn will be available to spend on your next turn. ",
-
-"what is heat" | "what is <:cost_heat:1099344819492495451>" | "what are heats" => "
-Heats are an alernate cost type in IMR (Inscryption Multiplayer Redux). You gain heats when a card is discarded from your hand. Unspent heat are kept across turn.",
-
-"what is sap" | "what is <:cost_sap:1125555492853403708>" | "what are saps"=> "
-Saps are an alternate cost type in IMR (Inscryption Multiplayer Redux). Saps function identical to blood only you can also sacrifice bloodless card for saps.",
-
-        _ => ""
-    }
-}
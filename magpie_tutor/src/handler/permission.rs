@@ -0,0 +1,49 @@
+use poise::serenity_prelude::{
+    ComponentInteraction, Context, CreateInteractionResponse::Message,
+    CreateInteractionResponseMessage, Permissions,
+};
+
+use crate::Res;
+
+/// Custom id of the buttons that only the searcher or moderators can press.
+pub const PROTECTED_BUTTONS: &[&str] = &["remove_cache"];
+
+/// Check if a button can only be press by the searcher or moderators.
+pub fn is_protected(custom_id: &str) -> bool {
+    PROTECTED_BUTTONS.contains(&custom_id)
+}
+
+/// Check if the user can press a protected button on this message.
+///
+/// The user must be the author of the message the bot reply to or have the manage messages
+/// permission in the channel.
+pub fn can_manage(interaction: &ComponentInteraction) -> bool {
+    let is_author = interaction
+        .message
+        .referenced_message
+        .as_ref()
+        .is_some_and(|m| m.author.id == interaction.user.id);
+
+    is_author
+        || interaction
+            .member
+            .as_ref()
+            .and_then(|m| m.permissions)
+            .is_some_and(Permissions::manage_messages)
+}
+
+/// Tell the user they can't press this button.
+pub async fn deny(interaction: &ComponentInteraction, ctx: &Context) -> Res {
+    interaction
+        .create_response(
+            &ctx.http,
+            Message(
+                CreateInteractionResponseMessage::new()
+                    .content("Only the person who search or a moderator can use this button.")
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
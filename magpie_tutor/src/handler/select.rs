@@ -0,0 +1,101 @@
+use poise::serenity_prelude::{
+    ComponentInteraction, Context, CreateInteractionResponse::UpdateMessage,
+    CreateInteractionResponseMessage,
+};
+
+use super::button::{cache_remove_manual, remove_card_cache, CACHE_MANUAL_VALUE, CACHE_SELECT_ID};
+use crate::{
+    help::{help_message, HELP_SELECT_ID},
+    locale::guild_lang,
+    search::{query_pick_message, QUERY_PICK_PREFIX},
+    Data, Res,
+};
+
+pub async fn select_handler(
+    interaction: &ComponentInteraction,
+    ctx: &Context,
+    data: &Data,
+    custom_id: &str,
+    values: &[String],
+) -> Res {
+    match custom_id {
+        CACHE_SELECT_ID => cache_select(interaction, ctx, values).await,
+        HELP_SELECT_ID => help_select(interaction, ctx, values).await,
+        id if id.starts_with(QUERY_PICK_PREFIX) => {
+            query_pick(interaction, ctx, data, id, values).await
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn query_pick(
+    interaction: &ComponentInteraction,
+    ctx: &Context,
+    data: &Data,
+    custom_id: &str,
+    values: &[String],
+) -> Res {
+    let Some(value) = values.first() else {
+        return Ok(());
+    };
+
+    // generating the portrait can take a while
+    interaction.defer_ephemeral(&ctx.http).await?;
+
+    interaction
+        .edit_response(
+            &ctx.http,
+            query_pick_message(
+                data.interactions.get_shared(custom_id),
+                value,
+                guild_lang(interaction.guild_id),
+            )
+            .into(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn help_select(interaction: &ComponentInteraction, ctx: &Context, values: &[String]) -> Res {
+    let Some(page) = values.first() else {
+        return Ok(());
+    };
+
+    interaction
+        .create_response(
+            &ctx.http,
+            UpdateMessage(help_message(page, interaction.guild_id).into()),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn cache_select(interaction: &ComponentInteraction, ctx: &Context, values: &[String]) -> Res {
+    let Some(value) = values.first() else {
+        return Ok(());
+    };
+
+    if value == CACHE_MANUAL_VALUE {
+        return cache_remove_manual(interaction, ctx).await;
+    }
+
+    let content = match value.parse() {
+        Ok(id) if remove_card_cache(id) => "Cache removed",
+        _ => "Cache remove failed",
+    };
+
+    interaction
+        .create_response(
+            &ctx.http,
+            UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,233 @@
+//! Implementation for the health check.
+//!
+//! The bot keep track of the gateway connection and when the sets were loaded. This can be check
+//! with the `/health` command or through a tiny HTTP server for orchestrators, started when the
+//! `TUTOR_HEALTH_ADDR` environment variable is set. The server answer `GET /health` with the status
+//! as JSON and `GET /ready` with `200` only when the bot is connected and the sets are loaded.
+
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    http::{self, Response},
+    HEALTH, SETS,
+};
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude::{
+    colours::roles, ConnectionStage, CreateEmbed, FullEvent, ShardId, ShardStageUpdateEvent,
+};
+use serde::Serialize;
+use serde_json::json;
+
+/// Environment variable with the address for the health server to listen on.
+pub const HEALTH_ADDR_VAR: &str = "TUTOR_HEALTH_ADDR";
+
+/// The health state of the bot.
+#[derive(Debug, Clone)]
+pub struct Health {
+    /// When the bot started.
+    pub started: DateTime<Utc>,
+    /// If the gateway of each shard is connected.
+    pub shards: HashMap<u32, bool>,
+    /// When the last gateway event was received.
+    pub last_event: Option<DateTime<Utc>>,
+    /// When the sets were last loaded.
+    pub sets_loaded: Option<DateTime<Utc>>,
+    /// Set code to the error of every set that fail to load last time it was fetch.
+    pub failed_sets: HashMap<String, String>,
+}
+
+impl Health {
+    /// Create a new health state for a bot that just started.
+    pub fn new() -> Self {
+        Health {
+            started: Utc::now(),
+            shards: HashMap::new(),
+            last_event: None,
+            sets_loaded: None,
+            failed_sets: HashMap::new(),
+        }
+    }
+
+    /// Check if every shard is connected.
+    pub fn connected(&self) -> bool {
+        !self.shards.is_empty() && self.shards.values().all(|c| *c)
+    }
+
+    /// Check if the bot is ready to handle request.
+    pub fn is_ready(&self) -> bool {
+        self.connected() && self.sets_loaded.is_some()
+    }
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The load status of a set.
+#[derive(Serialize, Debug, Clone)]
+pub struct SetStatus {
+    /// The set code.
+    pub code: String,
+    /// The set name.
+    pub name: String,
+    /// How many cards the set have.
+    pub cards: usize,
+}
+
+/// Update the health state with a gateway event from a shard.
+pub fn record_event(event: &FullEvent, shard_id: ShardId) {
+    let mut health = HEALTH.lock().unwrap();
+    health.last_event = Some(Utc::now());
+
+    match event {
+        FullEvent::Ready { .. } | FullEvent::Resume { .. } => {
+            health.shards.insert(shard_id.0, true);
+        }
+        FullEvent::ShardStageUpdate {
+            event: ShardStageUpdateEvent { new, .. },
+        } => {
+            health
+                .shards
+                .insert(shard_id.0, *new == ConnectionStage::Connected);
+        }
+        _ => (),
+    }
+}
+
+/// Mark the sets as just loaded.
+pub fn mark_sets_loaded() {
+    HEALTH.lock().unwrap().sets_loaded = Some(Utc::now());
+}
+
+/// Record if a set fetch successfully or the error if it did not.
+pub fn mark_set_result(code: &str, error: Option<&str>) {
+    let mut health = HEALTH.lock().unwrap();
+
+    match error {
+        Some(err) => health.failed_sets.insert(code.to_owned(), err.to_owned()),
+        None => health.failed_sets.remove(code),
+    };
+}
+
+/// Check if any set fail to load and need to be fetch again.
+pub fn has_failed_sets() -> bool {
+    !HEALTH.lock().unwrap().failed_sets.is_empty()
+}
+
+/// Get the load status of every set sorted by set code.
+pub fn set_status() -> Vec<SetStatus> {
+    let mut status = SETS
+        .read()
+        .unwrap()
+        .values()
+        .map(|s| SetStatus {
+            code: s.code.code().to_owned(),
+            name: s.name.clone(),
+            cards: s.cards.len(),
+        })
+        .collect::<Vec<_>>();
+
+    status.sort_by(|a, b| a.code.cmp(&b.code));
+    status
+}
+
+/// Generate the health embed, the latency is the gateway heartbeat latency if it is known.
+pub fn health_embed(latency: Option<Duration>) -> CreateEmbed {
+    let health = HEALTH.lock().unwrap().clone();
+
+    let sets = set_status()
+        .iter()
+        .map(|s| format!("`{}` {}: {} cards", s.code, s.name, s.cards))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut failed = health
+        .failed_sets
+        .keys()
+        .map(|c| format!("`{c}`"))
+        .collect::<Vec<_>>();
+    failed.sort_unstable();
+
+    CreateEmbed::new()
+        .color(if !health.is_ready() {
+            roles::RED
+        } else if failed.is_empty() {
+            roles::GREEN
+        } else {
+            roles::ORANGE
+        })
+        .title("Health Check")
+        .field(
+            "Gateway",
+            format!(
+                "{}/{} shards connected",
+                health.shards.values().filter(|c| **c).count(),
+                health.shards.len()
+            ),
+            true,
+        )
+        .field(
+            "Latency",
+            latency.map_or(String::from("Unknown"), |l| format!("{l:.0?}")),
+            true,
+        )
+        .field("Uptime", format_uptime(health.started), true)
+        .field("Last event", format_time(health.last_event), true)
+        .field("Sets loaded", format_time(health.sets_loaded), true)
+        .field("Sets", sets, false)
+        .field(
+            "Failed sets",
+            if failed.is_empty() {
+                String::from("None")
+            } else {
+                failed.join(", ")
+            },
+            false,
+        )
+}
+
+fn format_time(time: Option<DateTime<Utc>>) -> String {
+    time.map_or(String::from("Never"), |t| {
+        format!("<t:{}:R>", t.timestamp())
+    })
+}
+
+fn format_uptime(started: DateTime<Utc>) -> String {
+    let uptime = Utc::now() - started;
+    format!(
+        "{}d {}h {}m",
+        uptime.num_days(),
+        uptime.num_hours() % 24,
+        uptime.num_minutes() % 60
+    )
+}
+
+/// Start the health server on an address.
+pub async fn start_server(addr: &str) {
+    http::serve("Health", addr, route).await;
+}
+
+fn route(target: &str) -> Response {
+    let health = HEALTH.lock().unwrap().clone();
+
+    match http::split_target(target).0 {
+        "/health" => Response::json(
+            "200 OK",
+            &json!({
+                "ready": health.is_ready(),
+                "connected": health.connected(),
+                "shards": health.shards,
+                "started": health.started.to_rfc3339(),
+                "last_event": health.last_event.map(|t| t.to_rfc3339()),
+                "sets_loaded": health.sets_loaded.map(|t| t.to_rfc3339()),
+                "sets": set_status(),
+                "failed_sets": health.failed_sets,
+            }),
+        ),
+        "/ready" if health.is_ready() => Response::text("200 OK", "ready"),
+        "/ready" => Response::text("503 Service Unavailable", "not ready"),
+        _ => Response::text("404 Not Found", "not found"),
+    }
+}
@@ -0,0 +1,194 @@
+//! Implementation for the paged help.
+//!
+//! Every page except the search syntax is generate from the tables the parsers use, the modifiers
+//! from [`modifiers`], the query keywords from [`all_keywords`] and the sets from [`SETS`] so the
+//! help never list something the bot doesn't understand.
+
+use std::fmt::Write;
+
+use magpie_engine::query::parse::all_keywords;
+use poise::serenity_prelude::{
+    colours::roles, CreateActionRow, CreateEmbed, CreateEmbedFooter, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption, GuildId,
+};
+
+use crate::{
+    delimiter::guild_delimiter,
+    engine::{FilterExt, MagpieCosts, MagpieExt},
+    locale::guild_lang,
+    search::{default_set, modifiers, options, NO_SEARCH_PREFIX},
+    tr, MessageAdapter, SETS,
+};
+
+/// Custom id of the help page select menu.
+pub const HELP_SELECT_ID: &str = "help_page";
+
+/// Value of the search syntax page, the first page of the help.
+pub const SEARCH_PAGE: &str = "search";
+
+/// Value of the modifiers page.
+const MODIFIERS_PAGE: &str = "modifiers";
+
+/// Value of the sets page.
+const SETS_PAGE: &str = "sets";
+
+/// Prefix of the value of the keyword pages, follow by the keyword category.
+const KEYWORDS_PREFIX: &str = "keywords_";
+
+/// Get the category of every query keyword in the order they first appear.
+fn keyword_categories() -> Vec<&'static str> {
+    let mut categories = vec![];
+    for kw in all_keywords::<MagpieExt, MagpieCosts, FilterExt>() {
+        if !categories.contains(&kw.category) {
+            categories.push(kw.category);
+        }
+    }
+
+    categories
+}
+
+/// Generate a help page, `page` is the value of the page in the select menu.
+///
+/// Unknown pages show the search syntax page.
+pub fn help_message(page: &str, guild_id: Option<GuildId>) -> MessageAdapter {
+    let lang = guild_lang(guild_id);
+    let embed = match page {
+        MODIFIERS_PAGE => modifiers_page(guild_id, lang),
+        SETS_PAGE => sets_page(guild_id, lang),
+        _ => match page.strip_prefix(KEYWORDS_PREFIX) {
+            Some(category) => keywords_page(category, lang),
+            None => CreateEmbed::new()
+                .title(tr!(lang, "help.search_page"))
+                .description(guild_delimiter(guild_id).apply(tr!(lang, "help.text"))),
+        },
+    };
+
+    let options = [
+        (
+            SEARCH_PAGE.to_owned(),
+            tr!(lang, "help.search_page").to_owned(),
+        ),
+        (
+            MODIFIERS_PAGE.to_owned(),
+            tr!(lang, "help.modifiers_page").to_owned(),
+        ),
+    ]
+    .into_iter()
+    .chain(keyword_categories().into_iter().map(|c| {
+        (
+            format!("{KEYWORDS_PREFIX}{c}"),
+            tr!(lang, "help.keywords_page", category = c),
+        )
+    }))
+    .chain([(SETS_PAGE.to_owned(), tr!(lang, "help.sets_page").to_owned())])
+    .map(|(value, label)| {
+        let default = value == page;
+        CreateSelectMenuOption::new(label, value).default_selection(default)
+    })
+    .collect();
+
+    MessageAdapter::new()
+        .embeds(vec![embed.color(roles::BLUE)])
+        .components(vec![CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(HELP_SELECT_ID, CreateSelectMenuKind::String { options })
+                .placeholder(tr!(lang, "help.pick_page")),
+        )])
+}
+
+/// Generate the modifiers and set codes list of `/show-modifiers`.
+pub fn modifiers_text() -> String {
+    format!(
+        "# Set Codes\n{}# Modifiers\n{}# Options\n{}",
+        set_lines(),
+        modifier_lines(),
+        option_lines()
+    )
+}
+
+/// List every modifier, one per line.
+fn modifier_lines() -> String {
+    let mut lines = String::new();
+    for (c, text) in modifiers() {
+        // the backtick need escaping to show in inline code
+        let c = if c == '`' {
+            "\\`".to_owned()
+        } else {
+            c.to_string()
+        };
+        writeln!(lines, "- `{c}`: {text}.").unwrap();
+    }
+
+    lines
+}
+
+/// List every option, one per line.
+fn option_lines() -> String {
+    let mut lines = String::new();
+    for (key, text) in options() {
+        writeln!(lines, "- `{key}=`: {text}.").unwrap();
+    }
+
+    lines
+}
+
+/// List every loaded set sorted by code, one per line.
+fn set_lines() -> String {
+    let sets = SETS.read().unwrap();
+    let mut sets = sets.iter().collect::<Vec<_>>();
+    sets.sort_by_key(|(code, _)| **code);
+
+    let mut lines = String::new();
+    for (code, set) in sets {
+        writeln!(lines, "- `{code}`: {}.", set.name).unwrap();
+    }
+
+    lines
+}
+
+/// Generate the modifiers page, the examples use the guild delimiter.
+fn modifiers_page(guild_id: Option<GuildId>, lang: &str) -> CreateEmbed {
+    let desc = tr!(
+        lang,
+        "help.modifiers",
+        modifiers = modifier_lines(),
+        options = option_lines(),
+        prefix = NO_SEARCH_PREFIX
+    );
+
+    CreateEmbed::new()
+        .title(tr!(lang, "help.modifiers_page"))
+        .description(guild_delimiter(guild_id).apply(&desc))
+}
+
+/// Generate the page of a keyword category.
+fn keywords_page(category: &str, lang: &str) -> CreateEmbed {
+    let mut desc = format!("{}\n", tr!(lang, "help.keywords"));
+    for kw in all_keywords::<MagpieExt, MagpieCosts, FilterExt>()
+        .into_iter()
+        .filter(|k| k.category == category)
+    {
+        let names = kw
+            .names
+            .iter()
+            .map(|n| format!("`{n}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(desc, "- {names}: {}.", kw.description).unwrap();
+    }
+
+    CreateEmbed::new()
+        .title(tr!(lang, "help.keywords_page", category = category))
+        .description(desc)
+}
+
+/// Generate the sets page with every loaded set.
+fn sets_page(guild_id: Option<GuildId>, lang: &str) -> CreateEmbed {
+    CreateEmbed::new()
+        .title(tr!(lang, "help.sets_page"))
+        .description(set_lines())
+        .footer(CreateEmbedFooter::new(tr!(
+            lang,
+            "help.default_set",
+            set = default_set(guild_id)
+        )))
+}
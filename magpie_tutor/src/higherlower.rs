@@ -0,0 +1,201 @@
+//! Implementation for the higher or lower minigame.
+//!
+//! The game show a card and players guess if the next random card have a higher or lower stat. A
+//! tie count as a correct guess. Cards are not shown twice in a game so the game end once every
+//! card with the stat was guess. The game state is keep in the [`InteractionStore`] under the
+//! button ids.
+
+use magpie_engine::Attack;
+use poise::serenity_prelude::{
+    colours::roles,
+    ButtonStyle::{Primary, Secondary},
+    CreateActionRow::Buttons,
+    CreateButton, CreateEmbed,
+};
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{interaction::InteractionStore, Card, MessageAdapter, Set, SETS};
+
+/// Prefix of the higher button custom id.
+pub const HIGHER_PREFIX: &str = "hl_h_";
+/// Prefix of the lower button custom id.
+pub const LOWER_PREFIX: &str = "hl_l_";
+
+/// The state of a higher or lower game.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct GameState {
+    /// Code of the set the cards are pick from.
+    set_code: String,
+    /// The stat being compare.
+    stat: Stat,
+    /// [Id](Card::id) of every card shown so far, the last one is the current card.
+    ///
+    /// Ids are keep instead of index so a set refresh that reorder the cards don't change them.
+    seen: Vec<u64>,
+    /// How many guesses in a row were correct.
+    streak: u32,
+}
+
+/// The stat to compare in a higher or lower game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum Stat {
+    /// The card attack, cards with special attack are skip.
+    Attack,
+    /// The card health.
+    Health,
+    /// The card blood cost.
+    Blood,
+    /// The card bone cost.
+    Bone,
+    /// The card energy cost.
+    Energy,
+}
+
+impl Stat {
+    /// Get the value of this stat on a card.
+    ///
+    /// Return [`None`] if the card can't be use for this stat.
+    pub fn value(self, card: &Card) -> Option<isize> {
+        match self {
+            Stat::Attack => match card.attack {
+                Attack::Num(a) => Some(a),
+                _ => None,
+            },
+            Stat::Health => Some(card.health),
+            Stat::Blood => Some(card.costs.as_ref().map_or(0, |c| c.blood)),
+            Stat::Bone => Some(card.costs.as_ref().map_or(0, |c| c.bone)),
+            Stat::Energy => Some(card.costs.as_ref().map_or(0, |c| c.energy)),
+        }
+    }
+}
+
+/// Start a new higher or lower game.
+pub fn start_message(
+    set_code: &str,
+    stat: Stat,
+    interactions: &InteractionStore,
+) -> MessageAdapter {
+    let sets = SETS.read().unwrap();
+    let Some(set) = sets.get(set_code) else {
+        return error_message(format!("Unknown set code `{set_code}`"));
+    };
+
+    let Some(card) = random_card(set, stat, &[]) else {
+        return error_message(format!("{} have no cards with {stat:?}", set.name));
+    };
+
+    let state = GameState {
+        set_code: set_code.to_owned(),
+        stat,
+        seen: vec![card.id()],
+        streak: 0,
+    };
+    round_message(card, &state, None, interactions)
+}
+
+/// Play the next round of a higher or lower game from a button id.
+///
+/// Return [`None`] if the game state expired.
+pub fn next_round(custom_id: &str, interactions: &InteractionStore) -> Option<MessageAdapter> {
+    let mut state = interactions.get::<GameState>(custom_id)?;
+    let stat = state.stat;
+
+    let sets = SETS.read().unwrap();
+    let Some((set, prev)) = sets.get(state.set_code.as_str()).and_then(|s| {
+        let id = *state.seen.last()?;
+        s.cards.iter().find(|c| c.id() == id).map(|c| (s, c))
+    }) else {
+        return Some(error_message(
+            "The card of this game no longer exist".to_owned(),
+        ));
+    };
+
+    let Some(next) = random_card(set, stat, &state.seen) else {
+        return Some(
+            MessageAdapter::new()
+                .embeds(vec![CreateEmbed::new()
+                    .color(roles::GREEN)
+                    .title("Game Over")
+                    .description(format!(
+                        "You went through every card of {} with {stat:?}!\nFinal streak: {}",
+                        set.name, state.streak
+                    ))])
+                .components(vec![]),
+        );
+    };
+
+    let (prev_value, next_value) = (
+        stat.value(prev).unwrap_or_default(),
+        stat.value(next).unwrap_or_default(),
+    );
+
+    let correct = if custom_id.starts_with(HIGHER_PREFIX) {
+        next_value >= prev_value
+    } else {
+        next_value <= prev_value
+    };
+
+    if correct {
+        state.seen.push(next.id());
+        state.streak += 1;
+        Some(round_message(next, &state, Some(prev), interactions))
+    } else {
+        Some(MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::RED)
+                .title("Game Over")
+                .description(format!(
+                    "**{}** have {next_value} {stat:?} while **{}** have {prev_value}.\nFinal streak: {}",
+                    next.name, prev.name, state.streak
+                ))])
+            .components(vec![]))
+    }
+}
+
+/// Pick a random card that have the stat and was not seen yet.
+fn random_card<'a>(set: &'a Set, stat: Stat, seen: &[u64]) -> Option<&'a Card> {
+    set.random_card(&mut thread_rng(), |c| {
+        stat.value(c).is_some() && !seen.contains(&c.id())
+    })
+}
+
+fn round_message(
+    card: &Card,
+    state: &GameState,
+    prev: Option<&Card>,
+    interactions: &InteractionStore,
+) -> MessageAdapter {
+    let (stat, streak) = (state.stat, state.streak);
+    let value = stat.value(card).unwrap_or_default();
+
+    let desc = prev.map_or(String::new(), |prev| {
+        format!(
+            "Correct! **{}** had {}.\n\n",
+            prev.name,
+            stat.value(prev).unwrap_or_default()
+        )
+    });
+
+    MessageAdapter::new()
+        .embeds(vec![CreateEmbed::new()
+            .color(roles::PURPLE)
+            .title(format!("Higher or Lower ({stat:?})"))
+            .description(format!(
+                "{desc}**{}** have **{value}** {stat:?}.\nWill the next card have a higher or lower {stat:?}?\n\nStreak: {streak}",
+                card.name
+            ))])
+        .components(vec![Buttons(vec![
+            CreateButton::new(interactions.insert(HIGHER_PREFIX, state)).style(Primary).label("Higher"),
+            CreateButton::new(interactions.insert(LOWER_PREFIX, state)).style(Secondary).label("Lower"),
+        ])])
+}
+
+fn error_message(msg: String) -> MessageAdapter {
+    MessageAdapter::new()
+        .embeds(vec![CreateEmbed::new()
+            .color(roles::RED)
+            .title("Higher or Lower Error")
+            .description(msg)])
+        .components(vec![])
+}
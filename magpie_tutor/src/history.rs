@@ -0,0 +1,243 @@
+//! Implementation for the user search history.
+//!
+//! History is opt-in, users turn it on with `/history enable` and only then are their searches
+//! record. Only the last [`MAX_HISTORY`] searches are keep and turning history off delete them.
+
+use std::collections::HashMap;
+
+use poise::serenity_prelude::{
+    colours::roles,
+    ButtonStyle::{Primary, Secondary},
+    CreateActionRow::{self, Buttons},
+    CreateButton, CreateEmbed, GuildId, UserId,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    current_epoch,
+    delimiter::guild_delimiter,
+    done,
+    interaction::InteractionStore,
+    search::{process_search, search_matches},
+    shard::{read_shared, shared_changed, with_shared_lock, write_shared},
+    Color, MessageAdapter, HISTORY,
+};
+
+/// Location of the history file.
+pub const HISTORY_FILE_PATH: &str = "./history.bin";
+
+/// Custom id prefix of the buttons to repeat a search.
+pub const HISTORY_PREFIX: &str = "hist_";
+
+/// How many searches are keep per user.
+pub const MAX_HISTORY: usize = 10;
+
+/// Type alias for the history table, user id to searches from newest to oldest.
+///
+/// Only users that opt-in are in the table.
+pub type History = HashMap<u64, Vec<HistoryEntry>>;
+
+/// A single search in the history.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    /// The searches of the message with the default delimiter, see [`record_history`].
+    pub content: String,
+    /// The guild the search was made in, `0` for DMs.
+    pub guild_id: u64,
+    /// When the search was made in seconds since epoch.
+    pub time: u64,
+}
+
+/// Load the history from the history file.
+///
+/// The history file is share by every shard process since users are not tied to a shard.
+pub(crate) fn load_history() -> History {
+    read_shared(HISTORY_FILE_PATH)
+        .ok()
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Reload the history if another process change it.
+fn reload_history() {
+    if shared_changed(HISTORY_FILE_PATH) {
+        *HISTORY.lock().unwrap() = load_history();
+    }
+}
+
+/// Save the history to the history file.
+///
+/// This lock [`HISTORY`] so make sure you drop any guard before calling this.
+pub fn save_history() {
+    write_shared(HISTORY_FILE_PATH, &*HISTORY);
+    done!("History save successfully to {}", HISTORY_FILE_PATH.green());
+}
+
+/// Turn the history on or off for a user, turning it off delete the history.
+///
+/// Return if the history is now on.
+pub fn toggle_history(user_id: UserId, enable: bool) -> bool {
+    with_shared_lock(HISTORY_FILE_PATH, || {
+        reload_history();
+        {
+            let mut history = HISTORY.lock().unwrap();
+            if enable {
+                history.entry(user_id.get()).or_default();
+            } else {
+                history.remove(&user_id.get());
+            }
+        }
+
+        save_history();
+    });
+    enable
+}
+
+/// Record a search if the user have history on.
+///
+/// Only the searches are keep and they are rewrite with the default delimiter, the guild
+/// delimiter is put back when they are show or repeat so they still match if it change.
+pub fn record_history(user_id: UserId, guild_id: Option<GuildId>, content: &str) {
+    let content = search_matches(content, guild_id)
+        .map(|c| format!("{}[[{}]]", &c[1], &c[2]))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    // most users have the history off so only lock the file for the ones that have it on
+    reload_history();
+    if !HISTORY.lock().unwrap().contains_key(&user_id.get()) {
+        return;
+    }
+
+    with_shared_lock(HISTORY_FILE_PATH, || {
+        reload_history();
+        {
+            let mut history = HISTORY.lock().unwrap();
+            let Some(entries) = history.get_mut(&user_id.get()) else {
+                return;
+            };
+
+            entries.insert(
+                0,
+                HistoryEntry {
+                    content,
+                    guild_id: guild_id.map_or(0, GuildId::get),
+                    #[allow(clippy::cast_possible_truncation)]
+                    time: (current_epoch() / 1000) as u64,
+                },
+            );
+            entries.truncate(MAX_HISTORY);
+        }
+
+        save_history();
+    });
+}
+
+/// The searches of a entry with the current delimiter of its guild.
+fn entry_content(entry: &HistoryEntry) -> String {
+    guild_delimiter(Some(entry.guild_id).filter(|id| *id != 0).map(GuildId::new))
+        .apply(&entry.content)
+}
+
+/// Generate the history message of a user with a button to repeat each search.
+pub fn history_message(user_id: UserId) -> MessageAdapter {
+    reload_history();
+    let entries = match HISTORY.lock().unwrap().get(&user_id.get()) {
+        None => {
+            return MessageAdapter::new()
+                .embeds(vec![CreateEmbed::new()
+                    .color(roles::ORANGE)
+                    .title("History is off")
+                    .description(
+                        "Turn it on with `/history enable` to start recording your searches.",
+                    )])
+                .ephemeral(true)
+        }
+        Some(entries) => entries.clone(),
+    };
+
+    if entries.is_empty() {
+        return MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::ORANGE)
+                .title("No searches yet")
+                .description("Your next searches will show up here.")])
+            .ephemeral(true);
+    }
+
+    let lines = entries
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            format!(
+                "{}. `{}` <t:{}:R>",
+                i + 1,
+                entry_content(e).replace('`', ""),
+                e.time
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let buttons = (0..entries.len())
+        .map(|i| {
+            let button = CreateButton::new(format!("{HISTORY_PREFIX}{i}"));
+            if i == 0 {
+                button.style(Primary).label("Repeat Last Search")
+            } else {
+                button.style(Secondary).label(format!("#{}", i + 1))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    MessageAdapter::new()
+        .embeds(vec![CreateEmbed::new()
+            .color(roles::BLUE)
+            .title(format!("Last {} searches", entries.len()))
+            .description(lines.join("\n"))])
+        .components(
+            buttons
+                .chunks(5)
+                .map(|row| Buttons(row.to_vec()))
+                .collect::<Vec<CreateActionRow>>(),
+        )
+        .ephemeral(true)
+}
+
+/// Repeat a search from the user history.
+///
+/// The result is ephemeral and have no buttons because the buttons need the original search
+/// message.
+pub fn repeat_message(
+    user_id: UserId,
+    custom_id: &str,
+    interactions: &InteractionStore,
+) -> MessageAdapter {
+    let entry = custom_id
+        .strip_prefix(HISTORY_PREFIX)
+        .and_then(|i| i.parse::<usize>().ok())
+        .and_then(|i| {
+            HISTORY
+                .lock()
+                .unwrap()
+                .get(&user_id.get())
+                .and_then(|e| e.get(i).cloned())
+        });
+
+    let Some(entry) = entry else {
+        return MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::RED)
+                .title("Search not found")
+                .description("This search is no longer in your history.")])
+            .ephemeral(true);
+    };
+
+    process_search(
+        &entry_content(&entry),
+        Some(entry.guild_id).filter(|id| *id != 0).map(GuildId::new),
+        false,
+        interactions,
+    )
+    .components(vec![])
+    .ephemeral(true)
+}
@@ -0,0 +1,205 @@
+//! Implementation for the guild homebrew sets.
+//!
+//! Each guild can build one custom set with `/homebrew`. The set have its own set code so it can
+//! be search like any other set, but only in the guild that made it. Homebrew sets are save to
+//! [`HOMEBREW_FILE_PATH`] and rebuild with the engine [`SetBuilder`] every time they are use so
+//! they are always valid.
+
+use std::collections::HashMap;
+
+use magpie_engine::prelude::*;
+use poise::serenity_prelude::GuildId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    done,
+    engine::{MagpieCosts, MagpieExt},
+    shard::{read_data, write_data},
+    Color, Set, HOMEBREW, SETS,
+};
+
+/// Location of the homebrew file.
+pub const HOMEBREW_FILE_PATH: &str = "./homebrew.bin";
+
+/// Most cards a homebrew set can have.
+pub const MAX_CARDS: usize = 200;
+
+/// Type alias for the homebrew sets, guild id to homebrew set.
+pub type Homebrew = HashMap<u64, HomebrewSet>;
+
+/// A guild homebrew set.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HomebrewSet {
+    /// The set code use to search this set.
+    pub code: String,
+    /// The set name.
+    pub name: String,
+    /// Sigil name to sigil description.
+    pub sigils: HashMap<String, String>,
+    /// The cards in the order they were added.
+    pub cards: Vec<HomebrewCard>,
+}
+
+/// A card in a homebrew set.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HomebrewCard {
+    /// The card name.
+    pub name: String,
+    /// The card description.
+    pub description: String,
+    /// The card portrait url, empty for no portrait.
+    pub portrait: String,
+    /// The card attack.
+    pub attack: isize,
+    /// The card health.
+    pub health: isize,
+    /// The blood cost.
+    pub blood: isize,
+    /// The bone cost.
+    pub bone: isize,
+    /// The energy cost.
+    pub energy: isize,
+    /// The card sigils, they must be in the set sigils.
+    pub sigils: Vec<String>,
+}
+
+impl HomebrewCard {
+    fn to_builder(&self) -> CardBuilder<MagpieExt, MagpieCosts> {
+        let costs = CostsBuilder::new()
+            .blood(self.blood)
+            .bone(self.bone)
+            .energy(self.energy)
+            .build();
+
+        CardBuilder::new(&self.name)
+            .description(&self.description)
+            .portrait(&self.portrait)
+            .attack(Attack::Num(self.attack))
+            .health(self.health)
+            .sigils(self.sigils.clone())
+            .costs(costs)
+    }
+}
+
+impl HomebrewSet {
+    /// Build the searchable set from the homebrew data.
+    pub fn to_set(&self) -> Result<Set, BuildError> {
+        let mut builder = SetBuilder::new(&self.code, &self.name)?;
+
+        for (name, desc) in &self.sigils {
+            builder = builder.sigil(name, desc);
+        }
+
+        for card in &self.cards {
+            builder = builder.card(card.to_builder())?;
+        }
+
+        Ok(builder.build())
+    }
+}
+
+/// Load the homebrew sets from the homebrew file.
+pub(crate) fn load_homebrew() -> Homebrew {
+    read_data(HOMEBREW_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the homebrew sets to the homebrew file.
+///
+/// This lock [`HOMEBREW`] so make sure you drop any guard before calling this.
+pub fn save_homebrew() {
+    write_data(HOMEBREW_FILE_PATH, &*HOMEBREW);
+    done!(
+        "Homebrew sets save successfully to {}",
+        HOMEBREW_FILE_PATH.green()
+    );
+}
+
+/// Get the searchable homebrew set of a guild if it have one, there is none outside of guilds.
+pub fn guild_set(guild_id: Option<GuildId>) -> Option<Set> {
+    HOMEBREW
+        .lock()
+        .unwrap()
+        .get(&guild_id?.get())
+        .and_then(|h| h.to_set().ok())
+}
+
+/// Create the homebrew set of a guild, replacing the old one.
+pub fn create_set(guild_id: GuildId, code: &str, name: &str) -> Result<(), String> {
+    if SETS.read().unwrap().contains_key(code) {
+        return Err(format!("Set code `{code}` is already use by magpie"));
+    }
+
+    let set = HomebrewSet {
+        code: code.to_owned(),
+        name: name.trim().to_owned(),
+        ..Default::default()
+    };
+    set.to_set().map_err(|e| e.to_string())?;
+
+    HOMEBREW.lock().unwrap().insert(guild_id.get(), set);
+    save_homebrew();
+
+    Ok(())
+}
+
+/// Add or replace a sigil in the homebrew set of a guild.
+pub fn add_sigil(guild_id: GuildId, name: &str, description: &str) -> Result<(), String> {
+    update_set(guild_id, |set| {
+        set.sigils
+            .insert(name.trim().to_owned(), description.trim().to_owned());
+        Ok(())
+    })
+}
+
+/// Add a card to the homebrew set of a guild.
+pub fn add_card(guild_id: GuildId, card: HomebrewCard) -> Result<(), String> {
+    update_set(guild_id, |set| {
+        if set.cards.len() >= MAX_CARDS {
+            return Err(format!("A homebrew set can only have {MAX_CARDS} cards"));
+        }
+
+        set.cards.push(card);
+        Ok(())
+    })
+}
+
+/// Update the homebrew set of a guild, the update is only save if the set is still valid after.
+fn update_set<F>(guild_id: GuildId, f: F) -> Result<(), String>
+where
+    F: FnOnce(&mut HomebrewSet) -> Result<(), String>,
+{
+    {
+        let mut homebrew = HOMEBREW.lock().unwrap();
+        let Some(old) = homebrew.get(&guild_id.get()) else {
+            return Err(String::from(
+                "This server have no homebrew set, create one with `/homebrew create`",
+            ));
+        };
+
+        let mut new = old.clone();
+        f(&mut new)?;
+        new.to_set().map_err(|e| e.to_string())?;
+
+        homebrew.insert(guild_id.get(), new);
+    }
+
+    save_homebrew();
+    Ok(())
+}
+
+/// Parse a cost string like `2 blood` or `3 bone, 1 energy` with the shared cost parser.
+///
+/// Homebrew cards only have blood, bone and energy costs so moxes are refuse.
+pub fn parse_costs(costs: &str) -> Result<Costs<MagpieCosts>, String> {
+    let costs = parse_cost_string(costs, &CostVocab::default())
+        .map_err(|err| format!("Invalid cost, {err}"))?
+        .unwrap_or_default();
+
+    if !costs.mox.is_empty() {
+        return Err("Homebrew cards cannot cost mox".to_owned());
+    }
+
+    Ok(costs)
+}
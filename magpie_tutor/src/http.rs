@@ -0,0 +1,151 @@
+//! A tiny HTTP server for the health check and the card API.
+//!
+//! Only `GET` requests are support and the connection is close after every response. This is
+//! enough for orchestrators and simple API consumers without pulling in a web framework.
+
+use std::{borrow::Cow, io};
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{done, error, Color};
+
+/// A HTTP response.
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// The status line, like `200 OK`.
+    pub status: &'static str,
+    /// The content type of the body.
+    pub content_type: &'static str,
+    /// The response body.
+    pub body: String,
+}
+
+impl Response {
+    /// Create a JSON response.
+    pub fn json(status: &'static str, value: &Value) -> Self {
+        Response {
+            status,
+            content_type: "application/json",
+            body: value.to_string(),
+        }
+    }
+
+    /// Create a plain text response.
+    pub fn text(status: &'static str, body: &str) -> Self {
+        Response {
+            status,
+            content_type: "text/plain",
+            body: body.to_owned(),
+        }
+    }
+}
+
+/// Start a HTTP server on an address, every request target is pass to the handler.
+///
+/// The request target is the path along with the query string, like `/api/cards?q=...`.
+pub async fn serve(name: &str, addr: &str, handler: fn(&str) -> Response) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(l) => l,
+        Err(err) => {
+            error!("Cannot start {name} server on {addr} due to: {err}");
+            return;
+        }
+    };
+
+    done!("{} server listening on {}", name.blue(), addr.green());
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(async move {
+                        if let Err(err) = respond(stream, handler).await {
+                            error!("Cannot respond to HTTP request due to: {err}");
+                        }
+                    });
+                }
+                Err(err) => error!("Cannot accept HTTP request due to: {err}"),
+            }
+        }
+    });
+}
+
+async fn respond(mut stream: TcpStream, handler: fn(&str) -> Response) -> io::Result<()> {
+    let mut buf = [0; 4096];
+    let len = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..len]);
+
+    // only the request line matter, `GET /path HTTP/1.1`
+    let mut line = request.split_whitespace();
+    let res = match (line.next(), line.next()) {
+        (Some("GET"), Some(target)) => handler(target),
+        (Some(_), Some(_)) => Response::text("405 Method Not Allowed", "method not allowed"),
+        _ => Response::text("400 Bad Request", "bad request"),
+    };
+
+    stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+                res.status,
+                res.content_type,
+                res.body.len(),
+                res.body
+            )
+            .as_bytes(),
+        )
+        .await
+}
+
+/// Split a request target into the path and the decoded query parameters.
+pub fn split_target(target: &str) -> (&str, Vec<(String, String)>) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let params = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (k, v) = p.split_once('=').unwrap_or((p, ""));
+            (decode(k).into_owned(), decode(v).into_owned())
+        })
+        .collect();
+
+    (path, params)
+}
+
+/// Decode a percent encoded url component, `+` is decoded to a space.
+pub fn decode(s: &str) -> Cow<'_, str> {
+    if !s.contains(['%', '+']) {
+        return Cow::Borrowed(s);
+    }
+
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    Cow::Owned(String::from_utf8_lossy(&out).into_owned())
+}
@@ -0,0 +1,144 @@
+//! Implementation for the interaction state store.
+//!
+//! Discord only give back the custom id of a button when it is click so buttons that need more
+//! than what fit in the id, like what page of a result a user is on, keep their state in the
+//! [`InteractionStore`] on [`Data`](crate::Data). The state is serialize to JSON under the button
+//! custom id and drop after [`STATE_TTL`] so old messages don't keep state around forever.
+//!
+//! State that can't be serialize, like a query result that keep its sets alive, is keep as is with
+//! [`InteractionStore::insert_shared`] instead.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use poise::serenity_prelude::CreateInteractionResponseMessage;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::Death;
+
+/// How long a state is keep after it was last set.
+pub const STATE_TTL: Duration = Duration::from_mins(15);
+
+/// A state keep without serializing it.
+type SharedState = Arc<dyn Any + Send + Sync>;
+
+/// Store of button custom id to their serialized state.
+#[derive(Debug, Default)]
+pub struct InteractionStore {
+    /// Custom id to when the state was set and the state as JSON.
+    states: Mutex<HashMap<String, (Instant, String)>>,
+    /// Custom id to when the state was set and the state, for the states that can't be serialize.
+    shared: Mutex<HashMap<String, (Instant, SharedState)>>,
+}
+
+impl InteractionStore {
+    /// Create a new empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a new state and return the custom id for the button, `prefix` follow by a unique id.
+    ///
+    /// The id is random so buttons from before a restart or from another shard never get the
+    /// state of a new button.
+    pub fn insert<T: Serialize>(&self, prefix: &str, state: &T) -> String {
+        let custom_id = self.new_id(prefix);
+        self.set(&custom_id, state);
+        custom_id
+    }
+
+    /// Store a new state without serializing it and return the custom id for the button, like
+    /// [`insert`](InteractionStore::insert).
+    pub fn insert_shared<T: Any + Send + Sync>(&self, prefix: &str, state: T) -> String {
+        let custom_id = self.new_id(prefix);
+
+        let mut shared = self.lock_shared();
+        shared.retain(|_, (time, _)| time.elapsed() < STATE_TTL);
+        shared.insert(custom_id.clone(), (Instant::now(), Arc::new(state)));
+        custom_id
+    }
+
+    /// Replace the state of a custom id and reset its expiration.
+    pub fn set<T: Serialize>(&self, custom_id: &str, state: &T) {
+        let state =
+            serde_json::to_string(state).unwrap_or_die("Cannot serialize interaction state");
+
+        let mut states = self.lock();
+        states.retain(|_, (time, _)| time.elapsed() < STATE_TTL);
+        states.insert(custom_id.to_owned(), (Instant::now(), state));
+    }
+
+    /// Get the state of a custom id, [`None`] if it expired or is not the right type.
+    pub fn get<T: DeserializeOwned>(&self, custom_id: &str) -> Option<T> {
+        self.lock()
+            .get(custom_id)
+            .filter(|(time, _)| time.elapsed() < STATE_TTL)
+            .and_then(|(_, state)| serde_json::from_str(state).ok())
+    }
+
+    /// Get a state store with [`insert_shared`](InteractionStore::insert_shared), [`None`] if it
+    /// expired or is not the right type.
+    pub fn get_shared<T: Any + Send + Sync>(&self, custom_id: &str) -> Option<Arc<T>> {
+        self.lock_shared()
+            .get(custom_id)
+            .filter(|(time, _)| time.elapsed() < STATE_TTL)
+            .and_then(|(_, state)| Arc::clone(state).downcast().ok())
+    }
+
+    /// Remove the state of a custom id.
+    pub fn remove(&self, custom_id: &str) {
+        self.lock().remove(custom_id);
+        self.lock_shared().remove(custom_id);
+    }
+
+    /// Remove every expired state.
+    pub fn cleanup(&self) {
+        self.lock()
+            .retain(|_, (time, _)| time.elapsed() < STATE_TTL);
+        self.lock_shared()
+            .retain(|_, (time, _)| time.elapsed() < STATE_TTL);
+    }
+
+    /// How many states are store.
+    pub fn len(&self) -> usize {
+        self.lock().len() + self.lock_shared().len()
+    }
+
+    /// Check if no state is store.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Generate a custom id that is not use by any state yet.
+    fn new_id(&self, prefix: &str) -> String {
+        loop {
+            let id = format!("{prefix}{:016x}", rand::random::<u64>());
+            if !self.lock().contains_key(&id) && !self.lock_shared().contains_key(&id) {
+                break id;
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (Instant, String)>> {
+        self.states
+            .lock()
+            .unwrap_or_die("Cannot lock interaction states")
+    }
+
+    fn lock_shared(&self) -> std::sync::MutexGuard<'_, HashMap<String, (Instant, SharedState)>> {
+        self.shared
+            .lock()
+            .unwrap_or_die("Cannot lock interaction states")
+    }
+}
+
+/// Response for a button which state expired.
+pub fn expired_message() -> CreateInteractionResponseMessage {
+    CreateInteractionResponseMessage::new()
+        .content("This button have expired, please search again.")
+        .ephemeral(true)
+}
@@ -0,0 +1,124 @@
+//! Implementation for the game result ladder.
+//!
+//! Players report their game results and each guild keep an Elo rating for every player. Results
+//! can be reported under a guild [`Format`](crate::formats::Format) so each format have its own
+//! ladder, otherwise they go to the open ladder. The ratings are save to [`LADDER_FILE_PATH`].
+
+use std::{cmp::Reverse, collections::HashMap};
+
+use poise::serenity_prelude::{GuildId, UserId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    done,
+    shard::{read_data, write_data},
+    Color, LADDER,
+};
+
+/// Location of the ladder file.
+pub const LADDER_FILE_PATH: &str = "./ladder.bin";
+
+/// The rating a new player start with.
+const START_RATING: i32 = 1000;
+
+/// How much a single game can change the rating.
+const K_FACTOR: f64 = 32.;
+
+/// The ladder name for results that are not under any format.
+const OPEN_LADDER: &str = "";
+
+/// The rating and record of a player.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rating {
+    /// The Elo rating of the player.
+    pub elo: i32,
+    /// How many games the player won.
+    pub wins: u32,
+    /// How many games the player lost.
+    pub losses: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            elo: START_RATING,
+            wins: 0,
+            losses: 0,
+        }
+    }
+}
+
+/// Type alias for the ladder table, guild id to format name to user id to rating.
+pub type Ladder = HashMap<u64, HashMap<String, HashMap<u64, Rating>>>;
+
+/// Load the ladder from the ladder file.
+pub(crate) fn load_ladder() -> Ladder {
+    read_data(LADDER_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the ladder to the ladder file.
+///
+/// This lock [`LADDER`] so make sure you drop any guard before calling this.
+pub fn save_ladder() {
+    write_data(LADDER_FILE_PATH, &*LADDER);
+    done!("Ladder save successfully to {}", LADDER_FILE_PATH.green());
+}
+
+/// Calculate how much rating the winner take from the loser.
+#[allow(clippy::cast_possible_truncation)]
+pub fn elo_change(winner: i32, loser: i32) -> i32 {
+    let expected = 1. / (1. + 10f64.powf(f64::from(loser - winner) / 400.));
+    (K_FACTOR * (1. - expected)).round() as i32
+}
+
+/// Record a game result in a guild ladder then save the ladder.
+///
+/// Return the new rating of the winner and loser and the rating change.
+pub fn report_result(
+    guild_id: GuildId,
+    format: Option<&str>,
+    winner: UserId,
+    loser: UserId,
+) -> (Rating, Rating, i32) {
+    let result = {
+        let mut ladder = LADDER.lock().unwrap();
+        let ratings = ladder
+            .entry(guild_id.get())
+            .or_default()
+            .entry(format.unwrap_or(OPEN_LADDER).to_lowercase())
+            .or_default();
+
+        let mut win = ratings.get(&winner.get()).copied().unwrap_or_default();
+        let mut lose = ratings.get(&loser.get()).copied().unwrap_or_default();
+        let change = elo_change(win.elo, lose.elo);
+
+        win.elo += change;
+        win.wins += 1;
+        lose.elo -= change;
+        lose.losses += 1;
+
+        ratings.insert(winner.get(), win);
+        ratings.insert(loser.get(), lose);
+
+        (win, lose, change)
+    };
+
+    save_ladder();
+    result
+}
+
+/// Get the ratings of a guild ladder from highest to lowest.
+pub fn leaderboard(guild_id: GuildId, format: Option<&str>) -> Vec<(u64, Rating)> {
+    let mut ratings = LADDER
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .and_then(|l| l.get(&format.unwrap_or(OPEN_LADDER).to_lowercase()))
+        .map(|r| r.clone().into_iter().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    ratings.sort_by_key(|(_, rating)| Reverse(rating.elo));
+    ratings
+}
@@ -0,0 +1,247 @@
+//! Implementation for looking for game lobbies.
+//!
+//! Guilds can opt in by marking channels as LFG channels. When someone in those channels say they
+//! want to play or fight (see [`FIGHT_REGEX`](crate::FIGHT_REGEX)) the bot open a lobby with a join
+//! button. The first person to join get paired with the host in a new thread. Open lobbies are only
+//! kept in memory while the LFG channels are save to [`LFG_FILE_PATH`].
+
+use std::collections::HashMap;
+
+use poise::serenity_prelude::{
+    ButtonStyle::{Danger, Success},
+    ChannelId, ComponentInteraction, Context,
+    CreateActionRow::Buttons,
+    CreateButton,
+    CreateInteractionResponse::{Message as Response, UpdateMessage},
+    CreateInteractionResponseMessage, CreateMessage, CreateThread, GuildId, Message, MessageId,
+    UserId,
+};
+
+use crate::{
+    current_epoch, done,
+    shard::{read_data, write_data},
+    Color, Res, LFG_CHANNELS, LOBBIES,
+};
+
+/// Location of the LFG channels file.
+pub const LFG_FILE_PATH: &str = "./lfg.bin";
+
+/// How long a lobby stay open in milliseconds.
+const LOBBY_TIMEOUT: u128 = 30 * 60 * 1000;
+
+/// The room code template post in the match thread.
+const ROOM_TEMPLATE: &str = "Host a room in the game and post the room code here:
+```
+Room code:
+```";
+
+/// Type alias for the LFG channels table, guild id to channel ids.
+pub type LfgChannels = HashMap<u64, Vec<u64>>;
+
+/// Type alias for the open lobbies table, lobby message id to lobby.
+pub type Lobbies = HashMap<MessageId, Lobby>;
+
+/// An open lobby waiting for an opponent.
+#[derive(Debug, Clone)]
+pub struct Lobby {
+    /// The user who open the lobby.
+    pub host: UserId,
+    /// The name of the user who open the lobby.
+    pub host_name: String,
+    /// The channel the lobby is in.
+    pub channel_id: ChannelId,
+    /// When the lobby was open.
+    pub created: u128,
+}
+
+/// Load the LFG channels from the LFG file.
+pub(crate) fn load_lfg() -> LfgChannels {
+    read_data(LFG_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the LFG channels to the LFG file.
+///
+/// This lock [`LFG_CHANNELS`] so make sure you drop any guard before calling this.
+pub fn save_lfg() {
+    write_data(LFG_FILE_PATH, &*LFG_CHANNELS);
+    done!(
+        "LFG channels save successfully to {}",
+        LFG_FILE_PATH.green()
+    );
+}
+
+/// Mark or unmark a channel as a LFG channel.
+///
+/// Return `true` if the channel is now a LFG channel.
+pub fn toggle_lfg_channel(guild_id: GuildId, channel_id: ChannelId) -> bool {
+    let enabled = {
+        let mut lfg = LFG_CHANNELS.lock().unwrap();
+        let channels = lfg.entry(guild_id.get()).or_default();
+
+        if let Some(i) = channels.iter().position(|c| *c == channel_id.get()) {
+            channels.remove(i);
+            false
+        } else {
+            channels.push(channel_id.get());
+            true
+        }
+    };
+
+    save_lfg();
+    enabled
+}
+
+/// Check if a channel is a LFG channel.
+pub fn is_lfg_channel(guild_id: GuildId, channel_id: ChannelId) -> bool {
+    LFG_CHANNELS
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .is_some_and(|c| c.contains(&channel_id.get()))
+}
+
+/// Get the open lobbies in a guild channels, removing lobbies that have time out.
+pub fn open_lobbies(guild_id: GuildId) -> Vec<Lobby> {
+    let channels = LFG_CHANNELS
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut lobbies = LOBBIES.lock().unwrap();
+    let now = current_epoch();
+    lobbies.retain(|_, l| now - l.created < LOBBY_TIMEOUT);
+
+    lobbies
+        .values()
+        .filter(|l| channels.contains(&l.channel_id.get()))
+        .cloned()
+        .collect()
+}
+
+/// Open a lobby for the message author.
+pub async fn open_lobby(msg: &Message, ctx: &Context) -> Res {
+    let lobby = msg
+        .channel_id
+        .send_message(
+            ctx,
+            CreateMessage::new()
+                .content(format!("<@{}> is looking for a game!", msg.author.id))
+                .components(vec![Buttons(vec![
+                    CreateButton::new("lfg_join").style(Success).label("Join"),
+                    CreateButton::new("lfg_cancel")
+                        .style(Danger)
+                        .label("Cancel"),
+                ])]),
+        )
+        .await?;
+
+    LOBBIES.lock().unwrap().insert(
+        lobby.id,
+        Lobby {
+            host: msg.author.id,
+            host_name: msg.author.name.clone(),
+            channel_id: msg.channel_id,
+            created: current_epoch(),
+        },
+    );
+
+    Ok(())
+}
+
+/// Join a lobby, pairing the user with the host in a new thread.
+pub async fn join_lobby(interaction: &ComponentInteraction, ctx: &Context) -> Res {
+    let user = interaction.user.id;
+
+    // only take the lobby out if someone else is joining
+    let lobby = {
+        let mut lobbies = LOBBIES.lock().unwrap();
+        match lobbies.get(&interaction.message.id) {
+            Some(l) if l.host != user && current_epoch() - l.created < LOBBY_TIMEOUT => {
+                lobbies.remove(&interaction.message.id)
+            }
+            _ => None,
+        }
+    };
+
+    let Some(lobby) = lobby else {
+        return ephemeral(interaction, ctx, "You can't join this lobby.").await;
+    };
+
+    interaction
+        .create_response(
+            ctx,
+            UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(format!("<@{}> and <@{user}> are playing!", lobby.host))
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+
+    let thread = lobby
+        .channel_id
+        .create_thread_from_message(
+            ctx,
+            interaction.message.id,
+            CreateThread::new(format!("{} vs {}", lobby.host_name, interaction.user.name)),
+        )
+        .await?;
+
+    thread
+        .send_message(
+            ctx,
+            CreateMessage::new().content(format!("<@{}> <@{user}>\n{ROOM_TEMPLATE}", lobby.host)),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Cancel a lobby, only the host can do this.
+pub async fn cancel_lobby(interaction: &ComponentInteraction, ctx: &Context) -> Res {
+    let removed = {
+        let mut lobbies = LOBBIES.lock().unwrap();
+        match lobbies.get(&interaction.message.id) {
+            Some(l) if l.host == interaction.user.id => {
+                lobbies.remove(&interaction.message.id).is_some()
+            }
+            _ => false,
+        }
+    };
+
+    if !removed {
+        return ephemeral(interaction, ctx, "Only the host can cancel this lobby.").await;
+    }
+
+    interaction
+        .create_response(
+            ctx,
+            UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content("Lobby canceled.")
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn ephemeral(interaction: &ComponentInteraction, ctx: &Context, content: &str) -> Res {
+    interaction
+        .create_response(
+            ctx,
+            Response(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -3,24 +3,65 @@
 use std::{
     collections::HashMap,
     fmt::Debug,
-    fs::File,
+    fs,
     hash::{DefaultHasher, Hash, Hasher},
-    io::{Cursor, Read},
-    sync::Mutex,
+    io::Cursor,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
 };
 
-use image::GenericImageView;
-use isahc::ReadResponseExt;
+use image::{imageops::FilterType, DynamicImage, GenericImageView};
 use lazy_static::lazy_static;
 use magpie_engine::prelude::*;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tokio::task;
 
+#[cfg(feature = "server")]
+pub mod api;
+pub mod autoresponse;
+pub mod bulk;
+pub mod cache;
+pub mod changelog;
+pub mod config;
+pub mod cotd;
+pub mod deck;
+pub mod delimiter;
 pub mod emojis;
 pub mod engine;
+pub mod export;
+pub mod faq;
+pub mod favorites;
+pub mod formats;
+pub mod health;
+pub mod help;
+pub mod higherlower;
+pub mod history;
+pub mod homebrew;
+pub mod http;
+pub mod interaction;
+pub mod ladder;
+pub mod lfg;
+pub mod lint;
+pub mod locale;
+pub mod logging;
+pub mod overrides;
+pub mod pack;
+pub mod preview;
 pub mod query;
+pub mod report;
+pub mod rulings;
 pub mod search;
+pub mod setinfo;
+pub mod shard;
+pub mod sigil;
+pub mod sources;
+pub mod spoiler;
+pub mod stats;
+pub mod status;
+pub mod timeline;
+pub mod translation;
+pub mod trivia;
 
 mod message;
 pub use message::*;
@@ -37,20 +78,52 @@ pub use fuzzy::*;
 #[macro_use]
 pub mod r#macro;
 
+#[doc(hidden)]
+pub use tracing;
+
 use self::{
+    autoresponse::{AutoResponses, Cooldowns},
+    changelog::Changelog,
+    cotd::Cotd,
+    delimiter::{Delimiter, Delimiters},
+    emojis::EmojiModes,
     engine::{FilterExt, MagpieCosts, MagpieExt},
-    fetch::AugBranch,
+    faq::{FaqEntry, GuildFaq},
+    favorites::Favorites,
+    fetch::{AugBranch, DownloadLimits},
+    formats::Formats,
+    health::Health,
+    history::History,
+    homebrew::Homebrew,
+    interaction::InteractionStore,
+    ladder::Ladder,
+    lfg::{LfgChannels, Lobbies},
+    locale::{Languages, Locale},
+    rulings::Rulings,
+    search::{PortraitFilter, PortraitLru},
+    sources::AugSource,
+    spoiler::SpoilerSets,
+    stats::Stats,
+    status::Service,
+    timeline::Timeline,
+    translation::CardAlias,
+    trivia::TriviaScores,
 };
 
 // Type definition for stuff
 
 /// Custom data carry between commands.
-pub struct Data {}
+pub struct Data {
+    /// State of the stateful buttons.
+    pub interactions: InteractionStore,
+}
 
 impl Data {
     /// Make a new instance of [`Data`]
     pub fn new() -> Self {
-        Data {}
+        Data {
+            interactions: InteractionStore::new(),
+        }
     }
 }
 
@@ -75,7 +148,7 @@ pub type Set = magpie_engine::Set<MagpieExt, MagpieCosts>;
 /// Filters type alias
 pub type Filters = magpie_engine::prelude::Filters<MagpieExt, MagpieCosts, FilterExt>;
 
-/// Type alias for caches
+/// Type alias for caches, card id to cache data.
 pub type Cache = HashMap<u64, CacheData>;
 
 /// The caches data.
@@ -87,25 +160,63 @@ pub struct CacheData {
     pub attachment_id: u64,
     /// The expire date of the portrait cache.
     pub expire_date: u64,
+    /// Hash of the portrait url the cache was made from, the cache is stale if it change.
+    pub portrait: u64,
 }
 
 /// Location of the cache file.
-pub const CACHE_FILE_PATH: &str = "./cache.bin";
+pub const CACHE_FILE_PATH: &str = "./portrait_cache.bin";
+
+/// Location of the old cache file that was key by portrait hash, it is migrate on first load.
+pub const OLD_CACHE_FILE_PATH: &str = "./cache.bin";
 
 lazy_static! {
-    /// The regex use to match for general search.
-    pub static ref SEARCH_REGEX: Regex = Regex::new(r"(\S*)\[\[(.*?)\]\]") .unwrap_or_die("Cannot compiling search regex fails");
+    /// The regex use to match for general search with each delimiter.
+    pub static ref SEARCH_REGEXES: HashMap<Delimiter, Regex> = delimiter::search_regexes();
+    /// The regex use to match the code blocks, inline code, spoilers and quotes that are not search.
+    pub static ref IGNORE_REGEX: Regex = Regex::new(r"(?s)```.*?```|`[^`\n]+`|\|\|.*?\|\||(?m:^>>> ).*|(?m:^> [^\n]*)").unwrap_or_die("Cannot compile ignore regex");
     /// The regex use to match cache attachment link.
-    pub static ref CACHE_REGEX: Regex = Regex::new(r"(\d+)\/(\d+)\/(\d+)\.png\?ex=(\w+)") .unwrap_or_die("Cannot compiling cache regex fails");
+    pub static ref CACHE_REGEX: Regex = Regex::new(r"(\d+)\/(\d+)\/(\d+)-(\d+)\.png\?ex=(\w+)") .unwrap_or_die("Cannot compiling cache regex fails");
     /// The regex use to match message and tokenize them
     pub static ref QUERY_REGEX: Regex = Regex::new(r#"(?:"(.+)")|([-\w]+)|([^\s\w"-]*)"#) .unwrap_or_die("Cannot compile query regex");
-    /// The regex use to match cost value in query
-    pub static ref COST_REGEX: Regex = Regex::new(r"(-?\d+)?([a-zA-Z])").unwrap_or_die("Cannot compile query regex");
+    /// The regex use to match custom emoji and get their name and id
+    pub static ref EMOJI_REGEX: Regex = Regex::new(r"<a?:(\w+):(\d+)>").unwrap_or_die("Cannot compile emoji regex");
+    /// The regex use to match the format keyword in query
+    pub static ref FORMAT_REGEX: Regex = Regex::new(r"format:(\w+)").unwrap_or_die("Cannot compile format regex");
+    /// The regex use to match the chart keyword in query
+    pub static ref CHART_REGEX: Regex = Regex::new(r"chart:(\w+)").unwrap_or_die("Cannot compile chart regex");
+    /// The regex use to match ansi color code in json log
+    pub static ref ANSI_REGEX: Regex = Regex::new(r"\\u001b\[[0-9;]*m").unwrap_or_die("Cannot compile ansi regex");
     /// The regex use to detech if a messagae asking for a game
     pub static ref FIGHT_REGEX: Regex = Regex::new(r"wants? to (?:play|fight)").unwrap_or_die("Cannot compile asking for fight regex");
 
-    /// Collection of all set magpie use
-    pub static ref SETS: Mutex<HashMap<&'static str, Set>> = Mutex::new(load_set());
+    /// Extra Augmented sheets from the environment, they must be load before the sets
+    pub static ref AUG_SOURCES: Vec<AugSource> = sources::load_aug_sources();
+    /// Resize filter of the sets that do not use the default
+    pub static ref PORTRAIT_FILTERS: HashMap<&'static str, PortraitFilter> = search::load_portrait_filters();
+    /// Timeout, size and content type limits of the portrait downloads
+    pub static ref PORTRAIT_LIMITS: DownloadLimits = search::load_portrait_limits();
+    /// Recently generated portraits
+    pub static ref PORTRAITS: Mutex<PortraitLru> = Mutex::new(PortraitLru::default());
+    /// Services to probe with `/status`
+    pub static ref STATUS_SERVICES: Vec<Service> = status::load_services();
+
+    /// Collection of all set magpie use, they are only read after loading so every shard can
+    /// search at the same time. The sets are share so query results can keep them alive after the
+    /// lock is release
+    pub static ref SETS: RwLock<HashMap<&'static str, Arc<Set>>> = RwLock::new(load_set());
+    /// Health state of the bot
+    pub static ref HEALTH: Mutex<Health> = Mutex::new(Health::new());
+
+    /// Portrait show while the real portraits of a search are generated, a single pixel the
+    /// color of Discord embeds
+    pub static ref PLACEHOLDER_PORTRAIT: Vec<u8> = {
+        let mut out = vec![];
+        image::RgbaImage::from_pixel(1, 1, image::Rgba([43, 45, 49, 255]))
+            .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+            .expect("Encode placeholder fails");
+        out
+    };
 
     /// Debug card use to test rendering
     pub static ref DEBUG_CARD: Card = Card {
@@ -116,6 +227,7 @@ lazy_static! {
 
         rarity: Rarity::RARE,
         temple: Temple::ARTISTRY,
+        other_temples: vec![],
         tribes: Some("Big Green Mother".to_string()),
 
         attack: Attack::Num(420),
@@ -161,14 +273,79 @@ lazy_static! {
             "NEW_DATA".to_owned(),
             "ANCIENT_DATA".to_owned(),
         ],
+        lower: LowerText::default(),
         extra: MagpieExt {
-            artist: String::from("artist")
+            artist: String::from("artist"),
+            errata: None,
+            rulings: vec![],
         },
     };
 
     /// Portrait Caches to save times on image processing
     pub static ref CACHE: Mutex<HashMap<u64, CacheData>> = load_cache();
 
+    /// Formats defined by each guild
+    pub static ref FORMATS: Mutex<Formats> = formats::load_formats();
+
+    /// Changelog channel of each guild
+    pub static ref CHANGELOG: Mutex<Changelog> = Mutex::new(changelog::load_changelog());
+
+    /// Homebrew set of each guild
+    pub static ref HOMEBREW: Mutex<Homebrew> = Mutex::new(homebrew::load_homebrew());
+
+    /// Card of the day config of each guild
+    pub static ref COTD: Mutex<Cotd> = Mutex::new(cotd::load_cotd());
+
+    /// Looking for game channels of each guild
+    pub static ref LFG_CHANNELS: Mutex<LfgChannels> = Mutex::new(lfg::load_lfg());
+    /// Open looking for game lobbies
+    pub static ref LOBBIES: Mutex<Lobbies> = Mutex::new(HashMap::new());
+
+    /// Game result ladder of each guild
+    pub static ref LADDER: Mutex<Ladder> = Mutex::new(ladder::load_ladder());
+
+    /// Usage statistics of the bot
+    pub static ref STATS: Mutex<Stats> = Mutex::new(stats::load_stats());
+
+    /// Trivia scores of each guild
+    pub static ref TRIVIA_SCORES: Mutex<TriviaScores> = Mutex::new(trivia::load_scores());
+
+    /// Favorite cards of each user
+    pub static ref FAVORITES: Mutex<Favorites> = Mutex::new(favorites::load_favorites());
+
+    /// Search history of each user that opt-in
+    pub static ref HISTORY: Mutex<History> = Mutex::new(history::load_history());
+
+    /// FAQ answers for every guild
+    pub static ref FAQ: Vec<FaqEntry> = faq::load_faq();
+    /// FAQ answers added by each guild
+    pub static ref GUILD_FAQ: Mutex<GuildFaq> = Mutex::new(faq::load_guild_faq());
+
+    /// Changes of every card found by the set refresh
+    pub static ref TIMELINE: Mutex<Timeline> = Mutex::new(timeline::load_timeline());
+
+    /// Rulings added by the judges
+    pub static ref RULINGS: Mutex<Rulings> = Mutex::new(rulings::load_rulings());
+
+    /// Bundled locales, language code to locale
+    pub static ref LOCALES: HashMap<&'static str, Locale> = locale::load_locales();
+    /// Language of each guild
+    pub static ref LANGUAGES: Mutex<Languages> = Mutex::new(locale::load_languages());
+    /// Spoiler sets of each guild
+    pub static ref SPOILER_SETS: Mutex<SpoilerSets> = Mutex::new(spoiler::load_spoiler_sets());
+    /// Emoji mode of each guild
+    pub static ref EMOJI_MODES: Mutex<EmojiModes> = Mutex::new(emojis::load_emoji_modes());
+    /// Search delimiters of each guild
+    pub static ref DELIMITERS: Mutex<Delimiters> = Mutex::new(delimiter::load_delimiters());
+
+    /// Localized card names from every translation table
+    pub static ref TRANSLATIONS: Vec<CardAlias> = translation::load_translations();
+
+    /// Auto responder rules of each guild
+    pub static ref AUTORESPONSES: Mutex<AutoResponses> = Mutex::new(autoresponse::load_autoresponses());
+    /// When each auto responder rule last trigger
+    pub static ref AUTORESPONSE_COOLDOWNS: Mutex<Cooldowns> = Mutex::new(HashMap::new());
+
     /// List of response that ping will return
     pub static ref PING_RESPONSE: [&'static str;16] = [
         "o jan Mike. sina toki la sina lape suli lon luka tenpo sike. mi mute li lukin e sin nasin. o pini lape",
@@ -190,8 +367,12 @@ lazy_static! {
     ];
 }
 
-fn load_set() -> HashMap<&'static str, Set> {
-    set_map! {
+/// Fetch every set magpie use, sets that fail to fetch are map to the error message.
+///
+/// The errata overrides and the local rulings are apply to every set that fetch successfully and
+/// the result of each set is record in the health state so failed sets can be retry later.
+pub fn fetch_sets() -> HashMap<&'static str, Result<Set, String>> {
+    let mut sets = set_map! {
         standard (std) => "https://raw.githubusercontent.com/107zxz/inscr-onln-ruleset/main/standard.json",
         eternal (ete) => "https://raw.githubusercontent.com/EternalHours/EternalFormat/main/IMF_Eternal.json",
         egg (egg) => "https://raw.githubusercontent.com/senor-huevo/Mr.Egg-s-Goofy/main/Mr.Egg's%20Goofy.json",
@@ -200,28 +381,57 @@ fn load_set() -> HashMap<&'static str, Set> {
         aug_main (Aug) => fetch_aug_set(AugBranch::Main),
         descryption (des) => fetch_desc_set(),
         custom_tcg (cti) => fetch_cti_set(),
+    };
+
+    for (code, set) in sources::fetch_sources() {
+        if sets.contains_key(code) {
+            error!("Custom aug set {} is already a built in set", code.red());
+            continue;
+        }
+        sets.insert(code, set);
     }
-}
 
-fn load_cache() -> Mutex<HashMap<u64, CacheData>> {
-    let bytes = task::block_in_place(|| {
-        let mut f = File::open(CACHE_FILE_PATH)
-            .unwrap_or_else(|_| File::create_new(CACHE_FILE_PATH).unwrap());
+    for (code, set) in &mut sets {
+        match set {
+            Ok(set) => {
+                overrides::apply_overrides(code, set);
+                rulings::apply_rulings(code, set);
+                health::mark_set_result(code, None);
+            }
+            Err(err) => health::mark_set_result(code, Some(err)),
+        }
+    }
 
-        let mut buf = vec![
-            0;
-            f.metadata()
-                .expect("Unable to get cache file metadata")
-                .len()
-                .try_into()
-                .expect("File len data been truncated")
-        ];
+    sets
+}
+
+/// Load every set, sets that fail are skip and retry later by the background refresh.
+fn load_set() -> HashMap<&'static str, Arc<Set>> {
+    let sets = fetch_sets()
+        .into_iter()
+        .filter_map(|(code, set)| match set {
+            Ok(set) => Some((code, Arc::new(set))),
+            Err(err) => {
+                error!(
+                    "{} set {}, it will be retry later: {err}",
+                    "SKIPPING".red(),
+                    code.yellow()
+                );
+                None
+            }
+        })
+        .collect();
 
-        f.read_exact(&mut buf).expect("Buffer overflow");
+    health::mark_sets_loaded();
+    sets
+}
 
-        buf
-    });
+fn load_cache() -> Mutex<HashMap<u64, CacheData>> {
+    if !Path::new(CACHE_FILE_PATH).exists() && Path::new(OLD_CACHE_FILE_PATH).exists() {
+        return Mutex::new(migrate_cache());
+    }
 
+    let bytes = task::block_in_place(|| shard::read_data(CACHE_FILE_PATH)).unwrap_or_default();
     if bytes.is_empty() {
         return Mutex::new(HashMap::new());
     }
@@ -230,17 +440,65 @@ fn load_cache() -> Mutex<HashMap<u64, CacheData>> {
     t
 }
 
+/// Migrate the old cache file to be key by card id.
+///
+/// Old caches are key by portrait hash so they are match to the cards that currently have that
+/// portrait, caches that match no card are drop.
+fn migrate_cache() -> Cache {
+    #[derive(Deserialize)]
+    struct OldCacheData {
+        channel_id: u64,
+        attachment_id: u64,
+        expire_date: u64,
+    }
+
+    info!(
+        "Migrating old caches from {}...",
+        OLD_CACHE_FILE_PATH.green()
+    );
+
+    let old: HashMap<u64, OldCacheData> = fs::read(OLD_CACHE_FILE_PATH)
+        .ok()
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default();
+
+    let mut cache = Cache::new();
+    for card in SETS.read().unwrap().values().flat_map(|s| &s.cards) {
+        let portrait = hash_card_url(card);
+        if let Some(old) = old.get(&portrait) {
+            cache.insert(
+                card.id(),
+                CacheData {
+                    channel_id: old.channel_id,
+                    attachment_id: old.attachment_id,
+                    expire_date: old.expire_date,
+                    portrait,
+                },
+            );
+        }
+    }
+
+    done!(
+        "Migrated {} out of {} old caches",
+        cache.len().green(),
+        old.len().green()
+    );
+    cache
+}
 
-/// Save the cache to the cache file.
+/// Save the cache to the cache file, see [`write_data`](shard::write_data).
 pub fn save_cache() {
-    bincode::serialize_into(
-        File::create(CACHE_FILE_PATH).expect("Cannot create cache file"),
-        &*CACHE,
-    )
-    .unwrap();
+    shard::write_data(CACHE_FILE_PATH, &*CACHE);
     done!("Caches save successfully to {}", CACHE_FILE_PATH.green());
 }
 
+/// The portrait file name of a card, the card id then the portrait hash.
+///
+/// The portrait hash is in the name so the cache can tell when the portrait change.
+fn portrait_filename(card: &Card) -> String {
+    format!("{}-{}.png", card.id(), hash_card_url(card))
+}
+
 /// Hash a card url. Just a wrapper around DefaultHasher.
 fn hash_card_url(card: &Card) -> u64 {
     let mut hasher = DefaultHasher::new();
@@ -248,36 +506,37 @@ fn hash_card_url(card: &Card) -> u64 {
     hasher.finish()
 }
 
-fn resize_img(img: &[u8], scale: u32) -> Vec<u8> {
+fn resize_img(img: &[u8], scale: u32, filter: FilterType) -> Vec<u8> {
     task::block_in_place(|| {
-        if img.is_empty() {
+        // the bytes can still be a broken image, the caller fallback on an empty portrait
+        let Ok(t) = image::load_from_memory(img) else {
             return Vec::new();
-        }
-        let t = image::load_from_memory(img).expect("Decode image fails");
-        let (w, h) = t.dimensions();
-        let mut out = vec![];
-        t.resize_exact(w * scale, h * scale, image::imageops::Nearest)
-            .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
-            .expect("Resize fails");
-        out
+        };
+        scale_img(&t, scale, filter)
     })
 }
 
+/// Scale an already decoded image and encode it to PNG, so it does not need to be decode again.
+fn scale_img(img: &DynamicImage, scale: u32, filter: FilterType) -> Vec<u8> {
+    let (w, h) = img.dimensions();
+    let mut out = vec![];
+    img.resize_exact(w * scale, h * scale, filter)
+        .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+        .expect("Resize fails");
+    out
+}
 
-/// Generate card embed from a card data.
+/// Download a card portrait with the engine http client, return nothing if it cannot be download.
+///
+/// The download is limit by [`PORTRAIT_LIMITS`] so a slow host or a huge file cannot hold up a
+/// search.
 pub fn get_portrait(url: &str) -> Vec<u8> {
-    match isahc::get(url) {
-        Ok(t) if t.status().is_success() => t,
-        _ => {
-            error!("Cannot reach url: {url}");
-            return Vec::new();
-        }
-    }
-    .bytes()
-    .unwrap_or_else(|_| {
-        error!("Cannot decode card portrait from url: {url}");
-        Vec::new()
-    })
+    fetch::client()
+        .get_limited(url, &PORTRAIT_LIMITS)
+        .unwrap_or_else(|err| {
+            error!("Cannot get card portrait from url {url} due to: {err}");
+            Vec::new()
+        })
 }
 
 /// Return the current epoch
@@ -286,4 +545,4 @@ pub fn current_epoch() -> u128 {
         .duration_since(std::time::UNIX_EPOCH)
         .expect("Are you Marty McFly? Return to the correct timeline")
         .as_millis()
-}
\ No newline at end of file
+}
@@ -0,0 +1,89 @@
+//! Implementation for the set lint report.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use poise::serenity_prelude::{colours::roles, CreateAttachment, CreateEmbed};
+
+use crate::{MessageAdapter, SETS};
+
+/// The file name of the full report attachment.
+const REPORT_NAME: &str = "lint.txt";
+
+/// How many issues of each kind to show in the embed, the full list is in the attachment.
+const MAX_SHOWN: usize = 10;
+
+/// Check a set for data problems and generate the lint report message.
+///
+/// The embed show a summary of each kind of issue and the full list is attached as a text file
+/// when it does not fit.
+pub fn lint_message(set_code: &str) -> MessageAdapter {
+    let sets = SETS.read().unwrap();
+    let Some(set) = sets.get(set_code) else {
+        return MessageAdapter::new().embeds(vec![CreateEmbed::new()
+            .color(roles::RED)
+            .title("Lint Error")
+            .description(format!("Unknown set code `{set_code}`"))]);
+    };
+
+    let issues = set.validate();
+
+    if issues.is_empty() {
+        return MessageAdapter::new().embeds(vec![CreateEmbed::new()
+            .color(roles::GREEN)
+            .title(format!("Lint Report ({})", set.name))
+            .description(format!("No issues found in {} cards.", set.cards.len()))]);
+    }
+
+    let mut kinds: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for issue in &issues {
+        kinds
+            .entry(issue.kind())
+            .or_default()
+            .push(issue.to_string());
+    }
+
+    let mut embed = CreateEmbed::new()
+        .color(roles::ORANGE)
+        .title(format!("Lint Report ({})", set.name))
+        .description(format!(
+            "Found {} issues in {} cards.",
+            issues.len(),
+            set.cards.len()
+        ));
+
+    let mut truncated = false;
+    for (kind, lines) in &kinds {
+        let mut value = lines
+            .iter()
+            .take(MAX_SHOWN)
+            .map(|l| format!("- {l}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if lines.len() > MAX_SHOWN {
+            truncated = true;
+            write!(value, "\n...and {} more", lines.len() - MAX_SHOWN).unwrap();
+        }
+
+        // embed field values are limited to 1024 characters
+        if value.chars().count() > 1024 {
+            truncated = true;
+            value = value.chars().take(1000).collect::<String>() + "...";
+        }
+
+        embed = embed.field(format!("{kind} ({})", lines.len()), value, false);
+    }
+
+    if !truncated {
+        return MessageAdapter::new().embeds(vec![embed]);
+    }
+
+    let report = issues.iter().fold(String::new(), |mut out, issue| {
+        writeln!(out, "[{}] {issue}", issue.kind()).unwrap();
+        out
+    });
+
+    MessageAdapter::new()
+        .embeds(vec![embed])
+        .attachments(vec![CreateAttachment::bytes(report, REPORT_NAME)])
+}
@@ -0,0 +1,164 @@
+//! Implementation for the localization of bot responses.
+//!
+//! User facing text is look up by key, like `search.not_found`, in the locale files under
+//! `locales/`. The files are bundle with the bot and every key must be in `en.toml`, other locales
+//! fall back to English for the keys they don't have. Use the [`tr`](crate::tr) macro to look up
+//! a text.
+//!
+//! Each guild can pick its language with `/language`, the choice is save to
+//! [`LANGUAGES_FILE_PATH`].
+
+use std::collections::HashMap;
+
+use poise::serenity_prelude::GuildId;
+
+use crate::{
+    done, error,
+    shard::{read_data, write_data},
+    Color, LANGUAGES, LOCALES,
+};
+
+/// Location of the guild languages file.
+pub const LANGUAGES_FILE_PATH: &str = "./languages.bin";
+
+/// The language use when a guild didn't pick one.
+pub const DEFAULT_LANG: &str = "en";
+
+/// The bundled locale files, language code to file content.
+const BUNDLED: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.toml")),
+    ("es", include_str!("../locales/es.toml")),
+];
+
+/// Type alias for a locale, key to text template.
+pub type Locale = HashMap<String, String>;
+
+/// Type alias for the guild languages table, guild id to language code.
+pub type Languages = HashMap<u64, String>;
+
+/// Flatten a toml table into dotted keys.
+fn flatten(prefix: &str, table: toml::Table, out: &mut Locale) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        match value {
+            toml::Value::Table(t) => flatten(&key, t, out),
+            toml::Value::String(s) => {
+                out.insert(key, s);
+            }
+            v => {
+                out.insert(key, v.to_string());
+            }
+        }
+    }
+}
+
+/// Load the bundled locales.
+pub(crate) fn load_locales() -> HashMap<&'static str, Locale> {
+    BUNDLED
+        .iter()
+        .filter_map(|(code, content)| match content.parse::<toml::Table>() {
+            Ok(table) => {
+                let mut locale = Locale::new();
+                flatten("", table, &mut locale);
+                Some((*code, locale))
+            }
+            Err(err) => {
+                error!("Cannot parse locale {code} due to: {err}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Load the guild languages from the languages file.
+pub(crate) fn load_languages() -> Languages {
+    read_data(LANGUAGES_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the guild languages to the languages file.
+///
+/// This lock [`LANGUAGES`] so make sure you drop any guard before calling this.
+pub fn save_languages() {
+    write_data(LANGUAGES_FILE_PATH, &*LANGUAGES);
+    done!(
+        "Languages save successfully to {}",
+        LANGUAGES_FILE_PATH.green()
+    );
+}
+
+/// Get the language of a guild, this is [`DEFAULT_LANG`] outside of guilds.
+pub fn guild_lang(guild_id: Option<GuildId>) -> &'static str {
+    let Some(guild_id) = guild_id else {
+        return DEFAULT_LANG;
+    };
+
+    LANGUAGES
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .and_then(|code| LOCALES.get_key_value(code.as_str()))
+        .map_or(DEFAULT_LANG, |(code, _)| code)
+}
+
+/// Set the language of a guild.
+///
+/// Return an error message if the language is not available.
+pub fn set_guild_lang(guild_id: GuildId, code: &str) -> Result<(), String> {
+    let code = code.trim().to_lowercase();
+    if !LOCALES.contains_key(code.as_str()) {
+        return Err(text_with(
+            DEFAULT_LANG,
+            "language.unknown",
+            &[("code", code), ("languages", available_languages())],
+        ));
+    }
+
+    LANGUAGES.lock().unwrap().insert(guild_id.get(), code);
+    save_languages();
+
+    Ok(())
+}
+
+/// List the available languages with their code.
+pub fn available_languages() -> String {
+    let mut langs = LOCALES
+        .keys()
+        .map(|code| format!("{} (`{code}`)", text(code, "name")))
+        .collect::<Vec<_>>();
+    langs.sort_unstable();
+    langs.join(", ")
+}
+
+/// Get the name of a language in that language, or the uppercase code if there is no locale for
+/// it.
+pub fn language_name(code: &str) -> String {
+    LOCALES
+        .get(code)
+        .and_then(|l| l.get("name"))
+        .cloned()
+        .unwrap_or_else(|| code.to_uppercase())
+}
+
+/// Look up a text, falling back to English and then to the key itself.
+pub fn text(lang: &str, key: &'static str) -> &'static str {
+    LOCALES
+        .get(lang)
+        .and_then(|l| l.get(key))
+        .or_else(|| LOCALES.get(DEFAULT_LANG).and_then(|l| l.get(key)))
+        .map_or(key, String::as_str)
+}
+
+/// Look up a text and replace each `{name}` with its value.
+pub fn text_with(lang: &str, key: &'static str, args: &[(&str, String)]) -> String {
+    args.iter()
+        .fold(text(lang, key).to_owned(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+}
@@ -0,0 +1,168 @@
+//! Implementation for the logging layer.
+//!
+//! The [`info!`](crate::info), [`done!`](crate::done) and [`error!`](crate::error) macros emit
+//! [`tracing`] events. Those events are print to the console in the same colored format as before
+//! and also write as JSON lines to a daily rotating file in [`LOG_DIR`] along with the span they
+//! happen in, so a search can be trace back to the guild, user and search term.
+
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+use chrono::Local;
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_appender::{non_blocking::NonBlocking, non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    fmt::{format, FmtContext, FormatEvent, FormatFields, MakeWriter},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    Layer,
+};
+
+use crate::{Color, ANSI_REGEX};
+
+/// Directory to write the log files to.
+pub const LOG_DIR: &str = "./logs";
+
+/// Prefix of the log files, the date is append to this.
+const LOG_FILE: &str = "magpie_tutor.log";
+
+/// Start the logging, this need to be call before anything get log.
+///
+/// The returned guard flush the log file when dropped so keep it alive until the bot exit.
+pub fn init_logging() -> WorkerGuard {
+    let (file, guard) = tracing_appender::non_blocking(rolling::daily(LOG_DIR, LOG_FILE));
+
+    // silent the noisy library logs unless something goes wrong
+    let console_filter = Targets::new()
+        .with_target("magpie_tutor", Level::INFO)
+        .with_default(LevelFilter::WARN);
+    let file_filter = Targets::new()
+        .with_target("magpie_tutor", Level::DEBUG)
+        .with_default(LevelFilter::INFO);
+
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .event_format(ConsoleFormat)
+                .with_filter(console_filter),
+        )
+        .with(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_writer(StripAnsi(file))
+                .with_filter(file_filter),
+        )
+        .init();
+
+    guard
+}
+
+/// Start the logging for command line tools.
+///
+/// Only the console format is use and it is print to stderr so the output can be pipe.
+pub fn init_cli_logging() {
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .event_format(ConsoleFormat)
+                .with_writer(io::stderr)
+                .with_filter(
+                    Targets::new()
+                        .with_target("magpie_tutor", Level::INFO)
+                        .with_default(LevelFilter::WARN),
+                ),
+        )
+        .init();
+}
+
+/// Console format matching the old println logs, `[ time | level ] message`.
+struct ConsoleFormat;
+
+impl<S, N> FormatEvent<S, N> for ConsoleFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _: &FmtContext<'_, S, N>,
+        mut writer: format::Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut visitor = ConsoleVisitor::default();
+        event.record(&mut visitor);
+
+        let level = match *event.metadata().level() {
+            _ if visitor.done => "done".green(),
+            Level::ERROR => "error".red(),
+            Level::WARN => "warn".yellow(),
+            Level::INFO => "info".blue(),
+            _ => "debug".magenta(),
+        };
+
+        writeln!(
+            writer,
+            "[ {} | {} ] {}",
+            Local::now().format("%H:%M:%S").yellow(),
+            level,
+            visitor.message
+        )
+    }
+}
+
+/// Collect the message and the done flag of an event.
+#[derive(Default)]
+struct ConsoleVisitor {
+    message: String,
+    done: bool,
+}
+
+impl Visit for ConsoleVisitor {
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        if field.name() == "done" {
+            self.done = value;
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        }
+    }
+}
+
+/// Writer that remove the color codes so the log file stay readable.
+struct StripAnsi(NonBlocking);
+
+impl<'a> MakeWriter<'a> for StripAnsi {
+    type Writer = StripAnsiWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        StripAnsiWriter(self.0.clone())
+    }
+}
+
+/// The writer made by [`StripAnsi`].
+struct StripAnsiWriter(NonBlocking);
+
+impl Write for StripAnsiWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let line = String::from_utf8_lossy(buf);
+        self.0
+            .write_all(ANSI_REGEX.replace_all(&line, "").as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
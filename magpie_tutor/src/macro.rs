@@ -1,65 +1,27 @@
 //! Some helper macro
 
-/// Info print.
+/// Info log, see [`logging`](crate::logging) for where it go.
 #[macro_export]
 macro_rules! info {
-    ($string:literal) => {
-        println!(
-            "[ {} | {} ] {}",
-            $crate::Color::yellow(&chrono::Local::now().format("%H:%M:%S")),
-            $crate::Color::blue("info"),
-            format!($string)
-        )
-    };
-    ($string:literal,$($args:expr),*) => {
-        println!(
-            "[ {} | {} ] {}",
-            $crate::Color::yellow(&chrono::Local::now().format("%H:%M:%S")),
-            $crate::Color::blue("info"),
-            format!($string, $($args,)*)
-        )
+    ($string:literal $(,$args:expr)* $(,)?) => {
+        $crate::tracing::info!($string $(,$args)*)
     };
 }
 
-/// Error print.
+/// Error log, see [`logging`](crate::logging) for where it go.
 #[macro_export]
 macro_rules! error {
-    ($string:literal) => {
-        println!(
-            "[ {} | {} ] {}",
-            $crate::Color::yellow(&chrono::Local::now().format("%H:%M:%S")),
-            $crate::Color::red("error"),
-            format!($string)
-        )
-    };
-    ($string:literal,$($args:expr),*) => {
-        println!(
-            "[ {} | {} ] {}",
-            $crate::Color::yellow(&chrono::Local::now().format("%H:%M:%S")),
-            $crate::Color::red("error"),
-            format!($string, $($args,)*)
-        )
+    ($string:literal $(,$args:expr)* $(,)?) => {
+        $crate::tracing::error!($string $(,$args)*)
     };
 }
 
-/// Done print.
+/// Done log, an info log mark as done, see [`logging`](crate::logging) for where it go.
 #[macro_export]
 macro_rules! done {
-    ($string:literal) => {
-        println!(
-            "[ {} | {} ] {}",
-            $crate::Color::yellow(&chrono::Local::now().format("%H:%M:%S")),
-            $crate::Color::green("done"),
-            format!($string)
-        );
-    };
-    ($string:literal,$($args:expr),*) => {
+    ($string:literal $(,$args:expr)* $(,)?) => {
         {
-            println!(
-                "[ {} | {} ] {}",
-                $crate::Color::yellow(&chrono::Local::now().format("%H:%M:%S")),
-                $crate::Color::green("done"), format!($string, $($args,)*)
-            );
+            $crate::tracing::info!(done = true, $string $(,$args)*);
         }
     };
 }
@@ -93,6 +55,25 @@ macro_rules! debug {
     }
 }
 
+/// Look up a localized text, see [`locale`](crate::locale).
+///
+/// Without arguments this return the `&'static str` template, with arguments each `{name}` in the
+/// template is replace and a [`String`] is return.
+///
+/// ```ignore
+/// tr!(lang, "search.retry");
+/// tr!(lang, "search.completed", time = format!("{latency:.1?}"));
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($lang:expr, $key:literal) => {
+        $crate::locale::text($lang, $key)
+    };
+    ($lang:expr, $key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::locale::text_with($lang, $key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
 /// Helper to create hashmap.
 #[macro_export]
 macro_rules! hashmap {
@@ -107,7 +88,7 @@ macro_rules! hashmap {
     };
 }
 
-/// Helper to create set map.
+/// Helper to create set map, each set is fetch and map to the result of fetching it.
 #[macro_export]
 macro_rules! set_map {
     (
@@ -123,15 +104,17 @@ macro_rules! set_map {
                         $link,
                         SetCode::new(stringify!($code)).unwrap()
                     )
-                    .unwrap_or_die(&format!("Cannot process {} set", stringify!($name)))
-                    .upgrade();
-
-                    done!(
-                        "Finish fetching {} set with code {} in {}",
-                        $crate::Color::blue(stringify!($name)),
-                        $crate::Color::yellow(stringify!($code)),
-                        $crate::Color::green(&format!("{:.2?}", now.elapsed()))
-                    );
+                    .map(|s| s.upgrade())
+                    .map_err(|e| format!("Cannot process {} set: {e:?}", stringify!($name)));
+
+                    if t.is_ok() {
+                        done!(
+                            "Finish fetching {} set with code {} in {}",
+                            $crate::Color::blue(stringify!($name)),
+                            $crate::Color::yellow(stringify!($code)),
+                            $crate::Color::green(&format!("{:.2?}", now.elapsed()))
+                        );
+                    }
 
                     t
                 },
@@ -143,14 +126,18 @@ macro_rules! set_map {
                         $($func_arg,)*
                         SetCode::new(stringify!($key_code)).unwrap()
                     )
-                    .unwrap_or_die(&format!("Cannot process {} set", stringify!($key)))
-                    .upgrade();
-                    done!(
-                        "Finish fetching {} set with code {} in {}",
-                        $crate::Color::blue(stringify!($key)),
-                        $crate::Color::yellow(stringify!($key_code)),
-                        $crate::Color::green(&format!("{:.2?}", now.elapsed()))
-                    );
+                    .map(|s| s.upgrade())
+                    .map_err(|e| format!("Cannot process {} set: {e:?}", stringify!($key)));
+
+                    if t.is_ok() {
+                        done!(
+                            "Finish fetching {} set with code {} in {}",
+                            $crate::Color::blue(stringify!($key)),
+                            $crate::Color::yellow(stringify!($key_code)),
+                            $crate::Color::green(&format!("{:.2?}", now.elapsed()))
+                        );
+                    }
+
                     t
                 },
             )*
@@ -207,17 +194,27 @@ macro_rules! frameworks {
     (global: $($gb_cmd:expr),*; $(guild($g_id:literal): $($g_cmd:expr),*;)*---$rest:block) => {
         poise::Framework::builder()
             .options(poise::FrameworkOptions {
-                commands: vec![$($gb_cmd,)* $($($g_cmd,)*)*],
+                // push one by one, a command array this big is too large for the stack
+                commands: {
+                    let mut commands = vec![];
+                    $(commands.push($gb_cmd);)*
+                    $($(commands.push($g_cmd);)*)*
+                    commands
+                },
                 event_handler: |ctx, event, fw, data| Box::pin(handler(ctx, event, fw, data)),
+                on_error: |err| Box::pin($crate::report::on_error(err)),
                 ..Default::default()
             })
             .setup(|ctx, _ready, framework| {
                 Box::pin(async move {
                     info!("Refreshing commands...");
 
+                    let mut global = vec![];
+                    $(global.push($gb_cmd);)*
+
                     poise::builtins::register_globally(
                         ctx.http(),
-                        &[$($gb_cmd,)*]
+                        &global
                     )
                     .await?;
 
@@ -240,4 +237,4 @@ macro_rules! frameworks {
             })
             .build()
     };
-}
\ No newline at end of file
+}
@@ -2,11 +2,65 @@
 
 use std::panic::PanicInfo;
 
+use magpie_engine::fetch::{set_client, HttpClient};
 use magpie_tutor::{
-    done, error, frameworks, handler, info, CmdCtx, Color, Data, Res, CACHE, CACHE_FILE_PATH,
-    PING_RESPONSE, SETS,
+    autoresponse::{
+        add_rule, guild_rules, remove_rule, toggle_ignore, AutoResponse, TriggerKind,
+        AUTORESPONSE_FILE_PATH,
+    },
+    bulk::{bulk_lookup_message, BulkFormat, MAX_BULK_SIZE},
+    cache::{purge_cache, PurgeTarget},
+    changelog::{disable_changelog, enable_changelog, CHANGELOG_FILE_PATH},
+    cotd::{disable_cotd, enable_cotd, parse_time, COTD_FILE_PATH},
+    deck::deck_message,
+    delimiter::{guild_delimiter, set_guild_delimiter, Delimiter, DELIMITERS_FILE_PATH},
+    done,
+    emojis::{
+        guild_emoji_mode, load_emojis, plain_emojis, set_guild_emoji_mode, with_plain_emojis_sync,
+        EmojiMode, EMOJI_FILE_PATH, EMOJI_MODE_FILE_PATH,
+    },
+    error,
+    faq::{add_faq, guild_questions, remove_faq, FAQ_FILE_PATH, GUILD_FAQ_FILE_PATH},
+    favorites::{favorites_message, FAVORITES_FILE_PATH},
+    formats::{
+        get_format, guild_formats, insert_format, remove_format, update_format, Format,
+        FORMATS_FILE_PATH,
+    },
+    frameworks, handler,
+    health::{health_embed, start_server, HEALTH_ADDR_VAR},
+    help::{help_message, modifiers_text, SEARCH_PAGE},
+    higherlower::{start_message, Stat},
+    history::{history_message, toggle_history, HISTORY_FILE_PATH},
+    homebrew::{add_card, add_sigil, create_set, parse_costs, HomebrewCard, HOMEBREW_FILE_PATH},
+    info,
+    ladder::{leaderboard, report_result, LADDER_FILE_PATH},
+    lfg::{open_lobbies, toggle_lfg_channel, LFG_FILE_PATH},
+    lint::lint_message,
+    locale::{available_languages, guild_lang, set_guild_lang, LANGUAGES_FILE_PATH},
+    logging::init_logging,
+    pack::pack_message,
+    preview::{preview_message, MAX_PREVIEW_SIZE},
+    rulings::{add_ruling, card_rulings, remove_ruling, RULINGS_FILE_PATH},
+    search::default_set,
+    setinfo::setinfo_message,
+    shard::ShardConfig,
+    sigil::sigil_message,
+    spoiler::{guild_spoiler_sets, toggle_spoiler, SPOILER_FILE_PATH},
+    stats::{set_summary_channel, stats_embed, STATS_FILE_PATH},
+    status::{probe_services, status_embed},
+    timeline::TIMELINE_FILE_PATH,
+    tr,
+    translation::TRANSLATIONS_DIR,
+    trivia::{guild_scores, play_trivia, TRIVIA_FILE_PATH},
+    CmdCtx, Color, Data, Res, AUTORESPONSES, CACHE, CACHE_FILE_PATH, CHANGELOG, COTD, DELIMITERS,
+    EMOJI_MODES, FAQ, FAVORITES, FORMATS, GUILD_FAQ, HISTORY, HOMEBREW, LADDER, LANGUAGES,
+    LFG_CHANNELS, LOCALES, PING_RESPONSE, RULINGS, SETS, SPOILER_SETS, STATS, TIMELINE,
+    TRANSLATIONS, TRIVIA_SCORES,
+};
+use poise::serenity_prelude::{
+    Attachment, CacheHttp, ClientBuilder, CreateAllowedMentions, GatewayIntents, GuildChannel,
+    GuildId, Permissions, User,
 };
-use poise::serenity_prelude::{CacheHttp, ClientBuilder, GatewayIntents, GuildId};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 
@@ -18,72 +72,1387 @@ async fn test(ctx: CmdCtx<'_>) -> Res {
 }
 
 /// Show help on what and how to use Magpie Tutor.
-#[poise::command(slash_command)]
+#[poise::command(slash_command, ephemeral)]
 async fn help(ctx: CmdCtx<'_>) -> Res {
-    ctx.say(
-r#"
-You can use Magpie to look up a card infomation by surrounding the card name in `[[]]`. A few "modifiers" can be added in front of the `[[]]` to change the output.
+    ctx.send(help_message(SEARCH_PAGE, ctx.guild_id()).into())
+        .await?;
+
+    Ok(())
+}
+
+/// Show the lists of all support modifiers and set code.
+#[poise::command(slash_command)]
+async fn show_modifiers(ctx: CmdCtx<'_>) -> Res {
+    ctx.say(modifiers_text()).await?;
+
+    Ok(())
+}
+
+/// Show a summary of a deck from a IMF deck file or a deck list.
+#[poise::command(slash_command)]
+async fn deck(
+    ctx: CmdCtx<'_>,
+    #[description = "IMF exported deck file"] file: Option<Attachment>,
+    #[description = "Deck list or IMF deck json"] code: Option<String>,
+    #[description = "Set code to look the cards up in"] set: Option<String>,
+    #[description = "Server format to check the deck against"] format: Option<String>,
+) -> Res {
+    ctx.defer().await?;
+
+    let format = if let Some(name) = format {
+        let Some(format) = ctx.guild_id().and_then(|g| get_format(g, &name)) else {
+            ctx.say(format!("Unknown format `{name}`")).await?;
+            return Ok(());
+        };
+        Some(format)
+    } else {
+        None
+    };
 
-You can see these modifier by using the `/show-modifers` command. Set code are a special type of modifer that are 3 characters long and is at the end of the modifiers list and can be use to change the selected set.
+    let code = match (file, code) {
+        (Some(file), _) => String::from_utf8(file.download().await?)?,
+        (None, Some(code)) => code,
+        (None, None) => {
+            ctx.say("You need to give either a deck file or a deck list.")
+                .await?;
+            return Ok(());
+        }
+    };
 
-For example:
-- `[[stoat]]`: Look up the card name `stoat` using the server default set.
-- `egg[[warren]]`: Look up the card name `warren` using the `egg` set.
+    let set = set
+        .or_else(|| format.as_ref().and_then(|f| f.sets.first().cloned()))
+        .unwrap_or_else(|| default_set(ctx.guild_id()).to_owned());
 
-"#,
+    ctx.send(deck_message(&code, &set, format.as_ref()).into())
+        .await?;
+
+    Ok(())
+}
+
+/// Manage the formats of this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    subcommands(
+        "format_create",
+        "format_delete",
+        "format_ban",
+        "format_unban",
+        "format_limit"
     )
+)]
+#[allow(clippy::unused_async)]
+async fn format(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Create or replace a format.
+#[poise::command(slash_command, rename = "create")]
+async fn format_create(
+    ctx: CmdCtx<'_>,
+    #[description = "Name of the format, only letters, numbers and _"] name: String,
+    #[description = "Space separated set codes allowed in this format"] sets: String,
+    #[description = "If decks in this format need a side deck"] side_deck: Option<bool>,
+) -> Res {
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        ctx.say("Format name can only contain letters, numbers and `_`.")
+            .await?;
+        return Ok(());
+    }
+
+    let sets: Vec<String> = sets.split_whitespace().map(ToOwned::to_owned).collect();
+    let unknown = {
+        let g_sets = SETS.read().unwrap();
+        sets.iter()
+            .find(|s| !g_sets.contains_key(s.as_str()))
+            .cloned()
+    };
+
+    if let Some(code) = unknown {
+        ctx.say(format!("Unknown set code `{code}`")).await?;
+        return Ok(());
+    }
+    if sets.is_empty() {
+        ctx.say("A format need at least one set.").await?;
+        return Ok(());
+    }
+
+    let mut format = Format::new(sets);
+    format.ruleset.side_deck = side_deck.unwrap_or_default();
+
+    insert_format(ctx.guild_id().unwrap(), &name, format);
+    ctx.say(format!("Format `{name}` created.")).await?;
+
+    Ok(())
+}
+
+/// Delete a format.
+#[poise::command(slash_command, rename = "delete")]
+async fn format_delete(ctx: CmdCtx<'_>, #[description = "Name of the format"] name: String) -> Res {
+    ctx.say(if remove_format(ctx.guild_id().unwrap(), &name) {
+        format!("Format `{name}` deleted.")
+    } else {
+        format!("Unknown format `{name}`")
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Ban a card in a format.
+#[poise::command(slash_command, rename = "ban")]
+async fn format_ban(
+    ctx: CmdCtx<'_>,
+    #[description = "Name of the format"] name: String,
+    #[description = "Name of the card to ban"] card: String,
+) -> Res {
+    let updated = update_format(ctx.guild_id().unwrap(), &name, |f| {
+        if !f.ruleset.is_banned(&card) {
+            f.ruleset.banned.push(card.clone());
+        }
+    });
+
+    ctx.say(if updated {
+        format!("`{card}` is now banned in `{name}`.")
+    } else {
+        format!("Unknown format `{name}`")
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Unban a card in a format.
+#[poise::command(slash_command, rename = "unban")]
+async fn format_unban(
+    ctx: CmdCtx<'_>,
+    #[description = "Name of the format"] name: String,
+    #[description = "Name of the card to unban"] card: String,
+) -> Res {
+    let updated = update_format(ctx.guild_id().unwrap(), &name, |f| {
+        f.ruleset.banned.retain(|b| !b.eq_ignore_ascii_case(&card));
+    });
+
+    ctx.say(if updated {
+        format!("`{card}` is no longer banned in `{name}`.")
+    } else {
+        format!("Unknown format `{name}`")
+    })
     .await?;
 
     Ok(())
 }
 
-macro_rules! mod_help {
-    ($($code:ident: $code_desc:literal;)*---$($mod:literal: $desc:literal;)*) => {
-        concat!(
-            "# Set Codes\n",
-            $(concat!("- `", stringify!($code), "`: ", $code_desc, ".\n"),)*
-            "# Modifiers\n",
-            $(concat!("- `", $mod,"`: ", $desc, " ", ".\n"),)*
+/// Set the copies limit of a rarity in a format.
+#[poise::command(slash_command, rename = "limit")]
+async fn format_limit(
+    ctx: CmdCtx<'_>,
+    #[description = "Name of the format"] name: String,
+    #[description = "The rarity to limit"]
+    #[choices("common", "uncommon", "rare", "unique")]
+    rarity: &'static str,
+    #[description = "Maximum copies, leave empty to remove the limit"] copies: Option<usize>,
+) -> Res {
+    let updated = update_format(ctx.guild_id().unwrap(), &name, |f| {
+        let limit = match rarity {
+            "common" => &mut f.ruleset.common,
+            "uncommon" => &mut f.ruleset.uncommon,
+            "rare" => &mut f.ruleset.rare,
+            _ => &mut f.ruleset.unique,
+        };
+        *limit = copies;
+    });
+
+    ctx.say(match (updated, copies) {
+        (false, _) => format!("Unknown format `{name}`"),
+        (true, Some(c)) => format!("{rarity} cards in `{name}` are now limited to {c}."),
+        (true, None) => format!("{rarity} cards in `{name}` are no longer limited."),
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Show the formats of this server.
+#[poise::command(slash_command, guild_only)]
+async fn formats(ctx: CmdCtx<'_>) -> Res {
+    let formats = guild_formats(ctx.guild_id().unwrap());
+
+    if formats.is_empty() {
+        ctx.say("This server have no formats yet.").await?;
+        return Ok(());
+    }
+
+    let limit = |l: Option<usize>| l.map_or("no limit".to_owned(), |l| l.to_string());
+    let out = formats
+        .iter()
+        .map(|(name, Format { sets, ruleset })| {
+            format!(
+                "**{name}**: {}\n-# common {}, uncommon {}, rare {}, unique {}{}\n-# banned: {}",
+                sets.join(", "),
+                limit(ruleset.common),
+                limit(ruleset.uncommon),
+                limit(ruleset.rare),
+                limit(ruleset.unique),
+                if ruleset.side_deck { ", side deck" } else { "" },
+                if ruleset.banned.is_empty() {
+                    "none".to_owned()
+                } else {
+                    ruleset.banned.join(", ")
+                }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    ctx.say(out).await?;
+
+    Ok(())
+}
+
+/// Open random booster packs from a set, multiple packs make a sealed pool.
+#[poise::command(slash_command)]
+async fn pack(
+    ctx: CmdCtx<'_>,
+    #[description = "Set code to open the pack from"] set: Option<String>,
+    #[description = "How many packs to open"]
+    #[min = 1]
+    #[max = 5]
+    packs: Option<usize>,
+) -> Res {
+    let set = set.unwrap_or_else(|| default_set(ctx.guild_id()).to_owned());
+
+    let message = with_plain_emojis_sync(plain_reply(ctx), || {
+        pack_message(&set, packs.unwrap_or(1), guild_lang(ctx.guild_id()))
+    });
+    ctx.send(message.into()).await?;
+
+    Ok(())
+}
+
+/// Show statistics of a set like the rarity, temple and cost curve.
+#[poise::command(slash_command)]
+async fn setinfo(
+    ctx: CmdCtx<'_>,
+    #[description = "Set code to show the statistics of"] code: String,
+    #[description = "Add a bar chart of the cost curves"] chart: Option<bool>,
+) -> Res {
+    ctx.send(setinfo_message(&code, chart.unwrap_or(false)).into())
+        .await?;
+
+    Ok(())
+}
+
+/// Look up a sigil and its category.
+#[poise::command(slash_command)]
+async fn sigil(
+    ctx: CmdCtx<'_>,
+    #[description = "The sigil name"] name: String,
+    #[description = "Set code to look up the sigil in"] set: Option<String>,
+) -> Res {
+    let set = set.unwrap_or_else(|| default_set(ctx.guild_id()).to_owned());
+    ctx.send(sigil_message(&set, &name, ctx.guild_id()).into())
+        .await?;
+
+    Ok(())
+}
+
+/// Check a set for data problems like missing sigils or duplicate names.
+#[poise::command(
+    slash_command,
+    rename = "lint-set",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+async fn lint_set(ctx: CmdCtx<'_>, #[description = "Set code to check"] code: String) -> Res {
+    ctx.send(lint_message(&code).into()).await?;
+
+    Ok(())
+}
+
+/// Preview a IMF card or ruleset json like Magpie would show it.
+#[poise::command(slash_command, rename = "preview-card")]
+async fn preview_card(
+    ctx: CmdCtx<'_>,
+    #[description = "IMF card or ruleset json file"] file: Attachment,
+) -> Res {
+    let lang = guild_lang(ctx.guild_id());
+
+    if file.size > MAX_PREVIEW_SIZE {
+        ctx.send(
+            poise::CreateReply::default()
+                .content(tr!(lang, "preview.too_big", size = MAX_PREVIEW_SIZE / 1024))
+                .ephemeral(true),
         )
-    };
+        .await?;
+        return Ok(());
+    }
+
+    // generating the portraits can take a while
+    ctx.defer().await?;
+
+    let json = String::from_utf8_lossy(&file.download().await?).into_owned();
+    let message = with_plain_emojis_sync(plain_reply(ctx), || preview_message(&json, lang));
+    ctx.send(message.into()).await?;
+
+    Ok(())
 }
 
-/// Show the lists of all support modifiers and set code.
+/// Look up every card name in a text file, one name per line.
+#[poise::command(slash_command, rename = "bulk-lookup")]
+async fn bulk_lookup(
+    ctx: CmdCtx<'_>,
+    #[description = "Text file with a card name per line"] file: Attachment,
+    #[description = "Set code to look the cards up in"] set: Option<String>,
+    #[description = "File format of the matches"] format: Option<BulkFormat>,
+) -> Res {
+    if file.size > MAX_BULK_SIZE {
+        ctx.send(
+            poise::CreateReply::default()
+                .content(format!(
+                    "The file is too big, only up to {}KB are allowed.",
+                    MAX_BULK_SIZE / 1024
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+
+    let content = String::from_utf8_lossy(&file.download().await?).into_owned();
+    let set = set.unwrap_or_else(|| default_set(ctx.guild_id()).to_owned());
+
+    let msg = bulk_lookup_message(&content, &set, format.unwrap_or_default(), ctx.guild_id());
+    ctx.send(msg.into()).await?;
+
+    Ok(())
+}
+
+/// Guess the card trivia game.
+#[poise::command(slash_command, guild_only, subcommands("trivia_play", "trivia_scores"))]
+#[allow(clippy::unused_async)]
+async fn trivia(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Start a trivia game in this channel.
+#[poise::command(slash_command, rename = "play")]
+async fn trivia_play(
+    ctx: CmdCtx<'_>,
+    #[description = "Set code to pick the card from"] set: Option<String>,
+) -> Res {
+    ctx.defer().await?;
+
+    let set = set.unwrap_or_else(|| default_set(ctx.guild_id()).to_owned());
+
+    play_trivia(ctx, &set).await
+}
+
+/// Show the trivia scores of this server.
+#[poise::command(slash_command, rename = "scores")]
+async fn trivia_scores(ctx: CmdCtx<'_>) -> Res {
+    let scores = guild_scores(ctx.guild_id().unwrap());
+
+    if scores.is_empty() {
+        ctx.say("No one have won a trivia in this server yet.")
+            .await?;
+        return Ok(());
+    }
+
+    let out = scores
+        .iter()
+        .take(10)
+        .enumerate()
+        .map(|(i, (user, score))| format!("{}. <@{user}>: {score}", i + 1))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(out)
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// List your favorite cards, star cards with the ⭐ button on a search.
 #[poise::command(slash_command)]
-async fn show_modifiers(ctx: CmdCtx<'_>) -> Res {
-    ctx.say(mod_help! {
-        com: "IMF Competitive";
-        egg: "Mr.Egg's Goofy";
-        ete: "IMF Eternal";
-        aug: "Augmented Snapshot";
-        cti: "Custom TCG Inscryption";
-        Aug: "Augmented Main";
-        ---
-        "q": "Query instead of normal fuzzy search";
-        "*": "Select all supported set";
-        "d": "Output the raw data instead of embed";
-        "c": "Output the embed in compact mode to save space";
-        "\\`": "Skip this search match";
+async fn favorites(ctx: CmdCtx<'_>) -> Res {
+    ctx.send(favorites_message(ctx.author().id).into()).await?;
+
+    Ok(())
+}
+
+/// Your search history, it is off until you enable it.
+#[poise::command(
+    slash_command,
+    subcommands("history_show", "history_enable", "history_disable")
+)]
+#[allow(clippy::unused_async)]
+async fn history(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Show your last searches with buttons to repeat them.
+#[poise::command(slash_command, rename = "show")]
+async fn history_show(ctx: CmdCtx<'_>) -> Res {
+    ctx.send(history_message(ctx.author().id).into()).await?;
+
+    Ok(())
+}
 
+/// Start recording your searches.
+#[poise::command(slash_command, rename = "enable", ephemeral)]
+async fn history_enable(ctx: CmdCtx<'_>) -> Res {
+    toggle_history(ctx.author().id, true);
+    ctx.say("Your searches will now be recorded, see them with `/history show`.")
+        .await?;
+
+    Ok(())
+}
+
+/// Stop recording your searches and delete your history.
+#[poise::command(slash_command, rename = "disable", ephemeral)]
+async fn history_disable(ctx: CmdCtx<'_>) -> Res {
+    toggle_history(ctx.author().id, false);
+    ctx.say("Your searches are no longer recorded and your history have been deleted.")
+        .await?;
+
+    Ok(())
+}
+
+/// Manage the FAQ answers of this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("faq_add", "faq_remove", "faq_list")
+)]
+#[allow(clippy::unused_async)]
+async fn faq(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Add an answer to the server FAQ, `{user}` in the answer is replace with a mention.
+#[poise::command(
+    slash_command,
+    rename = "add",
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn faq_add(
+    ctx: CmdCtx<'_>,
+    #[description = "The question, like \"what is sap\""] question: String,
+    #[description = "The answer"] answer: String,
+) -> Res {
+    ctx.say(match add_faq(ctx.guild_id().unwrap(), &question, &answer) {
+        Ok(()) => format!("Magpie will now answer `{}`.", question.trim()),
+        Err(err) => err,
     })
     .await?;
 
     Ok(())
 }
 
-/// Test to see if the IMF tunnel is online
+/// Remove an answer from the server FAQ.
+#[poise::command(
+    slash_command,
+    rename = "remove",
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn faq_remove(
+    ctx: CmdCtx<'_>,
+    #[description = "The question to remove"] question: String,
+) -> Res {
+    ctx.say(if remove_faq(ctx.guild_id().unwrap(), &question) {
+        format!("Removed the answer to `{}`.", question.trim())
+    } else {
+        format!(
+            "There is no answer to `{}` in this server.",
+            question.trim()
+        )
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// List the questions of the server FAQ.
+#[poise::command(slash_command, rename = "list", ephemeral)]
+async fn faq_list(ctx: CmdCtx<'_>) -> Res {
+    let questions = guild_questions(ctx.guild_id().unwrap());
+
+    ctx.say(if questions.is_empty() {
+        String::from("This server have no FAQ answers, add one with `/faq add`.")
+    } else {
+        questions
+            .iter()
+            .map(|q| format!("- {q}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Manage the auto responder rules of this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    subcommands(
+        "autoresponse_add",
+        "autoresponse_remove",
+        "autoresponse_ignore",
+        "autoresponse_list"
+    )
+)]
+#[allow(clippy::unused_async)]
+async fn autoresponse(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Add or replace a rule, `{user}` and `{channel}` in the response are replace with mentions.
+#[poise::command(slash_command, rename = "add", ephemeral)]
+async fn autoresponse_add(
+    ctx: CmdCtx<'_>,
+    #[description = "The rule name"] name: String,
+    #[description = "The keyword or regex that trigger the rule"] trigger: String,
+    #[description = "The response, use \\n for a new line"] response: String,
+    #[description = "How the trigger is match, default to keyword"] kind: Option<TriggerKind>,
+    #[description = "Only trigger in this channel"] channel: Option<GuildChannel>,
+    #[description = "Seconds before the rule can trigger again, default to 60"] cooldown: Option<
+        u64,
+    >,
+) -> Res {
+    let rule = AutoResponse {
+        name: name.trim().to_owned(),
+        trigger,
+        kind: kind.unwrap_or(TriggerKind::Keyword),
+        response: response.replace("\\n", "\n"),
+        channels: channel.iter().map(|c| c.id.get()).collect(),
+        ignore_channels: vec![],
+        cooldown: cooldown.unwrap_or(60),
+        regex: None,
+    };
+
+    ctx.say(match add_rule(ctx.guild_id().unwrap(), rule) {
+        Ok(()) => format!("Rule `{}` added.", name.trim()),
+        Err(err) => err,
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Remove a rule.
+#[poise::command(slash_command, rename = "remove", ephemeral)]
+async fn autoresponse_remove(
+    ctx: CmdCtx<'_>,
+    #[description = "The rule name"] name: String,
+) -> Res {
+    ctx.say(if remove_rule(ctx.guild_id().unwrap(), name.trim()) {
+        format!("Rule `{}` removed.", name.trim())
+    } else {
+        format!("There is no rule named `{}` in this server.", name.trim())
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Toggle if a rule ignore this channel.
+#[poise::command(slash_command, rename = "ignore", ephemeral)]
+async fn autoresponse_ignore(
+    ctx: CmdCtx<'_>,
+    #[description = "The rule name"] name: String,
+) -> Res {
+    ctx.say(
+        match toggle_ignore(ctx.guild_id().unwrap(), name.trim(), ctx.channel_id()) {
+            Ok(true) => format!("Rule `{}` now ignore this channel.", name.trim()),
+            Ok(false) => format!("Rule `{}` no longer ignore this channel.", name.trim()),
+            Err(err) => err,
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// List the rules of this server.
+#[poise::command(slash_command, rename = "list", ephemeral)]
+async fn autoresponse_list(ctx: CmdCtx<'_>) -> Res {
+    let rules = guild_rules(ctx.guild_id().unwrap());
+
+    ctx.say(if rules.is_empty() {
+        String::from("This server have no auto responder rules, add one with `/autoresponse add`.")
+    } else {
+        rules
+            .iter()
+            .map(|r| {
+                let mention = |ids: &Vec<u64>| {
+                    ids.iter()
+                        .map(|c| format!("<#{c}>"))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                };
+
+                let mut parts = vec![
+                    format!("{:?} `{}`", r.kind, r.trigger.replace('`', "")),
+                    format!("{}s cooldown", r.cooldown),
+                ];
+                if !r.channels.is_empty() {
+                    parts.push(format!("only in {}", mention(&r.channels)));
+                }
+                if !r.ignore_channels.is_empty() {
+                    parts.push(format!("ignore {}", mention(&r.ignore_channels)));
+                }
+
+                format!("- **{}**: {}", r.name, parts.join(", "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// The language Magpie talk in this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("language_show", "language_set")
+)]
+#[allow(clippy::unused_async)]
+async fn language(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Show the language of this server and the available languages.
+#[poise::command(slash_command, rename = "show", ephemeral)]
+async fn language_show(ctx: CmdCtx<'_>) -> Res {
+    let lang = guild_lang(ctx.guild_id());
+    ctx.say(tr!(
+        lang,
+        "language.show",
+        language = tr!(lang, "name"),
+        languages = available_languages()
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Change the language of this server.
+#[poise::command(
+    slash_command,
+    rename = "set",
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn language_set(
+    ctx: CmdCtx<'_>,
+    #[description = "The language code, like en or es"] code: String,
+) -> Res {
+    ctx.say(match set_guild_lang(ctx.guild_id().unwrap(), &code) {
+        Ok(()) => {
+            let lang = guild_lang(ctx.guild_id());
+            tr!(lang, "language.set", language = tr!(lang, "name"))
+        }
+        Err(err) => err,
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// How the emojis in Magpie replies render in this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    default_member_permissions = "MANAGE_GUILD"
+)]
+async fn emojis(
+    ctx: CmdCtx<'_>,
+    #[description = "Auto use plain text where custom emojis cannot show"] mode: Option<EmojiMode>,
+) -> Res {
+    let guild_id = ctx.guild_id().unwrap();
+
+    if let Some(mode) = mode {
+        set_guild_emoji_mode(guild_id, mode);
+    }
+
+    ctx.say(format!(
+        "Emojis are render in `{:?}` mode.",
+        guild_emoji_mode(guild_id)
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// What searches are wrap in for this server, for when other bots also use `[[...]]`.
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    default_member_permissions = "MANAGE_GUILD"
+)]
+async fn delimiters(
+    ctx: CmdCtx<'_>,
+    #[description = "The new delimiters to search with"] delimiter: Option<Delimiter>,
+) -> Res {
+    if let Some(delimiter) = delimiter {
+        set_guild_delimiter(ctx.guild_id().unwrap(), delimiter);
+    }
+
+    let (open, close) = guild_delimiter(ctx.guild_id()).pair();
+    ctx.say(format!(
+        "Searches are wrap in `{open}{close}` here, like `{open}stoat{close}`."
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Check if the reply to a command should use the plain text emojis.
+fn plain_reply(ctx: CmdCtx<'_>) -> bool {
+    let app_permissions = match ctx {
+        poise::Context::Application(ctx) => ctx.interaction.app_permissions,
+        poise::Context::Prefix(_) => None,
+    };
+
+    plain_emojis(ctx.guild_id(), app_permissions)
+}
+
+/// Manage the spoiler sets of this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    subcommands("spoiler_toggle", "spoiler_list")
+)]
+#[allow(clippy::unused_async)]
+async fn spoiler(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Toggle a set as a spoiler set, its cards are hidden behind a reveal button in searches.
+#[poise::command(slash_command, rename = "toggle", ephemeral)]
+async fn spoiler_toggle(
+    ctx: CmdCtx<'_>,
+    #[description = "Set code of the set"] code: String,
+) -> Res {
+    ctx.say(match toggle_spoiler(ctx.guild_id().unwrap(), &code) {
+        Ok(true) => format!("`{code}` is now a spoiler set."),
+        Ok(false) => format!("`{code}` is no longer a spoiler set."),
+        Err(err) => err,
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// List the spoiler sets of this server.
+#[poise::command(slash_command, rename = "list", ephemeral)]
+async fn spoiler_list(ctx: CmdCtx<'_>) -> Res {
+    let sets = guild_spoiler_sets(ctx.guild_id().unwrap());
+
+    ctx.say(if sets.is_empty() {
+        String::from("This server have no spoiler sets.")
+    } else {
+        sets.iter()
+            .map(|s| format!("`{s}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Guess if the next card have a higher or lower stat.
 #[poise::command(slash_command)]
-async fn tunnel_status(ctx: CmdCtx<'_>) -> Res {
-    ctx.defer().await?;
-    ctx.say(match isahc::get("http://localtunnel.me") {
-        Ok(_) => "Tunnel is up and running. If you have issue check out [this faq](https://discord.com/channels/994573431880286289/1168644586319659100/1168657617141366805).",
-        Err(_) => "I cannot reach tunnel right now, this may mean tunnel is down but you can [check yourself](https://isitdownorjust.me/localtunnel-me/)."
+async fn higherlower(
+    ctx: CmdCtx<'_>,
+    #[description = "Set code to pick the cards from"] set: Option<String>,
+    #[description = "The stat to compare, default to attack"] stat: Option<Stat>,
+) -> Res {
+    let set = set.unwrap_or_else(|| default_set(ctx.guild_id()).to_owned());
+
+    let msg = start_message(&set, stat.unwrap_or(Stat::Attack), &ctx.data().interactions);
+    ctx.send(msg.into()).await?;
+
+    Ok(())
+}
+
+/// Manage the card of the day of this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    subcommands("cotd_enable", "cotd_disable")
+)]
+#[allow(clippy::unused_async)]
+async fn cotd(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Post a random card daily in a channel.
+#[poise::command(slash_command, rename = "enable")]
+async fn cotd_enable(
+    ctx: CmdCtx<'_>,
+    #[description = "Channel to post the card in"]
+    #[channel_types("Text")]
+    channel: GuildChannel,
+    #[description = "Time to post the card at in UTC, like 14:30"] time: String,
+    #[description = "Set code to pick the card from"] set: Option<String>,
+) -> Res {
+    let Some(time) = parse_time(&time) else {
+        ctx.say("Invalid time, it should look like `14:30`.")
+            .await?;
+        return Ok(());
+    };
+
+    let guild_id = ctx.guild_id().unwrap();
+    let set = set.unwrap_or_else(|| default_set(Some(guild_id)).to_owned());
+
+    if !SETS.read().unwrap().contains_key(set.as_str()) {
+        ctx.say(format!("Unknown set code `{set}`")).await?;
+        return Ok(());
+    }
+
+    enable_cotd(guild_id, channel.id, set, time);
+    ctx.say(format!(
+        "Card of the day will be posted in <#{}> at {:02}:{:02} UTC.",
+        channel.id, time.0, time.1
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Stop posting the card of the day.
+#[poise::command(slash_command, rename = "disable")]
+async fn cotd_disable(ctx: CmdCtx<'_>) -> Res {
+    ctx.say(if disable_cotd(ctx.guild_id().unwrap()) {
+        "Card of the day disabled."
+    } else {
+        "Card of the day is not enabled."
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Manage the set changelog of this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    subcommands("changelog_enable", "changelog_disable")
+)]
+#[allow(clippy::unused_async)]
+async fn changelog(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Post a changelog in a channel whenever a set get updated.
+#[poise::command(slash_command, rename = "enable")]
+async fn changelog_enable(
+    ctx: CmdCtx<'_>,
+    #[description = "Channel to post the changelog in"]
+    #[channel_types("Text")]
+    channel: GuildChannel,
+) -> Res {
+    enable_changelog(ctx.guild_id().unwrap(), channel.id);
+    ctx.say(format!(
+        "Set changelog will be posted in <#{}>.",
+        channel.id
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Stop posting the set changelog.
+#[poise::command(slash_command, rename = "disable")]
+async fn changelog_disable(ctx: CmdCtx<'_>) -> Res {
+    ctx.say(if disable_changelog(ctx.guild_id().unwrap()) {
+        "Set changelog disabled."
+    } else {
+        "Set changelog is not enabled."
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Manage the homebrew set of this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    subcommands("homebrew_create", "homebrew_add_sigil", "homebrew_add_card")
+)]
+#[allow(clippy::unused_async)]
+async fn homebrew(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Create the homebrew set of this server, this replace the old one.
+#[poise::command(slash_command, rename = "create")]
+async fn homebrew_create(
+    ctx: CmdCtx<'_>,
+    #[description = "The 3 characters set code use to search this set"] code: String,
+    #[description = "The name of the set"] name: String,
+) -> Res {
+    ctx.say(match create_set(ctx.guild_id().unwrap(), &code, &name) {
+        Ok(()) => format!(
+            "Homebrew set `{name}` created, search it with `{}`.",
+            guild_delimiter(ctx.guild_id()).apply(&format!("{code}[[card name]]"))
+        ),
+        Err(err) => err,
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Add or replace a sigil in the homebrew set.
+#[poise::command(slash_command, rename = "add-sigil")]
+async fn homebrew_add_sigil(
+    ctx: CmdCtx<'_>,
+    #[description = "The sigil name"] name: String,
+    #[description = "What the sigil do"] description: String,
+) -> Res {
+    ctx.say(
+        match add_sigil(ctx.guild_id().unwrap(), &name, &description) {
+            Ok(()) => format!("Sigil `{name}` added."),
+            Err(err) => err,
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "New Homebrew Card"]
+struct CardModal {
+    #[name = "Name"]
+    #[max_length = 100]
+    name: String,
+    #[name = "Attack/Health"]
+    #[placeholder = "1/2"]
+    stats: String,
+    #[name = "Costs"]
+    #[placeholder = "2 blood, 3 bone or 1 energy. Leave empty for a free card"]
+    costs: Option<String>,
+    #[name = "Sigils"]
+    #[placeholder = "Comma separated, they must be added with /homebrew add-sigil first"]
+    sigils: Option<String>,
+    #[name = "Description"]
+    #[paragraph]
+    description: Option<String>,
+}
+
+/// Add a card to the homebrew set.
+#[poise::command(slash_command, rename = "add-card")]
+async fn homebrew_add_card(
+    ctx: poise::ApplicationContext<'_, Data, magpie_tutor::Error>,
+    #[description = "Link to the card portrait"] portrait: Option<String>,
+) -> Res {
+    use poise::Modal as _;
+
+    let Some(form) = CardModal::execute(ctx).await? else {
+        return Ok(());
+    };
+
+    let card = (|| {
+        let (attack, health) = form
+            .stats
+            .split_once('/')
+            .and_then(|(a, h)| Some((a.trim().parse().ok()?, h.trim().parse().ok()?)))
+            .ok_or_else(|| format!("`{}` is not in the attack/health format", form.stats))?;
+        let costs = parse_costs(form.costs.as_deref().unwrap_or_default())?;
+
+        Ok::<_, String>(HomebrewCard {
+            name: form.name.trim().to_owned(),
+            description: form.description.clone().unwrap_or_default(),
+            portrait: portrait.unwrap_or_default(),
+            attack,
+            health,
+            blood: costs.blood,
+            bone: costs.bone,
+            energy: costs.energy,
+            sigils: form
+                .sigils
+                .iter()
+                .flat_map(|s| s.split(','))
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(ToOwned::to_owned)
+                .collect(),
+        })
+    })()
+    .and_then(|card| add_card(ctx.guild_id().unwrap(), card));
+
+    ctx.say(match card {
+        Ok(()) => format!("Card `{}` added.", form.name.trim()),
+        Err(err) => err,
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Check if the author of a command can manage the server.
+fn is_moderator(ctx: CmdCtx<'_>) -> bool {
+    match ctx {
+        poise::Context::Application(ctx) => ctx
+            .interaction
+            .member
+            .as_ref()
+            .and_then(|m| m.permissions)
+            .is_some_and(Permissions::manage_guild),
+        poise::Context::Prefix(_) => false,
+    }
+}
+
+/// Report the result of a game you lost, moderators can report any game.
+#[poise::command(slash_command, guild_only, rename = "report-result")]
+async fn report_result_cmd(
+    ctx: CmdCtx<'_>,
+    #[description = "The player who won"] winner: User,
+    #[description = "The player who lost"] loser: User,
+    #[description = "The format the game was played in"] format: Option<String>,
+) -> Res {
+    let guild_id = ctx.guild_id().unwrap();
+
+    let err = if winner.id == loser.id {
+        Some(String::from(
+            "The winner and loser must be different players.",
+        ))
+    } else if winner.bot || loser.bot {
+        Some(String::from("Bots can't be on the ladder."))
+    } else if ctx.author().id != loser.id && !is_moderator(ctx) {
+        // the winner alone could farm rating off anyone so the loser have to confirm by reporting
+        Some(format!(
+            "Only the loser or a moderator can report a result, ask <@{}> to report it.",
+            loser.id
+        ))
+    } else {
+        format
+            .as_ref()
+            .filter(|f| get_format(guild_id, f).is_none())
+            .map(|name| format!("Unknown format `{name}`"))
+    };
+
+    if let Some(err) = err {
+        ctx.send(poise::CreateReply::default().content(err).ephemeral(true))
+            .await?;
+        return Ok(());
+    }
+
+    let (win, lose, change) = report_result(guild_id, format.as_deref(), winner.id, loser.id);
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!(
+                "Result recorded in the {} ladder.\n<@{}>: {} (+{change})\n<@{}>: {} (-{change})",
+                format.as_deref().unwrap_or("open"),
+                winner.id,
+                win.elo,
+                loser.id,
+                lose.elo,
+            ))
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Show the ladder of this server.
+#[poise::command(slash_command, guild_only, rename = "leaderboard")]
+async fn leaderboard_cmd(
+    ctx: CmdCtx<'_>,
+    #[description = "The format ladder to show, default to the open ladder"] format: Option<String>,
+) -> Res {
+    let ratings = leaderboard(ctx.guild_id().unwrap(), format.as_deref());
+
+    if ratings.is_empty() {
+        ctx.say("No one have played on this ladder yet.").await?;
+        return Ok(());
+    }
+
+    let out = ratings
+        .iter()
+        .take(10)
+        .enumerate()
+        .map(|(i, (user, r))| {
+            format!(
+                "{}. <@{user}>: {} ({}W {}L)",
+                i + 1,
+                r.elo,
+                r.wins,
+                r.losses
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!(
+                "## {} ladder\n{out}",
+                format.as_deref().unwrap_or("Open")
+            ))
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Looking for game lobbies.
+#[poise::command(slash_command, guild_only, subcommands("lfg_channel", "lfg_lobbies"))]
+#[allow(clippy::unused_async)]
+async fn lfg(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Toggle this channel as a looking for game channel.
+#[poise::command(
+    slash_command,
+    rename = "channel",
+    required_permissions = "MANAGE_CHANNELS"
+)]
+async fn lfg_channel(ctx: CmdCtx<'_>) -> Res {
+    ctx.say(
+        if toggle_lfg_channel(ctx.guild_id().unwrap(), ctx.channel_id()) {
+            "This channel is now a looking for game channel. Say you want to play or fight to open a lobby."
+        } else {
+            "This channel is no longer a looking for game channel."
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Show the open lobbies in this server.
+#[poise::command(slash_command, rename = "lobbies")]
+async fn lfg_lobbies(ctx: CmdCtx<'_>) -> Res {
+    let lobbies = open_lobbies(ctx.guild_id().unwrap());
+
+    if lobbies.is_empty() {
+        ctx.say("There are no open lobbies right now.").await?;
+        return Ok(());
+    }
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(
+                lobbies
+                    .iter()
+                    .map(|l| format!("- <@{}> in <#{}>", l.host, l.channel_id))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Bot usage statistics, only for the bot owners.
+#[poise::command(slash_command, owners_only, subcommands("stats_show", "stats_summary"))]
+#[allow(clippy::unused_async)]
+async fn stats(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Show the usage statistics.
+#[poise::command(slash_command, rename = "show")]
+async fn stats_show(ctx: CmdCtx<'_>) -> Res {
+    let (total, week) = {
+        let stats = STATS.lock().unwrap();
+        (
+            stats_embed(&stats.total, "All Time"),
+            stats_embed(&stats.week, "This Week"),
+        )
+    };
+
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(total)
+            .embed(week)
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Set the channel to post the weekly summary in, leave empty to stop posting.
+#[poise::command(slash_command, rename = "summary")]
+async fn stats_summary(
+    ctx: CmdCtx<'_>,
+    #[description = "The channel to post the summary in"]
+    #[channel_types("Text")]
+    channel: Option<GuildChannel>,
+) -> Res {
+    set_summary_channel(channel.as_ref().map(|c| c.id));
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(match channel {
+                Some(c) => format!("Weekly summary will be posted in <#{}>.", c.id),
+                None => String::from("Weekly summary disabled."),
+            })
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Manage the portrait cache, only for the bot owners.
+#[poise::command(slash_command, owners_only, subcommands("cache_purge"))]
+#[allow(clippy::unused_async)]
+async fn cache(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Remove the cache entries of a set, every entries or the expired ones.
+#[poise::command(slash_command, rename = "purge")]
+async fn cache_purge(
+    ctx: CmdCtx<'_>,
+    #[description = "A set code, all or expired"] target: String,
+) -> Res {
+    let content = match purge_cache(&PurgeTarget::parse(&target)) {
+        Ok(removed) => format!("Removed {removed} cache entries."),
+        Err(err) => err,
+    };
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(content)
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Manage the card rulings, only for the bot owners as they are the judges.
+#[poise::command(
+    slash_command,
+    owners_only,
+    subcommands("ruling_add", "ruling_remove", "ruling_list")
+)]
+#[allow(clippy::unused_async)]
+async fn ruling(_: CmdCtx<'_>) -> Res {
+    Ok(())
+}
+
+/// Add a ruling to a card, it show up in the card rulings button.
+#[poise::command(slash_command, rename = "add", ephemeral)]
+async fn ruling_add(
+    ctx: CmdCtx<'_>,
+    #[description = "Set code of the card"] set: String,
+    #[description = "The card name"] card: String,
+    #[description = "The ruling"] text: String,
+) -> Res {
+    ctx.say(match add_ruling(&set, &card, &text) {
+        Ok(name) => format!("Added the ruling to **{name}**."),
+        Err(err) => err,
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Remove a ruling from a card, use `/ruling list` to get the number.
+#[poise::command(slash_command, rename = "remove", ephemeral)]
+async fn ruling_remove(
+    ctx: CmdCtx<'_>,
+    #[description = "Set code of the card"] set: String,
+    #[description = "The card name"] card: String,
+    #[description = "The number of the ruling"]
+    #[min = 1]
+    number: usize,
+) -> Res {
+    ctx.say(match remove_ruling(&set, &card, number) {
+        Ok(ruling) => format!("Removed the ruling `{}`.", ruling.text),
+        Err(err) => err,
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// List the rulings added to a card, the rulings from the sheet are not list.
+#[poise::command(slash_command, rename = "list", ephemeral)]
+async fn ruling_list(
+    ctx: CmdCtx<'_>,
+    #[description = "Set code of the card"] set: String,
+    #[description = "The card name"] card: String,
+) -> Res {
+    let rulings = card_rulings(&set, &card);
+
+    ctx.say(if rulings.is_empty() {
+        format!("`{}` have no rulings in `{set}`.", card.trim())
+    } else {
+        rulings
+            .iter()
+            .enumerate()
+            .map(|(i, r)| match r.time {
+                Some(time) => format!("{}. {} (<t:{time}:d>)", i + 1, r.text),
+                None => format!("{}. {}", i + 1, r.text),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     })
     .await?;
 
     Ok(())
 }
 
+/// Check if Magpie Tutor is connected and the sets are loaded.
+#[poise::command(slash_command)]
+async fn health(ctx: CmdCtx<'_>) -> Res {
+    let latency = ctx.ping().await;
+
+    ctx.send(
+        poise::CreateReply::default().embed(health_embed((!latency.is_zero()).then_some(latency))),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Check if the services Magpie and the community use are up.
+#[poise::command(slash_command)]
+async fn status(ctx: CmdCtx<'_>) -> Res {
+    ctx.defer().await?;
+    let results = probe_services().await;
+    ctx.send(poise::CreateReply::default().embed(status_embed(&results)))
+        .await?;
+
+    Ok(())
+}
+
 #[poise::command(slash_command)]
 async fn ping(ctx: CmdCtx<'_>) -> Res {
     let choose = PING_RESPONSE.choose(&mut thread_rng());
@@ -94,6 +1463,17 @@ async fn ping(ctx: CmdCtx<'_>) -> Res {
 // main entry point of the bot
 #[tokio::main]
 async fn main() {
+    // keep the guard alive so the log file get flush on exit
+    let _guard = init_logging();
+
+    // every fetch go through this client so it need to be set before the sets are fetch
+    let mut http =
+        HttpClient::builder().user_agent(concat!("magpie_tutor/", env!("CARGO_PKG_VERSION")));
+    if let Ok(proxy) = std::env::var("TUTOR_PROXY") {
+        http = http.proxy(&proxy);
+    }
+    set_client(http.build().expect("Cannot build http client")).expect("Http client already set");
+
     // your token need to be in the environment variable
     let token = std::env::var("TUTOR_TOKEN").expect("missing token in env var");
     let intents = GatewayIntents::privileged()
@@ -102,9 +1482,8 @@ async fn main() {
 
     // poise framework
     let framework = frameworks! {
-        global: help(), show_modifiers(), ping();
+        global: help(), show_modifiers(), ping(), deck(), format(), formats(), pack(), setinfo(), sigil(), lint_set(), preview_card(), bulk_lookup(), trivia(), higherlower(), cotd(), changelog(), homebrew(), lfg(), report_result_cmd(), leaderboard_cmd(), stats(), cache(), ruling(), health(), favorites(), history(), faq(), autoresponse(), status(), language(), spoiler(), emojis(), delimiters();
         guild (1115010083168997376): test();
-        guild (1115010083168997376): tunnel_status();
         ---
         {
             Ok(Data::new())
@@ -116,10 +1495,151 @@ async fn main() {
     tokio::task::block_in_place(|| {
         done!(
             "Finish fetching {} sets",
-            SETS.lock().unwrap().len().green()
+            SETS.read().unwrap().len().green()
         );
     });
 
+    info!("Loading emojis from {}...", EMOJI_FILE_PATH.green());
+    load_emojis();
+
+    info!("Loading formats from {}...", FORMATS_FILE_PATH.green());
+    done!(
+        "Finish loading formats for {} servers",
+        FORMATS.lock().unwrap().len().green()
+    );
+
+    info!("Loading card of the day from {}...", COTD_FILE_PATH.green());
+    done!(
+        "Finish loading card of the day for {} servers",
+        COTD.lock().unwrap().len().green()
+    );
+
+    info!(
+        "Loading changelog channels from {}...",
+        CHANGELOG_FILE_PATH.green()
+    );
+    done!(
+        "Finish loading changelog channels for {} servers",
+        CHANGELOG.lock().unwrap().len().green()
+    );
+
+    info!(
+        "Loading homebrew sets from {}...",
+        HOMEBREW_FILE_PATH.green()
+    );
+    done!(
+        "Finish loading homebrew sets for {} servers",
+        HOMEBREW.lock().unwrap().len().green()
+    );
+
+    info!("Loading LFG channels from {}...", LFG_FILE_PATH.green());
+    done!(
+        "Finish loading LFG channels for {} servers",
+        LFG_CHANNELS.lock().unwrap().len().green()
+    );
+
+    info!("Loading ladder from {}...", LADDER_FILE_PATH.green());
+    done!(
+        "Finish loading ladder for {} servers",
+        LADDER.lock().unwrap().len().green()
+    );
+
+    info!("Loading stats from {}...", STATS_FILE_PATH.green());
+    done!(
+        "Finish loading stats with {} searches",
+        STATS.lock().unwrap().total.searches.green()
+    );
+
+    info!("Loading trivia scores from {}...", TRIVIA_FILE_PATH.green());
+    done!(
+        "Finish loading trivia scores for {} servers",
+        TRIVIA_SCORES.lock().unwrap().len().green()
+    );
+
+    info!("Loading favorites from {}...", FAVORITES_FILE_PATH.green());
+    done!(
+        "Finish loading favorites for {} users",
+        FAVORITES.lock().unwrap().len().green()
+    );
+
+    info!("Loading FAQ from {}...", FAQ_FILE_PATH.green());
+    done!("Finish loading {} FAQ answers", FAQ.len().green());
+
+    info!("Loading timeline from {}...", TIMELINE_FILE_PATH.green());
+    done!(
+        "Finish loading timeline for {} sets",
+        TIMELINE.lock().unwrap().len().green()
+    );
+
+    info!("Loading rulings from {}...", RULINGS_FILE_PATH.green());
+    done!(
+        "Finish loading rulings for {} sets",
+        RULINGS.lock().unwrap().len().green()
+    );
+
+    info!("Loading guild FAQ from {}...", GUILD_FAQ_FILE_PATH.green());
+    done!(
+        "Finish loading guild FAQ for {} servers",
+        GUILD_FAQ.lock().unwrap().len().green()
+    );
+
+    info!(
+        "Loading auto responder rules from {}...",
+        AUTORESPONSE_FILE_PATH.green()
+    );
+    done!(
+        "Finish loading auto responder rules for {} servers",
+        AUTORESPONSES.lock().unwrap().len().green()
+    );
+
+    info!("Loading locales...");
+    done!("Finish loading {} locales", LOCALES.len().green());
+
+    info!("Loading languages from {}...", LANGUAGES_FILE_PATH.green());
+    done!(
+        "Finish loading languages for {} servers",
+        LANGUAGES.lock().unwrap().len().green()
+    );
+
+    info!(
+        "Loading translation tables from {}...",
+        TRANSLATIONS_DIR.green()
+    );
+    done!(
+        "Finish loading {} localized card names",
+        TRANSLATIONS.len().green()
+    );
+
+    info!("Loading spoiler sets from {}...", SPOILER_FILE_PATH.green());
+    done!(
+        "Finish loading spoiler sets for {} servers",
+        SPOILER_SETS.lock().unwrap().len().green()
+    );
+
+    info!(
+        "Loading emoji modes from {}...",
+        EMOJI_MODE_FILE_PATH.green()
+    );
+    done!(
+        "Finish loading emoji modes for {} servers",
+        EMOJI_MODES.lock().unwrap().len().green()
+    );
+
+    info!(
+        "Loading delimiters from {}...",
+        DELIMITERS_FILE_PATH.green()
+    );
+    done!(
+        "Finish loading delimiters for {} servers",
+        DELIMITERS.lock().unwrap().len().green()
+    );
+
+    info!("Loading history from {}...", HISTORY_FILE_PATH.green());
+    done!(
+        "Finish loading history for {} users",
+        HISTORY.lock().unwrap().len().green()
+    );
+
     info!("Loading caches from {}...", CACHE_FILE_PATH.green());
     // Use block_in_place for loading caches (since it's a blocking operation)
     tokio::task::block_in_place(|| {
@@ -129,17 +1649,24 @@ async fn main() {
         );
     });
 
+    if let Ok(addr) = std::env::var(HEALTH_ADDR_VAR) {
+        info!("Starting health server on {}...", addr.green());
+        start_server(&addr).await;
+    }
+
     std::panic::set_hook(Box::new(panic_hook));
 
     // client time
-    let client = ClientBuilder::new(token, intents)
+    let shards = ShardConfig::from_env().unwrap_or_else(|err| panic!("{err}"));
+
+    let mut client = ClientBuilder::new(token, intents)
         .framework(framework)
-        .await;
+        .await
+        .unwrap();
 
-    client.unwrap().start().await.unwrap();
+    shards.start(&mut client).await.unwrap();
 }
 
-
 fn panic_hook(info: &PanicInfo) {
     if let Some(loc) = info.location() {
         error!(
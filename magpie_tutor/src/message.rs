@@ -1,9 +1,16 @@
-use poise::serenity_prelude::{
-    CreateActionRow, CreateAllowedMentions, CreateAttachment, CreateEmbed,
-    CreateInteractionResponseMessage, CreateMessage, EditAttachments, EditInteractionResponse,
-    InteractionResponseFlags, MessageFlags,
+use std::fmt::Write;
+
+use poise::{
+    serenity_prelude::{
+        CreateActionRow, CreateAllowedMentions, CreateAttachment, CreateEmbed,
+        CreateInteractionResponseMessage, CreateMessage, EditAttachments, EditInteractionResponse,
+        EditMessage, InteractionResponseFlags, MessageFlags,
+    },
+    CreateReply,
 };
 
+use serde_json::Value;
+
 use crate::builder;
 
 builder! {
@@ -75,6 +82,32 @@ impl From<MessageAdapter> for EditInteractionResponse {
     }
 }
 
+/// The attachments replace every attachment the message had.
+impl From<MessageAdapter> for EditMessage {
+    fn from(
+        MessageAdapter {
+            content,
+            embeds,
+            attachments,
+            allowed_mentions,
+            components,
+            ..
+        }: MessageAdapter,
+    ) -> Self {
+        let mut new_attach = EditAttachments::new();
+        for a in attachments {
+            new_attach = new_attach.add(a);
+        }
+
+        EditMessage::new()
+            .content(content)
+            .embeds(embeds)
+            .attachments(new_attach)
+            .allowed_mentions(allowed_mentions)
+            .components(components)
+    }
+}
+
 impl From<MessageAdapter> for CreateInteractionResponseMessage {
     fn from(
         MessageAdapter {
@@ -98,3 +131,87 @@ impl From<MessageAdapter> for CreateInteractionResponseMessage {
             .flags(flags)
     }
 }
+
+impl From<MessageAdapter> for CreateReply {
+    fn from(
+        MessageAdapter {
+            content,
+            embeds,
+            attachments,
+            allowed_mentions,
+            components,
+            ephemeral,
+        }: MessageAdapter,
+    ) -> Self {
+        CreateReply {
+            content: (!content.is_empty()).then_some(content),
+            embeds,
+            attachments,
+            allowed_mentions: Some(allowed_mentions),
+            components: Some(components),
+            ephemeral: Some(ephemeral),
+            ..Default::default()
+        }
+    }
+}
+
+impl MessageAdapter {
+    /// Render the message as plain text, use to see what a message look like without Discord.
+    ///
+    /// Embeds are print with their title, description, fields, footer and image, attachments
+    /// with their size and buttons with their custom id.
+    pub fn to_text(&self) -> String {
+        let mut out = self.content.clone();
+        let text = |v: &Value| v.as_str().unwrap_or_default().to_owned();
+
+        for embed in &self.embeds {
+            let embed = serde_json::to_value(embed).unwrap_or_default();
+
+            write!(out, "\n\n== {} ==", text(&embed["title"])).unwrap();
+            if let Some(description) = embed["description"].as_str() {
+                write!(out, "\n{}", description.trim_end()).unwrap();
+            }
+            for field in embed["fields"].as_array().into_iter().flatten() {
+                write!(
+                    out,
+                    "\n-- {} --\n{}",
+                    text(&field["name"]),
+                    text(&field["value"]).trim_end()
+                )
+                .unwrap();
+            }
+            if let Some(footer) = embed["footer"]["text"].as_str() {
+                write!(out, "\n({})", footer.trim()).unwrap();
+            }
+            for image in ["thumbnail", "image"] {
+                if let Some(url) = embed[image]["url"].as_str() {
+                    write!(out, "\n{image}: {url}").unwrap();
+                }
+            }
+        }
+
+        if !self.attachments.is_empty() {
+            out.push_str("\n\nAttachments:");
+            for a in &self.attachments {
+                write!(out, "\n- {} ({} bytes)", a.filename, a.data.len()).unwrap();
+            }
+        }
+
+        if !self.components.is_empty() {
+            out.push('\n');
+        }
+        for row in &self.components {
+            let row = serde_json::to_value(row).unwrap_or_default();
+            let buttons = row["components"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|b| format!("[{}] ({})", text(&b["label"]), text(&b["custom_id"])))
+                .collect::<Vec<_>>();
+
+            write!(out, "\n{}", buttons.join(" ")).unwrap();
+        }
+
+        out
+    }
+}
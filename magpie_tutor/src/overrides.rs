@@ -0,0 +1,147 @@
+//! Implementation for the errata overrides.
+//!
+//! Upstream sheets are sometime wrong or lag behind rulings. A set can be patch by putting a
+//! `<set code>.toml` file in [`OVERRIDES_DIR`], the overrides are apply every time the set is
+//! fetch and the patched cards are marked as errata'd in their embed.
+//!
+//! ```toml
+//! # sigils the patched cards need that the set does not have
+//! [sigils]
+//! "Sharp Quills" = "When this card is struck, the striker is dealt 1 damage."
+//!
+//! [cards."Porcupine"]
+//! health = 2
+//! sigils = ["Sharp Quills"]
+//! note = "Health changed to 2 by the latest ruling"
+//! ```
+//!
+//! Every card field is optional, only the fields that are set get patched.
+
+use std::{collections::HashMap, fs, io::ErrorKind, path::Path, sync::Arc};
+
+use magpie_engine::Attack;
+use serde::Deserialize;
+
+use crate::{done, error, Color, Set};
+
+/// Directory with the override files.
+pub const OVERRIDES_DIR: &str = "./overrides";
+
+/// The errata note for overrides that does not give one.
+const DEFAULT_NOTE: &str = "This card data was corrected by the bot maintainers";
+
+/// The overrides for a set.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SetOverride {
+    /// Extra sigils to add to the sigils description.
+    #[serde(default)]
+    pub sigils: HashMap<String, String>,
+    /// Card name to the fields to patch.
+    #[serde(default)]
+    pub cards: HashMap<String, CardOverride>,
+}
+
+/// The fields to patch on a card.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CardOverride {
+    /// The new attack.
+    pub attack: Option<isize>,
+    /// The new health.
+    pub health: Option<isize>,
+    /// The new sigils, they must be in the set or override sigils description.
+    pub sigils: Option<Vec<String>>,
+    /// The new description.
+    pub description: Option<String>,
+    /// Why the card was patch, this is shown in the embed.
+    pub note: Option<String>,
+}
+
+/// Load the overrides for a set code.
+///
+/// Return [`None`] if the set have no override file or if the file is invalid.
+pub fn load_override(code: &str) -> Option<SetOverride> {
+    let path = Path::new(OVERRIDES_DIR).join(format!("{code}.toml"));
+
+    let content = match fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(err) if err.kind() == ErrorKind::NotFound => return None,
+        Err(err) => {
+            error!("Cannot read override file {} due to: {err}", path.display());
+            return None;
+        }
+    };
+
+    match toml::from_str(&content) {
+        Ok(o) => Some(o),
+        Err(err) => {
+            error!(
+                "Cannot parse override file {} due to: {err}",
+                path.display()
+            );
+            None
+        }
+    }
+}
+
+/// Apply the overrides of a set code to a set if there is any.
+pub fn apply_overrides(code: &str, set: &mut Set) {
+    let Some(overrides) = load_override(code) else {
+        return;
+    };
+
+    set.sigils_description
+        .extend(overrides.sigils.into_iter().map(|(k, v)| (k.into(), v)));
+
+    let mut patched = 0;
+    for (name, patch) in overrides.cards {
+        let Some(card) = set.cards.iter_mut().find(|c| c.name == name) else {
+            error!(
+                "Override for card {} does not match any card in {}",
+                name.red(),
+                set.name
+            );
+            continue;
+        };
+
+        if let Some(attack) = patch.attack {
+            card.attack = Attack::Num(attack);
+        }
+        if let Some(health) = patch.health {
+            card.health = health;
+        }
+        if let Some(sigils) = patch.sigils {
+            // embeds expect every sigil to have a description
+            match sigils
+                .iter()
+                .find(|s| !set.sigils_description.contains_key(s.as_str()))
+            {
+                Some(missing) => error!(
+                    "Override for card {} use sigil {} that have no description",
+                    name.red(),
+                    missing.red()
+                ),
+                None => {
+                    card.sigils = sigils
+                        .iter()
+                        .filter_map(|s| set.sigils_description.get_key_value(s.as_str()))
+                        .map(|(s, _)| Arc::clone(s))
+                        .collect();
+                }
+            }
+        }
+        if let Some(description) = patch.description {
+            card.description = description;
+        }
+
+        card.extra.errata = Some(patch.note.unwrap_or_else(|| DEFAULT_NOTE.to_owned()));
+        patched += 1;
+    }
+    // the lowercase text and full text index need the patched text
+    set.reindex();
+
+    done!(
+        "Apply overrides to {} cards in {}",
+        patched.green(),
+        set.name.blue()
+    );
+}
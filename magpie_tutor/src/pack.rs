@@ -0,0 +1,58 @@
+//! Implementation for opening booster packs.
+
+use std::fmt::Write;
+
+use magpie_engine::prelude::*;
+use poise::serenity_prelude::{colours::roles, CreateEmbed};
+use rand::thread_rng;
+
+use crate::{search::gen_plain_embed, MessageAdapter, SETS};
+
+/// Most packs that can be open at once, the card list must fit in the message content.
+pub const MAX_PACKS: usize = 5;
+
+/// Open packs from a set and generate the pack message.
+///
+/// The card names are list in the message content, a single pack also show the card embeds in the
+/// same order. Embeds can't be spoiler tagged so the cards are show as is.
+pub fn pack_message(set_code: &str, count: usize, lang: &str) -> MessageAdapter {
+    let sets = SETS.read().unwrap();
+    let Some(set) = sets.get(set_code) else {
+        return MessageAdapter::new().embeds(vec![CreateEmbed::new()
+            .color(roles::RED)
+            .title("Pack Error")
+            .description(format!("Unknown set code `{set_code}`"))]);
+    };
+
+    let packs = PackRules::default().open_many(set, count.clamp(1, MAX_PACKS), &mut thread_rng());
+
+    if packs.iter().all(Vec::is_empty) {
+        return MessageAdapter::new().embeds(vec![CreateEmbed::new()
+            .color(roles::RED)
+            .title("Pack Error")
+            .description(format!("{} have no cards to put in a pack", set.name))]);
+    }
+
+    let mut content = String::new();
+    for (i, pack) in packs.iter().enumerate() {
+        if packs.len() == 1 {
+            writeln!(content, "**{} Pack**", set.name).unwrap();
+        } else {
+            writeln!(content, "**{} Pack {}**", set.name, i + 1).unwrap();
+        }
+        for card in pack {
+            writeln!(content, "{} ({})", card.name, card.rarity).unwrap();
+        }
+    }
+
+    // a sealed pool have too many cards for the embeds
+    let embeds = match &packs[..] {
+        [pack] => pack
+            .iter()
+            .map(|card| gen_plain_embed(card, set, true, lang))
+            .collect(),
+        _ => vec![],
+    };
+
+    MessageAdapter::new().content(content).embeds(embeds)
+}
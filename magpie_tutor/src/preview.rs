@@ -0,0 +1,92 @@
+//! Implementation for previewing homebrew cards.
+//!
+//! `/preview-card` take a IMF card or ruleset json, parse it with the engine and show the cards
+//! like a search would so designers can see how their cards look before publishing them.
+
+use magpie_engine::prelude::*;
+use poise::serenity_prelude::{colours::roles, CreateAttachment, CreateEmbed};
+
+use crate::{
+    portrait_filename,
+    search::{gen_plain_embed, gen_portrait},
+    tr, MessageAdapter, SETS,
+};
+
+/// Set code the preview cards are put in.
+pub const PREVIEW_SET_CODE: &str = "prv";
+
+/// Biggest json file that can be preview, in bytes.
+pub const MAX_PREVIEW_SIZE: u32 = 1 << 20;
+
+/// Most cards show in a preview, Discord only allow 10 embeds per message.
+const MAX_PREVIEW_CARDS: usize = 10;
+
+/// Generate the preview message for a IMF json.
+pub fn preview_message(json: &str, lang: &str) -> MessageAdapter {
+    let set = match parse_imf_set(json, SetCode::new(PREVIEW_SET_CODE).unwrap()) {
+        Ok(set) => set,
+        Err(err) => {
+            return MessageAdapter::new()
+                .embeds(vec![CreateEmbed::new()
+                    .color(roles::RED)
+                    .title(tr!(lang, "preview.invalid_title"))
+                    .description(tr!(lang, "preview.invalid", error = err))])
+                .ephemeral(true)
+        }
+    };
+
+    let mut set: crate::Set = set.upgrade();
+
+    // a single card doesn't come with its sigil descriptions so use magpie's
+    {
+        let g_sets = SETS.read().unwrap();
+        for (sigil, desc) in &mut set.sigils_description {
+            if desc.is_empty() {
+                *desc = g_sets
+                    .values()
+                    .find_map(|s| s.sigils_description.get(sigil))
+                    .cloned()
+                    .unwrap_or_else(|| tr!(lang, "preview.unknown_sigil").to_owned());
+            }
+        }
+    }
+
+    if set.cards.is_empty() {
+        return MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::RED)
+                .title(tr!(lang, "preview.empty_title"))
+                .description(tr!(lang, "preview.empty"))])
+            .ephemeral(true);
+    }
+
+    let mut embeds = vec![];
+    let mut attachments: Vec<CreateAttachment> = vec![];
+
+    for card in set.cards.iter().take(MAX_PREVIEW_CARDS) {
+        let mut embed = gen_plain_embed(card, &set, false, lang);
+
+        let filename = portrait_filename(card);
+        embed = embed.thumbnail(format!("attachment://{filename}"));
+
+        if !attachments.iter().any(|a| a.filename == filename) {
+            attachments.push(CreateAttachment::bytes(gen_portrait(card), filename));
+        }
+
+        embeds.push(embed);
+    }
+
+    let mut msg = MessageAdapter::new()
+        .embeds(embeds)
+        .attachments(attachments);
+    if set.cards.len() > MAX_PREVIEW_CARDS {
+        msg = msg.content(tr!(
+            lang,
+            "preview.truncated",
+            shown = MAX_PREVIEW_CARDS,
+            total = set.cards.len()
+        ));
+    }
+
+    msg
+}
@@ -1,49 +1,309 @@
 //! Contain the main querying function and implementations.
 //!
-//! The query take in a input pass it into a simple lexer and then into a simple parser to get a
-//! list of keywords. These keywords then get converted into a set of filters to then be use for
-//! [`QueryBuilder`]
+//! The query is parse into filters by the engine [`parse_query`], the tutor only keywords are
+//! implement by [`FilterExt`]. The filters are then use for [`QueryBuilder`]
 
-use std::vec;
+use std::{collections::HashMap, sync::Arc};
 
-use magpie_engine::prelude::*;
-use poise::serenity_prelude::{colours::roles, CreateEmbed};
+use magpie_engine::{prelude::*, query::check_rarities};
+use poise::serenity_prelude::{
+    colours::roles, CreateAttachment, CreateEmbed, CreateEmbedFooter, GuildId,
+};
 
-use crate::{Filters, Set};
+use crate::{
+    emojis::ToEmoji,
+    engine::{FilterExt, MagpieCosts, MagpieExt},
+    formats::take_format,
+    locale::guild_lang,
+    setinfo::{cost_lines, gen_cost_chart, gen_temple_chart, temple_lines, COST_CHART_LEGEND},
+    spoiler::is_spoiler,
+    tr, Card, Filters, Set, CHART_REGEX,
+};
 
-mod lexer;
-mod parser;
+/// Most fields in an embed, results from more sets than this are not grouped.
+const MAX_FIELDS: usize = 25;
 
-use lexer::tokenize_query;
+/// Embed field values are limited to 1024 characters.
+const MAX_FIELD_LEN: usize = 1024;
 
-use self::parser::QueryParser;
+/// The 6000 characters limit is share by every embed of a message and a search can have many
+/// queries, so the grouped fields keep to the same length as a ungrouped result.
+const MAX_GROUPED_LEN: usize = 2000;
 
 macro_rules! unwrap {
-    ($expr:expr) => {
+    ($expr:expr, $lang:expr) => {
         match $expr {
             Ok(it) => it,
             Err(err) => {
                 return CreateEmbed::new()
                     .color(roles::RED)
-                    .title("Query Error")
+                    .title(tr!($lang, "query.error_title"))
                     .description(err)
             }
         }
     };
 }
 
-/// Query a message
-pub fn query_message(sets: Vec<&Set>, query: &str) -> CreateEmbed {
-    let tokens = unwrap!(tokenize_query(query));
-    let keywords = unwrap!(QueryParser::gen_ast_with(tokens));
+/// The chart of the `chart:<kind>` keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    /// The cost curves of the results.
+    Cost,
+    /// How many results are in each temple.
+    Temple,
+}
+
+/// Take the `chart:<kind>` keyword out of a query.
+///
+/// Return the query without the keyword and the chart if there is one, or an error message if
+/// the kind is not `cost` or `temple`.
+pub fn take_chart(query: &str) -> Result<(String, Option<ChartKind>), String> {
+    let Some(caps) = CHART_REGEX.captures(query) else {
+        return Ok((query.to_owned(), None));
+    };
+
+    let kind = match caps[1].to_lowercase().as_str() {
+        "cost" => ChartKind::Cost,
+        "temple" => ChartKind::Temple,
+        kind => return Err(format!("Unknown chart `{kind}`, use `cost` or `temple`")),
+    };
+
+    Ok((CHART_REGEX.replace(query, "").into_owned(), Some(kind)))
+}
+
+/// Generate the chart embed of query results, the image is attach as `filename`.
+///
+/// Return [`None`] if there is nothing to chart.
+pub fn chart_embed(
+    cards: &[&Card],
+    kind: ChartKind,
+    filename: &str,
+) -> Option<(CreateEmbed, CreateAttachment)> {
+    let stats = SetStats::from_cards(cards.iter().copied());
+    let (image, desc, legend) = match kind {
+        ChartKind::Cost => (
+            gen_cost_chart(&stats)?,
+            cost_lines(&stats),
+            COST_CHART_LEGEND,
+        ),
+        ChartKind::Temple => (
+            gen_temple_chart(&stats)?,
+            temple_lines(&stats),
+            "Temples in the same order as the list, card with many temples are count in each",
+        ),
+    };
+
+    let embed = CreateEmbed::new()
+        .color(roles::PURPLE)
+        .title(format!("Chart: {} results", stats.size))
+        .description(desc)
+        .image(format!("attachment://{filename}"))
+        .footer(CreateEmbedFooter::new(legend));
+
+    Some((embed, CreateAttachment::bytes(image, filename)))
+}
+
+/// Type alias for the result of a tutor query, it keep its sets alive so it can be store as the
+/// state of a interaction.
+pub type QueryResult = OwnedQuery<MagpieExt, MagpieCosts, FilterExt>;
+
+/// Parse a query and run it on the sets.
+///
+/// If there is a guild and the query have a `format:<name>` keyword, the sets are replaced with
+/// the format allowed sets and cards banned in the format are removed from the result. Cards from
+/// the guild spoiler sets are never in the result.
+#[allow(clippy::implicit_hasher)]
+pub fn run_query(
+    sets: Vec<Arc<Set>>,
+    all_sets: &HashMap<&'static str, Arc<Set>>,
+    query: &str,
+    guild_id: Option<GuildId>,
+) -> Result<QueryResult, String> {
+    run_query_counted(sets, all_sets, query, guild_id).map(|(query, _)| query)
+}
+
+/// How many cards were removed from a query result after the filters.
+#[derive(Debug, Default, Clone, Copy)]
+struct Removed {
+    /// Cards banned in the query format.
+    banned: usize,
+    /// Cards from the guild spoiler sets.
+    spoiler: usize,
+}
+
+/// [`run_query`] but also count the cards removed after the filters.
+fn run_query_counted(
+    mut sets: Vec<Arc<Set>>,
+    all_sets: &HashMap<&'static str, Arc<Set>>,
+    query: &str,
+    guild_id: Option<GuildId>,
+) -> Result<(QueryResult, Removed), String> {
+    let (query, format) = match guild_id {
+        Some(guild_id) => take_format(query, guild_id)?,
+        None => (query.to_owned(), None),
+    };
+
+    if let Some(format) = &format {
+        sets = format
+            .sets
+            .iter()
+            .filter_map(|code| all_sets.get(code.as_str()).cloned())
+            .collect();
+    }
+
+    let mut filters: Vec<Filters> = parse_query(&query).map_err(|e| e.to_string())?;
+    {
+        let sets = sets.iter().map(Arc::as_ref).collect::<Vec<_>>();
+        check_rarities(&filters, &sets).map_err(|e| e.to_string())?;
+        for filter in &mut filters {
+            fill_sigil_category(filter, &sets);
+        }
+    }
+
+    let mut builder = QueryBuilder::from_arcs(sets);
+    for filter in filters {
+        builder.add_filter_mut(filter);
+    }
+    let mut query = builder.par_query();
+    let mut removed = Removed::default();
+
+    if let Some(format) = &format {
+        let before = query.indices.len();
+        query.retain(|c| !format.ruleset.is_banned(&c.name));
+        removed.banned = before - query.indices.len();
+    }
+    let before = query.indices.len();
+    query.retain(|c| !is_spoiler(guild_id, c.set.code()));
+    removed.spoiler = before - query.indices.len();
+
+    Ok((query, removed))
+}
+
+/// Fill in the sigils of every sigil category filter with the sigils of that category in the sets.
+fn fill_sigil_category(filter: &mut Filters, sets: &[&Set]) {
+    match filter {
+        Filters::Extra(FilterExt::SigilCategory(category, sigils)) => {
+            for set in sets {
+                for sigil in set.sigils_in_category(category) {
+                    if !sigils.iter().any(|s| s == sigil) {
+                        sigils.push(sigil.to_owned());
+                    }
+                }
+            }
+        }
+        Filters::Or(a, b) => {
+            fill_sigil_category(a, sets);
+            fill_sigil_category(b, sets);
+        }
+        Filters::Not(f) => fill_sigil_category(f, sets),
+        Filters::AnyOf(filters) | Filters::AllOf(filters) => {
+            for f in filters {
+                fill_sigil_category(f, sets);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Group cards by set code, the sets and the cards keep the order they first appear in.
+fn group_by_set<'a>(cards: &[&'a Card]) -> Vec<(&'a str, Vec<&'a Card>)> {
+    let mut groups: Vec<(&str, Vec<&Card>)> = vec![];
+    for card in cards {
+        match groups.iter_mut().find(|(code, _)| *code == card.set.code()) {
+            Some((_, group)) => group.push(card),
+            None => groups.push((card.set.code(), vec![card])),
+        }
+    }
+
+    groups
+}
+
+/// Join the card names of a group so it fit in a field, the rest are only count.
+///
+/// The names are prefix with the rarity emoji when `emoji` is set.
+fn group_value(cards: &[&Card], emoji: bool) -> String {
+    let mut value = String::new();
+    for (i, card) in cards.iter().enumerate() {
+        let name = if emoji {
+            format!("{} {}", card.rarity.to_emoji(), card.name)
+        } else {
+            card.name.clone()
+        };
+        let more = format!("...and {} more", cards.len() - i);
+
+        if !value.is_empty() {
+            value.push_str(", ");
+        }
+
+        if value.len() + name.len() + more.len() + 2 > MAX_FIELD_LEN {
+            value.push_str(&more);
+            break;
+        }
+        value.push_str(&name);
+    }
 
-    let mut filters: Vec<Filters> = vec![];
+    value
+}
 
-    for kw in keywords {
-        filters.push(unwrap!(kw.try_into()));
+/// Add a field per set with the set cards to an embed.
+///
+/// The rarity emojis are drop when the fields are too long and the names too if it still is.
+fn grouped_fields(
+    mut embed: CreateEmbed,
+    groups: &[(&str, Vec<&Card>)],
+    all_sets: &HashMap<&'static str, Arc<Set>>,
+) -> CreateEmbed {
+    let values = [true, false]
+        .into_iter()
+        .map(|emoji| {
+            groups
+                .iter()
+                .map(|(_, cards)| group_value(cards, emoji))
+                .collect::<Vec<_>>()
+        })
+        .find(|values| values.iter().map(String::len).sum::<usize>() <= MAX_GROUPED_LEN)
+        .unwrap_or_else(|| vec![String::from("Too many to list"); groups.len()]);
+
+    for ((code, cards), value) in groups.iter().zip(values) {
+        let name = all_sets.get(code).map_or(*code, |s| s.name.as_str());
+        embed = embed.field(format!("{name} ({})", cards.len()), value, false);
     }
 
-    let query = QueryBuilder::with_filters(sets, filters).query();
+    embed
+}
+
+/// Query a message, return the result embed and the query result, [`None`] if the query is
+/// invalid.
+///
+/// Results from more than one set are grouped in a field per set. See [`run_query`] for how the
+/// `format:<name>` keyword is handle.
+#[allow(clippy::implicit_hasher)]
+#[tracing::instrument(skip(sets, all_sets, guild_id), fields(guild = ?guild_id))]
+pub fn query_message(
+    sets: Vec<Arc<Set>>,
+    all_sets: &HashMap<&'static str, Arc<Set>>,
+    query: &str,
+    guild_id: Option<GuildId>,
+) -> (CreateEmbed, Option<QueryResult>) {
+    let result = match run_query(sets, all_sets, query, guild_id) {
+        Ok(query) => query,
+        Err(err) => {
+            let embed = CreateEmbed::new()
+                .color(roles::RED)
+                .title(tr!(guild_lang(guild_id), "query.error_title"))
+                .description(err);
+            return (embed, None);
+        }
+    };
+    let query = result.as_query();
+    let cards = query.cards.clone();
+    let groups = group_by_set(&cards);
+    let grouped = groups.len() > 1 && groups.len() <= MAX_FIELDS;
+    let best = query
+        .cards
+        .first()
+        .map(|c| (c.name.as_str(), query.score(c)))
+        .filter(|(_, score)| *score > 0.0);
 
     let output = query
         .cards
@@ -52,24 +312,86 @@ pub fn query_message(sets: Vec<&Set>, query: &str) -> CreateEmbed {
         .collect::<Vec<_>>()
         .join(", ");
 
+    let filters = query
+        .filters
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(" and ");
+
+    let embed = CreateEmbed::new().color(roles::PURPLE).title(format!(
+        "Result: {} cards in selected sets",
+        query.cards.len()
+    ));
+
+    let embed = if grouped {
+        grouped_fields(
+            embed.description(format!("Cards that {filters}")),
+            &groups,
+            all_sets,
+        )
+    } else {
+        embed.description(if query.cards.len() >= 200 || output.len() >= 2000 {
+            String::from("Too many results...Try narrowing your search")
+        } else {
+            format!("Cards that {filters}\n{output}")
+        })
+    };
+
+    let embed = match best {
+        Some((name, score)) => embed.footer(CreateEmbedFooter::new(format!(
+            "Sorted by relevance, best match is {name} with a score of {score:.1}"
+        ))),
+        None => embed,
+    };
+
+    (embed, Some(result))
+}
+
+/// Explain a query, show how many cards each filter eliminated.
+///
+/// Use by the `dq[[...]]` modifier so users can see why their query return nothing.
+#[allow(clippy::implicit_hasher)]
+#[tracing::instrument(skip(sets, all_sets, guild_id), fields(guild = ?guild_id))]
+pub fn explain_message(
+    sets: Vec<Arc<Set>>,
+    all_sets: &HashMap<&'static str, Arc<Set>>,
+    query: &str,
+    guild_id: Option<GuildId>,
+) -> CreateEmbed {
+    let (result, removed) = unwrap!(
+        run_query_counted(sets, all_sets, query, guild_id),
+        guild_lang(guild_id)
+    );
+    let query = result.as_query();
+    let steps = query.explain();
+
+    let mut lines = vec![format!("Start with {} cards", query.pool.len())];
+    for step in &steps {
+        lines.push(format!(
+            "Cards that {}: eliminated {}, {} left",
+            step.filter, step.eliminated, step.remaining
+        ));
+    }
+
+    let mut remaining = steps.last().map_or(query.pool.len(), |s| s.remaining);
+    for (label, eliminated) in [
+        ("Banned in the format", removed.banned),
+        ("Spoiler cards", removed.spoiler),
+    ] {
+        if eliminated != 0 {
+            remaining -= eliminated;
+            lines.push(format!(
+                "{label}: eliminated {eliminated}, {remaining} left"
+            ));
+        }
+    }
+
     CreateEmbed::new()
         .color(roles::PURPLE)
         .title(format!(
-            "Result: {} cards in selected sets",
+            "Explain: {} cards in selected sets",
             query.cards.len()
         ))
-        .description(if query.cards.len() >= 200 || output.len() >= 2000 {
-            String::from("Too many results...Try narrowing your search")
-        } else {
-            format!(
-                "Cards that {}\n{}",
-                query
-                    .filters
-                    .into_iter()
-                    .map(|f| f.to_string())
-                    .collect::<Vec<String>>()
-                    .join(" and "),
-                output
-            )
-        })
+        .description(lines.join("\n"))
 }
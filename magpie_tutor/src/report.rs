@@ -0,0 +1,211 @@
+//! Implementation for error reporting.
+//!
+//! Errors from commands and events are log with a short correlation id, post to the report
+//! channel set in the `TUTOR_REPORT_CHANNEL` environment variable if there is one, and the user
+//! get a friendly embed with the same id so the error can be found later.
+
+use std::{env, fmt::Write};
+
+use poise::{
+    serenity_prelude::{
+        colours::roles, ChannelId, ComponentInteraction, Context, CreateEmbed,
+        CreateInteractionResponse, CreateInteractionResponseFollowup,
+        CreateInteractionResponseMessage, CreateMessage, FullEvent, GuildId, Interaction, UserId,
+    },
+    CreateReply, FrameworkError,
+};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+
+use crate::{error, Color, Data, Error};
+
+/// Environment variable with the channel id to post error reports in.
+pub const REPORT_CHANNEL_VAR: &str = "TUTOR_REPORT_CHANNEL";
+
+/// Maximum length of the error message in a report, embed fields are limited to 1024 characters.
+const MAX_ERROR_LEN: usize = 900;
+
+/// Where an error happen.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorSource {
+    /// What was running when the error happen, like a command or event name.
+    pub origin: String,
+    /// The guild the error happen in.
+    pub guild_id: Option<GuildId>,
+    /// The user that trigger the error.
+    pub user_id: Option<UserId>,
+}
+
+/// Generate a short random id to match the user message with the report.
+pub fn correlation_id() -> String {
+    thread_rng()
+        .sample_iter(Alphanumeric)
+        .take(8)
+        .map(char::from)
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Remove anything that should not be post to a channel from an error message.
+pub fn sanitize(err: &str) -> String {
+    let mut out = err.replace("```", "'''");
+
+    if let Ok(token) = env::var("TUTOR_TOKEN") {
+        if !token.is_empty() {
+            out = out.replace(&token, "[token]");
+        }
+    }
+
+    if out.chars().count() > MAX_ERROR_LEN {
+        out = out.chars().take(MAX_ERROR_LEN).collect::<String>() + "...";
+    }
+
+    out
+}
+
+/// The embed shown to the user when something goes wrong.
+pub fn user_error_embed(id: &str) -> CreateEmbed {
+    CreateEmbed::new()
+        .color(roles::RED)
+        .title("Something went wrong")
+        .description(format!(
+            "Something went wrong, it's been reported.\nIf you need help mention this id: `{id}`"
+        ))
+}
+
+/// Log an error and post it to the report channel.
+///
+/// Return the correlation id of the report.
+pub async fn report_error(ctx: &Context, err: &Error, source: &ErrorSource) -> String {
+    let id = correlation_id();
+    error!(
+        "[{}] Error in {} due to: {err}",
+        id.yellow(),
+        source.origin.blue()
+    );
+
+    let Some(channel_id) = env::var(REPORT_CHANNEL_VAR)
+        .ok()
+        .and_then(|c| c.parse::<u64>().ok())
+        .map(ChannelId::new)
+    else {
+        return id;
+    };
+
+    let mut location = String::new();
+    if let Some(guild_id) = source.guild_id {
+        writeln!(location, "**Guild:** {guild_id}").unwrap();
+    }
+    if let Some(user_id) = source.user_id {
+        writeln!(location, "**User:** {user_id}").unwrap();
+    }
+
+    let embed = CreateEmbed::new()
+        .color(roles::RED)
+        .title(format!("Error `{id}`"))
+        .description(format!("**Origin:** {}\n{location}", source.origin))
+        .field(
+            "Error",
+            format!("```\n{}\n```", sanitize(&err.to_string())),
+            false,
+        );
+
+    if let Err(err) = channel_id
+        .send_message(ctx, CreateMessage::new().embed(embed))
+        .await
+    {
+        error!("Cannot post error report {id} due to: {err}");
+    }
+
+    id
+}
+
+/// Poise error handler, report command and event errors and tell the user about it.
+pub async fn on_error(err: FrameworkError<'_, Data, Error>) {
+    match err {
+        FrameworkError::Command { error, ctx, .. } => {
+            let source = ErrorSource {
+                origin: format!("command /{}", ctx.command().qualified_name),
+                guild_id: ctx.guild_id(),
+                user_id: Some(ctx.author().id),
+            };
+            let id = report_error(ctx.serenity_context(), &error, &source).await;
+
+            if let Err(err) = ctx
+                .send(
+                    CreateReply::default()
+                        .embed(user_error_embed(&id))
+                        .ephemeral(true),
+                )
+                .await
+            {
+                error!("Cannot send error message for {id} due to: {err}");
+            }
+        }
+        FrameworkError::EventHandler {
+            error, ctx, event, ..
+        } => event_error(ctx, &error, event).await,
+        err => {
+            if let Err(err) = poise::builtins::on_error(err).await {
+                error!("Cannot handle framework error due to: {err}");
+            }
+        }
+    }
+}
+
+async fn event_error(ctx: &Context, error: &Error, event: &FullEvent) {
+    let mut source = ErrorSource {
+        origin: format!("{} event", event.snake_case_name()),
+        ..Default::default()
+    };
+
+    match event {
+        FullEvent::Message { new_message: msg } => {
+            source.guild_id = msg.guild_id;
+            source.user_id = Some(msg.author.id);
+
+            let id = report_error(ctx, error, &source).await;
+            let reply = CreateMessage::new()
+                .embed(user_error_embed(&id))
+                .reference_message(msg);
+
+            if let Err(err) = msg.channel_id.send_message(ctx, reply).await {
+                error!("Cannot send error message for {id} due to: {err}");
+            }
+        }
+        FullEvent::InteractionCreate {
+            interaction: Interaction::Component(interaction),
+        } => {
+            source.guild_id = interaction.guild_id;
+            source.user_id = Some(interaction.user.id);
+
+            let id = report_error(ctx, error, &source).await;
+            interaction_error(ctx, interaction, &id).await;
+        }
+        _ => {
+            report_error(ctx, error, &source).await;
+        }
+    }
+}
+
+/// Tell the user about an error in an interaction, following up if it was already responded.
+async fn interaction_error(ctx: &Context, interaction: &ComponentInteraction, id: &str) {
+    let msg = CreateInteractionResponseMessage::new()
+        .embed(user_error_embed(id))
+        .ephemeral(true);
+
+    if interaction
+        .create_response(ctx, CreateInteractionResponse::Message(msg))
+        .await
+        .is_ok()
+    {
+        return;
+    }
+
+    let followup = CreateInteractionResponseFollowup::new()
+        .embed(user_error_embed(id))
+        .ephemeral(true);
+
+    if let Err(err) = interaction.create_followup(ctx, followup).await {
+        error!("Cannot send error message for {id} due to: {err}");
+    }
+}
@@ -0,0 +1,238 @@
+//! Implementation for the rulings store.
+//!
+//! Rulings come from the rulings tab of the sheet for the sets that have one and from the local
+//! rulings file at [`RULINGS_FILE_PATH`] where the judges add official clarifications with
+//! `/ruling add`. The local rulings are timestamp and apply to the cards every time the set is
+//! fetch, the same way as the [`overrides`](crate::overrides). The rulings file is share by every
+//! shard process and reload when another process change it.
+//!
+//! ```toml
+//! [[std.Stoat]]
+//! text = "Stoat can be play on the first turn with the starting blood."
+//! time = 1700000000
+//! ```
+
+use std::{collections::HashMap, io::ErrorKind, mem, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    done, error,
+    shard::{read_shared, replace_file, shared_changed, with_shared_lock},
+    Color, Set, RULINGS, SETS,
+};
+
+/// Location of the rulings file.
+pub const RULINGS_FILE_PATH: &str = "./rulings.toml";
+
+/// Longest ruling that can be added, so a card rulings still fit in an embed.
+const MAX_RULING_LEN: usize = 1000;
+
+/// A ruling on a card.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Ruling {
+    /// The ruling text.
+    pub text: String,
+    /// When the ruling was added as a unix timestamp, rulings from the sheet have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<i64>,
+}
+
+impl From<String> for Ruling {
+    fn from(text: String) -> Self {
+        Ruling { text, time: None }
+    }
+}
+
+/// Type alias for the rulings table, set code to card name to rulings in the order they were
+/// added.
+pub type Rulings = HashMap<String, HashMap<String, Vec<Ruling>>>;
+
+/// Load the rulings from the rulings file.
+pub(crate) fn load_rulings() -> Rulings {
+    let content = match read_shared(RULINGS_FILE_PATH) {
+        Ok(c) => String::from_utf8_lossy(&c).into_owned(),
+        Err(err) if err.kind() == ErrorKind::NotFound => return Rulings::new(),
+        Err(err) => {
+            error!("Cannot read rulings file {RULINGS_FILE_PATH} due to: {err}");
+            return Rulings::new();
+        }
+    };
+
+    toml::from_str(&content).unwrap_or_else(|err| {
+        error!("Cannot parse rulings file {RULINGS_FILE_PATH} due to: {err}");
+        Rulings::new()
+    })
+}
+
+/// Save the rulings to the rulings file.
+///
+/// This lock [`RULINGS`] so make sure you drop any guard before calling this.
+pub fn save_rulings() {
+    let content = toml::to_string(&*RULINGS.lock().unwrap()).expect("Cannot serialize rulings");
+    replace_file(RULINGS_FILE_PATH, content.as_bytes());
+    done!("Rulings save successfully to {}", RULINGS_FILE_PATH.green());
+}
+
+/// Reload the rulings if another process change them, the cards of the loaded sets are update too.
+///
+/// This lock [`SETS`] and [`RULINGS`] so make sure you drop any guard before calling this.
+pub fn reload_rulings() {
+    if !shared_changed(RULINGS_FILE_PATH) {
+        return;
+    }
+
+    let old = mem::replace(&mut *RULINGS.lock().unwrap(), load_rulings());
+    let mut sets = SETS.write().unwrap();
+    for (code, set) in sets.iter_mut() {
+        let set = Arc::make_mut(set);
+        // the old local rulings are remove before the new ones are add
+        for (name, rulings) in old.get(*code).into_iter().flatten() {
+            if let Some(card) = set.cards.iter_mut().find(|c| c.name == *name) {
+                card.extra.rulings.retain(|r| !rulings.contains(r));
+            }
+        }
+        apply_rulings(code, set);
+    }
+}
+
+/// Add the local rulings of a set code to the cards of a set.
+pub fn apply_rulings(code: &str, set: &mut Set) {
+    let rulings = RULINGS.lock().unwrap();
+    let Some(cards) = rulings.get(code) else {
+        return;
+    };
+
+    for (name, rulings) in cards {
+        let Some(card) = set.cards.iter_mut().find(|c| c.name == *name) else {
+            error!(
+                "Rulings for card {} does not match any card in {}",
+                name.red(),
+                set.name
+            );
+            continue;
+        };
+
+        card.extra.rulings.extend(rulings.iter().cloned());
+    }
+}
+
+/// Add a ruling to a card, the card is look up ignoring case.
+///
+/// Return the card name or an error message for the user.
+pub fn add_ruling(code: &str, name: &str, text: &str) -> Result<String, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(String::from("The ruling cannot be empty."));
+    }
+    if text.chars().count() > MAX_RULING_LEN {
+        return Err(format!(
+            "Rulings can only be up to {MAX_RULING_LEN} characters long."
+        ));
+    }
+
+    let ruling = Ruling {
+        text: text.to_owned(),
+        time: Some(chrono::Utc::now().timestamp()),
+    };
+
+    with_shared_lock(RULINGS_FILE_PATH, || {
+        reload_rulings();
+        insert_ruling(code, name, ruling)
+    })
+}
+
+fn insert_ruling(code: &str, name: &str, ruling: Ruling) -> Result<String, String> {
+    let name = {
+        let mut sets = SETS.write().unwrap();
+        let set = sets
+            .get_mut(code)
+            .map(Arc::make_mut)
+            .ok_or_else(|| format!("Unknown set code `{code}`."))?;
+        let card = set
+            .cards
+            .iter_mut()
+            .find(|c| c.name.eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("There is no card named `{}` in {}.", name.trim(), set.name))?;
+
+        card.extra.rulings.push(ruling.clone());
+        card.name.clone()
+    };
+
+    RULINGS
+        .lock()
+        .unwrap()
+        .entry(code.to_owned())
+        .or_default()
+        .entry(name.clone())
+        .or_default()
+        .push(ruling);
+    save_rulings();
+
+    Ok(name)
+}
+
+/// Remove a local ruling from a card, `index` start at 1 and only count the local rulings.
+///
+/// Return the removed ruling or an error message for the user.
+pub fn remove_ruling(code: &str, name: &str, index: usize) -> Result<Ruling, String> {
+    with_shared_lock(RULINGS_FILE_PATH, || {
+        reload_rulings();
+        take_ruling(code, name, index)
+    })
+}
+
+fn take_ruling(code: &str, name: &str, index: usize) -> Result<Ruling, String> {
+    let (card, removed) = {
+        let mut rulings = RULINGS.lock().unwrap();
+        let (card, list) = rulings
+            .get_mut(code)
+            .and_then(|cards| {
+                cards
+                    .iter_mut()
+                    .find(|(card, _)| card.eq_ignore_ascii_case(name.trim()))
+            })
+            .ok_or_else(|| format!("`{}` have no rulings in `{code}`.", name.trim()))?;
+
+        if index == 0 || index > list.len() {
+            return Err(format!("`{card}` only have {} rulings.", list.len()));
+        }
+
+        let card = card.clone();
+        let removed = list.remove(index - 1);
+        if list.is_empty() {
+            rulings.get_mut(code).unwrap().remove(&card);
+        }
+
+        (card, removed)
+    };
+
+    if let Some(card) = SETS
+        .write()
+        .unwrap()
+        .get_mut(code)
+        .map(Arc::make_mut)
+        .and_then(|set| set.cards.iter_mut().find(|c| c.name == card))
+    {
+        card.extra.rulings.retain(|r| *r != removed);
+    }
+    save_rulings();
+
+    Ok(removed)
+}
+
+/// Get the local rulings of a card, the card is look up ignoring case.
+pub fn card_rulings(code: &str, name: &str) -> Vec<Ruling> {
+    reload_rulings();
+    RULINGS
+        .lock()
+        .unwrap()
+        .get(code)
+        .and_then(|cards| {
+            cards
+                .iter()
+                .find(|(card, _)| card.eq_ignore_ascii_case(name.trim()))
+        })
+        .map(|(_, list)| list.clone())
+        .unwrap_or_default()
+}
@@ -1,41 +1,201 @@
 //! Contain the main search function and implementations.
-use std::{hash::Hash, time::Instant, vec};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    hash::Hash,
+    mem,
+    sync::Arc,
+    time::Instant,
+    vec,
+};
 
 use bitflags::bitflags;
+use magpie_engine::{fetch::to_imf_set, printings};
 use poise::serenity_prelude::{
     colours::roles,
-    ButtonStyle::{Danger, Primary},
+    ButtonStyle::{Danger, Primary, Secondary},
     Context,
-    CreateActionRow::Buttons,
-    CreateAttachment, CreateButton, CreateEmbed, CreateMessage, GuildId, Message,
+    CreateActionRow::{Buttons, SelectMenu},
+    CreateAttachment, CreateButton, CreateEmbed, CreateMessage, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption, EditAttachments, EditMessage, GuildId, Message,
 };
+use serde::{Deserialize, Serialize};
+use tokio::task;
 
 use crate::{
-    current_epoch, done, fuzzy_best, hash_card_url, info, query::query_message, save_cache,
-    CacheData, Card, Color, Death, FuzzyRes, MessageAdapter, MessageCreateExt, Res, CACHE,
-    CACHE_REGEX, DEBUG_CARD, SEARCH_REGEX, SETS,
+    cache::{
+        cache_channel, cached_url, is_expired, refresh_expired, upload_portraits, PendingPortrait,
+    },
+    delimiter::search_regex,
+    done, error,
+    favorites::FAVORITE_PREFIX,
+    fuzzy_best, hash_card_url,
+    history::record_history,
+    homebrew, info,
+    interaction::InteractionStore,
+    locale::{guild_lang, language_name},
+    portrait_filename,
+    query::{chart_embed, explain_message, query_message, take_chart, QueryResult},
+    rulings::reload_rulings,
+    save_cache,
+    spoiler::{is_spoiler, spoiler_embed, SPOILER_PREFIX},
+    stats::{record_search, SearchRecord},
+    timeline::{has_timeline, TIMELINE_PREFIX},
+    tr,
+    translation::{find_translated, CardAlias},
+    CacheData, Card, Color, Death, FuzzyRes, MessageAdapter, MessageCreateExt, Res, Set, CACHE,
+    CACHE_REGEX, DEBUG_CARD, IGNORE_REGEX, LOCALES, PLACEHOLDER_PORTRAIT, SETS,
 };
 
 mod portrait;
-#[allow(clippy::wildcard_imports)]
-use portrait::*;
+pub use portrait::{
+    gen_portrait, gen_portraits, load_portrait_filters, load_portrait_limits, missing_filename,
+    missing_portrait, parse_portrait_filters, try_gen_portrait, PortraitFilter, PortraitLru,
+    MISSING_PORTRAIT, PORTRAIT_FILTERS_VAR, PORTRAIT_MAX_BYTES_VAR, PORTRAIT_TIMEOUT_VAR,
+};
 
 mod embed;
+pub use embed::gen_plain_embed;
 #[allow(clippy::wildcard_imports)]
 use embed::*;
 
+/// Message starting with this are never search.
+pub const NO_SEARCH_PREFIX: &str = "!nosearch";
+
+/// Custom id prefix of the other printings buttons.
+pub const PRINTING_PREFIX: &str = "print_";
+
+/// Custom id prefix of the rulings buttons.
+pub const RULINGS_PREFIX: &str = "rulings_";
+
+/// Filename of the placeholder portrait of a two phase reply.
+const PLACEHOLDER_FILENAME: &str = "placeholder.png";
+
+/// Custom id prefix of the retry buttons, the state is a [`SearchState`].
+pub const RETRY_PREFIX: &str = "retry_";
+
+/// Custom id prefix of the retry in all sets buttons, the state is a [`SearchState`].
+pub const RETRY_ALL_PREFIX: &str = "retryall_";
+
+/// Custom id prefix of the query result select menus, the state is the result of every query in
+/// the message and the options are the query and result index.
+pub const QUERY_PICK_PREFIX: &str = "querypick_";
+
+/// Most other printings buttons in a search message, Discord allow 5 buttons per row.
+const MAX_JUMPS: usize = 5;
+
+/// Most query results in the select menu, Discord allow 25 options per select menu.
+const MAX_QUERY_PICKS: usize = 25;
+
+/// Discord allow 5 rows of components per message.
+const MAX_ROWS: usize = 5;
+
+/// Discord limit custom ids to 100 characters.
+const MAX_CUSTOM_ID_LEN: usize = 100;
+
+/// Message content are limited to 2000 characters, leave some room for the completed line.
+const MAX_SUMMARY_LEN: usize = 1800;
+
 bitflags! {
+    #[derive(Clone, Copy)]
     struct Modifier: u8 {
         const QUERY = 1;
         const ALL_SET = 1 << 1;
         const DEBUG = 1 << 2;
         const COMPACT = 1 << 3;
+        const SKIP = 1 << 4;
     }
 }
 
+/// A modifier in the [`MODIFIERS`] registry.
+struct ModifierInfo {
+    /// The character of the modifier in the search.
+    char: char,
+    /// The flag the modifier set, [`process_search`] handle the search according to it.
+    flag: Modifier,
+    /// What the modifier do, show in the help.
+    description: &'static str,
+}
+
+/// Every modifier, both the search and the help use this so a new modifier only need to be add
+/// here and handle in [`process_search`].
+const MODIFIERS: &[ModifierInfo] = &[
+    ModifierInfo {
+        char: 'q',
+        flag: Modifier::QUERY,
+        description: "Query instead of normal fuzzy search",
+    },
+    ModifierInfo {
+        char: '*',
+        flag: Modifier::ALL_SET,
+        description: "Select all supported set",
+    },
+    ModifierInfo {
+        char: 'd',
+        flag: Modifier::DEBUG,
+        description: "Output the raw data instead of embed, or explain what each filter \
+            eliminated when use with q",
+    },
+    ModifierInfo {
+        char: 'c',
+        flag: Modifier::COMPACT,
+        description: "Output the embed in compact mode to save space",
+    },
+    ModifierInfo {
+        char: '`',
+        flag: Modifier::SKIP,
+        description: "Skip this search match",
+    },
+];
+
+/// Get the character and description of every modifier.
+pub fn modifiers() -> impl Iterator<Item = (char, &'static str)> {
+    MODIFIERS.iter().map(|m| (m.char, m.description))
+}
+
+/// Every `key=value` option with its description, the help is generate from this.
+///
+/// Options are separate from each other and from the modifiers with `;`, like
+/// `c;set=aug,std;lang=fr[[stoat]]`.
+const OPTIONS: &[(&str, &str)] = &[
+    (
+        "set",
+        "Search in these sets, the set codes are separate by `,`",
+    ),
+    (
+        "lang",
+        "Only match the card names of this language and show the result in it when the bot \
+            speak it",
+    ),
+];
+
+/// Get the key and description of every option.
+pub fn options() -> impl Iterator<Item = (&'static str, &'static str)> {
+    OPTIONS.iter().copied()
+}
+
+/// The original search of a message, keep for the retry buttons.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchState {
+    /// The message content with the modifiers.
+    pub content: String,
+    /// The guild the search was in, `None` for DMs.
+    pub guild_id: Option<u64>,
+}
+
 /// Main searching function.
-pub async fn search_message(ctx: &Context, msg: &Message, guild_id: GuildId) -> Res {
-    if !SEARCH_REGEX.is_match(&msg.content) {
+///
+/// The reply is send right away with a placeholder for the portraits that are not cache, then it
+/// is edit with the real portraits once they are generated. The search also work in DMs, where the
+/// guild settings are skip and [`DEFAULT_SET`] is use.
+#[tracing::instrument(skip_all, fields(guild = ?guild_id, user = %msg.author.id))]
+pub async fn search_message(
+    ctx: &Context,
+    msg: &Message,
+    guild_id: Option<GuildId>,
+    interactions: &InteractionStore,
+) -> Res {
+    if search_matches(&msg.content, guild_id).next().is_none() {
         return Ok(());
     }
     info!(
@@ -44,34 +204,177 @@ pub async fn search_message(ctx: &Context, msg: &Message, guild_id: GuildId) ->
         msg.author.name.magenta()
     );
 
-    let msg = msg
+    let (author, content) = (msg.author.id, msg.content.clone());
+
+    // reply right away with placeholders then edit the portraits in once they are generated
+    let (reply, pending) = build_search(&content, guild_id, false, interactions, true);
+    let mut msg = msg
         .channel_id
-        .send_message(
-            &ctx.http,
-            Into::<CreateMessage>::into(process_search(&msg.content, guild_id)).reply(msg),
-        )
+        .send_message(&ctx.http, Into::<CreateMessage>::into(reply).reply(msg))
         .await?;
 
+    if let Some(pending) = pending {
+        let card_ids = pending
+            .portraits
+            .iter()
+            .map(|(c, _, _)| c.id())
+            .collect::<Vec<_>>();
+        if let Err(err) = refresh_expired(&ctx.http, &card_ids).await {
+            error!("Cannot refresh the expired portraits due to: {err}");
+        }
+
+        // only the missing portraits are generate, the rest of the reply is already done
+        let cards = pending
+            .portraits
+            .iter()
+            .map(|(c, _, _)| c)
+            .filter(|c| cached_url(c).is_none())
+            .collect::<Vec<_>>();
+        let data = task::block_in_place(|| gen_portraits(&cards))
+            .into_iter()
+            .zip(&cards)
+            .filter_map(|(data, card)| Some((card.id(), data?)))
+            .collect::<HashMap<_, _>>();
+
+        if let Some(channel_id) = cache_channel() {
+            let portraits = cards
+                .iter()
+                .filter_map(|card| {
+                    Some(PendingPortrait::with_data(
+                        card,
+                        data.get(&card.id())?.clone(),
+                    ))
+                })
+                .collect();
+            if let Err(err) = upload_portraits(ctx, channel_id, portraits).await {
+                error!("Cannot upload portraits to the cache channel due to: {err}");
+            }
+        }
+
+        msg.edit(&ctx.http, pending.finish(data)).await?;
+    }
+
+    // the portraits upload to the cache channel are already cache and skip, the ones that
+    // failed to upload are attach to the reply so they are cache from it instead
     update_cache(&msg);
+    record_history(author, guild_id, &content);
 
     Ok(())
 }
 
-/// Process a search with a content and return the message to send
-pub fn process_search(content: &str, guild_id: GuildId) -> MessageAdapter {
-    let start = Instant::now();
+/// Get the search matches of a content that are not inside code, spoilers or quotes.
+///
+/// The matches use the delimiter of the guild. There is none if the content start with
+/// [`NO_SEARCH_PREFIX`].
+pub(crate) fn search_matches(
+    content: &str,
+    guild_id: Option<GuildId>,
+) -> impl Iterator<Item = regex::Captures<'_>> {
+    let opt_out = content.trim_start().starts_with(NO_SEARCH_PREFIX);
+    let ignored = IGNORE_REGEX
+        .find_iter(content)
+        .map(|m| m.range())
+        .collect::<Vec<_>>();
 
-    let mut embeds = vec![];
-    let mut attachments: Vec<CreateAttachment> = vec![];
+    // the modifiers eat any text before the delimiter, like the end of `||s||[[stoat]]`, so the
+    // term start is check instead of the match start
+    search_regex(guild_id)
+        .captures_iter(content)
+        .filter(move |c| {
+            let start = c.get(2).map_or(0, |m| m.start());
+            !opt_out && !ignored.iter().any(|r| r.contains(&start))
+        })
+}
+
+/// What a search term resolve to, list in the search summary.
+enum TermStatus {
+    /// The card name and set code the term match.
+    Found(String, String),
+    /// The set code the term was not found in.
+    NotFound(String),
+    /// The card name and set code of a card that is already show.
+    Skipped(String, String),
+    /// The set code of the spoiler card, the name is hidden.
+    Spoiler(String),
+    /// The term is a query.
+    Query,
+}
 
-    let g_sets = SETS.lock().unwrap();
+impl TermStatus {
+    /// Generate the summary line of a term.
+    fn line(&self, term: &str, lang: &str) -> String {
+        // backtick would break out of the inline code
+        let term = term.replace('`', "'");
+        match self {
+            TermStatus::Found(name, set) => tr!(
+                lang,
+                "search.summary_found",
+                term = term,
+                name = name,
+                set = set
+            ),
+            TermStatus::NotFound(set) => {
+                tr!(lang, "search.summary_not_found", term = term, set = set)
+            }
+            TermStatus::Skipped(name, set) => tr!(
+                lang,
+                "search.summary_skipped",
+                term = term,
+                name = name,
+                set = set
+            ),
+            TermStatus::Spoiler(set) => tr!(lang, "search.summary_spoiler", term = term, set = set),
+            TermStatus::Query => tr!(lang, "search.summary_query", term = term),
+        }
+    }
+}
 
-    'outer: for (modifier, search_term) in SEARCH_REGEX.captures_iter(content).map(|c| {
+/// A single search term along with the modifiers and sets it resolve to.
+struct SearchTerm<'a, 's> {
+    modifier: Modifier,
+    sets: Vec<&'s Arc<Set>>,
+    term: &'a str,
+    /// The language from the `lang` option.
+    lang: Option<&'a str>,
+}
+
+/// Split a message content into search terms, resolving each modifier, option and set code.
+///
+/// Skipped terms (the `` ` `` modifier) and terms in code, spoilers or quotes are left out of the
+/// result. A term with `;` is split into a term for each card. The guild homebrew set is
+/// search like the other sets but only in that guild. Every term search all sets if `all_sets` is
+/// set, like they all have the `*` modifier.
+fn parse_search<'a, 's>(
+    content: &'a str,
+    guild_id: Option<GuildId>,
+    g_sets: &'s HashMap<&'static str, Arc<Set>>,
+    homebrew: Option<&'s Arc<Set>>,
+    all_sets: bool,
+) -> Vec<SearchTerm<'a, 's>> {
+    let mut terms = vec![];
+
+    'outer: for (modifier, search_term) in search_matches(content, guild_id).map(|c| {
         (
             c.get(1).map_or("", |s| s.as_str()),
             c.get(2).map_or("", |s| s.as_str()),
         )
     }) {
+        let mut option_sets = vec![];
+        let mut lang = None;
+        let modifier = {
+            let mut rest = "";
+            for part in modifier.split(';') {
+                match part.split_once('=') {
+                    Some(("set", codes)) => option_sets.extend(codes.split(',')),
+                    Some(("lang", code)) => lang = Some(code),
+                    // unknown options are ignore like unknown modifiers
+                    Some(_) => (),
+                    None => rest = part,
+                }
+            }
+            rest
+        };
+
         let (set_code, modifier): (Vec<&str>, &str) = 'a: {
             // Just leave if we don;t have anything to process
             if modifier.is_empty() {
@@ -99,19 +402,22 @@ pub fn process_search(content: &str, guild_id: GuildId) -> MessageAdapter {
         };
 
         let modifier = {
-            let mut t = Modifier::empty();
+            let mut t = if all_sets {
+                Modifier::ALL_SET
+            } else {
+                Modifier::empty()
+            };
             for m in modifier.chars() {
-                t |= match m {
-                    'q' => Modifier::QUERY,
-                    '*' => Modifier::ALL_SET,
-                    'd' => Modifier::DEBUG,
-                    'c' => Modifier::COMPACT,
-                    '`' => continue 'outer, // exit this search term
-
-                    _ => continue,
+                if let Some(info) = MODIFIERS.iter().find(|info| info.char == m) {
+                    t |= info.flag;
                 }
             }
 
+            // exit this search term
+            if t.contains(Modifier::SKIP) {
+                continue 'outer;
+            }
+
             // smart detech query
             if search_term.contains(':') {
                 t |= Modifier::QUERY;
@@ -123,101 +429,527 @@ pub fn process_search(content: &str, guild_id: GuildId) -> MessageAdapter {
         let mut sets = vec![];
         if modifier.contains(Modifier::ALL_SET) {
             sets.extend(g_sets.values());
+            sets.extend(homebrew);
         } else {
-            for set in set_code {
-                if let Some(set) = g_sets.get(set) {
+            for code in set_code.into_iter().chain(option_sets) {
+                if let Some(set) = g_sets
+                    .get(code)
+                    .or(homebrew.filter(|h| h.code.code() == code))
+                {
                     sets.push(set);
                 }
             }
         }
 
+        // the default set may have fail to load
         if sets.is_empty() {
-            sets.push(
-                g_sets
-                    .get(match guild_id.get() {
-                    // Default to aug in the augmented server
-                    1028530290727063604 => "aug",
-                    // Default to des in the descryption server
-                    1257552767984074803 => "des",
-                    // Default to pvp in the pvp server
-                    1115010083168997376 => "cti",
-
-                    _ => "std",
-                })
-                .unwrap(),
-        );
+            sets.extend(g_sets.get(default_set(guild_id)));
+        }
+
+        // `[[stoat; warren]]` is a search for each card sharing the modifiers, queries are keep
+        // whole since `;` can be in their values
+        let mut parts = search_term
+            .split(';')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>();
+        if modifier.contains(Modifier::QUERY) || parts.is_empty() {
+            parts = vec![search_term];
+        }
+
+        for term in parts {
+            terms.push(SearchTerm {
+                modifier,
+                sets: sets.clone(),
+                term,
+                lang,
+            });
+        }
+    }
+
+    terms
+}
+
+/// The set search by default outside of guilds and in guilds without their own default.
+pub const DEFAULT_SET: &str = "std";
+
+/// Get the default set code of a guild, this is [`DEFAULT_SET`] outside of guilds.
+pub fn default_set(guild_id: Option<GuildId>) -> &'static str {
+    match guild_id.map_or(0, GuildId::get) {
+        // Default to aug in the augmented server
+        1028530290727063604 => "aug",
+        // Default to des in the descryption server
+        1257552767984074803 => "des",
+        // Default to pvp in the pvp server
+        1115010083168997376 => "cti",
+
+        _ => DEFAULT_SET,
+    }
+}
+
+/// Fuzzy search for the best matching card in a set.
+///
+/// The localized card names are search too, see [`translation`](crate::translation).
+pub fn find_card<'a>(search_term: &str, set: &'a Set) -> Option<FuzzyRes<'a, Card>> {
+    find_card_translated(search_term, set, None).map(|(res, _)| res)
+}
+
+/// Same as [`find_card`] but also return the localized name when the card was match by one.
+///
+/// The English name win when both match equally well. Only the names of `lang` are search if
+/// there is one.
+pub fn find_card_translated<'a>(
+    search_term: &str,
+    set: &'a Set,
+    lang: Option<&str>,
+) -> Option<(FuzzyRes<'a, Card>, Option<&'static CardAlias>)> {
+    if search_term == "old_data" {
+        return Some((
+            FuzzyRes {
+                rank: 4.2,
+                data: &*DEBUG_CARD,
+            },
+            None,
+        ));
+    }
+
+    let english = fuzzy_best(search_term, set.cards.iter().collect(), 0.5, |c: &Card| {
+        c.name.as_str()
+    });
+
+    match (english, find_translated(search_term, set, lang)) {
+        (Some(res), Some((translated, _))) if res.rank >= translated.rank => Some((res, None)),
+        (_, Some((translated, alias))) => Some((translated, Some(alias))),
+        (res, None) => res.map(|res| (res, None)),
     }
+}
+
+/// Process a search with a content and return the message to send
+///
+/// The search is store in `interactions` for the retry buttons, `all_sets` search every term in
+/// all sets.
+pub fn process_search(
+    content: &str,
+    guild_id: Option<GuildId>,
+    all_sets: bool,
+    interactions: &InteractionStore,
+) -> MessageAdapter {
+    build_search(content, guild_id, all_sets, interactions, false).0
+}
+
+/// A search reply that is still waiting on some portraits, see [`build_search`].
+struct PendingSearch {
+    /// The embeds of the reply, the thumbnails already point at the portrait file names.
+    embeds: Vec<CreateEmbed>,
+    /// The attachments of the reply without the placeholder.
+    attachments: Vec<CreateAttachment>,
+    /// The cards, file name and embed indexes of the portraits to generate.
+    portraits: Vec<(Card, String, Vec<usize>)>,
+    record: SearchRecord,
+    start: Instant,
+}
+
+impl PendingSearch {
+    /// Generate the edit that show the portraits, `data` is the generated portraits by card id.
+    ///
+    /// The portraits that were refresh or upload to the cache channel meanwhile use the cached
+    /// attachment.
+    fn finish(self, mut data: HashMap<u64, Vec<u8>>) -> EditMessage {
+        let PendingSearch {
+            mut embeds,
+            mut attachments,
+            portraits,
+            mut record,
+            start,
+        } = self;
+
+        for (card, filename, indexes) in portraits {
+            let generated = data.remove(&card.id());
+            let url = cached_url(&card);
+            // refreshed portraits were in the cache all along
+            if generated.is_none() && url.is_some() {
+                record.cache_hits += 1;
+            } else {
+                record.cache_misses += 1;
+            }
+
+            if let Some(url) = url {
+                for i in indexes {
+                    embeds[i] = mem::take(&mut embeds[i]).thumbnail(&url);
+                }
+            } else {
+                // an expired entry that could not be refresh is replace by the reply one
+                CACHE
+                    .lock()
+                    .unwrap_or_die("Cannot lock cache")
+                    .remove(&card.id());
+                attach_portrait(
+                    &mut embeds,
+                    &mut attachments,
+                    (&card, filename, &indexes),
+                    generated,
+                );
+            }
+        }
+
+        record.latency = start.elapsed();
+        record_search(&record);
+
+        let mut new_attach = EditAttachments::new();
+        for a in attachments {
+            new_attach = new_attach.add(a);
+        }
+        EditMessage::new().embeds(embeds).attachments(new_attach)
+    }
+}
+
+/// Attach a generated portrait to a search reply, the embeds at the indexes show the card
+/// placeholder instead when the portrait failed to generate.
+fn attach_portrait(
+    embeds: &mut [CreateEmbed],
+    attachments: &mut Vec<CreateAttachment>,
+    (card, filename, indexes): (&Card, String, &[usize]),
+    data: Option<Vec<u8>>,
+) {
+    if let Some(data) = data {
+        attachments.push(CreateAttachment::bytes(data, filename));
+        return;
+    }
+
+    // the placeholder get its own file name so it is not cache as the card portrait
+    let filename = missing_filename(card);
+    for &i in indexes {
+        embeds[i] = mem::take(&mut embeds[i]).thumbnail(format!("attachment://{filename}"));
+    }
+    attachments.push(CreateAttachment::bytes(missing_portrait(card), filename));
+}
+
+/// Build the search message, with `placeholder` the portraits that are not cache are not
+/// generated and [`PLACEHOLDER_PORTRAIT`] is show instead.
+///
+/// Return the message and what it is waiting on if any portrait was left out. The stats are
+/// only record once every portrait is there so a two phase reply is not count twice.
+#[tracing::instrument(skip_all, fields(guild = ?guild_id))]
+fn build_search(
+    content: &str,
+    guild_id: Option<GuildId>,
+    all_sets: bool,
+    interactions: &InteractionStore,
+    placeholder: bool,
+) -> (MessageAdapter, Option<PendingSearch>) {
+    let start = Instant::now();
+    let lang = guild_lang(guild_id);
+
+    let mut embeds = vec![];
+    let mut attachments: Vec<CreateAttachment> = vec![];
+    let mut expandable = false;
+    // if any card can be export to IMF
+    let mut exportable = false;
+    // custom id and label of the other printings buttons
+    let mut jumps: Vec<(String, String)> = vec![];
+    // custom id and label of the rulings buttons
+    let mut rulings: Vec<(String, String)> = vec![];
+    // custom id and label of the History buttons
+    let mut timelines: Vec<(String, String)> = vec![];
+    // custom id and label of the favorite buttons
+    let mut favorites: Vec<(String, String)> = vec![];
+    // custom id of the reveal buttons for spoiler cards
+    let mut spoilers: Vec<String> = vec![];
+    // cards, filename and embed indexes of the portraits to generate once every term is search
+    let mut portraits: Vec<(&Card, String, Vec<usize>)> = vec![];
+    // same as `portraits` but for the ones replace by the placeholder
+    let mut pending: Vec<(&Card, String, Vec<usize>)> = vec![];
+    // set code and name of the cards already show, a card is only show once per set
+    let mut shown: HashSet<(&str, &str)> = HashSet::new();
+    // every search term and what it resolve to
+    let mut statuses: Vec<(&str, TermStatus)> = vec![];
+    // the query results and the query index, result index, set code and name of the ones to
+    // pick from
+    let mut queries: Vec<QueryResult> = vec![];
+    let mut picks: Vec<(usize, usize, String, String)> = vec![];
+    let mut record = SearchRecord::default();
+
+    // the rulings buttons need the rulings add by the other processes
+    reload_rulings();
+    let homebrew = homebrew::guild_set(guild_id).map(Arc::new);
+    let g_sets = SETS.read().unwrap();
+
+    for SearchTerm {
+        modifier,
+        sets,
+        term: search_term,
+        lang: term_lang,
+    } in parse_search(content, guild_id, &g_sets, homebrew.as_ref(), all_sets)
+    {
+        // the result is only show in the term language if the bot speak it
+        let lang = term_lang
+            .and_then(|l| LOCALES.get_key_value(l))
+            .map_or(lang, |(code, _)| *code);
+
+        let _span = tracing::info_span!("term", term = search_term).entered();
+
+        if modifier.contains(Modifier::QUERY | Modifier::DEBUG) {
+            let sets = sets.into_iter().cloned().collect();
+            embeds.push(explain_message(sets, &g_sets, search_term, guild_id));
+            statuses.push((search_term, TermStatus::Query));
+            record.queries += 1;
+            continue;
+        }
 
         if modifier.contains(Modifier::QUERY) {
-            embeds.push(query_message(sets, search_term));
+            let (query, chart) = match take_chart(search_term) {
+                Ok(res) => res,
+                Err(err) => {
+                    embeds.push(
+                        CreateEmbed::new()
+                            .color(roles::RED)
+                            .title(tr!(lang, "query.error_title"))
+                            .description(err),
+                    );
+                    continue;
+                }
+            };
+
+            let sets = sets.into_iter().cloned().collect();
+            let (embed, result) = query_message(sets, &g_sets, &query, guild_id);
+            embeds.push(embed);
+
+            if let Some(result) = result {
+                let cards = result.cards();
+
+                // the chart go right after the result it is for
+                let filename = format!("chart_{}.png", embeds.len());
+                if let Some((embed, image)) = chart.and_then(|k| chart_embed(&cards, k, &filename))
+                {
+                    embeds.push(embed);
+                    attachments.push(image);
+                }
+
+                // spoiler cards are already left out of the result by the query
+                for (i, card) in cards.iter().enumerate() {
+                    let code = card.set.code();
+                    if picks.len() < MAX_QUERY_PICKS
+                        && !picks
+                            .iter()
+                            .any(|(_, _, c, n)| c == code && *n == card.name)
+                    {
+                        picks.push((queries.len(), i, code.to_owned(), card.name.clone()));
+                    }
+                }
+                queries.push(result);
+            }
+            statuses.push((search_term, TermStatus::Query));
+            record.queries += 1;
             continue;
         }
 
         for set in sets {
-            let FuzzyRes { rank, data: card } = if search_term == "old_data" {
-                FuzzyRes {
-                    rank: 4.2,
-                    data: &*DEBUG_CARD,
-                }
-            } else if let Some(best) =
-                fuzzy_best(search_term, set.cards.iter().collect(), 0.5, |c: &Card| {
-                    c.name.as_str()
-                })
-            {
-                best
-            } else {
+            let Some((FuzzyRes { rank, data: card }, alias)) =
+                find_card_translated(search_term, set, term_lang)
+            else {
                 embeds.push({
                     CreateEmbed::new()
                         .color(roles::RED)
-                        .title(format!("Card \"{search_term}\" not found"))
-                        .description(
-                            "No card found with sufficient similarity with the search term in the selected set(s).",
-                        )
+                        .title(tr!(lang, "search.not_found_title", term = search_term))
+                        .description(tr!(lang, "search.not_found"))
                 });
+                statuses.push((
+                    search_term,
+                    TermStatus::NotFound(set.code.code().to_owned()),
+                ));
                 continue;
             };
 
-            if modifier.contains(Modifier::DEBUG) {
-                embeds.push(CreateEmbed::new().color(roles::BLUE).description(format!(
-                    "Hash: {:?}\n```\n{card:#?}\n```",
-                    hash_card_url(card)
-                )));
+            // spoiler names are hidden until the user reveal them, even for repeats
+            let spoiler = is_spoiler(guild_id, card.set.code());
+
+            // the same card from another set is fine, only the exact repeat is skip
+            if !shown.insert((card.set.code(), card.name.as_str())) {
+                statuses.push((
+                    search_term,
+                    if spoiler {
+                        TermStatus::Spoiler(card.set.code().to_owned())
+                    } else {
+                        TermStatus::Skipped(card.name.clone(), card.set.code().to_owned())
+                    },
+                ));
+                continue;
+            }
+
+            statuses.push((
+                search_term,
+                if spoiler {
+                    TermStatus::Spoiler(card.set.code().to_owned())
+                } else {
+                    TermStatus::Found(card.name.clone(), card.set.code().to_owned())
+                },
+            ));
+
+            // the full card is attach as json since it doesn't fit in a embed, spoiler cards
+            // are not since that would show the card
+            if modifier.contains(Modifier::DEBUG) && !spoiler {
+                let filename = format!("{}.json", card.id());
+                embeds.push(
+                    CreateEmbed::new()
+                        .color(roles::BLUE)
+                        .title(&card.name)
+                        .description(format!(
+                            "Id: {}\nSet: `{}`\nHash: {:?}\nData: `{filename}`",
+                            card.id(),
+                            card.set.code(),
+                            hash_card_url(card)
+                        )),
+                );
+
+                if !attachments.iter().any(|a| a.filename == filename) {
+                    match serde_json::to_vec_pretty(card) {
+                        Ok(json) => attachments.push(CreateAttachment::bytes(json, filename)),
+                        Err(err) => error!("Cannot serialize card {} due to: {err}", card.id()),
+                    }
+                }
+                continue;
+            }
+
+            record
+                .cards
+                .push((card.set.code().to_owned(), card.name.clone()));
+
+            // spoiler cards are only show to the user who press the reveal button
+            if spoiler {
+                let id = format!("{SPOILER_PREFIX}{}_{}", card.set.code(), card.name);
+                let index = spoilers.iter().position(|i| i == &id).unwrap_or_else(|| {
+                    spoilers.push(id);
+                    spoilers.len() - 1
+                });
+
+                let set = g_sets.get(card.set.code()).unwrap_or(set);
+                embeds.push(spoiler_embed(&set.name, index + 1, lang));
                 continue;
             }
 
+            if modifier.contains(Modifier::COMPACT) && !card.sigils.is_empty() {
+                expandable = true;
+            }
+            exportable = true;
+
             let mut embed = gen_embed(
                 rank,
                 card,
-                g_sets.get(card.set.code()).unwrap(),
+                g_sets.get(card.set.code()).unwrap_or(set),
                 modifier.contains(Modifier::COMPACT),
+                lang,
             );
-            let hash = hash_card_url(card);
+
+            if let Some(alias) = alias {
+                embed = embed.field(
+                    tr!(lang, "search.translated_title"),
+                    tr!(
+                        lang,
+                        "search.translated",
+                        language = language_name(&alias.lang),
+                        name = alias.name
+                    ),
+                    false,
+                );
+            }
+
+            let others = other_printings(card, &g_sets);
+            if !others.is_empty() {
+                embed = embed.field(
+                    tr!(lang, "search.other_printings"),
+                    others
+                        .iter()
+                        .map(|(code, name)| format!("{name} (`{code}`)"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    false,
+                );
+
+                for (code, _) in others {
+                    let id = format!("{PRINTING_PREFIX}{code}_{}", card.name);
+                    if jumps.len() < MAX_JUMPS
+                        && id.len() <= MAX_CUSTOM_ID_LEN
+                        && !jumps.iter().any(|(i, _)| i == &id)
+                    {
+                        jumps.push((id, format!("{} ({code})", card.name)));
+                    }
+                }
+            }
+
+            let id = format!("{FAVORITE_PREFIX}{}", card.id());
+            if favorites.len() < MAX_JUMPS && !favorites.iter().any(|(i, _)| i == &id) {
+                favorites.push((id, format!("⭐ {}", card.name)));
+            }
+
+            if !card.extra.rulings.is_empty() {
+                let id = format!("{RULINGS_PREFIX}{}_{}", card.set.code(), card.name);
+                if id.len() <= MAX_CUSTOM_ID_LEN && !rulings.iter().any(|(i, _)| i == &id) {
+                    rulings.push((id, tr!(lang, "search.rulings", name = card.name)));
+                }
+            }
+
+            if has_timeline(card.set.code(), &card.name) {
+                let id = format!("{TIMELINE_PREFIX}{}_{}", card.set.code(), card.name);
+                if timelines.len() < MAX_JUMPS
+                    && id.len() <= MAX_CUSTOM_ID_LEN
+                    && !timelines.iter().any(|(i, _)| i == &id)
+                {
+                    timelines.push((id, tr!(lang, "search.timeline", name = card.name)));
+                }
+            }
+
+            let id = card.id();
+            let filename = portrait_filename(card);
             let mut cache_guard = CACHE.lock().unwrap_or_die("Cannot lock cache");
 
-            #[allow(clippy::cast_lossless)]
-            match cache_guard.get(&hash) {
-                Some(CacheData {
-                    channel_id,
-                    attachment_id,
-                    expire_date,
-                }) if current_epoch() >= *expire_date as u128 => {
-                    embed = embed.thumbnail(format!("https://cdn.discordapp.com/attachments/{channel_id}/{attachment_id}/{hash}.png"));
+            match cache_guard.get(&id) {
+                Some(
+                    data @ CacheData {
+                        channel_id,
+                        attachment_id,
+                        portrait,
+                        ..
+                    },
+                ) if !is_expired(data) && *portrait == hash_card_url(card) => {
+                    record.cache_hits += 1;
+                    embed = embed.thumbnail(format!("https://cdn.discordapp.com/attachments/{channel_id}/{attachment_id}/{filename}"));
                 }
                 option => {
-                    // remove the cache when the thing expire
-                    if option.is_some() {
-                        info!("Cache for {} have expire removing...", hash.blue());
-                        cache_guard.remove(&hash);
-                        done!("{} cache for card hash {}", "Removed".red(), hash.blue());
+                    // remove the cache when the thing expire or the portrait change, expired
+                    // portraits are refresh in the second phase of a two phase reply instead
+                    if option.is_some_and(|c| !placeholder || c.portrait != hash_card_url(card)) {
+                        info!("Cache for {} have expire removing...", id.blue());
+                        cache_guard.remove(&id);
+                        done!("{} cache for card id {}", "Removed".red(), id.blue());
                     }
 
-                    let filename = hash.to_string() + ".png";
+                    // cards without art get their placeholder right away since it is bundled
+                    if placeholder && !card.portrait.is_empty() {
+                        embed = embed.thumbnail(format!("attachment://{PLACEHOLDER_FILENAME}"));
+                        match pending.iter_mut().find(|(_, f, _)| *f == filename) {
+                            Some((_, _, indexes)) => indexes.push(embeds.len()),
+                            None => pending.push((card, filename, vec![embeds.len()])),
+                        }
 
-                    embed = embed.thumbnail(format!("attachment://{filename}"));
-                    if !card.portrait.is_empty()
-                        && !attachments.iter().any(|a| a.filename == filename)
-                    {
-                        attachments.push(CreateAttachment::bytes(gen_portrait(card), filename));
+                        if !attachments
+                            .iter()
+                            .any(|a| a.filename == PLACEHOLDER_FILENAME)
+                        {
+                            attachments.push(CreateAttachment::bytes(
+                                PLACEHOLDER_PORTRAIT.clone(),
+                                PLACEHOLDER_FILENAME,
+                            ));
+                        }
+                    } else {
+                        if !card.portrait.is_empty() {
+                            record.cache_misses += 1;
+                        }
+                        embed = embed.thumbnail(format!("attachment://{filename}"));
+
+                        match portraits.iter_mut().find(|(_, f, _)| *f == filename) {
+                            Some((_, _, indexes)) => indexes.push(embeds.len()),
+                            None => portraits.push((card, filename, vec![embeds.len()])),
+                        }
                     }
                 }
             }
@@ -226,26 +958,491 @@ pub fn process_search(content: &str, guild_id: GuildId) -> MessageAdapter {
         }
     }
 
+    // check before generating the portraits so none are download for embeds that are not send,
+    // the attachments and buttons of the cards go with them
     if embeds.len() > 10 {
         embeds.clear();
+        attachments.clear();
+        portraits.clear();
+        pending.clear();
+        jumps.clear();
+        picks.clear();
+        rulings.clear();
+        timelines.clear();
+        favorites.clear();
+        spoilers.clear();
+        expandable = false;
+        exportable = false;
         embeds.push(
             CreateEmbed::new()
-                .title("Too many embeds")
-                .description("Too many added required, Discord only allow up to 10 embeds per message. Try separting your search across multiple message")
-                .color(roles::RED)
+                .title(tr!(lang, "search.too_many_title"))
+                .description(tr!(lang, "search.too_many"))
+                .color(roles::RED),
         );
     }
 
-    MessageAdapter::new()
-        .content(format!("Search completed in {:.1?}", start.elapsed()))
+    // portraits can take a while to download so don't keep the sets lock while generating them
+    let owned = |portraits: Vec<(&Card, String, Vec<usize>)>| {
+        portraits
+            .into_iter()
+            .map(|(card, filename, indexes)| (card.clone(), filename, indexes))
+            .collect::<Vec<_>>()
+    };
+    let (portraits, pending) = (owned(portraits), owned(pending));
+    drop(g_sets);
+
+    // generate the portraits all at once instead of one term at a time
+    let cards = portraits.iter().map(|(c, _, _)| c).collect::<Vec<_>>();
+    let portraits = gen_portraits(&cards).into_iter().zip(&portraits);
+    for (data, (card, filename, indexes)) in portraits {
+        attach_portrait(
+            &mut embeds,
+            &mut attachments,
+            (card, filename.clone(), indexes),
+            data,
+        );
+    }
+
+    let state = SearchState {
+        content: content.to_owned(),
+        guild_id: guild_id.map(GuildId::get),
+    };
+    let mut buttons = vec![
+        CreateButton::new(interactions.insert(RETRY_PREFIX, &state))
+            .style(Primary)
+            .label(tr!(lang, "search.retry")),
+        CreateButton::new(interactions.insert(RETRY_ALL_PREFIX, &state))
+            .style(Secondary)
+            .label(tr!(lang, "search.retry_all")),
+        CreateButton::new("remove_cache")
+            .style(Danger)
+            .label(tr!(lang, "search.remove_cache")),
+    ];
+
+    // only offer to expand when there are compact sigils to expand
+    if expandable {
+        buttons.push(
+            CreateButton::new("expand_sigils")
+                .style(Secondary)
+                .label(tr!(lang, "search.expand_sigils")),
+        );
+    }
+
+    if exportable {
+        buttons.push(
+            CreateButton::new("export_imf")
+                .style(Secondary)
+                .label(tr!(lang, "search.export_imf")),
+        );
+    }
+
+    // the first row can fit 2 more buttons so rulings go there
+    buttons.extend(
+        rulings
+            .into_iter()
+            .take(MAX_JUMPS - buttons.len())
+            .map(|(id, label)| CreateButton::new(id).style(Secondary).label(label)),
+    );
+
+    let spoilers = spoilers
+        .into_iter()
+        .enumerate()
+        .filter(|(_, id)| id.len() <= MAX_CUSTOM_ID_LEN)
+        .take(MAX_JUMPS)
+        .map(|(i, id)| (id, tr!(lang, "spoiler.reveal", index = i + 1)))
+        .collect::<Vec<_>>();
+
+    let mut components = vec![Buttons(buttons)];
+    if !picks.is_empty() {
+        let options = picks
+            .into_iter()
+            .map(|(query, i, code, name)| {
+                CreateSelectMenuOption::new(name, format!("{query}_{i}")).description(code)
+            })
+            .collect();
+
+        components.push(SelectMenu(
+            CreateSelectMenu::new(
+                interactions.insert_shared(QUERY_PICK_PREFIX, queries),
+                CreateSelectMenuKind::String { options },
+            )
+            .placeholder(tr!(lang, "search.query_pick")),
+        ));
+    }
+
+    // when there is too many rows the timelines give way first and then the other printings, the
+    // spoilers and favorites can't be reach any other way
+    let mut rows = [spoilers, jumps, timelines, favorites];
+    for i in [2, 1] {
+        if components.len() + rows.iter().filter(|r| !r.is_empty()).count() > MAX_ROWS {
+            rows[i].clear();
+        }
+    }
+    for row in rows {
+        if !row.is_empty() {
+            components.push(Buttons(
+                row.into_iter()
+                    .map(|(id, label)| CreateButton::new(id).style(Secondary).label(label))
+                    .collect(),
+            ));
+        }
+    }
+
+    record.latency = start.elapsed();
+    if pending.is_empty() {
+        record_search(&record);
+        tracing::debug!(
+            latency = ?record.latency,
+            cards = record.cards.len(),
+            queries = record.queries,
+            "Search completed"
+        );
+    }
+
+    let mut summary = tr!(
+        lang,
+        "search.completed",
+        time = format!("{:.1?}", record.latency)
+    );
+    for (i, (term, status)) in statuses.iter().enumerate() {
+        let line = status.line(term, lang);
+        if summary.len() + line.len() > MAX_SUMMARY_LEN {
+            summary.push('\n');
+            summary.push_str(&tr!(
+                lang,
+                "search.summary_more",
+                count = statuses.len() - i
+            ));
+            break;
+        }
+        write!(summary, "\n- {line}").unwrap();
+    }
+
+    // what the reply look like once the portraits are generated
+    let pending = (!pending.is_empty()).then(|| {
+        let mut embeds = embeds.clone();
+        for (_, filename, indexes) in &pending {
+            for &i in indexes {
+                embeds[i] = mem::take(&mut embeds[i]).thumbnail(format!("attachment://{filename}"));
+            }
+        }
+
+        PendingSearch {
+            embeds,
+            attachments: attachments
+                .iter()
+                .filter(|a| a.filename != PLACEHOLDER_FILENAME)
+                .cloned()
+                .collect(),
+            portraits: pending,
+            record: record.clone(),
+            start,
+        }
+    });
+
+    let msg = MessageAdapter::new()
+        .content(summary)
         .embeds(embeds)
         .attachments(attachments)
-        .components(vec![Buttons(vec![
-            CreateButton::new("retry").style(Primary).label("Retry"),
-            CreateButton::new("remove_cache")
-                .style(Danger)
-                .label("Remove Cache"),
-        ])])
+        .components(components);
+
+    (msg, pending)
+}
+
+/// Get the set code and set name of every other set with a card of the same name.
+///
+/// The printings are sorted by set code so the buttons stay in the same order between searches.
+fn other_printings<'a>(
+    card: &Card,
+    g_sets: &'a HashMap<&'static str, Arc<Set>>,
+) -> Vec<(&'a str, &'a str)> {
+    let mut others = printings(g_sets.values().map(Arc::as_ref), &card.name)
+        .into_iter()
+        .filter(|c| c.set.code() != card.set.code())
+        .filter_map(|c| {
+            g_sets
+                .get_key_value(c.set.code())
+                .map(|(code, set)| (*code, set.name.as_str()))
+        })
+        .collect::<Vec<_>>();
+
+    others.sort_unstable();
+    others
+}
+
+/// Generate the message for an other printings button.
+///
+/// The custom id is [`PRINTING_PREFIX`] follow by the set code and the card name.
+pub fn printing_message(custom_id: &str, lang: &str) -> MessageAdapter {
+    let not_found = || {
+        MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::RED)
+                .title("Printing not found")
+                .description("This printing is no longer in the set.")])
+            .ephemeral(true)
+    };
+
+    let Some((code, name)) = custom_id
+        .strip_prefix(PRINTING_PREFIX)
+        .and_then(|id| id.split_once('_'))
+    else {
+        return not_found();
+    };
+
+    let g_sets = SETS.read().unwrap();
+    let Some((card, embed)) = g_sets.get(code).and_then(|set| {
+        set.get_card(name)
+            .map(|card| (card.clone(), card_embed(card, set, lang)))
+    }) else {
+        return not_found();
+    };
+    drop(g_sets);
+
+    card_message(&card, embed)
+}
+
+/// Generate the message for a query result select menu.
+///
+/// `queries` is the state of the select menu and `value` the picked option, the query index and
+/// the result index. The card is pick from the sets the query was run on so it is the same as
+/// the one list even if the sets was refresh since.
+pub fn query_pick_message(
+    queries: Option<Arc<Vec<QueryResult>>>,
+    value: &str,
+    lang: &str,
+) -> MessageAdapter {
+    let found = queries
+        .zip(value.split_once('_'))
+        .and_then(|(queries, (query, i))| {
+            let query = queries.get(query.parse::<usize>().ok()?)?;
+            let card = query.get(i.parse::<usize>().ok()?)?;
+            let set = query
+                .sets
+                .iter()
+                .find(|s| s.code.code() == card.set.code())?;
+            Some((card.clone(), card_embed(card, set, lang)))
+        });
+
+    let Some((card, embed)) = found else {
+        return MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::RED)
+                .title(tr!(lang, "search.query_pick_expired_title"))
+                .description(tr!(lang, "search.query_pick_expired"))])
+            .ephemeral(true);
+    };
+
+    card_message(&card, embed)
+}
+
+/// Generate the card embed for a [`card_message`], with the portrait as thumbnail.
+pub fn card_embed(card: &Card, set: &Set, lang: &str) -> CreateEmbed {
+    gen_plain_embed(card, set, false, lang)
+        .thumbnail(format!("attachment://{}", portrait_filename(card)))
+}
+
+/// Generate an ephemeral message with a single card embed from [`card_embed`] and its portrait.
+///
+/// The portrait may need downloading so clone the card and release the sets lock before calling
+/// this.
+pub fn card_message(card: &Card, embed: CreateEmbed) -> MessageAdapter {
+    MessageAdapter::new()
+        .embeds(vec![embed])
+        .attachments(vec![CreateAttachment::bytes(
+            gen_portrait(card),
+            portrait_filename(card),
+        )])
+        .ephemeral(true)
+}
+
+/// Generate the message for a rulings button.
+///
+/// The custom id is [`RULINGS_PREFIX`] follow by the set code and the card name.
+pub fn rulings_message(custom_id: &str, lang: &str) -> MessageAdapter {
+    reload_rulings();
+    let g_sets = SETS.read().unwrap();
+    let Some(card) = custom_id
+        .strip_prefix(RULINGS_PREFIX)
+        .and_then(|id| id.split_once('_'))
+        .and_then(|(code, name)| g_sets.get(code).and_then(|set| set.get_card(name)))
+    else {
+        return MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::RED)
+                .title(tr!(lang, "rulings.not_found_title"))
+                .description(tr!(lang, "rulings.not_found"))])
+            .ephemeral(true);
+    };
+
+    let mut description = card
+        .extra
+        .rulings
+        .iter()
+        .map(|r| match r.time {
+            Some(time) => format!("- {} (<t:{time}:d>)", r.text),
+            None => format!("- {}", r.text),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // embed descriptions are limited to 4096 characters
+    if description.chars().count() > 4096 {
+        description = description.chars().take(4000).collect::<String>() + "...";
+    }
+
+    MessageAdapter::new()
+        .embeds(vec![CreateEmbed::new()
+            .color(roles::BLUE)
+            .title(tr!(lang, "rulings.title", name = card.name))
+            .description(description)])
+        .ephemeral(true)
+}
+
+/// Generate the full sigil descriptions for every compact search in a content.
+///
+/// Use by the expand sigils button so compact embeds can stay small.
+pub fn expand_sigils(content: &str, guild_id: Option<GuildId>) -> MessageAdapter {
+    let mut embeds = vec![];
+
+    let homebrew = homebrew::guild_set(guild_id).map(Arc::new);
+    let g_sets = SETS.read().unwrap();
+
+    for SearchTerm {
+        modifier,
+        sets,
+        term,
+        lang,
+    } in parse_search(content, guild_id, &g_sets, homebrew.as_ref(), false)
+    {
+        if !modifier.contains(Modifier::COMPACT) || modifier.contains(Modifier::QUERY) {
+            continue;
+        }
+
+        for set in sets {
+            let Some((FuzzyRes { data: card, .. }, _)) = find_card_translated(term, set, lang)
+            else {
+                continue;
+            };
+
+            if card.sigils.is_empty() || is_spoiler(guild_id, card.set.code()) {
+                continue;
+            }
+
+            let set = g_sets.get(card.set.code()).unwrap_or(set);
+            let mut desc = String::with_capacity(card.sigils.iter().map(|s| s.len()).sum());
+
+            for s in &card.sigils {
+                let sigil = set.sigil(s);
+                let category = sigil
+                    .and_then(|s| s.category)
+                    .map(|c| format!(" *({c})*"))
+                    .unwrap_or_default();
+                let text = sigil.map_or("", |s| s.text);
+                writeln!(desc, "**{s}{category}:** {text}").unwrap();
+            }
+
+            embeds.push(
+                CreateEmbed::new()
+                    .color(roles::BLUE)
+                    .title(format!("{} ({})", card.name, set.name))
+                    .description(desc),
+            );
+        }
+    }
+
+    if embeds.is_empty() {
+        embeds.push(
+            CreateEmbed::new()
+                .color(roles::RED)
+                .title("No sigils to expand")
+                .description("None of the compact cards in this search have any sigils."),
+        );
+    }
+
+    embeds.truncate(10);
+
+    MessageAdapter::new().embeds(embeds).ephemeral(true)
+}
+
+/// Generate a IMF ruleset with every card in a search.
+///
+/// Use by the export json button so custom card creators can pull cards into their own ruleset.
+pub fn export_imf(content: &str, guild_id: Option<GuildId>, lang: &str) -> MessageAdapter {
+    let homebrew = homebrew::guild_set(guild_id).map(Arc::new);
+    let g_sets = SETS.read().unwrap();
+
+    let mut cards: Vec<&Card> = vec![];
+    let mut sigils_description = HashMap::new();
+
+    for SearchTerm {
+        modifier,
+        sets,
+        term,
+        lang,
+    } in parse_search(content, guild_id, &g_sets, homebrew.as_ref(), false)
+    {
+        if modifier.intersects(Modifier::QUERY | Modifier::DEBUG) {
+            continue;
+        }
+
+        for set in sets {
+            let Some((FuzzyRes { data: card, .. }, _)) = find_card_translated(term, set, lang)
+            else {
+                continue;
+            };
+
+            if is_spoiler(guild_id, card.set.code())
+                || cards
+                    .iter()
+                    .any(|c| c.name == card.name && c.set.code() == card.set.code())
+            {
+                continue;
+            }
+
+            let set = g_sets.get(card.set.code()).unwrap_or(set);
+            for s in &card.sigils {
+                if let Some(desc) = set.sigils_description.get(s) {
+                    sigils_description
+                        .entry(s.clone())
+                        .or_insert_with(|| desc.clone());
+                }
+            }
+            cards.push(card);
+        }
+    }
+
+    if cards.is_empty() {
+        return MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::RED)
+                .title(tr!(lang, "search.export_empty_title"))
+                .description(tr!(lang, "search.export_empty"))])
+            .ephemeral(true);
+    }
+
+    let filename = "magpie_export.json";
+    let ruleset = to_imf_set("Magpie Export", cards, &sigils_description);
+    let json = match serde_json::to_vec_pretty(&ruleset) {
+        Ok(json) => json,
+        Err(err) => {
+            error!("Cannot serialize export due to: {err}");
+            return MessageAdapter::new()
+                .embeds(vec![CreateEmbed::new()
+                    .color(roles::RED)
+                    .title(tr!(lang, "search.export_failed_title"))
+                    .description(tr!(lang, "search.export_failed", error = err))])
+                .ephemeral(true);
+        }
+    };
+
+    MessageAdapter::new()
+        .embeds(vec![CreateEmbed::new()
+            .color(roles::BLUE)
+            .title(tr!(lang, "search.export_title"))
+            .description(tr!(lang, "search.export", filename = filename))])
+        .attachments(vec![CreateAttachment::bytes(json, filename)])
+        .ephemeral(true)
 }
 
 /// Uodate the cache with the messagge attachment
@@ -263,13 +1460,13 @@ fn update_cache(msg: &Message) {
         .iter()
         .filter_map(|e| e.thumbnail.as_ref().map(|e| &e.url))
     {
-        let capture: [&str; 4] = CACHE_REGEX
-            .captures(url)
-            .unwrap_or_else(|| panic!("Cannot find a match in url: {url}"))
-            .extract()
-            .1;
+        // placeholder portraits have their own file name so they are never cache
+        let Some(capture) = CACHE_REGEX.captures(url) else {
+            continue;
+        };
+        let capture: [&str; 5] = capture.extract().1;
 
-        let filename = capture[2].parse().unwrap();
+        let id = capture[2].parse().unwrap();
         let cache_data = CacheData {
             channel_id: capture[0]
                 .parse()
@@ -277,22 +1474,21 @@ fn update_cache(msg: &Message) {
             attachment_id: capture[1]
                 .parse()
                 .unwrap_or_else(|_| panic!("Cannot parse attachment id: {}", capture[1])),
-            expire_date: u64::from_str_radix(capture[3], 16)
-                .unwrap_or_else(|_| panic!("Cannot parse expire date: {}", capture[3])),
+            expire_date: u64::from_str_radix(capture[4], 16)
+                .unwrap_or_else(|_| panic!("Cannot parse expire date: {}", capture[4])),
+            portrait: capture[3]
+                .parse()
+                .unwrap_or_else(|_| panic!("Cannot parse portrait hash: {}", capture[3])),
         };
 
-        if cache_guard.get(&filename).is_some() {
-            info!("Cache for {} found skipping...", filename.blue());
+        if cache_guard.get(&id).is_some() {
+            info!("Cache for {} found skipping...", id.blue());
             continue;
         }
 
         // Insert in the new cache replacing the old one
-        if cache_guard.insert(filename, cache_data).is_none() {
-            done!(
-                "{} cache for card hash {}",
-                "Created".green(),
-                filename.blue()
-            );
+        if cache_guard.insert(id, cache_data).is_none() {
+            done!("{} cache for card id {}", "Created".green(), id.blue());
             new_cache += 1;
         };
     }
@@ -1,9 +1,11 @@
 //! Contain implementation for generate card embed from card and a few other info
+use magpie_engine::{Mox, MoxCount};
 use poise::serenity_prelude::{CreateEmbed, CreateEmbedFooter};
 
 use crate::{
-    emojis::{number, ToEmoji},
-    Card, Set,
+    emojis::{cost, number, ToEmoji},
+    sources::is_aug_source,
+    tr, Card, Set,
 };
 
 mod aug;
@@ -22,29 +24,89 @@ type EmbedRes = (CreateEmbed, String);
 ///
 /// Sigils and other traits use the embed field because they are optional and not every card have
 /// them.
-pub fn gen_embed(rank: f32, card: &Card, set: &Set, compact: bool) -> CreateEmbed {
+///
+/// Labels are in the `lang` language, see [`locale`](crate::locale).
+pub fn gen_embed(rank: f32, card: &Card, set: &Set, compact: bool, lang: &str) -> CreateEmbed {
+    let (embed, footer) = gen_set_embed(card, set, compact, lang);
+    embed.footer(CreateEmbedFooter::new(format!(
+        "{footer}\n{}",
+        tr!(
+            lang,
+            "search.match",
+            percent = format!("{:.2}", rank * 100.)
+        )
+    )))
+}
+
+/// Generate card embed from a card data without the search match in the footer.
+///
+/// Use this for cards that are not from a search.
+pub fn gen_plain_embed(card: &Card, set: &Set, compact: bool, lang: &str) -> CreateEmbed {
+    let (embed, footer) = gen_set_embed(card, set, compact, lang);
+    embed.footer(CreateEmbedFooter::new(footer))
+}
+
+fn gen_set_embed(card: &Card, set: &Set, compact: bool, lang: &str) -> EmbedRes {
     // The specific gen embed function should return the embed and the footer that they would like
     // to add.
-
-    let (embed, footer) = match card.set.code() {
-        "aug" | "Aug" | "cti" => aug::gen_embed(card, set, compact),
-        "std" | "ete" | "egg" => imf::gen_embed(card, set, compact),
-        "des" => desc::gen_embed(card, set, compact),
-        code => todo!("embed for set code is not implemented yet: {code}"),
+    let (mut embed, mut footer) = match card.set.code() {
+        "aug" | "Aug" | "cti" => aug::gen_embed(card, set, compact, lang),
+        code if is_aug_source(code) => aug::gen_embed(card, set, compact, lang),
+        "des" => desc::gen_embed(card, set, compact, lang),
+        // std, ete, egg and homebrew sets only have the imf fields
+        _ => imf::gen_embed(card, set, compact, lang),
     };
-    embed.footer(CreateEmbedFooter::new(format!(
-        "{footer}\nMatch {:.2}% with the search term",
-        rank * 100.
-    )))
+
+    // errata'd cards differ from the upstream sheet so always tell the user
+    if let Some(note) = &card.extra.errata {
+        embed = embed.field(tr!(lang, "embed.errata_field"), note, false);
+        footer.push('\n');
+        footer.push_str(tr!(lang, "embed.errata_footer"));
+    }
+
+    if card.portrait.is_empty() {
+        footer.push('\n');
+        footer.push_str(tr!(lang, "embed.missing_art"));
+    }
+
+    (embed, footer)
 }
 
 #[allow(clippy::inline_always)] // this is just a helper function so inline it
 #[inline(always)]
 fn append_cost(out: &mut String, count: isize, labe: &str, icon: &str) {
     #[rustfmt::skip] // it look nicer like this
-    let t = format!( "**{} Cost:**{}{}{}\n", labe, icon, number::X, count.to_emoji());
+    let t = format!( "**{}:**{}{}{}\n", labe, icon, number::X, count.to_emoji());
 
     if count != 0 {
         out.push_str(&t);
     }
 }
+
+/// Render the mox part of a cost as emojis.
+///
+/// Every [`Mox`] flag is supported. If the cost does not have a [`MoxCount`] each color is count
+/// as 1.
+fn mox_display(mox: Mox, count: Option<&MoxCount>) -> String {
+    let mut out = String::new();
+
+    for m in mox.iter() {
+        let (icon, n) = match m {
+            Mox::O => (&cost::ORANGE, count.map_or(1, |c| c.o)),
+            Mox::G => (&cost::GREEN, count.map_or(1, |c| c.g)),
+            Mox::B => (&cost::BLUE, count.map_or(1, |c| c.b)),
+            Mox::Y => (&cost::GRAY, count.map_or(1, |c| c.y)),
+            Mox::R => (&cost::RED, count.map_or(1, |c| c.r)),
+            Mox::E => (&cost::YELLOW, count.map_or(1, |c| c.e)),
+            Mox::P => (&cost::PURPLE, count.map_or(1, |c| c.p)),
+            Mox::K => (&cost::BLACK, count.map_or(1, |c| c.k)),
+            Mox::P1 => (&cost::PLUS1, 1),
+            // unknown bits keep from deserializing have no icon
+            _ => continue,
+        };
+
+        out.push_str(&icon.repeat(n));
+    }
+
+    out
+}
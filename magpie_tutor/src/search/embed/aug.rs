@@ -1,29 +1,18 @@
+use std::fmt::Write;
+
 use magpie_engine::prelude::*;
-use poise::serenity_prelude::{colours::roles, CreateEmbed};
+use poise::serenity_prelude::CreateEmbed;
 
 use crate::{
-    emojis::{cost, ToEmoji},
-    hash_card_url, Card, Set,
+    emojis::{cost, AppEmojis, ToEmoji},
+    engine::CardExt,
+    portrait_filename, tr, Card, Set,
 };
 
-use super::{append_cost, EmbedRes};
-
-pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
-    let color = if let Some(t) = card.temple.iter().next() {
-        match t {
-            Temple::BEAST => roles::DARK_GOLD,
-            Temple::UNDEAD => roles::GREEN,
-            Temple::TECH => roles::BLUE,
-            Temple::MAGICK => roles::RED,
-            Temple::FOOL => roles::MAGENTA,
-            _ if t.is_empty() => roles::LIGHT_GREY,
-            _ => unreachable!(),
-        }
-    } else {
-        unreachable!()
-    };
+use super::{append_cost, mox_display, EmbedRes};
 
-    let mut embed = CreateEmbed::new().color(color).title(format!(
+pub fn gen_embed(card: &Card, set: &Set, compact: bool, lang: &str) -> EmbedRes {
+    let mut embed = CreateEmbed::new().color(card.accent_color()).title(format!(
         "{} ({}) {}",
         card.name,
         set.name,
@@ -40,59 +29,87 @@ pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
     };
 
     desc.push_str(&format!(
-        "**Tier:** {}\n",
+        "**{}:** {}\n",
+        tr!(lang, "embed.tier"),
         match &card.rarity {
             Rarity::UNIQUE => String::from("talking"),
             a => a.to_string(),
         }
     ));
     if let Some(t) = &card.tribes {
-        desc.push_str(&format!("**Tribes:** {t}\n"));
+        desc.push_str(&format!("**{}:** {t}\n", tr!(lang, "embed.tribes")));
     }
 
     desc.push('\n'); // cost separator
     let mut out = String::new();
 
     if let Some(costs) = &card.costs {
-        append_cost(&mut out, costs.blood, " Blood", cost::BLOOD);
-        append_cost(&mut out, costs.bone, " Bone", cost::BONE);
-        append_cost(&mut out, costs.energy, " Energy", cost::ENERGY);
-        append_cost(&mut out, costs.extra.max, " Overcharge", cost::MAX);
+        append_cost(
+            &mut out,
+            costs.blood,
+            tr!(lang, "embed.blood_cost"),
+            &cost::BLOOD,
+        );
+        append_cost(
+            &mut out,
+            costs.bone,
+            tr!(lang, "embed.bone_cost"),
+            &cost::BONE,
+        );
+        append_cost(
+            &mut out,
+            costs.energy,
+            tr!(lang, "embed.energy_cost"),
+            &cost::ENERGY,
+        );
+        append_cost(
+            &mut out,
+            costs.extra.max,
+            tr!(lang, "embed.overcharge_cost"),
+            &cost::MAX,
+        );
 
         if !costs.mox.is_empty() {
-            let mut mox_cost = String::new();
-            let count = costs.mox_count.clone().unwrap_or_default();
-
-            for m in costs.mox.iter() {
-                match m {
-                    Mox::O => mox_cost.extend(vec![cost::ORANGE; count.o]),
-                    Mox::G => mox_cost.extend(vec![cost::GREEN; count.g]),
-                    Mox::B => mox_cost.extend(vec![cost::BLUE; count.b]),
-                    Mox::Y => mox_cost.extend(vec![cost::GRAY; count.y]),
-                    Mox::R => mox_cost.extend(vec![cost::RED; count.r]),
-                    Mox::E => mox_cost.extend(vec![cost::YELLOW; count.e]),
-                    Mox::P => mox_cost.extend(vec![cost::PURPLE; count.p]),
-                    _ => unreachable!(),
+            // shattered mox also set the mox flags so only count what is not shattered, without
+            // a count every mox is need once so the shattered colors are the only ones left out
+            let mox_cost = match (&costs.mox_count, &costs.extra.shattered_count) {
+                (None, Some(s)) => {
+                    let shattered = [
+                        (Mox::O, s.o),
+                        (Mox::G, s.g),
+                        (Mox::B, s.b),
+                        (Mox::Y, s.y),
+                        (Mox::R, s.r),
+                        (Mox::E, s.e),
+                        (Mox::P, s.p),
+                        (Mox::K, s.k),
+                    ]
+                    .into_iter()
+                    .filter(|(_, n)| *n != 0)
+                    .fold(Mox::empty(), |flags, (mox, _)| flags | mox);
+                    mox_display(costs.mox - shattered, None)
                 }
-            }
+                (count, _) => mox_display(costs.mox, count.as_ref()),
+            };
 
             if !mox_cost.is_empty() {
-                out.push_str("**Mox Cost:**");
+                write!(out, "**{}:**", tr!(lang, "embed.mox_cost")).unwrap();
                 out.push_str(&mox_cost);
                 out.push('\n');
             }
         }
 
         if let Some(shattered) = &costs.extra.shattered_count {
-            let mut mox_cost = String::from("**Shattered cost:** ");
+            let mut mox_cost = format!("**{}:** ", tr!(lang, "embed.shattered_cost"));
 
-            mox_cost.extend(vec![cost::SHATTERED_ORANGE; shattered.o]);
-            mox_cost.extend(vec![cost::SHATTERED_GREEN; shattered.g]);
-            mox_cost.extend(vec![cost::SHATTERED_BLUE; shattered.b]);
-            mox_cost.extend(vec![cost::SHATTERED_GRAY; shattered.y]);
-            mox_cost.extend(vec![cost::SHATTERED_RED; shattered.r]);
-            mox_cost.extend(vec![cost::SHATTERED_YELLOW; shattered.e]);
-            mox_cost.extend(vec![cost::SHATTERED_PURPLE; shattered.p]);
+            mox_cost.extend(vec![&*cost::SHATTERED_ORANGE; shattered.o]);
+            mox_cost.extend(vec![&*cost::SHATTERED_GREEN; shattered.g]);
+            mox_cost.extend(vec![&*cost::SHATTERED_BLUE; shattered.b]);
+            mox_cost.extend(vec![&*cost::SHATTERED_GRAY; shattered.y]);
+            mox_cost.extend(vec![&*cost::SHATTERED_RED; shattered.r]);
+            mox_cost.extend(vec![&*cost::SHATTERED_YELLOW; shattered.e]);
+            mox_cost.extend(vec![&*cost::SHATTERED_PURPLE; shattered.p]);
+            mox_cost.extend(vec![&*cost::SHATTERED_BLACK; shattered.k]);
 
             out.push_str(&mox_cost);
             out.push('\n');
@@ -100,36 +117,35 @@ pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
     }
 
     if out.is_empty() {
-        out.push_str("**Free**\n");
+        writeln!(out, "**{}**", tr!(lang, "embed.free")).unwrap();
     }
 
     desc.push_str(&out); // the card cost
     desc.push('\n'); // stat separator
 
-    // remove this once we actually parse aug spatk
-    #[allow(clippy::match_wildcard_for_single_variants)]
     desc.push_str(&format!(
-        "**Stat:** {} / {}",
-        match &card.attack {
-            Attack::Num(a) => a.to_string(),
-            Attack::Str(s) => s.to_owned(),
-            _ => unreachable!(),
-        },
+        "**{}:** {} / {}",
+        tr!(lang, "embed.stat"),
+        card.attack.to_display(&AppEmojis),
         card.health
     ));
 
     if !card.sigils.is_empty() {
         if compact {
-            desc.push_str(&format!("**Sigils:** {}\n", card.sigils.join(", ")));
+            desc.push_str(&format!(
+                "**{}:** {}\n",
+                tr!(lang, "embed.sigils"),
+                card.sigils.join(", ")
+            ));
         } else {
-            let mut desc = String::with_capacity(card.sigils.iter().map(String::len).sum());
+            let mut desc = String::with_capacity(card.sigils.iter().map(|s| s.len()).sum());
 
             for s in &card.sigils {
                 let text = set.sigils_description.get(s).unwrap();
                 desc.push_str(&format!("**{s}:** {text}\n"));
             }
 
-            embed = embed.field("== SIGILS ==", desc, false);
+            embed = embed.field(tr!(lang, "embed.sigils_field"), desc, false);
         }
     }
 
@@ -138,25 +154,54 @@ pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
     }) = &card.traits
     {
         if compact {
-            desc.push_str(&format!("**Traits:** {}", t.join(", ")));
+            desc.push_str(&format!(
+                "**{}:** {}",
+                tr!(lang, "embed.traits"),
+                t.join(", ")
+            ));
         } else {
             let mut desc = String::with_capacity(t.iter().map(String::len).sum());
 
             for s in t {
-                let text = set.sigils_description.get(s).unwrap();
+                let text = set.sigils_description.get(s.as_str()).unwrap();
                 desc.push_str(&format!("**{s}:** {text}\n"));
             }
 
-            embed = embed.field("== TRAITS ==", desc, false);
+            embed = embed.field(tr!(lang, "embed.traits_field"), desc, false);
         }
     }
 
+    // cards that list this card as related, like the cards that create a token
+    let related_by = set
+        .related_by(&card.name)
+        .into_iter()
+        .filter(|c| c.name != card.name)
+        .map(|c| c.name.as_str())
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![];
     if !card.related.is_empty() {
-        let value = format!("**Token:** {}", card.related.join(", "));
+        lines.push(format!(
+            "**{}:** {}",
+            tr!(lang, "embed.token"),
+            card.related.join(", ")
+        ));
+    }
+    if !related_by.is_empty() {
+        lines.push(format!(
+            "**{}:** {}",
+            tr!(lang, "embed.token_of"),
+            related_by.join(", ")
+        ));
+    }
+
+    if !lines.is_empty() {
+        let value = lines.join("\n");
+
         if compact {
             desc.push_str(&value);
         } else {
-            embed = embed.field("== EXTRA INFO ==", value, false);
+            embed = embed.field(tr!(lang, "embed.extra_info_field"), value, false);
         }
     }
 
@@ -167,11 +212,11 @@ pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
     (
         embed
             .description(desc)
-            .thumbnail(format!("attachment://{}.png", hash_card_url(card))),
+            .thumbnail(format!("attachment://{}", portrait_filename(card))),
         if card.extra.artist.is_empty() {
             String::new()
         } else {
-            format!("This card art was drawn by {}", card.extra.artist)
+            tr!(lang, "embed.artist", artist = card.extra.artist)
         },
     )
 }
@@ -1,30 +1,20 @@
 #![allow(unused)] // shush im fixing them
 
+use std::fmt::Write;
+
 use magpie_engine::prelude::*;
-use poise::serenity_prelude::{colours::roles, Colour, CreateEmbed};
+use poise::serenity_prelude::CreateEmbed;
 
 use crate::{
-    emojis::{cost, ToEmoji},
-    hash_card_url, Card, Set,
+    emojis::{cost, AppEmojis, ToEmoji},
+    engine::CardExt,
+    portrait_filename, tr, Card, Set,
 };
 
-use super::{append_cost, EmbedRes};
-
-pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
-    let color = if let Some(t) = card.temple.iter().next() {
-        match t {
-            Temple::BEAST => roles::DARK_GOLD,
-            Temple::UNDEAD => roles::GREEN,
-            Temple::TECH => roles::BLUE,
-            Temple::MAGICK => roles::MAGENTA,
-            Temple::ARTISTRY => Colour::new(u32::from_str_radix("3c3f4a", 16).unwrap()),
-            _ => unreachable!(),
-        }
-    } else {
-        unreachable!()
-    };
+use super::{append_cost, mox_display, EmbedRes};
 
-    let mut embed = CreateEmbed::new().color(color).title(format!(
+pub fn gen_embed(card: &Card, set: &Set, compact: bool, lang: &str) -> EmbedRes {
+    let mut embed = CreateEmbed::new().color(card.accent_color()).title(format!(
         "{} ({}) {}",
         card.name,
         set.name,
@@ -40,71 +30,88 @@ pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
         format!("*{}*\n\n", card.description)
     };
 
-    desc.push_str(&format!("**Rarity:** {}\n", &card.rarity.to_string()));
+    desc.push_str(&format!(
+        "**{}:** {}\n",
+        tr!(lang, "embed.rarity"),
+        &card.rarity.to_string()
+    ));
     if let Some(t) = &card.tribes {
-        desc.push_str(&format!("**Tribes:** {t}\n"));
+        desc.push_str(&format!("**{}:** {t}\n", tr!(lang, "embed.tribes")));
     }
 
     desc.push('\n'); // cost separator
     let mut out = String::new();
 
     if let Some(costs) = &card.costs {
-        append_cost(&mut out, costs.blood, "Blood", cost::BLOOD);
-        append_cost(&mut out, costs.bone, "Bone", cost::BONE);
-        append_cost(&mut out, costs.energy, "Energy", cost::ENERGY);
-        append_cost(&mut out, costs.extra.link, "Link", cost::LINK);
-        append_cost(&mut out, costs.extra.gold, "Gold", cost::GOLD);
+        append_cost(
+            &mut out,
+            costs.blood,
+            tr!(lang, "embed.blood_cost"),
+            &cost::BLOOD,
+        );
+        append_cost(
+            &mut out,
+            costs.bone,
+            tr!(lang, "embed.bone_cost"),
+            &cost::BONE,
+        );
+        append_cost(
+            &mut out,
+            costs.energy,
+            tr!(lang, "embed.energy_cost"),
+            &cost::ENERGY,
+        );
+        append_cost(
+            &mut out,
+            costs.extra.link,
+            tr!(lang, "embed.link_cost"),
+            &cost::LINK,
+        );
+        append_cost(
+            &mut out,
+            costs.extra.gold,
+            tr!(lang, "embed.gold_cost"),
+            &cost::GOLD,
+        );
 
         if !costs.mox.is_empty() {
-            let mut mox_cost = String::from("**Mox cost:** ");
-
-            for m in costs.mox.iter() {
-                match m {
-                    Mox::O => mox_cost.push_str(cost::ORANGE),
-                    Mox::G => mox_cost.push_str(cost::GREEN),
-                    Mox::B => mox_cost.push_str(cost::BLUE),
-                    Mox::K => mox_cost.push_str(cost::BLACK),
-                    Mox::P1 => mox_cost.push_str(cost::PLUS1),
-                    Mox::Y => mox_cost.push_str(cost::GRAY),
-                    _ => todo!(),
-                }
-            }
+            let mut mox_cost = format!("**{}:** ", tr!(lang, "embed.mox_cost"));
+            mox_cost.push_str(&mox_display(costs.mox, costs.mox_count.as_ref()));
             out.push_str(&mox_cost);
             out.push('\n');
         }
     }
 
     if out.is_empty() {
-        out.push_str("**Free**\n");
+        writeln!(out, "**{}**", tr!(lang, "embed.free")).unwrap();
     }
 
     desc.push_str(&out); // the card cost
     desc.push('\n'); // stat separator
 
-    // remove this once we actually parse aug spatk
-    #[allow(clippy::match_wildcard_for_single_variants)]
     desc.push_str(&format!(
-        "**Stat:** {} / {}",
-        match &card.attack {
-            Attack::Num(a) => a.to_string(),
-            Attack::Str(s) => s.to_owned(),
-            _ => unimplemented!(),
-        },
+        "**{}:** {} / {}",
+        tr!(lang, "embed.stat"),
+        card.attack.to_display(&AppEmojis),
         card.health
     ));
 
     if !card.sigils.is_empty() {
         if compact {
-            desc.push_str(&format!("**Sigils:** {}\n", card.sigils.join(", ")));
+            desc.push_str(&format!(
+                "**{}:** {}\n",
+                tr!(lang, "embed.sigils"),
+                card.sigils.join(", ")
+            ));
         } else {
-            let mut desc = String::with_capacity(card.sigils.iter().map(String::len).sum());
+            let mut desc = String::with_capacity(card.sigils.iter().map(|s| s.len()).sum());
 
             for s in &card.sigils {
                 let text = set.sigils_description.get(s).unwrap();
                 desc.push_str(&format!("**{s}:** {text}\n"));
             }
 
-            embed = embed.field("== SIGILS ==", desc, false);
+            embed = embed.field(tr!(lang, "embed.sigils_field"), desc, false);
         }
     }
 
@@ -113,8 +120,8 @@ pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
     }) = &card.traits
     {
         embed = embed.field(
-            "== TRAITS ==",
-            format!("**Traits:** {}", t.join(", ")),
+            tr!(lang, "embed.traits_field"),
+            format!("**{}:** {}", tr!(lang, "embed.traits"), t.join(", ")),
             false,
         );
     }
@@ -126,7 +133,7 @@ pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
     (
         embed
             .description(desc)
-            .thumbnail(format!("attachment://{}.png", hash_card_url(card))),
+            .thumbnail(format!("attachment://{}", portrait_filename(card))),
         String::new(),
     )
 }
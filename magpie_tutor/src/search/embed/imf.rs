@@ -1,29 +1,25 @@
-use magpie_engine::prelude::*;
-use poise::serenity_prelude::{colours::roles, CreateEmbed};
+use std::fmt::Write;
+
+use poise::serenity_prelude::CreateEmbed;
 
 use crate::{
-    emojis::{cost, ToEmoji},
-    Card, Set,
+    emojis::{cost, AppEmojis, ToEmoji},
+    engine::CardExt,
+    tr, Card, Set,
 };
 
-use super::{append_cost, EmbedRes};
+use super::{append_cost, mox_display, EmbedRes};
 
-pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
-    let mut embed = CreateEmbed::new()
-        .color(if card.rarity.eq(&Rarity::RARE) {
-            roles::GREEN
-        } else {
-            roles::LIGHT_GREY
-        })
-        .title(format!(
-            "{} ({}) {}",
-            card.name,
-            set.name,
-            match &card.traits {
-                Some(tr) => tr.flags.to_emoji(),
-                None => String::new(),
-            }
-        ));
+pub fn gen_embed(card: &Card, set: &Set, compact: bool, lang: &str) -> EmbedRes {
+    let mut embed = CreateEmbed::new().color(card.accent_color()).title(format!(
+        "{} ({}) {}",
+        card.name,
+        set.name,
+        match &card.traits {
+            Some(tr) => tr.flags.to_emoji(),
+            None => String::new(),
+        }
+    ));
 
     let mut desc = if card.description.is_empty() || compact {
         String::new()
@@ -34,69 +30,107 @@ pub fn gen_embed(card: &Card, set: &Set, compact: bool) -> EmbedRes {
     let mut out = String::new();
 
     if let Some(costs) = &card.costs {
-        append_cost(&mut out, costs.blood, "Blood", cost::BLOOD);
-        append_cost(&mut out, costs.bone, "Bone", cost::BONE);
-        append_cost(&mut out, costs.energy, "Energy", cost::ENERGY);
-        append_cost(&mut out, costs.extra.max, "Max", cost::MAX);
+        append_cost(
+            &mut out,
+            costs.blood,
+            tr!(lang, "embed.blood_cost"),
+            &cost::BLOOD,
+        );
+        append_cost(
+            &mut out,
+            costs.bone,
+            tr!(lang, "embed.bone_cost"),
+            &cost::BONE,
+        );
+        append_cost(
+            &mut out,
+            costs.energy,
+            tr!(lang, "embed.energy_cost"),
+            &cost::ENERGY,
+        );
+        append_cost(
+            &mut out,
+            costs.extra.max,
+            tr!(lang, "embed.max_cost"),
+            &cost::MAX,
+        );
 
         if !costs.mox.is_empty() {
-            let mut mox_cost = String::from("**Mox cost:** ");
-            let count = costs.mox_count.clone().unwrap_or_default();
-
-            for m in costs.mox.iter() {
-                match m {
-                    Mox::O => mox_cost.extend(vec![cost::ORANGE; count.o]),
-                    Mox::G => mox_cost.extend(vec![cost::GREEN; count.g]),
-                    Mox::B => mox_cost.extend(vec![cost::BLUE; count.b]),
-                    Mox::Y => mox_cost.extend(vec![cost::GRAY; count.y]),
-                    _ => unreachable!(),
-                }
-            }
+            let mut mox_cost = format!("**{}:** ", tr!(lang, "embed.mox_cost"));
+            mox_cost.push_str(&mox_display(costs.mox, costs.mox_count.as_ref()));
             out.push_str(&mox_cost);
             out.push('\n');
         }
     }
 
     if out.is_empty() {
-        out.push_str("**Free**\n");
+        writeln!(out, "**{}**", tr!(lang, "embed.free")).unwrap();
     }
 
     desc.push_str(&out); // the card cost
     desc.push('\n'); // stat separator
 
-    // imf shouldn't have any other thing
-    #[allow(clippy::match_wildcard_for_single_variants)]
     desc.push_str(&format!(
-        "**Stat:** {} / {}\n",
-        match &card.attack {
-            Attack::Num(a) => a.to_string(),
-            Attack::SpAtk(sp) => sp.to_emoji(),
-            _ => unreachable!(),
-        },
+        "**{}:** {} / {}\n",
+        tr!(lang, "embed.stat"),
+        card.attack.to_display(&AppEmojis),
         card.health
     ));
 
     if !card.sigils.is_empty() {
         if compact {
-            desc.push_str(&format!("**Sigils:** {}\n", card.sigils.join(", ")));
+            desc.push_str(&format!(
+                "**{}:** {}\n",
+                tr!(lang, "embed.sigils"),
+                card.sigils.join(", ")
+            ));
         } else {
-            let mut desc = String::with_capacity(card.sigils.iter().map(String::len).sum());
+            let mut desc = String::with_capacity(card.sigils.iter().map(|s| s.len()).sum());
 
             for s in &card.sigils {
-                let text = set.sigils_description.get(s).unwrap();
-                desc.push_str(&format!("**{s}:** {text}\n"));
+                let sigil = set.sigil(s).unwrap();
+                let category = sigil
+                    .category
+                    .map(|c| format!(" *({c})*"))
+                    .unwrap_or_default();
+                desc.push_str(&format!("**{s}{category}:** {}\n", sigil.text));
             }
 
-            embed = embed.field("== SIGILS ==", desc, false);
+            embed = embed.field(tr!(lang, "embed.sigils_field"), desc, false);
         }
     }
 
+    // cards that list this card as related, like the cards that create a token
+    let related_by = set
+        .related_by(&card.name)
+        .into_iter()
+        .filter(|c| c.name != card.name)
+        .map(|c| c.name.as_str())
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![];
     if !card.related.is_empty() {
-        let value = format!("**Related:** {}\n", card.related.join(", "));
+        lines.push(format!(
+            "**{}:** {}",
+            tr!(lang, "embed.related"),
+            card.related.join(", ")
+        ));
+    }
+    if !related_by.is_empty() {
+        lines.push(format!(
+            "**{}:** {}",
+            tr!(lang, "embed.related_to"),
+            related_by.join(", ")
+        ));
+    }
+
+    if !lines.is_empty() {
+        let value = lines.join("\n");
+
         if compact {
             desc.push_str(&value);
         } else {
-            embed = embed.field("== EXTRA INFO ==", value, false);
+            embed = embed.field(tr!(lang, "embed.extra_info_field"), value, false);
         }
     }
 
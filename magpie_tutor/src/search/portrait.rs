@@ -1,27 +1,278 @@
-use image::{imageops, ImageFormat};
-use magpie_engine::{Rarity, Temple};
-use std::io::Cursor;
+use image::{imageops, imageops::FilterType, ImageFormat};
+use magpie_engine::{fetch::DownloadLimits, Rarity, Temple};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Cursor,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
 
-use crate::{get_portrait, resize_img, Card};
+use crate::{
+    config::{load_env_pairs, parse_env_pairs, static_set_code},
+    error, get_portrait, resize_img, scale_img,
+    sources::is_aug_source,
+    Card, Color, PORTRAITS, PORTRAIT_FILTERS,
+};
 
+/// Environment variable with the resize filter of each set.
+///
+/// It is a comma separated list of `<set code>=<filter>` like `std=lanczos`, the filter is
+/// `nearest` or `lanczos`. Sets not in the list use `nearest` since most sets are pixel art.
+pub const PORTRAIT_FILTERS_VAR: &str = "TUTOR_PORTRAIT_FILTERS";
+
+/// Environment variable with how many seconds a portrait download can take.
+pub const PORTRAIT_TIMEOUT_VAR: &str = "TUTOR_PORTRAIT_TIMEOUT";
+
+/// Environment variable with the biggest portrait that can be download in bytes.
+pub const PORTRAIT_MAX_BYTES_VAR: &str = "TUTOR_PORTRAIT_MAX_BYTES";
+
+/// Portrait download timeout when [`PORTRAIT_TIMEOUT_VAR`] is not set.
+const DEFAULT_PORTRAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Biggest portrait download when [`PORTRAIT_MAX_BYTES_VAR`] is not set, 8MB.
+const DEFAULT_PORTRAIT_MAX_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Portrait show when a card have no art and no temple with its own placeholder.
+pub const MISSING_PORTRAIT: &[u8] = include_bytes!("../../assets/missing_art.png");
+
+/// Placeholder portrait of each temple, tinted with the temple color.
+#[rustfmt::skip] // it look nicer like this
+const TEMPLE_MISSING_PORTRAITS: [(Temple, &[u8]); 6] = [
+    (Temple::BEAST, include_bytes!("../../assets/missing_beast.png")),
+    (Temple::UNDEAD, include_bytes!("../../assets/missing_undead.png")),
+    (Temple::TECH, include_bytes!("../../assets/missing_tech.png")),
+    (Temple::MAGICK, include_bytes!("../../assets/missing_magick.png")),
+    (Temple::FOOL, include_bytes!("../../assets/missing_fool.png")),
+    (Temple::ARTISTRY, include_bytes!("../../assets/missing_artistry.png")),
+];
+
+/// Most portraits keep in [`PORTRAITS`], the least recently use are drop first.
+const MAX_CACHED_PORTRAITS: usize = 64;
+
+/// Most portraits generated at the same time by [`gen_portraits`].
+const MAX_PORTRAIT_JOBS: usize = 4;
+
+/// The filter to scale a portrait up with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PortraitFilter {
+    /// Keep the pixels sharp, for pixel art.
+    #[default]
+    Nearest,
+    /// Smooth the pixels, for HD art.
+    Lanczos,
+}
+
+impl From<PortraitFilter> for FilterType {
+    fn from(filter: PortraitFilter) -> Self {
+        match filter {
+            PortraitFilter::Nearest => FilterType::Nearest,
+            PortraitFilter::Lanczos => FilterType::Lanczos3,
+        }
+    }
+}
+
+/// Parse a list of `<set code>=<filter>` separated by comma, see [`parse_env_pairs`].
+pub fn parse_portrait_filters(
+    value: &str,
+) -> Result<HashMap<&'static str, PortraitFilter>, String> {
+    parse_env_pairs(value, "<set code>=<filter>", |code, filter| {
+        let filter = match filter.to_lowercase().as_str() {
+            "nearest" => PortraitFilter::Nearest,
+            "lanczos" => PortraitFilter::Lanczos,
+            _ => return Err(format!("`{filter}` is not nearest or lanczos")),
+        };
+
+        Ok((static_set_code(code)?, filter))
+    })
+    .map(|filters| filters.into_iter().collect())
+}
+
+/// Load the resize filter of each set from [`PORTRAIT_FILTERS_VAR`].
+pub fn load_portrait_filters() -> HashMap<&'static str, PortraitFilter> {
+    load_env_pairs(PORTRAIT_FILTERS_VAR, parse_portrait_filters).unwrap_or_default()
+}
+
+/// Load the portrait download limits from [`PORTRAIT_TIMEOUT_VAR`] and [`PORTRAIT_MAX_BYTES_VAR`].
+///
+/// Only images are accepted. Invalid values are log and the default is use instead.
+pub fn load_portrait_limits() -> DownloadLimits {
+    let var = |name: &str, default: u64| match env::var(name) {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            error!("Ignoring {} since {value} is not a number", name.red());
+            default
+        }),
+        Err(_) => default,
+    };
+
+    DownloadLimits::new()
+        .timeout(Duration::from_secs(var(
+            PORTRAIT_TIMEOUT_VAR,
+            DEFAULT_PORTRAIT_TIMEOUT.as_secs(),
+        )))
+        .max_bytes(var(PORTRAIT_MAX_BYTES_VAR, DEFAULT_PORTRAIT_MAX_BYTES))
+        .content_type("image/")
+}
+
+/// Recently generated portraits, key by the card id, portrait and resize filter.
+///
+/// The card id is also keep with each portrait so the portraits of a card can be remove.
+#[derive(Debug, Default)]
+pub struct PortraitLru {
+    entries: VecDeque<(u64, u64, Vec<u8>)>,
+}
+
+impl PortraitLru {
+    /// Get a portrait and mark it as the most recently use.
+    pub fn get(&mut self, key: u64) -> Option<Vec<u8>> {
+        let i = self.entries.iter().position(|(k, _, _)| *k == key)?;
+        let entry = self.entries.remove(i)?;
+        let data = entry.2.clone();
+        self.entries.push_front(entry);
+        Some(data)
+    }
+
+    /// Insert a portrait of a card, the least recently use one is drop if it is full.
+    pub fn insert(&mut self, key: u64, card_id: u64, data: Vec<u8>) {
+        self.entries.retain(|(k, _, _)| *k != key);
+        self.entries.push_front((key, card_id, data));
+        self.entries.truncate(MAX_CACHED_PORTRAITS);
+    }
+
+    /// Remove every portrait of these cards.
+    pub fn remove_cards(&mut self, card_ids: &HashSet<u64>) {
+        self.entries.retain(|(_, id, _)| !card_ids.contains(id));
+    }
+
+    /// Remove every portrait.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Get the placeholder portrait of a card base on its first temple.
+///
+/// Cards without a temple flag, including the ones with only [other temples](Card::other_temples),
+/// use [`MISSING_PORTRAIT`].
+pub fn missing_portrait(card: &Card) -> &'static [u8] {
+    card.temple
+        .iter()
+        .next()
+        .and_then(|t| {
+            TEMPLE_MISSING_PORTRAITS
+                .iter()
+                .find(|(temple, _)| *temple == t)
+        })
+        .map_or(MISSING_PORTRAIT, |(_, data)| data)
+}
+
+/// Get the file name of a card placeholder portrait.
+///
+/// It is different from the [`portrait_filename`](crate::portrait_filename) so the placeholder is
+/// never cache as the card portrait.
+pub fn missing_filename(card: &Card) -> String {
+    format!("missing_{}.png", card.id())
+}
+
+/// Generate the portrait of a card, styled according to the card set.
+///
+/// The result is keep in [`PORTRAITS`] so the same card is not generated again right away. Return
+/// [`None`] if the card have no art or the portrait cannot be download, use [`gen_portrait`] to get
+/// the [`missing_portrait`] instead.
+pub fn try_gen_portrait(card: &Card) -> Option<Vec<u8>> {
+    if card.portrait.is_empty() {
+        return None;
+    }
+
+    let filter = PORTRAIT_FILTERS
+        .get(card.set.code())
+        .copied()
+        .unwrap_or_default();
+
+    let key = {
+        let mut hasher = DefaultHasher::new();
+        (card.id(), &card.portrait, filter).hash(&mut hasher);
+        hasher.finish()
+    };
+
+    if let Some(data) = PORTRAITS.lock().unwrap().get(key) {
+        return Some(data);
+    }
+
+    let filter = filter.into();
+    let data = match card.set.code() {
+        "aug" | "Aug" => gen_aug_portrait(card, filter),
+        code if is_aug_source(code) => gen_aug_portrait(card, filter),
+        "std" | "ete" | "egg" | "des" => gen_scale_portrait(card, 4, filter),
+        // cti and homebrew portraits are already full size so leave them as is
+        _ => gen_simple_portrait(card),
+    };
+
+    // failed downloads are not keep so they are retry next time
+    if data.is_empty() {
+        return None;
+    }
+
+    PORTRAITS
+        .lock()
+        .unwrap()
+        .insert(key, card.id(), data.clone());
+    Some(data)
+}
+
+/// Generate the portrait of a card or get its [`missing_portrait`] if it cannot be generated.
+///
+/// Only use this where the portrait is not cache, see [`try_gen_portrait`].
 pub fn gen_portrait(card: &Card) -> Vec<u8> {
-    match card.set.code() {
-        "aug" | "Aug" => gen_aug_portrait(card),
-        "cti" => gen_simple_portrait(card),
-        "std" | "ete" | "egg" | "des" => gen_scale_portrait(card, 4),
-        code => todo!("portrait for set code is not implemented yet: {code}"),
+    try_gen_portrait(card).unwrap_or_else(|| missing_portrait(card).to_vec())
+}
+
+/// Generate the portraits of many cards at the same time with [`try_gen_portrait`], the result are
+/// in the same order as the cards.
+///
+/// Only [`MAX_PORTRAIT_JOBS`] portraits are generated at once so a big search doesn't flood the
+/// portrait hosts with downloads.
+pub fn gen_portraits(cards: &[&Card]) -> Vec<Option<Vec<u8>>> {
+    // no point spawning threads for a single portrait
+    if cards.len() <= 1 {
+        return cards.iter().map(|c| try_gen_portrait(c)).collect();
     }
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; cards.len()]);
+
+    thread::scope(|s| {
+        for _ in 0..MAX_PORTRAIT_JOBS.min(cards.len()) {
+            // each worker take the next card until there is none left
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(card) = cards.get(i) else {
+                    break;
+                };
+
+                let data = try_gen_portrait(card);
+                results.lock().unwrap()[i] = data;
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
 }
 
-fn gen_scale_portrait(card: &Card, scale: u32) -> Vec<u8> {
-    resize_img(&get_portrait(&card.portrait), scale)
+fn gen_scale_portrait(card: &Card, scale: u32, filter: FilterType) -> Vec<u8> {
+    resize_img(&get_portrait(&card.portrait), scale, filter)
 }
 
 fn gen_simple_portrait(card: &Card) -> Vec<u8> {
     get_portrait(&card.portrait)
 }
 
-fn gen_aug_portrait(card: &Card) -> Vec<u8> {
+fn gen_aug_portrait(card: &Card, filter: FilterType) -> Vec<u8> {
     let Ok(portrait) = image::load(Cursor::new(get_portrait(&card.portrait)), ImageFormat::Png)
     else {
         return Vec::new();
@@ -31,30 +282,26 @@ fn gen_aug_portrait(card: &Card) -> Vec<u8> {
         "https://raw.githubusercontent.com/answearingmachine/card-printer/main/dist/printer/assets/bg/bg_{}_{}.png",
 
         match &card.rarity {
-            Rarity::COMMON | Rarity::UNCOMMON | Rarity::SIDE => "common",
+            Rarity::COMMON | Rarity::UNCOMMON | Rarity::SIDE | Rarity::Other(_) => "common",
             Rarity::RARE | Rarity::UNIQUE => "rare",
         },
 
-        if let Some(t) = card.temple.iter().next() {
-            match t {
-                Temple::BEAST => "beast",
-                Temple::UNDEAD => "undead",
-                Temple::TECH => "tech",
-                Temple::MAGICK => "magick",
-                _ => unreachable!(),
-            }
-        } else {
-            unreachable!()
+        // there is no background for the other temples, like the ones that fail to parse and are
+        // only in the other temples, so they use the beast one
+        match card.temple.iter().next() {
+            Some(Temple::UNDEAD) => "undead",
+            Some(Temple::TECH) => "tech",
+            Some(Temple::MAGICK) => "magick",
+            _ => "beast",
         },
     );
 
-    let mut bg = image::load(Cursor::new(get_portrait(bg)), ImageFormat::Png).unwrap();
+    let Ok(mut bg) = image::load(Cursor::new(get_portrait(bg)), ImageFormat::Png) else {
+        return Vec::new();
+    };
 
     imageops::overlay(&mut bg, &portrait, 0, 0);
 
-    let mut out = vec![];
-    bg.write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
-        .unwrap();
-
-    resize_img(&out, 2)
+    // scale the composite directly instead of encoding it only to decode it again
+    tokio::task::block_in_place(|| scale_img(&bg, 2, filter))
 }
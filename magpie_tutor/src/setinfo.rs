@@ -0,0 +1,233 @@
+//! Implementation for showing set statistics.
+
+use std::{collections::BTreeMap, fmt::Write, io::Cursor};
+
+use image::{ImageFormat, Rgba, RgbaImage};
+use magpie_engine::SetStats;
+use poise::serenity_prelude::{colours::roles, CreateAttachment, CreateEmbed, CreateEmbedFooter};
+
+use crate::{
+    deck::{append_curve, draw_glyphs, fill, glyphs_width, number_glyphs},
+    engine::aug_color,
+    MessageAdapter, SETS,
+};
+
+/// The file name of the chart image attachment.
+const CHART_IMAGE_NAME: &str = "setinfo.png";
+
+/// What the colors of the cost chart mean.
+pub(crate) const COST_CHART_LEGEND: &str =
+    "Cost curve: red is blood, white is bone and blue is energy";
+
+/// Size of the number in the chart.
+const CHART_SCALE: u32 = 2;
+/// Width of each bar in the chart.
+const BAR_WIDTH: u32 = 24;
+/// Gap between bars of the same curve.
+const BAR_GAP: u32 = 8;
+/// Gap between each curve.
+const CURVE_GAP: u32 = 32;
+/// Height of the tallest bar.
+const BAR_HEIGHT: u32 = 160;
+/// Padding around the chart.
+const PADDING: u32 = 16;
+
+/// Generate the statistics message of a set, optionally with a bar chart of the cost curves.
+pub fn setinfo_message(set_code: &str, chart: bool) -> MessageAdapter {
+    let sets = SETS.read().unwrap();
+    let Some(set) = sets.get(set_code) else {
+        return MessageAdapter::new().embeds(vec![CreateEmbed::new()
+            .color(roles::RED)
+            .title("Set Info Error")
+            .description(format!("Unknown set code `{set_code}`"))]);
+    };
+
+    let stats = set.stats();
+
+    let mut embed = CreateEmbed::new()
+        .color(roles::PURPLE)
+        .title(format!("{} ({})", set.name, set.code))
+        .description(format!(
+            "**Size:** {} cards\n**Sigils:** {}",
+            stats.size,
+            set.sigils_description.len()
+        ));
+
+    let rarities = count_lines(stats.rarities.iter().map(|(r, c)| (r.to_string(), *c)));
+    if !rarities.is_empty() {
+        embed = embed.field("== RARITY ==", rarities, true);
+    }
+
+    let temples = temple_lines(&stats);
+    if !temples.is_empty() {
+        embed = embed.field("== TEMPLE ==", temples, true);
+    }
+
+    let mut costs = cost_lines(&stats);
+    write!(
+        costs,
+        "**Average Stat:** {:.1} / {:.1}",
+        stats.average_attack, stats.average_health
+    )
+    .unwrap();
+
+    embed = embed.field("== STATS ==", costs, false);
+
+    match chart.then(|| gen_cost_chart(&stats)).flatten() {
+        Some(image) => MessageAdapter::new()
+            .embeds(vec![embed
+                .image(format!("attachment://{CHART_IMAGE_NAME}"))
+                .footer(CreateEmbedFooter::new(COST_CHART_LEGEND))])
+            .attachments(vec![CreateAttachment::bytes(image, CHART_IMAGE_NAME)]),
+        None => MessageAdapter::new().embeds(vec![embed]),
+    }
+}
+
+/// Format the free, cost curves and mox counts as `**cost:** count` lines.
+pub(crate) fn cost_lines(stats: &SetStats) -> String {
+    let mut costs = String::new();
+    if stats.free != 0 {
+        writeln!(costs, "**Free:** {}", stats.free).unwrap();
+    }
+    append_curve(&mut costs, "Blood", &stats.blood_curve);
+    append_curve(&mut costs, "Bone", &stats.bone_curve);
+    append_curve(&mut costs, "Energy", &stats.energy_curve);
+    if stats.mox != 0 {
+        writeln!(costs, "**Mox:** {}", stats.mox).unwrap();
+    }
+
+    costs
+}
+
+/// Format the non zero counts as `**name:** count` lines.
+fn count_lines(counts: impl Iterator<Item = (String, usize)>) -> String {
+    counts
+        .filter(|(_, c)| *c != 0)
+        .map(|(name, c)| format!("**{name}:** {c}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format the temple and other temple counts as `**temple:** count` lines.
+pub(crate) fn temple_lines(stats: &SetStats) -> String {
+    count_lines(
+        stats
+            .temples
+            .iter()
+            .map(|(t, c)| (t.to_string(), *c))
+            .chain(stats.other_temples.iter().cloned()),
+    )
+}
+
+/// Draw the cost curves as a bar chart with the count above and the cost below each bar.
+///
+/// Return [`None`] if every curve is empty.
+pub(crate) fn gen_cost_chart(stats: &SetStats) -> Option<Vec<u8>> {
+    let curve = |c: &BTreeMap<isize, usize>| {
+        c.iter()
+            .map(|(cost, count)| (Some(cost.unsigned_abs()), *count))
+            .collect()
+    };
+
+    gen_bar_chart(&[
+        (curve(&stats.blood_curve), Rgba([200, 40, 40, 255])),
+        (curve(&stats.bone_curve), Rgba([220, 220, 200, 255])),
+        (curve(&stats.energy_curve), Rgba([60, 140, 230, 255])),
+    ])
+}
+
+/// Draw the temples as a bar chart with a bar of the temple color per temple.
+///
+/// Other temples have no color of their own so they are all grey.
+///
+/// Return [`None`] if no card have a temple.
+pub(crate) fn gen_temple_chart(stats: &SetStats) -> Option<Vec<u8>> {
+    let groups = stats
+        .temples
+        .iter()
+        .map(|(temple, count)| (aug_color(*temple), *count))
+        .chain(
+            stats
+                .other_temples
+                .iter()
+                .map(|(_, count)| (roles::LIGHT_GREY, *count)),
+        )
+        .filter(|(_, count)| *count != 0)
+        .map(|(color, count)| {
+            (
+                vec![(None, count)],
+                Rgba([color.r(), color.g(), color.b(), 255]),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    gen_bar_chart(&groups)
+}
+
+/// A group of bars of the same color, the number to draw below each bar and the count.
+type BarGroup = (Vec<(Option<usize>, usize)>, Rgba<u8>);
+
+/// Draw groups of bars with the count above and the number below each bar if it have one.
+///
+/// Return [`None`] if every group is empty.
+#[allow(clippy::cast_possible_truncation)]
+fn gen_bar_chart(groups: &[BarGroup]) -> Option<Vec<u8>> {
+    let curves = groups
+        .iter()
+        .filter(|(c, _)| !c.is_empty())
+        .collect::<Vec<_>>();
+
+    let max = curves
+        .iter()
+        .flat_map(|(c, _)| c.iter().map(|(_, count)| *count))
+        .max()?;
+
+    let bars = curves.iter().map(|(c, _)| c.len() as u32).sum::<u32>();
+    let width = PADDING * 2 + bars * (BAR_WIDTH + BAR_GAP) - BAR_GAP * curves.len() as u32
+        + CURVE_GAP * (curves.len() as u32 - 1);
+    let label = 5 * CHART_SCALE;
+    let height = PADDING * 2 + BAR_HEIGHT + (label + 4) * 2;
+
+    let mut img = RgbaImage::from_pixel(width, height, Rgba([43, 45, 49, 255]));
+    let white = Rgba([255; 4]);
+    let base = PADDING + label + 4 + BAR_HEIGHT;
+
+    let mut x = PADDING;
+    for (curve, color) in curves {
+        for (below, count) in curve {
+            let bar = (*count as u32 * BAR_HEIGHT / max as u32).max(1);
+            fill(&mut img, (x, base - bar), (x + BAR_WIDTH, base), *color);
+
+            let count = number_glyphs(*count);
+            let center = |len| x + BAR_WIDTH.saturating_sub(glyphs_width(len, CHART_SCALE)) / 2;
+            draw_glyphs(
+                &mut img,
+                &count,
+                center(count.len()),
+                base - bar - label - 4,
+                CHART_SCALE,
+                white,
+            );
+
+            if let Some(below) = below {
+                let below = number_glyphs(*below);
+                draw_glyphs(
+                    &mut img,
+                    &below,
+                    center(below.len()),
+                    base + 4,
+                    CHART_SCALE,
+                    white,
+                );
+            }
+
+            x += BAR_WIDTH + BAR_GAP;
+        }
+        x += CURVE_GAP - BAR_GAP;
+    }
+
+    let mut out = vec![];
+    img.write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+        .expect("Encode chart fails");
+    Some(out)
+}
@@ -0,0 +1,227 @@
+//! Implementation for sharding.
+//!
+//! By default the bot run a single shard. Bigger deployments can set `TUTOR_SHARDS` to `auto` to
+//! let Discord pick the shard count or to a number to run that many shards in this process. To
+//! split the shards across processes set `TUTOR_SHARD_ID` and `TUTOR_SHARD_TOTAL` on each process.
+//!
+//! Shards in the same process share all the global state. Processes that run a single shard each
+//! keep their own copy of the guild data files, see [`data_path`], so they never overwrite each
+//! other changes. A guild always land on the same shard so its settings stay together.
+//!
+//! The data that is not tied to a guild, like the rulings, the stats and the per user favorites
+//! and history, is in a single file share by every process instead. It is only change while
+//! holding the file lock, see [`with_shared_lock`], and reload when another process change it,
+//! see [`shared_changed`]. The scheduled posts are only made by the process that have the guild
+//! in its cache.
+
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, File},
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    time::SystemTime,
+};
+
+use poise::serenity_prelude::{Client, Error};
+use serde::Serialize;
+
+use crate::{info, Color};
+
+/// The shard this process run when it only run a single shard out of many.
+fn process_shard() -> Option<u32> {
+    static SHARD: OnceLock<Option<u32>> = OnceLock::new();
+
+    *SHARD.get_or_init(|| match ShardConfig::from_env() {
+        Ok(ShardConfig::Shard { id, .. }) => Some(id),
+        _ => None,
+    })
+}
+
+/// Get the path of a data file for this process.
+///
+/// Processes that run a single shard get the shard id before the extension, like
+/// `./languages.shard1.bin`, other processes use the path as is.
+pub fn data_path(path: &str) -> String {
+    let Some(id) = process_shard() else {
+        return path.to_owned();
+    };
+
+    match path.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.contains('/') => {
+            format!("{stem}.shard{id}.{ext}")
+        }
+        _ => format!("{path}.shard{id}"),
+    }
+}
+
+/// Read a data file of this process, see [`data_path`].
+///
+/// A process without its own copy yet start from the shared file, like after switching to one
+/// process per shard.
+pub fn read_data(path: &str) -> Option<Vec<u8>> {
+    fs::read(data_path(path)).or_else(|_| fs::read(path)).ok()
+}
+
+/// Write a data file of this process as bincode, see [`data_path`].
+pub fn write_data<T: Serialize + ?Sized>(path: &str, data: &T) {
+    let bytes = bincode::serialize(data).expect("Cannot serialize data file");
+    replace_file(&data_path(path), &bytes);
+}
+
+/// When each shared data file was last change as far as this process know.
+static SEEN: Mutex<Option<HashMap<String, Option<SystemTime>>>> = Mutex::new(None);
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn mark_seen(path: &str, time: Option<SystemTime>) {
+    SEEN.lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(path.to_owned(), time);
+}
+
+/// Read a data file share by every process.
+pub fn read_shared(path: &str) -> io::Result<Vec<u8>> {
+    // the time is take first so a change made while reading is reload again later
+    let time = modified(path);
+    let bytes = fs::read(path);
+    mark_seen(path, time);
+    bytes
+}
+
+/// Write a data file share by every process as bincode, use [`with_shared_lock`] to reload it
+/// first.
+pub fn write_shared<T: Serialize + ?Sized>(path: &str, data: &T) {
+    let bytes = bincode::serialize(data).expect("Cannot serialize data file");
+    replace_file(path, &bytes);
+}
+
+/// Check if another process change a shared data file since this process last read or write it.
+pub fn shared_changed(path: &str) -> bool {
+    let time = modified(path);
+    SEEN.lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|seen| seen.get(path))
+        != Some(&time)
+}
+
+/// Run `f` while holding the lock of a shared data file.
+///
+/// Only one process at a time can hold the lock, so a store that is reload, change then save in
+/// `f` never lose the changes of another process.
+pub fn with_shared_lock<R>(path: &str, f: impl FnOnce() -> R) -> R {
+    let lock = File::create(format!("{path}.lock")).expect("Cannot open data file lock");
+    lock.lock().expect("Cannot lock data file");
+    f()
+}
+
+/// Replace a file with new content.
+///
+/// The content is write to a temporary file unique to this write then move over the file, so the
+/// file is never half written even when it is save from many places at once.
+pub fn replace_file(path: &str, bytes: &[u8]) {
+    static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+    let tmp = format!(
+        "{path}.{}.{}.tmp",
+        std::process::id(),
+        NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+    );
+
+    fs::write(&tmp, bytes).expect("Cannot write data file");
+    fs::rename(&tmp, path).expect("Cannot replace data file");
+    mark_seen(path, modified(path));
+}
+
+/// Environment variable with the shard count or `auto`.
+pub const SHARDS_VAR: &str = "TUTOR_SHARDS";
+/// Environment variable with the shard id this process run.
+pub const SHARD_ID_VAR: &str = "TUTOR_SHARD_ID";
+/// Environment variable with the total shard count across every process.
+pub const SHARD_TOTAL_VAR: &str = "TUTOR_SHARD_TOTAL";
+
+/// How the bot should be shard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardConfig {
+    /// A single shard, the default.
+    Single,
+    /// Let Discord decide the shard count.
+    Auto,
+    /// Run this many shards in this process.
+    Total(u32),
+    /// Run a single shard out of the total, for deployments with one process per shard.
+    Shard {
+        /// The shard id this process run.
+        id: u32,
+        /// The total shard count across every process.
+        total: u32,
+    },
+}
+
+impl ShardConfig {
+    /// Read the shard config from the environment variables.
+    ///
+    /// Return an error message if the variables are set to something invalid.
+    pub fn from_env() -> Result<Self, String> {
+        let var = |name: &str| env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let num = |name: &str, value: String| {
+            value
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| format!("`{name}` must be a number but got `{value}`"))
+        };
+
+        match (var(SHARD_ID_VAR), var(SHARD_TOTAL_VAR), var(SHARDS_VAR)) {
+            (Some(id), Some(total), _) => {
+                let (id, total) = (num(SHARD_ID_VAR, id)?, num(SHARD_TOTAL_VAR, total)?);
+                if id >= total {
+                    return Err(format!(
+                        "`{SHARD_ID_VAR}` must be less than `{SHARD_TOTAL_VAR}`"
+                    ));
+                }
+                Ok(ShardConfig::Shard { id, total })
+            }
+            (Some(_), None, _) | (None, Some(_), _) => Err(format!(
+                "`{SHARD_ID_VAR}` and `{SHARD_TOTAL_VAR}` must be set together"
+            )),
+            (None, None, Some(shards)) if shards.trim().eq_ignore_ascii_case("auto") => {
+                Ok(ShardConfig::Auto)
+            }
+            (None, None, Some(shards)) => match num(SHARDS_VAR, shards)? {
+                0 => Err(format!("`{SHARDS_VAR}` must be at least 1")),
+                1 => Ok(ShardConfig::Single),
+                total => Ok(ShardConfig::Total(total)),
+            },
+            (None, None, None) => Ok(ShardConfig::Single),
+        }
+    }
+
+    /// Start the client with this shard config.
+    pub async fn start(self, client: &mut Client) -> Result<(), Error> {
+        match self {
+            ShardConfig::Single => {
+                info!("Starting with a single shard");
+                client.start().await
+            }
+            ShardConfig::Auto => {
+                info!("Starting with {} shards", "auto".green());
+                client.start_autosharded().await
+            }
+            ShardConfig::Total(total) => {
+                info!("Starting with {} shards", total.green());
+                client.start_shards(total).await
+            }
+            ShardConfig::Shard { id, total } => {
+                info!("Starting shard {} of {}", id.green(), total.green());
+                client.start_shard(id, total).await
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Implementation for looking up a sigil.
+
+use std::sync::Arc;
+
+use magpie_engine::match_sigil;
+use poise::serenity_prelude::{colours::roles, CreateEmbed, CreateEmbedFooter, GuildId};
+
+use crate::{homebrew, MessageAdapter, SETS};
+
+/// Look up a sigil in a set and generate the sigil message.
+///
+/// The name is match with [`match_sigil`] so small typos still find the sigil. The guild homebrew
+/// set can be look up too.
+pub fn sigil_message(set_code: &str, name: &str, guild_id: Option<GuildId>) -> MessageAdapter {
+    let error = |desc: String| {
+        MessageAdapter::new().embeds(vec![CreateEmbed::new()
+            .color(roles::RED)
+            .title("Sigil Error")
+            .description(desc)])
+    };
+
+    let homebrew = homebrew::guild_set(guild_id).filter(|h| h.code.code() == set_code);
+    let sets = SETS.read().unwrap();
+    let Some(set) = sets.get(set_code).map(Arc::as_ref).or(homebrew.as_ref()) else {
+        return error(format!("Unknown set code `{set_code}`"));
+    };
+
+    let Some(sigil) =
+        match_sigil(&set.sigils_description, name).and_then(|(name, _)| set.sigil(name))
+    else {
+        return error(format!("{} have no sigil named `{name}`", set.name));
+    };
+
+    let cards = set
+        .cards
+        .iter()
+        .filter(|c| c.sigils.iter().any(|s| &**s == sigil.name))
+        .count();
+
+    let mut embed = CreateEmbed::new()
+        .color(roles::BLUE)
+        .title(sigil.name)
+        .description(sigil.text)
+        .footer(CreateEmbedFooter::new(&set.name));
+
+    if let Some(category) = sigil.category {
+        embed = embed.field("Category", category, true);
+    }
+
+    MessageAdapter::new().embeds(vec![embed.field("Cards", cards.to_string(), true)])
+}
@@ -0,0 +1,72 @@
+//! Implementation for the extra set sources.
+//!
+//! Testers can point the bot at forked Augmented balance sheets by setting `TUTOR_AUG_SOURCES` to
+//! a comma separated list of `<set code>=<sheet id>`, like `xug=1abc,yug=1def`. Each source is
+//! fetch and refresh like the built in sets and can be search with its own set code.
+
+use std::collections::HashMap;
+
+use magpie_engine::{fetch::AugBranch, prelude::*};
+
+use crate::{
+    config::{load_env_pairs, parse_env_pairs, static_set_code},
+    done, Color, Set, AUG_SOURCES,
+};
+
+/// Environment variable with the extra Augmented sources.
+pub const AUG_SOURCES_VAR: &str = "TUTOR_AUG_SOURCES";
+
+/// An extra Augmented sheet to fetch.
+#[derive(Debug, Clone)]
+pub struct AugSource {
+    /// The set code to search this source with.
+    pub code: &'static str,
+    /// The google sheet id.
+    pub sheet_id: String,
+}
+
+/// Parse a list of `<set code>=<sheet id>` separated by comma, see [`parse_env_pairs`].
+pub fn parse_aug_sources(value: &str) -> Result<Vec<AugSource>, String> {
+    parse_env_pairs(value, "<set code>=<sheet id>", |code, sheet_id| {
+        Ok(AugSource {
+            code: static_set_code(code)?,
+            sheet_id: sheet_id.to_owned(),
+        })
+    })
+}
+
+/// Load the extra Augmented sources from [`AUG_SOURCES_VAR`].
+pub(crate) fn load_aug_sources() -> Vec<AugSource> {
+    load_env_pairs(AUG_SOURCES_VAR, parse_aug_sources).unwrap_or_default()
+}
+
+/// Check if a set code is one of the extra Augmented sources.
+pub fn is_aug_source(code: &str) -> bool {
+    AUG_SOURCES.iter().any(|s| s.code == code)
+}
+
+/// Fetch every extra source, sources that fail to fetch are map to the error message.
+pub fn fetch_sources() -> HashMap<&'static str, Result<Set, String>> {
+    AUG_SOURCES
+        .iter()
+        .map(|source| {
+            let now = std::time::Instant::now();
+            let set = fetch_aug_set(
+                AugBranch::Custom(source.sheet_id.clone()),
+                SetCode::new(source.code).unwrap(),
+            )
+            .map(magpie_engine::Set::upgrade)
+            .map_err(|e| format!("Cannot process custom aug set {}: {e:?}", source.code));
+
+            if set.is_ok() {
+                done!(
+                    "Finish fetching custom aug set with code {} in {}",
+                    source.code.yellow(),
+                    format!("{:.2?}", now.elapsed()).green()
+                );
+            }
+
+            (source.code, set)
+        })
+        .collect()
+}
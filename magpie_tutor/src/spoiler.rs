@@ -0,0 +1,132 @@
+//! Implementation for the spoiler sets.
+//!
+//! Guilds can flag sets as spoiler sets with `/spoiler`, this is mostly for set maintainers that
+//! want to preview snapshot cards without leaking them. Cards from a spoiler set are not show in
+//! searches, instead there is a placeholder with a reveal button that show the card to the user
+//! who press it with the portrait spoiler tagged.
+
+use std::collections::HashMap;
+
+use poise::serenity_prelude::{colours::roles, CreateAttachment, CreateEmbed, GuildId};
+
+use crate::{
+    done, portrait_filename,
+    search::{gen_plain_embed, gen_portrait},
+    shard::{read_data, write_data},
+    tr, Color, MessageAdapter, SETS, SPOILER_SETS,
+};
+
+/// Location of the spoiler sets file.
+pub const SPOILER_FILE_PATH: &str = "./spoiler.bin";
+
+/// Custom id prefix of the reveal buttons.
+pub const SPOILER_PREFIX: &str = "spoiler_";
+
+/// Type alias for the spoiler sets table, guild id to set codes.
+pub type SpoilerSets = HashMap<u64, Vec<String>>;
+
+/// Load the spoiler sets from the spoiler file.
+pub(crate) fn load_spoiler_sets() -> SpoilerSets {
+    read_data(SPOILER_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the spoiler sets to the spoiler file.
+///
+/// This lock [`SPOILER_SETS`] so make sure you drop any guard before calling this.
+pub fn save_spoiler_sets() {
+    write_data(SPOILER_FILE_PATH, &*SPOILER_SETS);
+    done!(
+        "Spoiler sets save successfully to {}",
+        SPOILER_FILE_PATH.green()
+    );
+}
+
+/// Check if a set is a spoiler set in a guild, nothing is a spoiler outside of guilds.
+pub fn is_spoiler(guild_id: Option<GuildId>, code: &str) -> bool {
+    let Some(guild_id) = guild_id else {
+        return false;
+    };
+
+    SPOILER_SETS
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .is_some_and(|sets| sets.iter().any(|s| s == code))
+}
+
+/// Flag or unflag a set as a spoiler set.
+///
+/// Return if the set is now a spoiler set or an error message if the set doesn't exist.
+pub fn toggle_spoiler(guild_id: GuildId, code: &str) -> Result<bool, String> {
+    if !SETS.read().unwrap().contains_key(code) {
+        return Err(format!("Unknown set code `{code}`"));
+    }
+
+    let flagged = {
+        let mut spoiler = SPOILER_SETS.lock().unwrap();
+        let sets = spoiler.entry(guild_id.get()).or_default();
+
+        if let Some(i) = sets.iter().position(|s| s == code) {
+            sets.remove(i);
+            false
+        } else {
+            sets.push(code.to_owned());
+            true
+        }
+    };
+
+    save_spoiler_sets();
+    Ok(flagged)
+}
+
+/// Get the spoiler sets of a guild.
+pub fn guild_spoiler_sets(guild_id: GuildId) -> Vec<String> {
+    SPOILER_SETS
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Generate the placeholder embed show in place of a spoiler card.
+pub fn spoiler_embed(set_name: &str, index: usize, lang: &str) -> CreateEmbed {
+    CreateEmbed::new()
+        .color(roles::DARK_GREY)
+        .title(tr!(lang, "spoiler.title", set = set_name))
+        .description(tr!(lang, "spoiler.description", index = index))
+}
+
+/// Generate the message for a reveal button.
+///
+/// The custom id is [`SPOILER_PREFIX`] follow by the set code and the card name. The portrait is
+/// send as a spoiler attachment instead of the embed thumbnail because thumbnails can't be
+/// spoiler tagged.
+pub fn reveal_message(custom_id: &str, lang: &str) -> MessageAdapter {
+    let g_sets = SETS.read().unwrap();
+    let Some((set, card)) = custom_id
+        .strip_prefix(SPOILER_PREFIX)
+        .and_then(|id| id.split_once('_'))
+        .and_then(|(code, name)| g_sets.get(code).map(|set| (set, name)))
+        .and_then(|(set, name)| set.get_card(name).map(|card| (set, card)))
+    else {
+        return MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::RED)
+                .title(tr!(lang, "spoiler.not_found_title"))
+                .description(tr!(lang, "spoiler.not_found"))])
+            .ephemeral(true);
+    };
+
+    let attachments = vec![CreateAttachment::bytes(
+        gen_portrait(card),
+        format!("SPOILER_{}", portrait_filename(card)),
+    )];
+
+    MessageAdapter::new()
+        .embeds(vec![gen_plain_embed(card, set, false, lang)])
+        .attachments(attachments)
+        .ephemeral(true)
+}
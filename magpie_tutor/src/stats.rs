@@ -0,0 +1,316 @@
+//! Implementation for the usage statistics.
+//!
+//! Every search update a set of counters like how many searches each set get, which cards are
+//! searched the most and how often the portrait cache is hit. The counters are kept for all time
+//! and for the current week, the weekly counters are reset after the weekly summary is posted. The
+//! stats are save to [`STATS_FILE_PATH`] periodically by the scheduler.
+//!
+//! The stats file is share by every shard process. Each process only add the searches it record
+//! since its last save to the file, so the counters of every process add up.
+
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt::Write,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use chrono::Utc;
+use poise::serenity_prelude::{colours::roles, ChannelId, Context, CreateEmbed, CreateMessage};
+use serde::{Deserialize, Serialize};
+use tokio::time;
+
+use crate::{
+    done, error, info,
+    shard::{read_shared, with_shared_lock, write_shared},
+    Color, STATS,
+};
+
+/// Location of the stats file.
+pub const STATS_FILE_PATH: &str = "./stats.bin";
+
+/// How often the scheduler save the stats and check for the weekly summary.
+#[allow(clippy::duration_suboptimal_units)]
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How long a week is in seconds.
+const WEEK: i64 = 7 * 24 * 60 * 60;
+
+/// How many entries to show in the top lists.
+const TOP_SIZE: usize = 5;
+
+/// Usage counters over a period of time.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// How many times each set was searched, keyed by set code.
+    pub sets: HashMap<String, u64>,
+    /// How many times each card was searched, keyed by card name.
+    pub cards: HashMap<String, u64>,
+    /// How many queries were run.
+    pub queries: u64,
+    /// How many portraits were taken from the cache.
+    pub cache_hits: u64,
+    /// How many portraits had to be generated.
+    pub cache_misses: u64,
+    /// How many search messages were processed.
+    pub searches: u64,
+    /// The total time spent processing searches in microseconds.
+    pub latency: u64,
+}
+
+impl Usage {
+    /// The average time taken to process a search.
+    pub fn avg_latency(&self) -> Duration {
+        Duration::from_micros(self.latency.checked_div(self.searches).unwrap_or_default())
+    }
+
+    /// The percentage of portraits taken from the cache.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn cache_hit_rate(&self) -> f64 {
+        let total = self.cache_hits + self.cache_misses;
+        if total == 0 {
+            0.
+        } else {
+            self.cache_hits as f64 / total as f64 * 100.
+        }
+    }
+
+    /// Add the counters of another usage to this one.
+    fn merge(&mut self, other: &Usage) {
+        for (set, count) in &other.sets {
+            *self.sets.entry(set.clone()).or_default() += count;
+        }
+        for (card, count) in &other.cards {
+            *self.cards.entry(card.clone()).or_default() += count;
+        }
+
+        self.queries += other.queries;
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+        self.searches += other.searches;
+        self.latency += other.latency;
+    }
+
+    fn apply(&mut self, record: &SearchRecord) {
+        for (set, card) in &record.cards {
+            *self.sets.entry(set.clone()).or_default() += 1;
+            *self.cards.entry(card.clone()).or_default() += 1;
+        }
+
+        self.queries += record.queries;
+        self.cache_hits += record.cache_hits;
+        self.cache_misses += record.cache_misses;
+        self.searches += 1;
+        self.latency += u64::try_from(record.latency.as_micros()).unwrap_or(u64::MAX);
+    }
+}
+
+/// All the usage stats of the bot.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The all time counters.
+    pub total: Usage,
+    /// The counters of the current week.
+    pub week: Usage,
+    /// When the current week started in unix seconds.
+    pub week_start: i64,
+    /// The channel to post the weekly summary in.
+    pub summary_channel: Option<u64>,
+    /// The searches record by this process that are not in the stats file yet.
+    #[serde(skip)]
+    pub unsaved: Usage,
+}
+
+/// The counters of a single search message, recorded at once so [`STATS`] is only lock once.
+#[derive(Debug, Clone, Default)]
+pub struct SearchRecord {
+    /// The set code and name of every card found.
+    pub cards: Vec<(String, String)>,
+    /// How many queries were run.
+    pub queries: u64,
+    /// How many portraits were taken from the cache.
+    pub cache_hits: u64,
+    /// How many portraits had to be generated.
+    pub cache_misses: u64,
+    /// How long the search took.
+    pub latency: Duration,
+}
+
+/// Load the stats from the stats file.
+pub(crate) fn load_stats() -> Stats {
+    read_shared(STATS_FILE_PATH)
+        .ok()
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Change the stats then save them.
+///
+/// The stats are reload from the stats file first with the unsaved searches of this process add
+/// to them, so the changes and searches of the other processes are not lost. This lock [`STATS`]
+/// so make sure you drop any guard before calling this.
+pub fn update_stats<R>(f: impl FnOnce(&mut Stats) -> R) -> R {
+    with_shared_lock(STATS_FILE_PATH, || {
+        let mut stats = STATS.lock().unwrap();
+        let unsaved = std::mem::take(&mut stats.unsaved);
+
+        *stats = load_stats();
+        stats.total.merge(&unsaved);
+        stats.week.merge(&unsaved);
+
+        let res = f(&mut stats);
+        write_shared(STATS_FILE_PATH, &*stats);
+        res
+    })
+}
+
+/// Save the stats to the stats file, see [`update_stats`].
+pub fn save_stats() {
+    update_stats(|_| ());
+    done!("Stats save successfully to {}", STATS_FILE_PATH.green());
+}
+
+/// Record a search message in the stats.
+///
+/// The stats are not save right away, the scheduler save them periodically.
+pub fn record_search(record: &SearchRecord) {
+    let mut stats = STATS.lock().unwrap();
+    stats.total.apply(record);
+    stats.week.apply(record);
+    stats.unsaved.apply(record);
+}
+
+/// The name of the most searched cards of all time, the most searched first.
+pub fn top_cards(count: usize) -> Vec<String> {
+    let stats = STATS.lock().unwrap();
+    let mut cards = stats.total.cards.iter().collect::<Vec<_>>();
+    cards.sort_by_key(|(name, count)| (Reverse(**count), *name));
+
+    cards
+        .into_iter()
+        .take(count)
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Set the channel to post the weekly summary in, [`None`] to stop posting.
+pub fn set_summary_channel(channel_id: Option<ChannelId>) {
+    update_stats(|stats| stats.summary_channel = channel_id.map(ChannelId::get));
+}
+
+/// Generate an embed showing the usage counters.
+pub fn stats_embed(usage: &Usage, title: &str) -> CreateEmbed {
+    CreateEmbed::new()
+        .color(roles::BLUE)
+        .title(title)
+        .field("Searches", usage.searches.to_string(), true)
+        .field("Queries", usage.queries.to_string(), true)
+        .field(
+            "Average latency",
+            format!("{:.1?}", usage.avg_latency()),
+            true,
+        )
+        .field(
+            "Cache hit rate",
+            format!(
+                "{:.1}% ({} hits, {} misses)",
+                usage.cache_hit_rate(),
+                usage.cache_hits,
+                usage.cache_misses
+            ),
+            false,
+        )
+        .field("Top sets", top_list(&usage.sets), true)
+        .field("Top cards", top_list(&usage.cards), true)
+}
+
+fn top_list(counts: &HashMap<String, u64>) -> String {
+    let mut counts = counts.iter().collect::<Vec<_>>();
+    counts.sort_by_key(|(name, count)| (Reverse(**count), *name));
+
+    if counts.is_empty() {
+        return String::from("None");
+    }
+
+    counts
+        .iter()
+        .take(TOP_SIZE)
+        .enumerate()
+        .fold(String::new(), |mut out, (i, (name, count))| {
+            writeln!(out, "{}. {name}: {count}", i + 1).unwrap();
+            out
+        })
+}
+
+/// Start the stats scheduler, only the first call start it.
+///
+/// The scheduler save the stats and post the weekly summary when a week have passed.
+pub fn start_scheduler(ctx: &Context) {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        info!("Stats scheduler started");
+        let mut interval = time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            // only the process that can see the channel post the summary
+            let summary = update_stats(|stats| {
+                due_summary(stats, |id| {
+                    ctx.cache.guilds().into_iter().any(|g| {
+                        ctx.cache
+                            .guild(g)
+                            .is_some_and(|g| g.channels.contains_key(&id))
+                    })
+                })
+            });
+            done!("Stats save successfully to {}", STATS_FILE_PATH.green());
+
+            if let Some((channel_id, msg)) = summary {
+                if let Err(err) = channel_id.send_message(&ctx, msg).await {
+                    error!("Cannot post weekly stats summary in {channel_id} due to: {err}");
+                }
+            }
+        }
+    });
+}
+
+/// Get the weekly summary if a week have passed, then start a new week.
+fn due_summary(
+    stats: &mut Stats,
+    filter: impl Fn(ChannelId) -> bool,
+) -> Option<(ChannelId, CreateMessage)> {
+    let now = Utc::now().timestamp();
+
+    if stats
+        .summary_channel
+        .is_some_and(|id| !filter(ChannelId::new(id)))
+    {
+        return None;
+    }
+
+    if stats.week_start == 0 {
+        stats.week_start = now;
+    }
+
+    if now - stats.week_start < WEEK {
+        return None;
+    }
+
+    let week = std::mem::take(&mut stats.week);
+    stats.week_start = now;
+
+    stats.summary_channel.map(|id| {
+        (
+            ChannelId::new(id),
+            CreateMessage::new().embed(stats_embed(&week, "Weekly Summary")),
+        )
+    })
+}
@@ -0,0 +1,155 @@
+//! Implementation for the service status check.
+//!
+//! The `/status` command probe every service Magpie or the community depend on at the same time
+//! and show if they are up along with their latency. The services can be change by setting
+//! `TUTOR_STATUS_SERVICES` to a comma separated list of `<name>=<url>`, otherwise
+//! [`DEFAULT_SERVICES`] are use.
+//!
+//! A service is up if it answer with anything but a server error, some API like Notion reject
+//! request without a token but that still mean they are reachable.
+
+use std::time::{Duration, Instant};
+
+use isahc::{config::Configurable, AsyncReadResponseExt, HttpClient};
+use poise::serenity_prelude::{colours::roles, CreateEmbed};
+
+use crate::{
+    config::{load_env_pairs, parse_env_pairs},
+    STATUS_SERVICES,
+};
+
+/// Environment variable with the services to probe.
+pub const STATUS_SERVICES_VAR: &str = "TUTOR_STATUS_SERVICES";
+
+/// The services to probe when [`STATUS_SERVICES_VAR`] is not set.
+pub const DEFAULT_SERVICES: [(&str, &str); 5] = [
+    ("Localtunnel", "http://localtunnel.me"),
+    ("Opensheet", "https://opensheet.elk.sh"),
+    (
+        "Augmented Sheet",
+        "https://opensheet.elk.sh/1tvTXSsFDK5xAVALQPdDPJOitBufJE6UB_MN4q5nbLXk/3",
+    ),
+    ("Notion", "https://api.notion.com/v1/users"),
+    ("GitHub Raw", "https://raw.githubusercontent.com"),
+];
+
+/// How long a probe can take before the service is consider down.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A service to probe.
+#[derive(Debug, Clone)]
+pub struct Service {
+    /// The name show in the status embed.
+    pub name: String,
+    /// The url to request.
+    pub url: String,
+}
+
+/// Parse a list of `<name>=<url>` separated by comma, see [`parse_env_pairs`].
+pub fn parse_services(value: &str) -> Result<Vec<Service>, String> {
+    parse_env_pairs(value, "<name>=<url>", |name, url| {
+        if !url.starts_with("http") {
+            return Err(format!("`{url}` is not an http url"));
+        }
+
+        Ok(Service {
+            name: name.to_owned(),
+            url: url.to_owned(),
+        })
+    })
+}
+
+/// Load the services from [`STATUS_SERVICES_VAR`], falling back to [`DEFAULT_SERVICES`].
+pub(crate) fn load_services() -> Vec<Service> {
+    load_env_pairs(STATUS_SERVICES_VAR, parse_services)
+        .filter(|services| !services.is_empty())
+        .unwrap_or_else(|| {
+            DEFAULT_SERVICES
+                .iter()
+                .map(|(name, url)| Service {
+                    name: (*name).to_owned(),
+                    url: (*url).to_owned(),
+                })
+                .collect()
+        })
+}
+
+/// Probe a single service, return the latency or why it is down.
+async fn probe(client: &HttpClient, url: &str) -> Result<Duration, String> {
+    let now = Instant::now();
+    let mut res = client.get_async(url).await.map_err(|e| e.to_string())?;
+    let latency = now.elapsed();
+
+    // drain the body so the connection can be reuse
+    res.consume().await.ok();
+
+    if res.status().is_server_error() {
+        Err(res.status().to_string())
+    } else {
+        Ok(latency)
+    }
+}
+
+/// Probe every service at the same time.
+pub async fn probe_services() -> Vec<(Service, Result<Duration, String>)> {
+    let client = match HttpClient::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(c) => c,
+        Err(err) => {
+            return STATUS_SERVICES
+                .iter()
+                .map(|s| (s.clone(), Err(err.to_string())))
+                .collect()
+        }
+    };
+
+    let handles = STATUS_SERVICES
+        .iter()
+        .map(|service| {
+            let client = client.clone();
+            let service = service.clone();
+            tokio::spawn(async move {
+                let res = probe(&client, &service.url).await;
+                (service, res)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut results = vec![];
+    for (handle, service) in handles.into_iter().zip(STATUS_SERVICES.iter()) {
+        results.push(
+            handle
+                .await
+                .unwrap_or_else(|e| (service.clone(), Err(e.to_string()))),
+        );
+    }
+    results
+}
+
+/// Generate the status embed from the probe results.
+pub fn status_embed(results: &[(Service, Result<Duration, String>)]) -> CreateEmbed {
+    let up = results.iter().filter(|(_, r)| r.is_ok()).count();
+
+    let lines = results
+        .iter()
+        .map(|(service, res)| match res {
+            Ok(latency) => format!("🟢 **{}**: up in {latency:.0?}", service.name),
+            Err(err) => format!("🔴 **{}**: down ({err})", service.name),
+        })
+        .collect::<Vec<_>>();
+
+    CreateEmbed::new()
+        .color(if up == results.len() {
+            roles::GREEN
+        } else if up == 0 {
+            roles::RED
+        } else {
+            roles::ORANGE
+        })
+        .title(format!("Service Status ({up}/{} up)", results.len()))
+        .description(lines.join("\n"))
+        .field(
+            "Tunnel issue?",
+            "If the tunnel is up but you still have issue check out [this faq](https://discord.com/channels/994573431880286289/1168644586319659100/1168657617141366805).",
+            false,
+        )
+}
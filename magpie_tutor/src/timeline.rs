@@ -0,0 +1,192 @@
+//! Implementation for the card change timeline.
+//!
+//! Every time the background refresh find a set that changed the [`SetDiff`] is record per card
+//! along with the time, so the History button on a search can show when a card was added, nerfed
+//! or buffed. Only the changes since the bot start tracking are known, the timeline is save to
+//! [`TIMELINE_FILE_PATH`] so it survive restarts.
+
+use std::{collections::HashMap, fmt::Write};
+
+use magpie_engine::prelude::*;
+use poise::serenity_prelude::{colours::roles, CreateEmbed};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    changelog::shorten,
+    current_epoch, done,
+    shard::{read_data, write_data},
+    Color, MessageAdapter, SETS, TIMELINE,
+};
+
+/// Location of the timeline file.
+pub const TIMELINE_FILE_PATH: &str = "./timeline.bin";
+
+/// Custom id prefix of the History buttons.
+pub const TIMELINE_PREFIX: &str = "timeline_";
+
+/// How many entries are keep per card, the oldest are drop first.
+const MAX_ENTRIES: usize = 25;
+
+/// Embed description are limited to 4096 characters, leave some room for the overflow line.
+const MAX_DESC_LEN: usize = 3900;
+
+/// Type alias for the timeline table, set code to card name to entries from oldest to newest.
+pub type Timeline = HashMap<String, HashMap<String, Vec<TimelineEntry>>>;
+
+/// A single change to a card.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimelineEntry {
+    /// When the change was found in seconds since epoch.
+    pub time: u64,
+    /// What changed.
+    pub kind: EntryKind,
+}
+
+/// The kind of change to a card.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum EntryKind {
+    /// The card was added to the set.
+    Added,
+    /// The card was removed from the set.
+    Removed,
+    /// Some fields of the card changed, the field name follow by the old and new value.
+    Changed(Vec<(String, String, String)>),
+}
+
+/// Load the timeline from the timeline file.
+pub(crate) fn load_timeline() -> Timeline {
+    read_data(TIMELINE_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the timeline to the timeline file.
+///
+/// This lock [`TIMELINE`] so make sure you drop any guard before calling this.
+pub fn save_timeline() {
+    write_data(TIMELINE_FILE_PATH, &*TIMELINE);
+    done!(
+        "Timeline save successfully to {}",
+        TIMELINE_FILE_PATH.green()
+    );
+}
+
+/// Record the changes of a set refresh in the timeline then save.
+pub fn record_changes(diffs: &[(&str, SetDiff)]) {
+    if diffs.is_empty() {
+        return;
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    let time = (current_epoch() / 1000) as u64;
+
+    {
+        let mut timeline = TIMELINE.lock().unwrap();
+        for (code, changes) in diffs {
+            let cards = timeline.entry((*code).to_owned()).or_default();
+            let mut push = |name: &str, kind| {
+                let entries = cards.entry(name.to_owned()).or_default();
+                entries.push(TimelineEntry { time, kind });
+                if entries.len() > MAX_ENTRIES {
+                    entries.remove(0);
+                }
+            };
+
+            for name in &changes.added {
+                push(name, EntryKind::Added);
+            }
+            for name in &changes.removed {
+                push(name, EntryKind::Removed);
+            }
+            for card in &changes.changed {
+                push(
+                    &card.name,
+                    EntryKind::Changed(
+                        card.fields
+                            .iter()
+                            .map(|f| (f.field.to_owned(), f.old.clone(), f.new.clone()))
+                            .collect(),
+                    ),
+                );
+            }
+        }
+    }
+
+    save_timeline();
+}
+
+/// Check if a card have anything in its timeline.
+pub fn has_timeline(code: &str, name: &str) -> bool {
+    TIMELINE
+        .lock()
+        .unwrap()
+        .get(code)
+        .is_some_and(|cards| cards.contains_key(name))
+}
+
+/// Generate the message for a History button.
+///
+/// The custom id is [`TIMELINE_PREFIX`] follow by the set code and the card name.
+pub fn timeline_message(custom_id: &str) -> MessageAdapter {
+    let entries = custom_id
+        .strip_prefix(TIMELINE_PREFIX)
+        .and_then(|id| id.split_once('_'))
+        .and_then(|(code, name)| {
+            TIMELINE
+                .lock()
+                .unwrap()
+                .get(code)
+                .and_then(|cards| cards.get(name).cloned())
+                .map(|e| (code, name, e))
+        });
+
+    let Some((code, name, entries)) = entries else {
+        return MessageAdapter::new()
+            .embeds(vec![CreateEmbed::new()
+                .color(roles::RED)
+                .title("History not found")
+                .description("This card have no recorded changes.")])
+            .ephemeral(true);
+    };
+
+    let lines = entries
+        .iter()
+        .rev()
+        .map(|e| {
+            let change = match &e.kind {
+                EntryKind::Added => String::from("Added to the set"),
+                EntryKind::Removed => String::from("Removed from the set"),
+                EntryKind::Changed(fields) => fields
+                    .iter()
+                    .map(|(field, old, new)| {
+                        format!("{field} `{}` → `{}`", shorten(old), shorten(new))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            format!("<t:{}:d> {change}", e.time)
+        })
+        .collect::<Vec<_>>();
+
+    let mut desc = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if desc.len() + line.len() > MAX_DESC_LEN {
+            write!(desc, "...and {} older changes", lines.len() - i).unwrap();
+            break;
+        }
+        writeln!(desc, "{line}").unwrap();
+    }
+
+    let set_name = SETS
+        .read()
+        .unwrap()
+        .get(code)
+        .map_or_else(|| code.to_owned(), |s| s.name.clone());
+
+    MessageAdapter::new()
+        .embeds(vec![CreateEmbed::new()
+            .color(roles::BLUE)
+            .title(format!("History of {name} ({set_name})"))
+            .description(desc)])
+        .ephemeral(true)
+}
@@ -0,0 +1,110 @@
+//! Implementation for the localized card names.
+//!
+//! Translation tables are load from [`TRANSLATIONS_DIR`], each `<language code>.toml` file map a
+//! localized name to the English name of the card:
+//!
+//! ```toml
+//! "Горностай" = "Stoat"
+//! "Hermelín" = "Stoat"
+//! ```
+//!
+//! Searches are match against the localized names along with the English names so `[[горностай]]`
+//! find the Stoat. A table is not tie to a set, a name is use in every set that have a card with
+//! the English name.
+
+use std::{collections::HashSet, fs};
+
+use crate::{error, fuzzy_best, Card, Color, FuzzyRes, Set, TRANSLATIONS};
+
+/// Location of the translation tables.
+pub const TRANSLATIONS_DIR: &str = "./translations";
+
+/// A localized name of a card.
+#[derive(Debug, Clone)]
+pub struct CardAlias {
+    /// The localized name.
+    pub name: String,
+    /// The language code of the table the name is from.
+    pub lang: String,
+    /// The English name of the card.
+    pub canonical: String,
+}
+
+/// Load every translation table in [`TRANSLATIONS_DIR`].
+///
+/// Tables that fail to parse are log and skip.
+pub(crate) fn load_translations() -> Vec<CardAlias> {
+    let Ok(dir) = fs::read_dir(TRANSLATIONS_DIR) else {
+        return vec![];
+    };
+
+    let mut aliases = vec![];
+    for path in dir.filter_map(|e| e.ok().map(|e| e.path())) {
+        if path.extension().is_none_or(|e| e != "toml") {
+            continue;
+        }
+
+        let Some(lang) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+
+        let table = match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|c| c.parse::<toml::Table>().map_err(|e| e.to_string()))
+        {
+            Ok(t) => t,
+            Err(err) => {
+                error!(
+                    "Cannot load translation table {} due to: {err}",
+                    path.display().red()
+                );
+                continue;
+            }
+        };
+
+        aliases.extend(table.into_iter().filter_map(|(name, canonical)| {
+            Some(CardAlias {
+                name,
+                lang: lang.to_owned(),
+                canonical: canonical.as_str()?.to_owned(),
+            })
+        }));
+    }
+
+    aliases
+}
+
+/// Fuzzy search the localized names of the cards in a set.
+///
+/// Return the card and the localized name that match. Only the names of `lang` are search if there
+/// is one.
+pub fn find_translated<'a>(
+    search_term: &str,
+    set: &'a Set,
+    lang: Option<&str>,
+) -> Option<(FuzzyRes<'a, Card>, &'static CardAlias)> {
+    if TRANSLATIONS.is_empty() {
+        return None;
+    }
+
+    let names = set
+        .cards
+        .iter()
+        .map(|c| c.name.to_lowercase())
+        .collect::<HashSet<_>>();
+
+    let FuzzyRes { rank, data: alias } = fuzzy_best(
+        search_term,
+        TRANSLATIONS
+            .iter()
+            .filter(|a| {
+                lang.is_none_or(|l| a.lang == l) && names.contains(&a.canonical.to_lowercase())
+            })
+            .collect(),
+        0.5,
+        |a: &CardAlias| a.name.as_str(),
+    )?;
+
+    set.get_card(&alias.canonical)
+        .map(|card| (FuzzyRes { rank, data: card }, alias))
+}
@@ -0,0 +1,284 @@
+//! Implementation for the guess the card trivia game.
+//!
+//! A trivia show some info about a random card like the sigils, cost and a blurred portrait. Then
+//! players can guess the card by replying with the card name or by picking one of the buttons. The
+//! first player to guess correctly get a point on their guild score table, which is save to
+//! [`TRIVIA_FILE_PATH`].
+
+use std::{cmp::Reverse, collections::HashMap, io::Cursor, time::Duration};
+
+use image::{GenericImageView, ImageFormat};
+use poise::serenity_prelude::{
+    colours::roles, ButtonStyle::Primary, ComponentInteractionCollector, CreateActionRow::Buttons,
+    CreateAttachment, CreateButton, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage, EditMessage, GuildId, MessageCollector, UserId,
+};
+use poise::{futures_util::StreamExt, CreateReply};
+use rand::{seq::SliceRandom, thread_rng};
+use tokio::task;
+
+use crate::{
+    done, lev,
+    search::gen_portrait,
+    shard::{read_data, write_data},
+    Card, CmdCtx, Color, Res, SETS, TRIVIA_SCORES,
+};
+
+/// Location of the trivia score file.
+pub const TRIVIA_FILE_PATH: &str = "./trivia.bin";
+
+/// How long players have to guess.
+pub const TRIVIA_TIME: Duration = Duration::from_secs(30);
+
+/// How similar a reply need to be with the card name to count as correct.
+const ANSWER_THRESHOLD: f32 = 0.8;
+
+/// How many choices to show as buttons.
+const CHOICES: usize = 4;
+
+/// Type alias for the trivia scores table, guild id to user id to score.
+pub type TriviaScores = HashMap<u64, HashMap<u64, u32>>;
+
+/// Load the trivia scores from the trivia file.
+pub(crate) fn load_scores() -> TriviaScores {
+    read_data(TRIVIA_FILE_PATH)
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Save the trivia scores to the trivia file.
+///
+/// This lock [`TRIVIA_SCORES`] so make sure you drop any guard before calling this.
+pub fn save_scores() {
+    write_data(TRIVIA_FILE_PATH, &*TRIVIA_SCORES);
+    done!(
+        "Trivia scores save successfully to {}",
+        TRIVIA_FILE_PATH.green()
+    );
+}
+
+/// Give a user a point in a guild then save the scores.
+pub fn add_score(guild_id: GuildId, user_id: UserId) {
+    *TRIVIA_SCORES
+        .lock()
+        .unwrap()
+        .entry(guild_id.get())
+        .or_default()
+        .entry(user_id.get())
+        .or_default() += 1;
+
+    save_scores();
+}
+
+/// Get the scores of a guild from highest to lowest.
+pub fn guild_scores(guild_id: GuildId) -> Vec<(u64, u32)> {
+    let mut scores = TRIVIA_SCORES
+        .lock()
+        .unwrap()
+        .get(&guild_id.get())
+        .map(|s| s.clone().into_iter().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    scores.sort_by_key(|(_, score)| Reverse(*score));
+    scores
+}
+
+/// Check if a guess is close enough to the answer.
+pub fn check_answer(guess: &str, answer: &str) -> bool {
+    lev(
+        &answer.to_lowercase(),
+        guess.trim().to_lowercase().as_str(),
+        ANSWER_THRESHOLD,
+    ) > 0.
+}
+
+/// A trivia question.
+struct Trivia {
+    /// The name of the card to guess.
+    answer: String,
+    /// The card names to show as buttons, one of them is the answer.
+    choices: Vec<String>,
+    /// The embed with the hints.
+    embed: CreateEmbed,
+    /// The blurred portrait, empty if the card have no portrait.
+    portrait: Vec<u8>,
+}
+
+/// Pick a random card from a set and make a trivia out of it.
+fn gen_trivia(set_code: &str) -> Option<Trivia> {
+    let sets = SETS.read().unwrap();
+    let set = sets.get(set_code)?;
+    let mut rng = thread_rng();
+
+    let card = set.random_card(&mut rng, |c| !c.portrait.is_empty())?;
+
+    let mut choices = set
+        .cards
+        .iter()
+        .filter(|c| c.name != card.name)
+        .collect::<Vec<_>>()
+        .choose_multiple(&mut rng, CHOICES - 1)
+        .map(|c| c.name.clone())
+        .collect::<Vec<_>>();
+    choices.push(card.name.clone());
+    choices.shuffle(&mut rng);
+
+    Some(Trivia {
+        answer: card.name.clone(),
+        choices,
+        embed: hint_embed(card, &set.name),
+        portrait: blur_portrait(card),
+    })
+}
+
+fn hint_embed(card: &Card, set_name: &str) -> CreateEmbed {
+    let sigils = if card.sigils.is_empty() {
+        String::from("None")
+    } else {
+        card.sigils.join(", ")
+    };
+
+    let costs = card
+        .costs
+        .as_ref()
+        .map_or(String::from("Free"), ToString::to_string);
+
+    CreateEmbed::new()
+        .color(roles::BLUE)
+        .title(format!("Guess the card ({set_name})"))
+        .description(format!(
+            "Reply with the card name or pick a button. You have {} seconds.",
+            TRIVIA_TIME.as_secs()
+        ))
+        .field("Sigils", sigils, false)
+        .field("Cost", costs, true)
+        .field("Rarity", card.rarity.to_string(), true)
+}
+
+fn blur_portrait(card: &Card) -> Vec<u8> {
+    task::block_in_place(|| {
+        let Ok(img) = image::load_from_memory(&gen_portrait(card)) else {
+            return Vec::new();
+        };
+
+        let mut out = vec![];
+        img.blur(img.dimensions().0 as f32 / 16.)
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .expect("Encode trivia portrait fails");
+        out
+    })
+}
+
+/// Run a trivia game in the command channel.
+pub async fn play_trivia(ctx: CmdCtx<'_>, set_code: &str) -> Res {
+    let Some(Trivia {
+        answer,
+        choices,
+        embed,
+        portrait,
+    }) = gen_trivia(set_code)
+    else {
+        ctx.say(format!("Cannot make a trivia from set `{set_code}`"))
+            .await?;
+        return Ok(());
+    };
+
+    // make the button ids unique to this game so other games don't pick them up
+    let id = ctx.id();
+    let buttons = choices
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            CreateButton::new(format!("trivia_{id}_{i}"))
+                .style(Primary)
+                .label(name)
+        })
+        .collect();
+
+    let mut reply = CreateReply::default().components(vec![Buttons(buttons)]);
+    reply = if portrait.is_empty() {
+        reply.embed(embed)
+    } else {
+        reply
+            .embed(embed.image("attachment://trivia.png"))
+            .attachment(CreateAttachment::bytes(portrait, "trivia.png"))
+    };
+    let msg = ctx.send(reply).await?.into_message().await?;
+
+    // the collectors are make once for the whole game, both stream end when the time is up
+    let mut replies = MessageCollector::new(ctx.serenity_context())
+        .channel_id(msg.channel_id)
+        .timeout(TRIVIA_TIME)
+        .stream();
+    let mut presses = ComponentInteractionCollector::new(ctx.serenity_context())
+        .message_id(msg.id)
+        .timeout(TRIVIA_TIME)
+        .stream();
+    let mut wrong: Vec<UserId> = vec![];
+
+    let winner = loop {
+        tokio::select! {
+            reply = replies.next() => {
+                match reply {
+                    Some(reply) if !reply.author.bot && check_answer(&reply.content, &answer) => {
+                        break Some(reply.author.id)
+                    }
+                    Some(_) => (),
+                    None => break None,
+                }
+            }
+            press = presses.next() => {
+                let Some(press) = press else { break None };
+
+                let picked = press
+                    .data
+                    .custom_id
+                    .rsplit('_')
+                    .next()
+                    .and_then(|i| i.parse::<usize>().ok())
+                    .and_then(|i| choices.get(i));
+
+                let content = if wrong.contains(&press.user.id) {
+                    "You already guessed wrong."
+                } else if picked.is_some_and(|p| *p == answer) {
+                    // the result is announce below, the press only need to not show as failed
+                    press
+                        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                        .await?;
+                    break Some(press.user.id);
+                } else {
+                    wrong.push(press.user.id);
+                    "Wrong guess, you can't pick again but you can still reply."
+                };
+
+                press
+                    .create_response(
+                        ctx,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .content(content)
+                                .ephemeral(true),
+                        ),
+                    )
+                    .await?;
+            }
+        }
+    };
+
+    let result = match (winner, ctx.guild_id()) {
+        (Some(user), Some(guild_id)) => {
+            add_score(guild_id, user);
+            format!("<@{user}> got it! The card was **{answer}**.")
+        }
+        (Some(user), None) => format!("<@{user}> got it! The card was **{answer}**."),
+        (None, _) => format!("Time's up! The card was **{answer}**."),
+    };
+
+    // remove the buttons so no one can guess after the game end
+    msg.channel_id
+        .edit_message(ctx, msg.id, EditMessage::new().components(vec![]))
+        .await?;
+    ctx.say(result).await?;
+
+    Ok(())
+}
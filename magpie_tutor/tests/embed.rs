@@ -0,0 +1,75 @@
+//! Golden tests for the card embeds of every set fetcher.
+//!
+//! The sets are fetch from the engine fixtures, see `magpie_engine/tests/common` for how they are
+//! serve and how to update the golden files.
+
+#[path = "../../magpie_engine/tests/common/mod.rs"]
+mod common;
+
+use std::fmt::Write;
+
+use common::{assert_golden, install_fixtures, IMF_URL};
+use magpie_engine::{fetch::AugBranch, prelude::*};
+use magpie_tutor::{search::gen_plain_embed, Set};
+use serde_json::Value;
+
+/// Render the title, description, fields and footer of every card embed in a set.
+fn embeds(set: &Set, compact: bool) -> String {
+    let mut out = String::new();
+
+    for card in &set.cards {
+        let embed = serde_json::to_value(gen_plain_embed(card, set, compact, "en")).unwrap();
+        let text = |v: &Value| v.as_str().unwrap_or_default().to_owned();
+
+        writeln!(out, "# {}", text(&embed["title"])).unwrap();
+        writeln!(out, "{}", text(&embed["description"])).unwrap();
+        for field in embed["fields"].as_array().into_iter().flatten() {
+            writeln!(
+                out,
+                "## {}\n{}",
+                text(&field["name"]),
+                text(&field["value"])
+            )
+            .unwrap();
+        }
+        writeln!(out, "-- {}\n", text(&embed["footer"]["text"])).unwrap();
+    }
+
+    out
+}
+
+/// Check both the normal and the compact embeds of a set.
+fn assert_embeds(name: &str, set: &Set) {
+    assert_golden(&format!("{name}.txt"), &embeds(set, false));
+    assert_golden(&format!("{name}_compact.txt"), &embeds(set, true));
+}
+
+#[test]
+fn imf_embeds() {
+    install_fixtures();
+    let set: Set = fetch_imf_set(IMF_URL, SetCode::new("std").unwrap())
+        .unwrap()
+        .upgrade();
+
+    assert_embeds("imf", &set);
+}
+
+#[test]
+fn aug_embeds() {
+    install_fixtures();
+    let set: Set = fetch_aug_set(AugBranch::Main, SetCode::new("aug").unwrap())
+        .unwrap()
+        .upgrade();
+
+    assert_embeds("aug", &set);
+}
+
+#[test]
+fn desc_embeds() {
+    install_fixtures();
+    let set: Set = fetch_desc_set(SetCode::new("des").unwrap())
+        .unwrap()
+        .upgrade();
+
+    assert_embeds("desc", &set);
+}
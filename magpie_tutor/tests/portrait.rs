@@ -0,0 +1,24 @@
+//! Tests for the generated card portraits.
+
+#[path = "../../magpie_engine/tests/common/mod.rs"]
+mod common;
+
+use common::{install_fixtures, PORTRAIT_URL};
+use magpie_engine::prelude::*;
+use magpie_tutor::{search::try_gen_portrait, Set};
+
+#[test]
+fn aug_portrait_without_temple() {
+    install_fixtures();
+
+    // cards with a temple that fail to parse only have it in the other temples
+    let set: Set = SetBuilder::new("aug", "Augmented")
+        .unwrap()
+        .card(CardBuilder::new("Fool").portrait(PORTRAIT_URL))
+        .unwrap()
+        .build();
+
+    let card = &set.cards[0];
+    assert!(card.temple.is_empty());
+    assert!(try_gen_portrait(card).is_some_and(|p| !p.is_empty()));
+}
@@ -0,0 +1,187 @@
+//! Snapshot tests for the card embeds of tricky cards.
+//!
+//! Every card is render by the IMF, Augmented and Descryption embed and the whole embed is
+//! compare as JSON to the golden files, see `magpie_engine/tests/common` to update them.
+
+#[path = "../../magpie_engine/tests/common/mod.rs"]
+mod common;
+
+use common::assert_golden;
+use magpie_engine::prelude::*;
+use magpie_tutor::{
+    emojis::with_plain_emojis_sync,
+    engine::{MagpieCosts, MagpieExt},
+    search::gen_plain_embed,
+    Card, Set, DEBUG_CARD,
+};
+use serde_json::{json, Value};
+
+/// Sigils with long descriptions for the card with every sigil.
+const SIGILS: &[(&str, &str)] = &[
+    ("Airborne", "This card attack the opponent directly, unless blocked by a card with Mighty Leap."),
+    ("Bifurcated Strike", "This card strike the spaces to the left and right of the space across from it."),
+    ("Bone King", "When this card die, 4 bones are award instead of 1."),
+    ("Burrower", "When an empty space would be struck, this card move to that space to receive the strike."),
+    ("Fecundity", "When this card is play, a copy of it is create in your hand."),
+    ("Guardian", "When an opposing card is play opposite an empty space, this card move to that space."),
+    ("Many Lives", "When this card is sacrifice, it does not perish."),
+    ("Mighty Leap", "This card will block an opposing card bearing the Airborne sigil."),
+    ("Sharp Quills", "Once this card is struck, the striker is then dealt a single damage point."),
+    ("Sprinter", "At the end of the owner's turn, this card move in the sigil's direction, looping when it reach the end of the board."),
+    ("Touch of Death", "When this card damage another card, that card perish."),
+    ("Unkillable", "When this card perish, a copy of it is create in your hand."),
+];
+
+/// Every mox with a count.
+fn mox_count(n: usize) -> MoxCount {
+    MoxCount {
+        o: n,
+        g: n,
+        b: n,
+        y: n,
+        r: n,
+        e: n,
+        p: n,
+        k: n,
+    }
+}
+
+/// The curated cards that are hard to render.
+fn tricky_cards() -> Vec<CardBuilder<MagpieExt, MagpieCosts>> {
+    let mut cards = vec![
+        CardBuilder::new("Every Mox")
+            .temple(Temple::MAGICK)
+            .attack(Attack::SpAtk(SpAtk::MOX))
+            .health(1)
+            .costs(CostsBuilder::new().mox(Mox::all()).build()),
+        CardBuilder::new("Many Mox")
+            .temple(Temple::MAGICK)
+            .rarity(Rarity::RARE)
+            .attack(Attack::Num(2))
+            .health(2)
+            .costs(
+                CostsBuilder::new()
+                    .mox(Mox::all() - Mox::P1)
+                    .mox_count(mox_count(3))
+                    .extra(MagpieCosts {
+                        shattered_count: Some(mox_count(1)),
+                        ..MagpieCosts::default()
+                    })
+                    .build(),
+            ),
+        CardBuilder::new("Every Cost")
+            .temple(Temple::TECH)
+            .attack(Attack::Num(1))
+            .health(1)
+            .costs(
+                CostsBuilder::new()
+                    .blood(4)
+                    .bone(10)
+                    .energy(6)
+                    .mox(Mox::O | Mox::K)
+                    .extra(MagpieCosts {
+                        max: 2,
+                        link: 3,
+                        gold: 1,
+                        ..MagpieCosts::default()
+                    })
+                    .build(),
+            ),
+        CardBuilder::new("Every Sigil")
+            .temple(Temple::BEAST)
+            .description("Far too many sigils for one card.")
+            .attack(Attack::Num(1))
+            .health(12)
+            .sigils(SIGILS.iter().map(|(s, _)| *s))
+            .related(vec!["Every Mox".to_owned(), "Missing Token".to_owned()]),
+    ];
+
+    // every special attack and the attacks some sets write as text
+    let attacks = [
+        SpAtk::MOX,
+        SpAtk::GREEN_MOX,
+        SpAtk::MIRROR,
+        SpAtk::ANT,
+        SpAtk::BONE,
+        SpAtk::BELL,
+        SpAtk::CARD,
+    ]
+    .map(Attack::SpAtk)
+    .into_iter()
+    .chain(
+        ["Green Mox", "ants", "Hand", "X+1", ""]
+            .into_iter()
+            .map(|s| Attack::Str(s.to_owned())),
+    );
+    for (i, attack) in attacks.enumerate() {
+        cards.push(
+            CardBuilder::new(&format!("Attack {i}"))
+                .temple(Temple::UNDEAD)
+                .attack(attack)
+                .health(1)
+                .costs(CostsBuilder::new().bone(1).build()),
+        );
+    }
+
+    cards
+}
+
+/// Build the curated cards into a set with a code, the code pick which embed render them.
+fn tricky_set(code: &str, name: &str) -> Set {
+    let mut builder = SetBuilder::new(code, name).unwrap();
+    for (name, text) in SIGILS {
+        builder = builder.sigil(name, text);
+    }
+    for card in tricky_cards() {
+        builder = builder.card(card).unwrap();
+    }
+    builder.build()
+}
+
+/// Every embed of a set as JSON, in both the normal and compact form.
+fn snapshot<'a>(cards: impl IntoIterator<Item = &'a Card>, set: &Set) -> String {
+    let embeds = cards
+        .into_iter()
+        .map(|card| {
+            let embed = |compact| serde_json::to_value(gen_plain_embed(card, set, compact, "en"));
+            json!({
+                "card": card.name,
+                "embed": embed(false).unwrap(),
+                "compact": embed(true).unwrap(),
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    serde_json::to_string_pretty(&embeds).unwrap() + "\n"
+}
+
+#[test]
+fn imf_tricky_cards() {
+    let set = tricky_set("std", "Standard");
+    assert_golden("snapshot_imf.json", &snapshot(&set.cards, &set));
+}
+
+#[test]
+fn aug_tricky_cards() {
+    let set = tricky_set("aug", "Augmented");
+    assert_golden("snapshot_aug.json", &snapshot(&set.cards, &set));
+}
+
+#[test]
+fn desc_tricky_cards() {
+    let set = tricky_set("des", "Descryption");
+    assert_golden("snapshot_desc.json", &snapshot(&set.cards, &set));
+}
+
+#[test]
+fn plain_emojis() {
+    let set = tricky_set("std", "Standard");
+    let snapshot = with_plain_emojis_sync(true, || snapshot(&set.cards, &set));
+    assert_golden("snapshot_plain.json", &snapshot);
+}
+
+#[test]
+fn debug_card() {
+    let set = tricky_set("des", "Descryption");
+    assert_golden("snapshot_debug.json", &snapshot([&*DEBUG_CARD], &set));
+}